@@ -31,13 +31,23 @@ use proc_macro::{LexError, TokenStream};
 /// Defines an intercom interface.
 ///
 /// ```rust,ignore
-/// #[com_interface(IID, base?)]
+/// #[com_interface(IID, base?, iid_namespace?, error_policy?, pointer_width?, dual?,
+///                 dispinterface?)]
 /// trait Foo { /* ... */ }
 /// ```
 ///
 /// - `IID` - A unique ID of the interface used to query for it. Must be either
-///           a valid GUID or `AUTO_GUID` specifier.
+///   a valid GUID or `AUTO_GUID` specifier.
 /// - `base` - Base interface. Defaults to `IUnknown` if not specified.
+/// - `iid_namespace` - Namespace GUID for the automatic IIDs, such as
+///   `iid_namespace = "GUID"`.
+/// - `error_policy` - Reporting of the errors, `error_policy = "error_info"`
+///   or `error_policy = "hresult_only"`. Defaults to the
+///   `IErrorInfo`.
+/// - `pointer_width` - Accepts `usize` and `isize` with
+///   `pointer_width = "target"`.
+/// - `dual` - Derives the interface from `IDispatch`.
+/// - `dispinterface` - Describes the interface as a `dispinterface`.
 ///
 /// Associated types: `trait`, `impl Struct`
 ///
@@ -48,6 +58,11 @@ use proc_macro::{LexError, TokenStream};
 /// Each interface automatically inherits from the base `IUnknown` interface,
 /// which provides the clients a way to perform reference counting and the
 /// ability to query for other interfaces the object might implement.
+///
+/// The methods and their parameters accept the `#[com_method(..)]`,
+/// `#[com_property(..)]`, `#[com_return(..)]` and `#[com_arg(..)]`
+/// attributes. See `docs/interfaces.md` in the repository for the options and
+/// for how the parameter and return types are exposed to the clients.
 #[proc_macro_attribute]
 pub fn com_interface(attr: TokenStream, tokens: TokenStream) -> TokenStream
 {
@@ -60,20 +75,48 @@ pub fn com_interface(attr: TokenStream, tokens: TokenStream) -> TokenStream
 /// Defines a COM class that implements one or more COM interfaces.
 ///
 /// ```rust,ignore
-/// #[com_class(CLSID, interfaces...)]
+/// #[com_class(CLSID, clsid_namespace?, locking?, on_release?, no_class_factory?, appobject?,
+///            default_interface?, interfaces...)]
 /// struct S { /* ... */ }
 /// ```
 ///
 /// - `CLSID` - A unique ID of the exposed class. The clients use the class ID
-///             to specify the class when they want to construct an object.
-///             The value must be a valid GUID, `AUTO_GUID` or `NO_GUID`.
+///   to specify the class when they want to construct an object.
+///   The value must be a valid GUID, `AUTO_GUID` or `NO_GUID`.
+/// - `clsid_namespace` - Namespace GUID for the automatic CLSID, such as
+///   `clsid_namespace = "GUID"`.
+/// - `locking` - Locking policy for the method calls, such as
+///   `locking = RwLock`. Required if the class exposes methods
+///   with a `&mut self` receiver. The lock is not re-entrant,
+///   a method calling back into the same object through COM
+///   deadlocks if either call has a `&mut self` receiver.
+/// - `on_release` - Name of a `fn(&mut self)` method invoked once when the
+///   last reference is released, before the object is
+///   dropped, such as `on_release = "cleanup"`.
+/// - `no_class_factory` - Leaves out the class factory for classes that are
+///   created only internally. Such classes do not need
+///   to implement `Default`.
+/// - `appobject` - Marks the class as the `appobject` of the library. The
+///   members of the application object are accessible without
+///   qualification from VBA. A library may have one at most.
+/// - `default_interface` - The `[default]` interface of the coclass in the
+///   type library, such as `default_interface = IBar`.
+///   Defaults to the first interface.
 /// - `interfaces` - Any number of interfaces that the class implements.
 ///
 /// Associated types: `struct`, `enum`
 ///
 /// If the `CLSID` is specified as `NO_GUID`, the class cannot be constructed
 /// by the clients. It can still be returned as a return value from other
-/// intercom methods.
+/// intercom methods. The `no_class_factory` classes are described in the type
+/// library as `noncreatable` coclasses with the same restriction.
+///
+/// If the `CLSID` is not specified, it is generated from the crate name and
+/// the class name. Specifying `clsid_namespace` generates it from the
+/// namespace and the class name instead, which keeps the CLSID stable even if
+/// the crate is renamed. As with the `iid_namespace`, the CLSID is a
+/// name-based version 5 UUID, using the class name suffixed with `:clsid` as
+/// the name.
 #[proc_macro_attribute]
 pub fn com_class(attr: TokenStream, tokens: TokenStream) -> TokenStream
 {
@@ -83,6 +126,33 @@ pub fn com_class(attr: TokenStream, tokens: TokenStream) -> TokenStream
     }
 }
 
+/// Describes the DLL entry points and constants of a module.
+///
+/// ```rust,ignore
+/// #[com_dll_module(dllname?)]
+/// mod exports { /* ... */ }
+/// ```
+///
+/// - `dllname` - Name of the DLL exporting the entry points, such as
+///   `dllname = "mydll.dll"`. Defaults to the crate name with
+///   the `.dll` extension.
+///
+/// Associated types: `mod`
+///
+/// The `extern "system"` functions in the module are exported with their
+/// Rust names and the literal `const` items are described as constants.
+/// Listing the module in the `com_library!` as `module exports` describes
+/// these in the IDL `module` block so that the Automation clients are able
+/// to declare functions against the DLL.
+#[proc_macro_attribute]
+pub fn com_dll_module(attr: TokenStream, tokens: TokenStream) -> TokenStream
+{
+    match expand_com_dll_module(attr, tokens) {
+        Ok(t) => t,
+        Err(e) => panic!("{}", e),
+    }
+}
+
 /// Defines a COM library sub-module.
 ///
 /// ```rust,ignore
@@ -110,12 +180,19 @@ pub fn com_module(args: TokenStream) -> TokenStream
 /// ```
 ///
 /// - `libid` - A unique ID that specifies the current intercom library.
-///             Optional, the libid is generated randomly if omitted.
+///   Optional, the libid is generated randomly if omitted.
+/// - `uses` - A type library such as `"other.tlb"` that defines the external
+///   interfaces referenced by this library. Optional. The library is
+///   imported in the generated IDL.
 /// - `items` - List of items contained in this library.
 ///
 /// The macro results in the implementation of the object creation
 /// infrastructure that allows external clients to load the library and
 /// instantiate the specified types.
+///
+/// The interfaces the `uses` library defines are listed as `import` items,
+/// such as `import other::IOther`. The IDL refers to them by their names in
+/// the imported library instead of defining them again.
 #[proc_macro]
 pub fn com_library(args: TokenStream) -> TokenStream
 {
@@ -125,42 +202,84 @@ pub fn com_library(args: TokenStream) -> TokenStream
     }
 }
 
+/// Asserts that values round trip through the conversions the type handlers
+/// generate for each type system and parameter direction.
+///
+/// ```rust,ignore
+/// intercom::__test_handler!(i32, [0, 1, -1, i32::MIN, i32::MAX]);
+/// ```
+///
+/// Meant for the tests of the type handlers. The type must be an owned type
+/// implementing `ExternInput`, `ExternOutput`, `Clone`, `PartialEq` and
+/// `Debug`.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __test_handler(args: TokenStream) -> TokenStream
+{
+    match expand_test_handler(args) {
+        Ok(t) => t,
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 /// Derives the implementation of the trait ForeignType for a type.
 #[proc_macro_derive(ForeignType)]
 pub fn named_type_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream
 {
     match expand_bidirectional_type_info(input) {
         Ok(t) => t,
-        Err(e) => panic!("{}", e),
+        Err(e) => e.to_compile_error().into(),
     }
 }
 
 /// Derives the implementation of the trait ExternType for a type.
-#[proc_macro_derive(ExternType)]
+///
+/// Fieldless enums are passed as their discriminant values. The enums are
+/// described in the type library so the IDL declares them as `enum` types
+/// and refers to them by name. The enums must specify `repr(C)` or an
+/// integer `repr`. Enums with fields are not supported.
+///
+/// The `repr(i32)` and `repr(u32)` enums, including the default `repr(C)`
+/// ones, are declared `[v1_enum]` in the IDL so they are 32 bits on the wire.
+/// Enums with narrower discriminants can opt in with `#[v1_enum]`.
+///
+/// The `#[repr(transparent)]` structs marked with `#[newtype]` are passed as
+/// their only field. The IDL declares the struct name as a typedef of the
+/// field type so that the domain types such as `UserId(u64)` keep their
+/// names. The newtypes derive `ExternInput` and `ExternOutput` the same way.
+#[proc_macro_derive(ExternType, attributes(v1_enum, newtype))]
 pub fn derive_extern_type(input: proc_macro::TokenStream) -> proc_macro::TokenStream
 {
     match expand_derive_extern_type(input) {
         Ok(t) => t,
-        Err(e) => panic!("{}", e),
+        Err(e) => e.to_compile_error().into(),
     }
 }
 
 /// Derives the implementation of the trait ExternInput for a type.
-#[proc_macro_derive(ExternInput)]
+///
+/// The incoming enum discriminants are validated against the variants. As an
+/// unknown discriminant can't be reported from an infallible method, the
+/// methods taking enum parameters must return `ComResult`.
+#[proc_macro_derive(ExternInput, attributes(newtype))]
 pub fn derive_extern_input(input: proc_macro::TokenStream) -> proc_macro::TokenStream
 {
     match expand_derive_extern_input(input) {
         Ok(t) => t,
-        Err(e) => panic!("{}", e),
+        Err(e) => e.to_compile_error().into(),
     }
 }
 
 /// Derives the implementation of the trait ExternOutput for a type.
-#[proc_macro_derive(ExternOutput)]
+///
+/// The enum discriminants received from the server are validated against
+/// the variants. As an unknown discriminant can't be reported from an
+/// infallible method, the methods returning enums must return `ComResult`.
+#[proc_macro_derive(ExternOutput, attributes(newtype))]
 pub fn derive_extern_output(input: proc_macro::TokenStream) -> proc_macro::TokenStream
 {
     match expand_derive_extern_output(input) {
         Ok(t) => t,
-        Err(e) => panic!("{}", e),
+        Err(e) => e.to_compile_error().into(),
     }
 }
@@ -0,0 +1,26 @@
+extern crate intercom;
+use intercom::*;
+
+// Classes with a locking policy can serve `&mut self` methods.
+
+#[com_class(locking = RwLock, Counter)]
+#[derive(Default)]
+pub struct Counter
+{
+    count: u32,
+}
+
+#[com_interface]
+impl Counter
+{
+    pub fn increment(&mut self) -> u32
+    {
+        self.count += 1;
+        self.count
+    }
+
+    pub fn count(&self) -> u32
+    {
+        self.count
+    }
+}
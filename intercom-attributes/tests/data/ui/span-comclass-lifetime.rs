@@ -0,0 +1,24 @@
+extern crate intercom;
+use intercom::*;
+
+pub struct Config;
+
+#[com_interface]
+pub trait IServer
+{
+    fn get_value(&self) -> u32;
+}
+
+#[com_class(IServer)]
+pub struct Server<'a>
+{
+    cfg: &'a Config,
+}
+
+impl<'a> IServer for Server<'a>
+{
+    fn get_value(&self) -> u32
+    {
+        0
+    }
+}
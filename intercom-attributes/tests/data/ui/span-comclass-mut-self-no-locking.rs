@@ -0,0 +1,36 @@
+extern crate intercom;
+use intercom::*;
+
+// `&mut self` methods require the class to specify a locking policy.
+
+#[com_class(Counter)]
+#[derive(Default)]
+pub struct Counter
+{
+    count: u32,
+}
+
+#[com_interface]
+impl Counter
+{
+    pub fn increment(&mut self) -> u32
+    {
+        self.count += 1;
+        self.count
+    }
+}
+
+#[com_interface]
+pub trait IReset
+{
+    fn reset(&mut self);
+}
+
+#[com_class(IReset)]
+#[derive(Default)]
+pub struct Resettable;
+
+impl IReset for Resettable
+{
+    fn reset(&mut self) {}
+}
@@ -0,0 +1,22 @@
+extern crate intercom;
+use intercom::*;
+
+// `&mut self` methods would allow moving the value so the classes that
+// aren't `Unpin` must use the `self: Pin<&mut Self>` receiver.
+
+#[com_class(locking = RwLock, Anchor)]
+pub struct Anchor
+{
+    count: u32,
+    _pin: std::marker::PhantomPinned,
+}
+
+#[com_interface]
+impl Anchor
+{
+    pub fn increment(&mut self) -> u32
+    {
+        self.count += 1;
+        self.count
+    }
+}
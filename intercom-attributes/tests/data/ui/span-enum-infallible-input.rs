@@ -0,0 +1,19 @@
+extern crate intercom;
+use intercom::*;
+
+#[derive(ExternType, ExternInput, ExternOutput, Clone, Copy)]
+#[repr(C)]
+pub enum Color
+{
+    Red,
+    Green,
+}
+
+// The enum parameters need a fallible method for reporting the unknown
+// discriminants.
+#[com_interface]
+trait IPalette
+{
+    fn code(&self, c: Color) -> u32;
+    fn next_color(&self, c: Color) -> ComResult<Color>;
+}
@@ -0,0 +1,19 @@
+extern crate intercom;
+use intercom::*;
+
+#[derive(ExternType, ExternInput, ExternOutput, Clone, Copy)]
+#[repr(C)]
+pub enum Color
+{
+    Red,
+    Green,
+}
+
+// The enum return values need a fallible method for reporting the unknown
+// discriminants.
+#[com_interface]
+trait IPalette
+{
+    fn default_color(&self) -> Color;
+    fn next_color(&self) -> ComResult<Color>;
+}
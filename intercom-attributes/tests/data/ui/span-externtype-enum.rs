@@ -0,0 +1,17 @@
+extern crate intercom;
+use intercom::*;
+
+// Enums are passed as their discriminants so the variants must not have
+// fields and the discriminant type must be known.
+#[derive(ExternType)]
+#[repr(C)]
+enum Shape {
+    Point,
+    Circle(f64),
+}
+
+#[derive(ExternType)]
+enum Mode {
+    Read,
+    Write,
+}
@@ -0,0 +1,8 @@
+extern crate intercom;
+use intercom::*;
+
+#[com_interface]
+pub trait ILookup
+{
+    fn code(&self, kind: u32) -> Option<u32>;
+}
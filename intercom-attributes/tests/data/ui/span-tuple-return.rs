@@ -0,0 +1,8 @@
+extern crate intercom;
+use intercom::*;
+
+#[com_interface]
+pub trait ILabels
+{
+    fn label(&self, kind: u32) -> (u32, String);
+}
@@ -0,0 +1,8 @@
+extern crate intercom;
+use intercom::*;
+
+#[com_interface]
+pub trait IBuffer
+{
+    fn resize(&self, len: usize) -> ComResult<()>;
+}
@@ -0,0 +1,8 @@
+extern crate intercom;
+use intercom::*;
+
+#[com_interface]
+pub trait IFormatter
+{
+    fn format(&self, args: &[Variant], separator: &str) -> ComResult<String>;
+}
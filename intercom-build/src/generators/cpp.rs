@@ -20,15 +20,27 @@ use serde_derive::Serialize;
 pub struct CppLibrary
 {
     pub lib_name: String,
+    pub structs: Vec<CppStruct>,
     pub interfaces: Vec<CppInterface>,
     pub coclass_count: usize,
     pub coclasses: Vec<CppClass>,
+
+    /// Class IDs of the classes that can be created through the library.
+    pub creatable_clsids: Vec<String>,
+}
+
+#[derive(PartialEq, Serialize, Debug)]
+pub struct CppStruct
+{
+    pub name: String,
+    pub fields: Vec<CppArg>,
 }
 
 #[derive(PartialEq, Serialize, Debug)]
 pub struct CppInterface
 {
     pub name: String,
+    pub iid: String,
     pub iid_struct: String,
     pub base: Option<String>,
     pub methods: Vec<CppMethod>,
@@ -60,20 +72,48 @@ pub struct CppClass
 
 impl CppLibrary
 {
-    fn try_from(lib: TypeLib, opts: &ModelOptions) -> Result<Self, GeneratorError>
+    fn try_from(lib: &TypeLib, opts: &ModelOptions) -> Result<Self, GeneratorError>
     {
-        let ctx = LibraryContext::from(&lib);
+        let ctx = LibraryContext::from(lib);
 
+        let mut structs = vec![];
         let mut interfaces = vec![];
         let mut coclasses = vec![];
+        let mut creatable_clsids = vec![];
         for t in &lib.types {
             match t {
                 TypeInfo::Class(cls) => {
-                    coclasses.push(CppClass::from(cls.as_ref(), opts, &ctx))
+                    let cpp_cls = CppClass::from(cls.as_ref(), opts, &ctx);
+                    if !cls.options.no_class_factory {
+                        creatable_clsids.push(cpp_cls.clsid_struct.clone());
+                    }
+                    coclasses.push(cpp_cls)
                 }
                 TypeInfo::Interface(itf) => {
                     interfaces.push(CppInterface::gather(itf.as_ref(), opts, &ctx)?)
                 }
+
+                // The C++ interfaces use the discriminant types of the enums.
+                TypeInfo::Enum(..) => {}
+
+                // The DLL entry points are not part of the C++ interfaces.
+                TypeInfo::Module(..) => {}
+
+                TypeInfo::Struct(s) => structs.push(CppStruct {
+                    name: s.name.to_string(),
+                    fields: s
+                        .fields
+                        .iter()
+                        .map(|f| CppArg {
+                            name: f.name.to_string(),
+                            arg_type: format!(
+                                "{}{}",
+                                f.ty,
+                                "*".repeat(f.indirection_level as usize)
+                            ),
+                        })
+                        .collect(),
+                }),
             }
         }
         let interfaces = interfaces
@@ -83,9 +123,11 @@ impl CppLibrary
 
         Ok(Self {
             lib_name: lib.name.to_string(),
+            structs,
             interfaces,
-            coclass_count: coclasses.len(),
+            coclass_count: creatable_clsids.len(),
             coclasses,
+            creatable_clsids,
         })
     }
 }
@@ -98,6 +140,12 @@ impl CppInterface
         ctx: &LibraryContext,
     ) -> Result<Vec<Self>, GeneratorError>
     {
+        // The dispinterfaces have no virtual table methods for the C++
+        // clients, which call them through `IDispatch`.
+        if itf.options.dispinterface {
+            return Ok(vec![]);
+        }
+
         Ok(opts
             .type_systems
             .iter()
@@ -118,10 +166,15 @@ impl CppInterface
         ctx: &LibraryContext,
     ) -> Result<Self, GeneratorError>
     {
+        let base = match itf.options.dual {
+            true => "IDispatch",
+            false => "IUnknown",
+        };
         Ok(Self {
             name: Self::final_name(&itf, ts_opts),
+            iid: format!("{:-X}", itf_variant.iid),
             iid_struct: guid_as_struct(&itf_variant.iid),
-            base: Some("IUnknown".to_string()),
+            base: Some(base.to_string()),
             methods: itf_variant
                 .methods
                 .iter()
@@ -177,6 +230,10 @@ impl CppArg
         match arg.direction {
             Direction::In => attrs.push("in"),
             Direction::Out => attrs.push("out"),
+            Direction::InOut => {
+                attrs.push("in");
+                attrs.push("out");
+            }
             Direction::Retval => {
                 attrs.push("out");
                 attrs.push("retval");
@@ -203,12 +260,13 @@ impl CppArg
             .unwrap_or_else(|| arg.ty.to_string());
         let indirection = match arg.direction {
             Direction::In | Direction::Return => arg.indirection_level,
-            Direction::Out | Direction::Retval => arg.indirection_level + 1,
+            Direction::Out | Direction::Retval | Direction::InOut => arg.indirection_level + 1,
         };
 
         let base_name = match base_name.as_ref() {
             "std::ffi::c_void" => "void".to_string(),
             "HRESULT" => "intercom::HRESULT".to_string(),
+            other if other.starts_with("SAFEARRAY(") => "intercom::SAFEARRAY*".to_string(),
             other => other.to_string(),
         };
 
@@ -218,22 +276,17 @@ impl CppArg
 
 impl CppClass
 {
-    fn from(
-        cls: &CoClass,
-        opts: &ModelOptions,
-        ctx: &LibraryContext,
-    ) -> Self
+    fn from(cls: &CoClass, opts: &ModelOptions, ctx: &LibraryContext) -> Self
     {
         let interfaces = cls
             .interfaces
             .iter()
-            .flat_map(|itf_ref| {
+            .map(|itf_ref| ctx.itfs_by_ref[itf_ref.name.as_ref()])
+            .filter(|itf| !itf.options.dispinterface)
+            .flat_map(|itf| {
                 opts.type_systems
                     .iter()
-                    .map(|opt| {
-                        let itf = ctx.itfs_by_ref[itf_ref.name.as_ref()];
-                        CppInterface::final_name(itf, opt)
-                    })
+                    .map(|opt| CppInterface::final_name(itf, opt))
                     .collect::<Vec<_>>()
             })
             .collect::<Vec<_>>();
@@ -250,8 +303,8 @@ impl CppClass
 ///
 /// - `out` - The writer to use for output.
 pub fn write(
-    lib: intercom::typelib::TypeLib,
-    opts: ModelOptions,
+    lib: &intercom::typelib::TypeLib,
+    opts: &ModelOptions,
     out_header: Option<&mut dyn Write>,
     out_source: Option<&mut dyn Write>,
 ) -> Result<(), GeneratorError>
@@ -262,7 +315,7 @@ pub fn write(
     reg.register_template_string("cpp_source", include_str!("cpp_source.hbs"))
         .expect("Error in the built-in C++ template.");
 
-    let cpp_model = CppLibrary::try_from(lib, &opts)?;
+    let cpp_model = CppLibrary::try_from(lib, opts)?;
 
     if let Some(out_header) = out_header {
         let rendered = reg
@@ -0,0 +1,394 @@
+//! Enables the generation of smart pointer wrappers for using intercom
+//! libraries from C++ projects.
+//!
+//! The wrappers are similar to the `.tlh`/`.tli` files generated by the
+//! Visual C++ `#import` directive. The methods returning `HRESULT` are
+//! forwarded so that the failures are thrown as exceptions and the
+//! `[out, retval]` parameter is returned as the value of the call.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Write;
+
+use super::cpp::{CppArg, CppInterface};
+use super::GeneratorError;
+use super::{pascal_case, LibraryContext, ModelOptions, TypeSystemOptions};
+
+use intercom::type_system::TypeSystemName;
+use intercom::typelib::{Arg, Direction, Interface, InterfaceVariant, Method, TypeInfo, TypeLib};
+
+use handlebars::Handlebars;
+use serde_derive::Serialize;
+
+/// The primitive types the wrappers are able to forward.
+///
+/// The interface pointers and the structs of the library are forwarded as
+/// the raw types declared in the C++ header.
+const SUPPORTED_TYPES: &[&str] = &[
+    "i8", "u8", "i16", "u16", "i32", "u32", "i64", "u64", "f32", "f64", "usize", "BSTR",
+];
+
+/// A method left out of the wrappers because of the types it uses.
+#[derive(PartialEq, Serialize, Debug)]
+pub struct SkippedMethod
+{
+    pub interface: String,
+    pub method: String,
+    pub reason: String,
+}
+
+impl fmt::Display for SkippedMethod
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(
+            f,
+            "{}::{} has no wrapper: {}",
+            self.interface, self.method, self.reason
+        )
+    }
+}
+
+#[derive(PartialEq, Serialize, Debug)]
+pub struct CppWrapperLibrary
+{
+    pub lib_name: String,
+    pub interfaces: Vec<CppWrapperInterface>,
+}
+
+#[derive(PartialEq, Serialize, Debug)]
+pub struct CppWrapperInterface
+{
+    pub name: String,
+    pub short_name: String,
+    pub ptr_name: String,
+    pub methods: Vec<CppWrapperMethod>,
+    pub skipped: Vec<SkippedMethod>,
+}
+
+#[derive(PartialEq, Serialize, Debug)]
+pub struct CppWrapperMethod
+{
+    pub name: String,
+    pub ret_type: String,
+    pub returns_hresult: bool,
+    pub retval_type: Option<String>,
+    pub args: Vec<CppArg>,
+    pub call_args: String,
+}
+
+/// The named types of the library the wrappers refer to.
+struct WrapperContext<'a>
+{
+    lib: LibraryContext<'a>,
+    structs: HashSet<&'a str>,
+    ts_opts: &'a TypeSystemOptions,
+}
+
+impl CppWrapperLibrary
+{
+    fn try_from(lib: &TypeLib, opts: &ModelOptions) -> Result<Self, GeneratorError>
+    {
+        // The wrappers are built for the Automation interfaces as these are
+        // the ones using BSTR for the strings similar to the #import.
+        let ts_opts = opts
+            .type_systems
+            .iter()
+            .find(|ts_opts| ts_opts.ts == TypeSystemName::Automation)
+            .or_else(|| opts.type_systems.first())
+            .ok_or_else(|| "No type systems specified".to_string())?;
+
+        let ctx = WrapperContext {
+            lib: LibraryContext::from(lib),
+            structs: lib
+                .types
+                .iter()
+                .filter_map(|t| match t {
+                    TypeInfo::Struct(s) => Some(s.as_ref().name.as_ref()),
+                    _ => None,
+                })
+                .collect(),
+            ts_opts,
+        };
+
+        let interfaces = lib
+            .types
+            .iter()
+            .filter_map(|t| match t {
+                TypeInfo::Interface(itf) => Some(itf.as_ref()),
+                _ => None,
+            })
+            .filter_map(|itf| {
+                itf.variants
+                    .iter()
+                    .find(|v| v.as_ref().ts == ts_opts.ts)
+                    .map(|v| CppWrapperInterface::from(itf, v.as_ref(), &ctx))
+            })
+            .collect();
+
+        Ok(Self {
+            lib_name: lib.name.to_string(),
+            interfaces,
+        })
+    }
+}
+
+impl CppWrapperInterface
+{
+    /// Creates the wrapper for the interface.
+    ///
+    /// The methods using types the wrappers do not support yet are listed
+    /// in `skipped` instead of `methods`.
+    fn from(itf: &Interface, itf_variant: &InterfaceVariant, ctx: &WrapperContext) -> Self
+    {
+        let short_opts = TypeSystemOptions {
+            ts: ctx.ts_opts.ts,
+            use_full_name: false,
+            hidden: ctx.ts_opts.hidden,
+        };
+        let short_name = CppInterface::final_name(itf, &short_opts);
+
+        let mut methods = vec![];
+        let mut skipped = vec![];
+        for method in &itf_variant.methods {
+            match CppWrapperMethod::try_from(method.as_ref(), ctx) {
+                Ok(m) => methods.push(m),
+                Err(reason) => skipped.push(SkippedMethod {
+                    interface: short_name.clone(),
+                    method: pascal_case(&method.as_ref().name),
+                    reason,
+                }),
+            }
+        }
+
+        Self {
+            name: CppInterface::final_name(itf, ctx.ts_opts),
+            ptr_name: format!("{}Ptr", short_name),
+            short_name,
+            methods,
+            skipped,
+        }
+    }
+}
+
+impl CppWrapperMethod
+{
+    /// Creates the wrapper for the method.
+    ///
+    /// Returns the reason for skipping the method if it uses types the
+    /// wrappers do not support.
+    fn try_from(method: &Method, ctx: &WrapperContext) -> Result<Self, String>
+    {
+        let returns_hresult = method.return_type.ty == "HRESULT";
+        let ret_type = match method.return_type.ty.as_ref() {
+            "HRESULT" | "void" => "void".to_string(),
+            _ => wrapper_type(&method.return_type, ctx)?,
+        };
+
+        // Methods returning a HRESULT turn the retval parameter into the
+        // return value of the wrapper.
+        let retval = match returns_hresult {
+            true => method
+                .parameters
+                .iter()
+                .find(|p| p.direction == Direction::Retval),
+            false => None,
+        };
+        let retval_type = match retval {
+            Some(retval) => Some(wrapper_type(retval, ctx)?),
+            None => None,
+        };
+
+        let mut args = vec![];
+        let mut call_args = vec![];
+        for param in &method.parameters {
+            if retval.map(|r| std::ptr::eq(r, param)).unwrap_or(false) {
+                call_args.push("&__result".to_string());
+                continue;
+            }
+
+            let arg_type = match param.direction {
+                Direction::In => wrapper_type(param, ctx)?,
+                _ => format!("{}*", wrapper_type(param, ctx)?),
+            };
+            args.push(CppArg {
+                name: param.name.to_string(),
+                arg_type,
+            });
+            call_args.push(param.name.to_string());
+        }
+
+        Ok(Self {
+            name: pascal_case(&method.name),
+            ret_type: retval_type.clone().unwrap_or(ret_type),
+            returns_hresult,
+            retval_type,
+            args,
+            call_args: call_args.join(", "),
+        })
+    }
+}
+
+/// Resolves the C++ type of the argument without the out-pointer.
+///
+/// The interface pointers are passed through as the raw interfaces without
+/// taking ownership of the references.
+fn wrapper_type(arg: &Arg, ctx: &WrapperContext) -> Result<String, String>
+{
+    let base_name = if SUPPORTED_TYPES.contains(&arg.ty.as_ref()) {
+        arg.ty.to_string()
+    } else if let Some(itf) = ctx.lib.itfs_by_name.get(arg.ty.as_ref()) {
+        format!("raw::{}", CppInterface::final_name(itf, ctx.ts_opts))
+    } else if ctx.structs.contains(arg.ty.as_ref()) {
+        format!("raw::{}", arg.ty)
+    } else {
+        return Err(format!("the type `{}` is not supported", arg.ty));
+    };
+    Ok(format!(
+        "{}{}",
+        base_name,
+        "*".repeat(arg.indirection_level as usize)
+    ))
+}
+
+/// Generates the C++ smart pointer wrappers.
+///
+/// Returns the methods left out of the wrappers. These are also listed as
+/// comments in the wrapper classes.
+///
+/// - `out` - The writer to use for output.
+pub fn write(
+    lib: &intercom::typelib::TypeLib,
+    opts: &ModelOptions,
+    out: &mut dyn Write,
+) -> Result<Vec<SkippedMethod>, GeneratorError>
+{
+    let mut reg = Handlebars::new();
+    reg.register_template_string("cpp_wrappers", include_str!("cpp_wrappers.hbs"))
+        .expect("Error in the built-in C++ template.");
+
+    let wrapper_model = CppWrapperLibrary::try_from(lib, opts)?;
+
+    let rendered = reg
+        .render("cpp_wrappers", &wrapper_model)
+        .expect("Rendering a valid ComCrate to C++ failed");
+    write!(out, "{}", rendered)?;
+
+    Ok(wrapper_model
+        .interfaces
+        .into_iter()
+        .flat_map(|itf| itf.skipped)
+        .collect())
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    use intercom::attributes::ComInterfaceTypeInfo;
+    use intercom::GUID;
+
+    #[intercom::com_interface]
+    trait ICalculator
+    {
+        fn add(&self, a: i32, b: i32) -> i32;
+        fn sqrt(&self, value: f64) -> intercom::ComResult<f64>;
+        fn describe(&self, value: u32) -> intercom::ComResult<String>;
+        fn reset(&self) -> intercom::ComResult<()>;
+    }
+
+    #[intercom::com_interface]
+    trait IWorkspace
+    {
+        fn calculator(&self) -> intercom::ComResult<intercom::ComRc<dyn ICalculator>>;
+        fn move_to(&self, point: (i32, i32)) -> intercom::ComResult<()>;
+        fn value(&self) -> intercom::ComResult<intercom::Variant>;
+    }
+
+    fn generate(types: Vec<TypeInfo>) -> (String, Vec<SkippedMethod>)
+    {
+        let lib = TypeLib::__new(
+            "calc_lib".into(),
+            GUID::parse("2f6c8a4e-1b3d-4e5f-8a7b-9c0d1e2f3a4b").unwrap(),
+            "1.0".into(),
+            vec![],
+            types,
+        );
+
+        let mut out = vec![];
+        let skipped = write(&lib, &ModelOptions::default(), &mut out).unwrap();
+        (String::from_utf8(out).unwrap(), skipped)
+    }
+
+    #[test]
+    fn interface_is_wrapped()
+    {
+        let (cpp, skipped) = generate(<dyn ICalculator>::gather_type_info());
+
+        assert!(cpp.contains("#include \"calc_lib.hpp\""));
+        assert!(
+            cpp.contains("    class ICalculatorPtr : public ComPtr< raw::ICalculator_Automation >")
+        );
+        assert!(cpp.contains(
+            "        i32 Add( i32 a, i32 b )
+        {
+            return m_ptr->Add( a, b );
+        }"
+        ));
+        assert!(cpp.contains(
+            "        f64 Sqrt( f64 value )
+        {
+            f64 __result{};
+            intercom::HRESULT hr = m_ptr->Sqrt( value, &__result );
+            if( intercom::failed( hr ) )
+                throw intercom::RuntimeError( hr, \"ICalculator::Sqrt failed.\" );
+            return __result;
+        }"
+        ));
+        assert!(cpp.contains("        BSTR Describe( u32 value )"));
+        assert!(cpp.contains(
+            "        void Reset()
+        {
+            intercom::HRESULT hr = m_ptr->Reset();
+            if( intercom::failed( hr ) )
+                throw intercom::RuntimeError( hr, \"ICalculator::Reset failed.\" );
+        }"
+        ));
+        assert_eq!(skipped, vec![]);
+    }
+
+    #[test]
+    fn interfaces_and_structs_are_passed_through()
+    {
+        let mut types = <dyn ICalculator>::gather_type_info();
+        types.extend(<dyn IWorkspace>::gather_type_info());
+        let (cpp, _) = generate(types);
+
+        assert!(cpp.contains(
+            "        raw::ICalculator_Automation* Calculator()
+        {
+            raw::ICalculator_Automation* __result{};"
+        ));
+        assert!(cpp.contains("        void MoveTo( raw::Tuple_i32_i32 point )"));
+    }
+
+    #[test]
+    fn unsupported_methods_are_reported()
+    {
+        let mut types = <dyn ICalculator>::gather_type_info();
+        types.extend(<dyn IWorkspace>::gather_type_info());
+        let (cpp, skipped) = generate(types);
+
+        assert_eq!(
+            skipped,
+            vec![SkippedMethod {
+                interface: "IWorkspace".to_string(),
+                method: "Value".to_string(),
+                reason: "the type `Variant` is not supported".to_string(),
+            }]
+        );
+        assert!(cpp.contains("        // Value: the type `Variant` is not supported"));
+        assert!(!cpp.contains("Value()"));
+    }
+}
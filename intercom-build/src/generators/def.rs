@@ -0,0 +1,92 @@
+//! Enables the generation of the module definition file, `.def`.
+//!
+//! The module definition file lists the entry points the `com_library!`
+//! macro exports from the library. It is needed when the library is linked
+//! with tools that do not export the `#[no_mangle]` functions on their own.
+
+use std::io::Write;
+
+use super::GeneratorError;
+
+use handlebars::Handlebars;
+use serde_derive::Serialize;
+
+use intercom::typelib::TypeLib;
+
+/// The entry points exported by the `com_library!` macro.
+///
+/// The COM entry points are `PRIVATE` so that they are left out of the import
+/// library, linking against them directly would bypass the COM runtime.
+const EXPORTS: &[&str] = &[
+    "DllGetClassObject PRIVATE",
+    "DllRegisterServer PRIVATE",
+    "DllUnregisterServer PRIVATE",
+    "IntercomTypeLib",
+    "IntercomListClassObjects",
+];
+
+#[derive(Debug, Serialize)]
+struct DefModule
+{
+    pub name: String,
+    pub exports: &'static [&'static str],
+}
+
+impl DefModule
+{
+    fn from(lib: &TypeLib) -> Self
+    {
+        Self {
+            name: lib.name.to_string(),
+            exports: EXPORTS,
+        }
+    }
+}
+
+/// Generates the module definition file.
+pub fn write(lib: &TypeLib, out: &mut dyn Write) -> Result<(), GeneratorError>
+{
+    let mut reg = Handlebars::new();
+    reg.register_template_string("def", include_str!("def.hbs"))
+        .expect("Error in the built-in module definition template.");
+
+    let rendered = reg
+        .render("def", &DefModule::from(lib))
+        .expect("Rendering a valid ComCrate to module definition failed");
+    write!(out, "{}", rendered)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    #[test]
+    fn exports_entry_points()
+    {
+        let lib = TypeLib::__new(
+            "fixture_lib".into(),
+            intercom::GUID::parse("c7a6a0f6-4d2b-4c4a-9c6d-1f1b1e1f0bb1").unwrap(),
+            "1.0".into(),
+            vec![],
+            vec![],
+        );
+
+        let mut def = vec![];
+        write(&lib, &mut def).unwrap();
+
+        assert_eq!(
+            String::from_utf8(def).unwrap(),
+            "; Module definition file for fixture_lib, generated by intercom.\n\
+             LIBRARY fixture_lib\n\
+             EXPORTS\n    \
+             DllGetClassObject PRIVATE\n    \
+             DllRegisterServer PRIVATE\n    \
+             DllUnregisterServer PRIVATE\n    \
+             IntercomTypeLib\n    \
+             IntercomListClassObjects\n"
+        );
+    }
+}
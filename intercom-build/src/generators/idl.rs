@@ -0,0 +1,2245 @@
+//! Enables the generation of IDL file that describes intercom library.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+
+use super::GeneratorError;
+use super::{
+    interface_array_element, pascal_case, LibraryContext, ModelOptions, PropertyNaming,
+    TypeSystemOptions,
+};
+
+use handlebars::Handlebars;
+use serde_derive::Serialize;
+
+use intercom::type_system::TypeSystemName;
+use intercom::typelib::{
+    Arg, CoClass, Direction, Enum, Interface, InterfaceVariant, Method, MethodKind, Module, Struct,
+    TypeInfo, TypeLib,
+};
+
+#[derive(Debug, Serialize)]
+struct IdlLibrary
+{
+    pub lib_name: String,
+    pub lib_id: String,
+    pub lib_version: String,
+    pub ole_imports: Vec<String>,
+    pub imports: Vec<String>,
+    pub definitions: Vec<IdlDefinition>,
+    pub interfaces: Vec<IdlInterface>,
+    pub coclasses: Vec<IdlClass>,
+    pub modules: Vec<IdlModule>,
+}
+
+/// Type definition declared before the interfaces.
+///
+/// The definition is either a `typedef` of `ty`, an enum with the `values` or
+/// a struct with the `fields`.
+#[derive(Debug, Serialize)]
+struct IdlDefinition
+{
+    pub name: String,
+    pub is_enum: bool,
+    pub is_struct: bool,
+    pub v1_enum: bool,
+    pub ty: Option<String>,
+    pub values: Vec<IdlEnumValue>,
+    pub fields: Vec<IdlStructField>,
+}
+
+#[derive(Debug, Serialize)]
+struct IdlTypedef
+{
+    pub name: String,
+    pub ty: String,
+}
+
+#[derive(Debug, Serialize)]
+struct IdlEnum
+{
+    pub name: String,
+    pub v1_enum: bool,
+    pub values: Vec<IdlEnumValue>,
+}
+
+#[derive(Debug, Serialize)]
+struct IdlStruct
+{
+    pub name: String,
+    pub fields: Vec<IdlStructField>,
+}
+
+#[derive(Debug, Serialize)]
+struct IdlStructField
+{
+    pub name: String,
+    pub ty: String,
+}
+
+#[derive(Debug, Serialize)]
+struct IdlEnumValue
+{
+    pub name: String,
+    pub value: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct IdlInterface
+{
+    name: String,
+    base: Option<String>,
+    iid: String,
+    helpstring: Option<String>,
+    hidden: bool,
+
+    /// The automation variant of a dual interface.
+    dual: bool,
+    dispinterface: bool,
+    methods: Vec<IdlMethod>,
+}
+
+#[derive(Debug, Serialize)]
+struct IdlMethod
+{
+    pub name: String,
+    pub attributes: String,
+    pub ret_type: String,
+    pub args: Vec<IdlArg>,
+}
+
+#[derive(Debug, Serialize)]
+struct IdlArg
+{
+    pub name: String,
+    pub arg_type: String,
+    pub attributes: String,
+}
+
+#[derive(Debug, Serialize)]
+struct IdlClass
+{
+    pub name: String,
+    pub clsid: String,
+    pub interfaces: Vec<String>,
+    pub helpstring: Option<String>,
+    pub noncreatable: bool,
+    pub appobject: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct IdlModule
+{
+    pub name: String,
+    pub dll_name: String,
+    pub constants: Vec<IdlConstant>,
+    pub functions: Vec<IdlEntryPoint>,
+}
+
+#[derive(Debug, Serialize)]
+struct IdlConstant
+{
+    pub name: String,
+    pub ty: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize)]
+struct IdlEntryPoint
+{
+    pub name: String,
+    pub entry: String,
+    pub ret_type: String,
+    pub args: Vec<IdlArg>,
+}
+
+impl IdlLibrary
+{
+    fn try_from(lib: &TypeLib, opts: &ModelOptions) -> Result<Self, GeneratorError>
+    {
+        let ctx = LibraryContext::from(lib);
+
+        let mut interfaces = vec![];
+        let mut coclasses = vec![];
+        let mut enums = vec![];
+        let mut structs = vec![];
+        let mut modules = vec![];
+        for t in &lib.types {
+            match t {
+                TypeInfo::Class(cls) => coclasses.push(IdlClass::from(cls.as_ref(), opts, &ctx)),
+                // The imported interfaces are defined by the imported library.
+                TypeInfo::Interface(itf) if itf.options.imported => {}
+                TypeInfo::Interface(itf) => {
+                    interfaces.push(IdlInterface::gather(itf.as_ref(), opts, &ctx)?)
+                }
+                TypeInfo::Enum(e) => enums.push(IdlEnum::from(e.as_ref())),
+                TypeInfo::Module(m) => modules.push(IdlModule::try_from(m.as_ref(), opts, &ctx)?),
+                TypeInfo::Struct(s) => structs.push(IdlStruct::from(s.as_ref())),
+            }
+        }
+        let interfaces = interfaces
+            .into_iter()
+            .flatten()
+            .collect::<Vec<IdlInterface>>();
+
+        // The members of the application object are global so there can be
+        // only one of them.
+        let appobjects = coclasses
+            .iter()
+            .filter(|cls| cls.appobject)
+            .map(|cls| cls.name.as_str())
+            .collect::<Vec<_>>();
+        if appobjects.len() > 1 {
+            return Err(format!("Multiple appobject classes: {}", appobjects.join(", ")).into());
+        }
+
+        Ok(Self {
+            lib_name: pascal_case(&lib.name),
+            lib_id: format!("{:-X}", lib.libid),
+            lib_version: library_version(&lib.version)?,
+            ole_imports: ole_imports(lib),
+            imports: lib.imports.iter().map(|i| i.to_string()).collect(),
+            definitions: IdlDefinition::order(IdlTypedef::gather(lib)?, enums, structs)?,
+            interfaces,
+            coclasses,
+            modules,
+        })
+    }
+}
+
+/// Lists the IDL files to import for the OLE types that `stdole2.tlb` does
+/// not declare, such as `SYSTEMTIME` and `IAgileObject`.
+fn ole_imports(lib: &TypeLib) -> Vec<String>
+{
+    let methods = lib
+        .types
+        .iter()
+        .flat_map(|t| -> Box<dyn Iterator<Item = &Method>> {
+            match t {
+                TypeInfo::Interface(itf) if !itf.options.imported => Box::new(
+                    itf.variants
+                        .iter()
+                        .flat_map(|v| v.methods.iter().map(|m| -> &Method { m.as_ref() })),
+                ),
+                TypeInfo::Module(m) => {
+                    Box::new(m.functions.iter().map(|f| -> &Method { f.as_ref() }))
+                }
+                _ => Box::new(std::iter::empty()),
+            }
+        });
+    let types = methods
+        .flat_map(|m| std::iter::once(&m.return_type).chain(&m.parameters))
+        .map(|arg| arg.ty.as_ref())
+        .collect::<std::collections::HashSet<_>>();
+
+    // The oaidl.idl imports the objidl.idl as well.
+    match (types.contains("SYSTEMTIME"), types.contains("IAgileObject")) {
+        (true, _) => vec!["oaidl.idl".to_string()],
+        (false, true) => vec!["objidl.idl".to_string()],
+        (false, false) => vec![],
+    }
+}
+
+impl IdlDefinition
+{
+    /// Orders the type definitions so that every definition follows the
+    /// definitions it refers to, as MIDL requires the types to be defined
+    /// before they are used.
+    ///
+    /// The independent definitions are ordered by kind, typedefs first and
+    /// enums last, and then by name so that the order is stable between runs.
+    fn order(
+        typedefs: Vec<IdlTypedef>,
+        enums: Vec<IdlEnum>,
+        structs: Vec<IdlStruct>,
+    ) -> Result<Vec<Self>, GeneratorError>
+    {
+        let definitions = typedefs
+            .into_iter()
+            .map(|t| IdlDefinition {
+                name: t.name,
+                is_enum: false,
+                is_struct: false,
+                v1_enum: false,
+                ty: Some(t.ty),
+                values: vec![],
+                fields: vec![],
+            })
+            .chain(structs.into_iter().map(|s| IdlDefinition {
+                name: s.name,
+                is_enum: false,
+                is_struct: true,
+                v1_enum: false,
+                ty: None,
+                values: vec![],
+                fields: s.fields,
+            }))
+            .chain(enums.into_iter().map(|e| IdlDefinition {
+                name: e.name,
+                is_enum: true,
+                is_struct: false,
+                v1_enum: e.v1_enum,
+                ty: None,
+                values: e.values,
+                fields: vec![],
+            }));
+        let mut pending = definitions
+            .map(|d| ((d.is_enum, d.is_struct, d.name.clone()), d))
+            .collect::<BTreeMap<_, _>>();
+        let names = pending
+            .values()
+            .map(|d| d.name.clone())
+            .collect::<HashSet<_>>();
+
+        // Only the types defined in the library are ordering constraints.
+        let dependencies = |d: &IdlDefinition| -> Vec<String> {
+            d.ty.iter()
+                .chain(d.fields.iter().map(|f| &f.ty))
+                .map(|ty| ty.trim_end_matches('*'))
+                .filter(|ty| *ty != d.name && names.contains(*ty))
+                .map(|ty| ty.to_string())
+                .collect()
+        };
+
+        let mut defined = HashSet::new();
+        let mut ordered = vec![];
+        while let Some(key) = pending
+            .iter()
+            .find(|(_, d)| dependencies(d).iter().all(|dep| defined.contains(dep)))
+            .map(|(key, _)| key.clone())
+        {
+            let d = pending.remove(&key).unwrap();
+            defined.insert(d.name.clone());
+            ordered.push(d);
+        }
+
+        // Every remaining definition depends on another remaining one, which
+        // means the dependencies form a cycle.
+        if let Some(first) = pending.values().next() {
+            let by_name = pending
+                .values()
+                .map(|d| (d.name.as_str(), d))
+                .collect::<HashMap<_, _>>();
+            let mut cycle = vec![first.name.clone()];
+            let mut current = first;
+            while let Some(dep) = dependencies(current)
+                .into_iter()
+                .find(|dep| by_name.contains_key(dep.as_str()))
+            {
+                let seen = cycle.contains(&dep);
+                cycle.push(dep.clone());
+                if seen {
+                    break;
+                }
+                current = by_name[dep.as_str()];
+            }
+            return Err(format!("Cyclic type dependency: {}", cycle.join(" -> ")).into());
+        }
+
+        Ok(ordered)
+    }
+}
+
+impl IdlTypedef
+{
+    /// Gathers the typedefs required by the IDL type overrides.
+    fn gather(lib: &TypeLib) -> Result<Vec<Self>, GeneratorError>
+    {
+        // The enums and structs are declared separately.
+        let enums = lib
+            .types
+            .iter()
+            .filter_map(|t| match t {
+                TypeInfo::Enum(e) => Some(e.name.as_ref()),
+                TypeInfo::Struct(s) => Some(s.name.as_ref()),
+                _ => None,
+            })
+            .collect::<std::collections::HashSet<_>>();
+
+        let mut typedefs = std::collections::BTreeMap::new();
+        let args = lib
+            .types
+            .iter()
+            .filter_map(|t| match t {
+                TypeInfo::Interface(itf) if !itf.options.imported => Some(itf),
+                TypeInfo::Interface(..)
+                | TypeInfo::Class(..)
+                | TypeInfo::Enum(..)
+                | TypeInfo::Module(..)
+                | TypeInfo::Struct(..) => None,
+            })
+            .flat_map(|itf| itf.variants.iter())
+            .flat_map(|v| v.methods.iter())
+            .flat_map(|m| m.parameters.iter().chain(std::iter::once(&m.return_type)));
+        for arg in args {
+            let name = match &arg.idl_ty {
+                Some(name) if !enums.contains(name.as_ref()) => name,
+                _ => continue,
+            };
+            let ty = match arg.ty.as_ref() {
+                "std::ffi::c_void" => "void",
+                other => other,
+            };
+            match typedefs.insert(name.to_string(), ty.to_string()) {
+                Some(previous) if previous != ty => {
+                    return Err(format!(
+                        "IDL type `{}` is used for both `{}` and `{}`",
+                        name, previous, ty
+                    )
+                    .into());
+                }
+                _ => {}
+            }
+        }
+
+        Ok(typedefs
+            .into_iter()
+            .map(|(name, ty)| IdlTypedef { name, ty })
+            .collect())
+    }
+}
+
+impl IdlEnum
+{
+    fn from(e: &Enum) -> Self
+    {
+        Self {
+            name: e.name.to_string(),
+            v1_enum: e.v1_enum,
+            values: e
+                .values
+                .iter()
+                .map(|v| IdlEnumValue {
+                    name: v.name.to_string(),
+                    value: v.value,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl IdlStruct
+{
+    fn from(s: &Struct) -> Self
+    {
+        Self {
+            name: s.name.to_string(),
+            fields: s
+                .fields
+                .iter()
+                .map(|f| IdlStructField {
+                    name: f.name.to_string(),
+                    ty: format!("{}{}", f.ty, "*".repeat(f.indirection_level as usize)),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl IdlModule
+{
+    fn try_from(
+        module: &Module,
+        opts: &ModelOptions,
+        ctx: &LibraryContext,
+    ) -> Result<Self, GeneratorError>
+    {
+        // The entry points receive the same values as the raw interfaces.
+        let default_opts = TypeSystemOptions {
+            ts: TypeSystemName::Raw,
+            use_full_name: false,
+            hidden: false,
+        };
+        let ts_opts = opts
+            .type_systems
+            .iter()
+            .find(|ts_opts| ts_opts.ts == TypeSystemName::Raw)
+            .or_else(|| opts.type_systems.first())
+            .unwrap_or(&default_opts);
+
+        Ok(Self {
+            name: pascal_case(&module.name),
+            dll_name: module.dll_name.to_string(),
+            constants: module
+                .constants
+                .iter()
+                .map(|c| IdlConstant {
+                    name: c.name.to_string(),
+                    ty: c.ty.to_string(),
+                    value: c.value.to_string(),
+                })
+                .collect(),
+            functions: module
+                .functions
+                .iter()
+                .map(|f| {
+                    Ok(IdlEntryPoint {
+                        name: pascal_case(&f.name),
+                        entry: f.name.to_string(),
+                        ret_type: IdlArg::idl_type(&f.return_type, ts_opts, ctx),
+                        args: f
+                            .parameters
+                            .iter()
+                            .map(|arg| IdlArg::try_from(arg, &f.parameters, ts_opts, ctx))
+                            .collect::<Result<Vec<_>, _>>()?,
+                    })
+                })
+                .collect::<Result<Vec<_>, GeneratorError>>()?,
+        })
+    }
+}
+
+impl IdlInterface
+{
+    fn gather(
+        itf: &Interface,
+        opts: &ModelOptions,
+        ctx: &LibraryContext,
+    ) -> Result<Vec<Self>, GeneratorError>
+    {
+        Ok(opts
+            .type_systems
+            .iter()
+            .filter(|ts_opts| Self::is_described(itf, ts_opts.ts))
+            .map(
+                |ts_opts| match itf.variants.iter().find(|v| v.as_ref().ts == ts_opts.ts) {
+                    Some(v) => Some(IdlInterface::try_from(
+                        itf,
+                        v.as_ref(),
+                        ts_opts,
+                        opts.property_naming,
+                        ctx,
+                    )),
+                    None => None,
+                },
+            )
+            .filter_map(|i| i)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    fn try_from(
+        itf: &Interface,
+        itf_variant: &InterfaceVariant,
+        ts_opts: &TypeSystemOptions,
+        property_naming: PropertyNaming,
+        ctx: &LibraryContext,
+    ) -> Result<Self, GeneratorError>
+    {
+        let methods = itf_variant
+            .methods
+            .iter()
+            .map(|m| -> &Method { m.as_ref() })
+            .collect::<Vec<&Method>>();
+
+        // The dual interfaces resolve the DISPIDs at run time, which
+        // recognizes only the marked property accessors and reserves the
+        // DISPID 0 for the default member.
+        let (property_naming, first_dispid, base) = match itf.options.dual {
+            true => (PropertyNaming::None, 1, "IDispatch"),
+            false => (property_naming, 0, "IUnknown"),
+        };
+        let accessors = PropertyAccessor::resolve(&methods, property_naming, first_dispid);
+        let dispinterface = itf.options.dispinterface;
+        Ok(Self {
+            name: Self::final_name(&itf, ts_opts),
+            iid: format!("{:-X}", itf_variant.iid),
+            base: Some(base.to_string()).filter(|_| !dispinterface),
+            helpstring: helpstring(&itf.doc),
+            hidden: ts_opts.hidden,
+            dual: itf.options.dual
+                && !dispinterface
+                && itf_variant.ts == TypeSystemName::Automation,
+            dispinterface,
+            methods: methods
+                .iter()
+                .zip(&accessors)
+                .enumerate()
+                .map(|(i, (m, accessor))| {
+                    let method =
+                        IdlMethod::try_from(i + first_dispid, m, accessor.as_ref(), ts_opts, ctx)?;
+                    Ok(match dispinterface {
+                        true => method.into_dispatch_method(),
+                        false => method,
+                    })
+                })
+                .collect::<Result<Vec<_>, GeneratorError>>()?,
+        })
+    }
+
+    /// Checks whether the type system variant is described in the IDL.
+    ///
+    /// The dispinterfaces are called through `IDispatch` only, which uses the
+    /// automation types.
+    fn is_described(itf: &Interface, ts: TypeSystemName) -> bool
+    {
+        !itf.options.dispinterface || ts == TypeSystemName::Automation
+    }
+
+    pub fn final_name(itf: &Interface, opts: &TypeSystemOptions) -> String
+    {
+        let base_name = if itf.options.class_impl_interface {
+            Cow::from(format!("I{}", itf.name))
+        } else {
+            itf.name.clone()
+        };
+
+        Self::qualified_name(&base_name, opts)
+    }
+
+    /// Appends the type system suffix to the interface name when required.
+    ///
+    /// Interfaces from the imported type libraries were named using the same
+    /// rules so this applies to them as well.
+    pub fn qualified_name(base_name: &str, opts: &TypeSystemOptions) -> String
+    {
+        match opts.use_full_name {
+            true => format!("{}_{:?}", base_name, opts.ts),
+            false => base_name.to_string(),
+        }
+    }
+}
+
+impl IdlMethod
+{
+    fn try_from(
+        id: usize,
+        method: &Method,
+        accessor: Option<&PropertyAccessor>,
+        opts: &TypeSystemOptions,
+        ctx: &LibraryContext,
+    ) -> Result<Self, GeneratorError>
+    {
+        // Trailing `&[Variant]` parameters are passed as SAFEARRAY(VARIANT).
+        let attributes = MethodAttributes {
+            dispid: accessor.map(|a| a.dispid as i32),
+            property: accessor.map(|a| a.kind).unwrap_or_default(),
+            vararg: method
+                .parameters
+                .last()
+                .map(|arg| arg.direction == Direction::In && arg.ty == "SAFEARRAY(VARIANT)")
+                .unwrap_or(false),
+            newenum: method.options.newenum,
+            restricted: method.options.restricted,
+            hidden: method.options.hidden,
+        };
+
+        let mut attributes = attributes.render(id)?;
+        if let Some(helpstring) = helpstring(&method.doc) {
+            attributes.push_str(&format!(", helpstring(\"{}\")", helpstring));
+        }
+
+        // The accessors of a property are named after the property.
+        let name = match accessor {
+            Some(accessor) => pascal_case(&accessor.property),
+            None => pascal_case(&method.name),
+        };
+
+        Ok(Self {
+            name,
+            attributes,
+            ret_type: IdlArg::idl_type(&method.return_type, opts, ctx),
+            args: method
+                .parameters
+                .iter()
+                .map(|arg| IdlArg::try_from(arg, &method.parameters, opts, ctx))
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+}
+
+impl IdlMethod
+{
+    /// Converts the method into a `dispinterface` method.
+    ///
+    /// The dispinterface methods return the value in place of the HRESULT
+    /// as the failures are reported by `IDispatch::Invoke`.
+    fn into_dispatch_method(mut self) -> Self
+    {
+        let retval = match self.args.last() {
+            Some(arg) if arg.attributes.contains("retval") => self.args.pop(),
+            _ => None,
+        };
+        self.ret_type = match retval {
+            Some(arg) => arg.arg_type[..arg.arg_type.len() - 1].to_string(),
+            None if self.ret_type == "HRESULT" => "void".to_string(),
+            None => self.ret_type,
+        };
+        self
+    }
+}
+
+/// Property accessor resolved for an interface method.
+#[derive(Debug, Clone, PartialEq)]
+struct PropertyAccessor
+{
+    property: String,
+    kind: PropertyKind,
+
+    /// The DISPID shared by the accessors of the property.
+    dispid: usize,
+}
+
+impl PropertyAccessor
+{
+    /// Resolves the property accessors among the methods.
+    ///
+    /// The methods marked with `#[com_property]` are always accessors. The
+    /// other methods are accessors only if they form a getter and setter
+    /// pair under the naming convention.
+    ///
+    /// - `first_dispid` - DISPID of the first method.
+    fn resolve(
+        methods: &[&Method],
+        naming: PropertyNaming,
+        first_dispid: usize,
+    ) -> Vec<Option<PropertyAccessor>>
+    {
+        let mut accessors = methods
+            .iter()
+            .map(|m| match m.kind {
+                MethodKind::Method => None,
+                MethodKind::PropertyGet => {
+                    Some((strip_prefixes(&m.name, &["get_"]), PropertyKind::Get))
+                }
+                MethodKind::PropertyPut => Some((
+                    strip_prefixes(&m.name, &["set_", "put_"]),
+                    PropertyKind::Put,
+                )),
+            })
+            .collect::<Vec<_>>();
+
+        let is_getter = |m: &Method| {
+            m.kind == MethodKind::Method
+                && m.parameters
+                    .iter()
+                    .all(|p| p.direction == Direction::Retval)
+                && !(m.parameters.is_empty() && m.return_type.ty == "void")
+        };
+        let is_setter = |m: &Method| {
+            m.kind == MethodKind::Method
+                && m.parameters.len() == 1
+                && m.parameters[0].direction == Direction::In
+        };
+        for (setter_idx, setter) in methods.iter().enumerate() {
+            let property = match setter.name.strip_prefix("set_") {
+                Some(property) if is_setter(setter) => property,
+                _ => continue,
+            };
+            let getter_name = match naming {
+                PropertyNaming::None => continue,
+                PropertyNaming::GetSet => format!("get_{}", property),
+                PropertyNaming::Noun => property.to_string(),
+            };
+            let getter_idx = methods
+                .iter()
+                .position(|m| m.name == getter_name.as_str() && is_getter(m));
+            if let Some(getter_idx) = getter_idx {
+                accessors[getter_idx] = Some((property.to_string(), PropertyKind::Get));
+                accessors[setter_idx] = Some((property.to_string(), PropertyKind::Put));
+            }
+        }
+
+        // The accessors share the DISPID of the first accessor.
+        let mut dispids = HashMap::new();
+        for (idx, accessor) in accessors.iter().enumerate() {
+            if let Some((property, _)) = accessor {
+                dispids
+                    .entry(property.clone())
+                    .or_insert(first_dispid + idx);
+            }
+        }
+        accessors
+            .into_iter()
+            .map(|accessor| {
+                accessor.map(|(property, kind)| PropertyAccessor {
+                    dispid: dispids[&property],
+                    property,
+                    kind,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Removes the first matching prefix from the method name.
+fn strip_prefixes(name: &str, prefixes: &[&str]) -> String
+{
+    prefixes
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(name)
+        .to_string()
+}
+
+/// Property accessor kind of a method.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PropertyKind
+{
+    #[default]
+    None,
+    Get,
+    Put,
+    PutRef,
+}
+
+/// IDL attributes of an interface method.
+///
+/// The attributes are emitted in the order `id`, property accessor,
+/// `vararg`, `restricted`, `hidden`. The allowed combinations are:
+///
+/// - At most one property accessor, `propget`, `propput` or `propputref`.
+/// - `hidden` and `restricted` combine with anything.
+/// - An explicit `dispid` must not be negative as the negative values are
+///   reserved for the system DISPIDs.
+/// - `newenum` marks the method as the `DISPID_NEWENUM` enumerator. It
+///   cannot be combined with an explicit `dispid`, `propput` or `propputref`.
+/// - `vararg` marks the last `SAFEARRAY(VARIANT)` parameter as a variable
+///   argument list.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MethodAttributes
+{
+    pub dispid: Option<i32>,
+    pub property: PropertyKind,
+    pub newenum: bool,
+    pub vararg: bool,
+    pub restricted: bool,
+    pub hidden: bool,
+}
+
+impl MethodAttributes
+{
+    /// `DISPID_NEWENUM` from `oaidl.h`.
+    const DISPID_NEWENUM: i32 = -4;
+
+    /// Validates the attributes and renders them in the canonical order.
+    ///
+    /// - `idx` - Method index used as the id unless an explicit one is given.
+    pub fn render(&self, idx: usize) -> Result<String, GeneratorError>
+    {
+        let id = match (self.newenum, self.dispid) {
+            (true, Some(_)) => {
+                return Err(
+                    "Method attribute `newenum` cannot be combined with `dispid`"
+                        .to_string()
+                        .into(),
+                )
+            }
+            (true, None) => Self::DISPID_NEWENUM,
+            (false, Some(dispid)) if dispid < 0 => {
+                return Err(format!(
+                    "Method attribute `dispid({})` is invalid, negative DISPIDs are reserved",
+                    dispid
+                )
+                .into())
+            }
+            (false, Some(dispid)) => dispid,
+            (false, None) => idx as i32,
+        };
+
+        let mut attrs = vec![format!("id({})", id)];
+        match (self.property, self.newenum) {
+            (PropertyKind::Put, true) | (PropertyKind::PutRef, true) => {
+                return Err("Method attribute `newenum` is only valid for methods and \
+                            `propget` accessors"
+                    .to_string()
+                    .into())
+            }
+            (PropertyKind::None, _) => {}
+            (PropertyKind::Get, _) => attrs.push("propget".to_string()),
+            (PropertyKind::Put, _) => attrs.push("propput".to_string()),
+            (PropertyKind::PutRef, _) => attrs.push("propputref".to_string()),
+        }
+        if self.vararg {
+            attrs.push("vararg".to_string());
+        }
+        if self.restricted {
+            attrs.push("restricted".to_string());
+        }
+        if self.hidden {
+            attrs.push("hidden".to_string());
+        }
+
+        Ok(attrs.join(", "))
+    }
+}
+
+impl IdlArg
+{
+    fn try_from(
+        arg: &Arg,
+        params: &[Arg],
+        opts: &TypeSystemOptions,
+        ctx: &LibraryContext,
+    ) -> Result<Self, GeneratorError>
+    {
+        // The buffers are sized by the input counts. The output and in-out
+        // counts give the number of elements the callee wrote into the buffer.
+        let counts = params
+            .iter()
+            .filter(|p| p.length_of.as_deref() == Some(arg.name.as_ref()))
+            .collect::<Vec<_>>();
+        let size = counts.iter().find(|c| c.direction == Direction::In);
+        let length = counts
+            .iter()
+            .find(|c| c.direction == Direction::Out || c.direction == Direction::InOut);
+
+        let mut attrs = vec![];
+        match arg.direction {
+            Direction::In if length.is_some() => attrs.push("out".to_string()),
+            Direction::In => attrs.push("in".to_string()),
+            Direction::Out => attrs.push("out".to_string()),
+            Direction::InOut => {
+                attrs.push("in".to_string());
+                attrs.push("out".to_string());
+            }
+            Direction::Retval => {
+                attrs.push("out".to_string());
+                attrs.push("retval".to_string());
+            }
+            Direction::Return => {
+                return Err("Direction::Return is invalid direction for arguments"
+                    .to_string()
+                    .into());
+            }
+        }
+        match (size, length) {
+            (Some(size), Some(length)) => {
+                attrs.push(format!("size_is({})", size.name));
+                attrs.push(format!("length_is(*{})", length.name));
+            }
+            (Some(size), None) => attrs.push(format!("size_is({})", size.name)),
+
+            // The out buffers are allocated by the callee so the size applies
+            // to the pointer the parameter points to.
+            (None, Some(length)) if arg.direction == Direction::Out => {
+                attrs.push(format!("size_is(, *{})", length.name))
+            }
+            (None, Some(length)) => attrs.push(format!("size_is(*{})", length.name)),
+            (None, None) => {}
+        }
+
+        Ok(Self {
+            name: arg.name.to_string(),
+            arg_type: Self::idl_type(arg, opts, ctx),
+            attributes: attrs.join(", "),
+        })
+    }
+
+    fn idl_type(arg: &Arg, opts: &TypeSystemOptions, ctx: &LibraryContext) -> String
+    {
+        let base_name = match (&arg.idl_ty, ctx.itfs_by_name.get(arg.ty.as_ref())) {
+            // The overridden types are declared with a typedef.
+            (Some(idl_ty), _) => idl_ty.to_string(),
+            (None, Some(itf)) => IdlInterface::final_name(itf, opts),
+
+            // The interface arrays name the interface of the elements.
+            (None, None) if interface_array_element(&arg.ty).is_some() => {
+                let elem = interface_array_element(&arg.ty).unwrap();
+                let elem = match ctx.itfs_by_name.get(elem) {
+                    Some(itf) => IdlInterface::final_name(itf, opts),
+                    None => elem.to_string(),
+                };
+                format!("SAFEARRAY({}*)", elem)
+            }
+            (None, None) => arg.ty.to_string(),
+        };
+        let indirection = match arg.direction {
+            Direction::In | Direction::Return => arg.indirection_level,
+            Direction::Out | Direction::Retval | Direction::InOut => arg.indirection_level + 1,
+        };
+
+        let base_name = match base_name.as_ref() {
+            "std::ffi::c_void" => "void".to_string(),
+            other => other.to_string(),
+        };
+
+        format!("{}{}", base_name, "*".repeat(indirection as usize))
+    }
+}
+
+impl IdlClass
+{
+    fn from(cls: &CoClass, opts: &ModelOptions, ctx: &LibraryContext) -> Self
+    {
+        let interfaces = cls
+            .interfaces
+            .iter()
+            .flat_map(|itf_ref| {
+                let itf = ctx.itfs_by_ref[itf_ref.name.as_ref()];
+                let keyword = match itf.options.dispinterface {
+                    true => "dispinterface",
+                    false => "interface",
+                };
+                opts.type_systems
+                    .iter()
+                    .filter(|opt| IdlInterface::is_described(itf, opt.ts))
+                    .map(|opt| format!("{} {}", keyword, IdlInterface::final_name(itf, opt)))
+                    .collect::<Vec<_>>()
+            })
+            .enumerate()
+            .map(|(idx, itf)| match idx {
+                // The type library lists the default interface first.
+                0 => format!("[default] {}", itf),
+                _ => itf,
+            })
+            .collect();
+        IdlClass {
+            name: cls.name.to_string(),
+            clsid: format!("{:-X}", cls.clsid),
+            interfaces,
+            helpstring: helpstring(&cls.doc),
+            noncreatable: cls.options.no_class_factory,
+            appobject: cls.options.appobject,
+        }
+    }
+}
+
+/// Validates the library version for the `version` attribute.
+///
+/// The type library versions consist of the `major.minor` numbers.
+fn library_version(version: &str) -> Result<String, GeneratorError>
+{
+    let mut parts = version.split('.').map(str::parse::<u16>);
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(major)), Some(Ok(minor)), None) => Ok(format!("{}.{}", major, minor)),
+        _ => Err(format!(
+            "Invalid library version \"{}\", expected major.minor",
+            version
+        )
+        .into()),
+    }
+}
+
+/// Converts the documentation into the contents of a `helpstring` literal.
+///
+/// The lines are collapsed into a single line and the quotes and backslashes
+/// are escaped.
+fn helpstring(doc: &Option<Cow<'static, str>>) -> Option<String>
+{
+    doc.as_ref().map(|doc| {
+        doc.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+    })
+}
+
+/// Generates the manifest content.
+///
+/// - `out` - The writer to use for output.
+pub fn write(
+    lib: &intercom::typelib::TypeLib,
+    opts: &ModelOptions,
+    out: &mut dyn Write,
+) -> Result<(), GeneratorError>
+{
+    let mut reg = Handlebars::new();
+    reg.register_template_string("idl", include_str!("idl.hbs"))
+        .expect("Error in the built-in IDL template.");
+
+    let idl_model = IdlLibrary::try_from(lib, opts)?;
+
+    let rendered = reg
+        .render("idl", &idl_model)
+        .expect("Rendering a valid ComCrate to IDL failed");
+    write!(out, "{}", rendered)?;
+
+    Ok(())
+}
+
+/// Writes the GUIDs of the IDL as Rust constants.
+///
+/// The constants are named after the IDL items, such as `IID_IFoo_Automation`
+/// and `CLSID_Foo`, and are meant to be `include!`d so the runtime can verify
+/// it uses the same GUIDs as the IDL.
+///
+/// - `out` - The writer to use for output.
+pub fn write_guids(
+    lib: &intercom::typelib::TypeLib,
+    opts: &ModelOptions,
+    out: &mut dyn Write,
+) -> Result<(), GeneratorError>
+{
+    let idl_model = IdlLibrary::try_from(lib, opts)?;
+
+    writeln!(
+        out,
+        "// Generated by intercom from the {} IDL.",
+        idl_model.lib_name
+    )?;
+    write_guid_const(out, "LIBID", &idl_model.lib_name, &idl_model.lib_id)?;
+    for itf in &idl_model.interfaces {
+        write_guid_const(out, "IID", &itf.name, &itf.iid)?;
+    }
+    for cls in &idl_model.coclasses {
+        write_guid_const(out, "CLSID", &cls.name, &cls.clsid)?;
+    }
+
+    Ok(())
+}
+
+fn write_guid_const(
+    out: &mut dyn Write,
+    prefix: &str,
+    name: &str,
+    guid: &str,
+) -> Result<(), GeneratorError>
+{
+    let guid = intercom::GUID::parse(guid)?;
+    let data4 = guid
+        .data4
+        .iter()
+        .map(|b| format!("0x{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(", ");
+    writeln!(out)?;
+    writeln!(out, "#[allow(non_upper_case_globals)]")?;
+    writeln!(
+        out,
+        "pub const {}_{}: intercom::GUID = intercom::GUID {{ \
+         data1: 0x{:08X}, data2: 0x{:04X}, data3: 0x{:04X}, data4: [{}] }};",
+        prefix, name, guid.data1, guid.data2, guid.data3, data4
+    )?;
+    Ok(())
+}
+
+/// Writes the type library model the IDL is generated from.
+///
+/// Lists the interfaces, classes, enums and modules with their GUIDs and the
+/// method signatures using the types and directions before they are mapped
+/// to IDL. Meant for diagnosing unexpected IDL output.
+///
+/// - `out` - The writer to use for output.
+pub fn write_model(lib: &TypeLib, out: &mut dyn Write) -> Result<(), GeneratorError>
+{
+    writeln!(out, "library {} {{{:-X}}}", lib.name, lib.libid)?;
+    for t in &lib.types {
+        match t {
+            TypeInfo::Interface(itf) => {
+                for variant in &itf.variants {
+                    let variant = variant.as_ref();
+                    writeln!(
+                        out,
+                        "interface {} ({:?}) {{{:-X}}}",
+                        itf.name, variant.ts, variant.iid
+                    )?;
+                    for method in &variant.methods {
+                        writeln!(out, "    {}", model_signature(method.as_ref()))?;
+                    }
+                }
+            }
+            TypeInfo::Class(cls) => {
+                writeln!(out, "class {} {{{:-X}}}", cls.name, cls.clsid)?;
+                for itf in &cls.interfaces {
+                    writeln!(out, "    {}", itf.name)?;
+                }
+            }
+            TypeInfo::Enum(e) => {
+                writeln!(out, "enum {}", e.name)?;
+                for v in &e.values {
+                    writeln!(out, "    {} = {}", v.name, v.value)?;
+                }
+            }
+            TypeInfo::Struct(s) => {
+                writeln!(out, "struct {}", s.name)?;
+                for f in &s.fields {
+                    let stars = "*".repeat(f.indirection_level as usize);
+                    writeln!(out, "    {}: {}{}", f.name, f.ty, stars)?;
+                }
+            }
+            TypeInfo::Module(m) => {
+                writeln!(out, "module {} ({})", m.name, m.dll_name)?;
+                for c in &m.constants {
+                    writeln!(out, "    const {}: {} = {}", c.name, c.ty, c.value)?;
+                }
+                for f in &m.functions {
+                    writeln!(out, "    {}", model_signature(f.as_ref()))?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Formats the method as `name(direction name: type, ..) -> type`.
+fn model_signature(method: &Method) -> String
+{
+    let model_type = |arg: &Arg| {
+        let ty = format!("{}{}", arg.ty, "*".repeat(arg.indirection_level as usize));
+        match &arg.idl_ty {
+            Some(idl_ty) => format!("{} as {}", ty, idl_ty),
+            None => ty,
+        }
+    };
+    let args = method
+        .parameters
+        .iter()
+        .map(|arg| {
+            let direction = match arg.direction {
+                Direction::In => "in",
+                Direction::Out => "out",
+                Direction::InOut => "inout",
+                Direction::Retval => "retval",
+                Direction::Return => "return",
+            };
+            format!("{} {}: {}", direction, arg.name, model_type(arg))
+        })
+        .collect::<Vec<_>>();
+    format!(
+        "{}({}) -> {}",
+        method.name,
+        args.join(", "),
+        model_type(&method.return_type)
+    )
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    use intercom::attributes::{ComClassTypeInfo, ComInterfaceTypeInfo};
+    use intercom::typelib::InterfaceOptions;
+    use intercom::{ComBox, ComItf, ComResult, ExternInput, ExternOutput, ExternType, GUID};
+
+    const LIBID: &str = "2f6c8a4e-1b3d-4e5f-8a7b-9c0d1e2f3a4b";
+
+    /// Creates a version 1.0 library of the types.
+    fn test_lib(name: &'static str, types: Vec<TypeInfo>) -> TypeLib
+    {
+        TypeLib::__new(
+            name.into(),
+            GUID::parse(LIBID).unwrap(),
+            "1.0".into(),
+            vec![],
+            types,
+        )
+    }
+
+    /// Passes the library through the type library interfaces as the
+    /// intercom-build does with the compiled libraries.
+    fn round_trip(lib: TypeLib) -> TypeLib
+    {
+        let lib_rc =
+            intercom::ComRc::<dyn intercom::typelib::IIntercomTypeLib>::from(ComBox::new(lib));
+        TypeLib::from_comrc(&lib_rc).unwrap()
+    }
+
+    fn render(lib: &TypeLib, opts: &ModelOptions) -> Result<String, GeneratorError>
+    {
+        let mut out = vec![];
+        write(lib, opts, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    fn to_idl(lib: &TypeLib) -> String
+    {
+        render(lib, &ModelOptions::default()).unwrap()
+    }
+
+    /// Renders the types the way the compiled libraries describe them.
+    fn types_idl(types: Vec<TypeInfo>) -> String
+    {
+        to_idl(&round_trip(test_lib("test_lib", types)))
+    }
+
+    fn library_error(lib: &TypeLib) -> String
+    {
+        match render(lib, &ModelOptions::default()) {
+            Err(GeneratorError::LibraryError(msg)) => msg,
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    fn arg(name: &str, ty: &'static str, direction: Direction) -> Arg
+    {
+        Arg {
+            name: name.to_string().into(),
+            ty: ty.into(),
+            indirection_level: 0,
+            direction,
+            idl_ty: None,
+            length_of: None,
+        }
+    }
+
+    /// Creates an Automation interface with a single method for the types
+    /// the interface attributes are unable to describe.
+    fn interface(name: &'static str, method: &'static str, parameters: Vec<Arg>) -> TypeInfo
+    {
+        let method = Method {
+            name: method.into(),
+            return_type: arg("", "HRESULT", Direction::Return),
+            parameters,
+            doc: None,
+            kind: MethodKind::Method,
+            options: Default::default(),
+        };
+        TypeInfo::Interface(ComBox::new(Interface {
+            name: name.into(),
+            options: InterfaceOptions::default(),
+            variants: vec![ComBox::new(InterfaceVariant {
+                ts: TypeSystemName::Automation,
+                iid: GUID::parse("5b1d3e0c-7f4a-4b6e-9d2c-8a1f3e5b7c9d").unwrap(),
+                methods: vec![ComBox::new(method)],
+            })],
+            doc: None,
+        }))
+    }
+
+    #[intercom::com_interface]
+    trait IOther {}
+
+    #[intercom::com_interface]
+    trait IUser
+    {
+        fn use_other(
+            &self,
+            other: &ComItf<dyn IOther>,
+            token: &ComItf<dyn intercom::cancellation::ICancellationToken>,
+            operation: &ComItf<dyn intercom::operation::IOperation>,
+            agile: &ComItf<dyn intercom::interfaces::IAgileObject>,
+        ) -> ComResult<()>;
+    }
+
+    #[test]
+    fn imported_interfaces_are_resolved_from_the_imports()
+    {
+        // The libraries describe the interfaces of the intercom module.
+        let types = <dyn IUser>::gather_type_info()
+            .into_iter()
+            .chain(TypeInfo::__imported(<dyn IOther>::gather_type_info()))
+            .chain(intercom::__gather_module_types())
+            .collect();
+        let lib = TypeLib::__new(
+            "user_lib".into(),
+            GUID::parse(LIBID).unwrap(),
+            "1.0".into(),
+            vec!["other.tlb".into()],
+            types,
+        );
+
+        let idl = to_idl(&round_trip(lib));
+
+        assert!(idl.starts_with("import \"objidl.idl\";\n["));
+        assert!(idl.contains(r#"importlib("other.tlb");"#));
+        assert!(idl.contains(
+            "HRESULT UseOther([in] IOther_Automation* other, \
+             [in] ICancellationToken_Automation* token, \
+             [in] IOperation_Automation* operation, [in] IAgileObject* agile);"
+        ));
+
+        // The imported library defines the imported interfaces.
+        assert!(!idl.contains("interface IOther_Automation"));
+        assert!(idl.contains("interface ICancellationToken_Automation : IUnknown"));
+        assert!(idl.contains("interface IOperation_Automation : IUnknown"));
+    }
+
+    #[intercom::com_interface]
+    trait IColorSource
+    {
+        fn set_color(&self, #[com_arg(idl_type = "COLORREF")] color: u32, alpha: u32) -> u32;
+    }
+
+    #[test]
+    fn idl_type_override_is_typedefd()
+    {
+        let idl = types_idl(<dyn IColorSource>::gather_type_info());
+
+        assert!(idl.contains("typedef u32 COLORREF;"));
+        assert!(idl.contains("u32 SetColor([in] COLORREF color, [in] u32 alpha);"));
+    }
+
+    /// Creates a library with a method taking the `(ty, idl_ty)` parameters.
+    fn typedef_lib(params: &[(&'static str, &'static str)], mut types: Vec<TypeInfo>) -> TypeLib
+    {
+        let params = params
+            .iter()
+            .enumerate()
+            .map(|(idx, (ty, idl_ty))| Arg {
+                idl_ty: Some((*idl_ty).into()),
+                ..arg(&format!("p{}", idx), ty, Direction::In)
+            })
+            .collect();
+        types.push(interface("IPainter", "paint", params));
+        test_lib("painter_lib", types)
+    }
+
+    #[test]
+    fn typedef_follows_its_enum()
+    {
+        let color = Enum {
+            name: "Color".into(),
+            values: vec![intercom::typelib::EnumValue {
+                name: "Red".into(),
+                value: 1,
+            }],
+            v1_enum: false,
+        };
+        let lib = typedef_lib(
+            &[("Color", "Accent"), ("u32", "Alpha")],
+            vec![TypeInfo::Enum(ComBox::new(color))],
+        );
+
+        let idl = to_idl(&lib);
+
+        // The independent typedef stays ahead of the enums while the typedef
+        // of the enum must follow the enum.
+        let alpha = idl.find("typedef u32 Alpha;").unwrap();
+        let color = idl.find("typedef enum Color").unwrap();
+        let accent = idl.find("typedef Color Accent;").unwrap();
+        assert!(alpha < color);
+        assert!(color < accent);
+    }
+
+    #[test]
+    fn cyclic_typedefs_are_reported()
+    {
+        let lib = typedef_lib(&[("Brush", "Pen"), ("Pen", "Brush")], vec![]);
+
+        assert_eq!(
+            library_error(&lib),
+            "Cyclic type dependency: Brush -> Pen -> Brush"
+        );
+    }
+
+    #[intercom::com_interface]
+    trait IPrimitives
+    {
+        fn toggle(&self, on: bool) -> bool;
+        fn scale(&self, x: f64) -> f64;
+        fn scale_single(&self, x: f32) -> f32;
+        fn get_offset(&self) -> ComResult<u64>;
+        fn seek(&self, delta: i64) -> ComResult<i64>;
+        fn list(&self) -> ComResult<Vec<String>>;
+        fn append(&self, #[com_arg(in_out)] text: &mut String) -> ComResult<()>;
+    }
+
+    #[test]
+    fn primitives_use_midl_types()
+    {
+        let idl = to_idl(&test_lib(
+            "primitives_lib",
+            <dyn IPrimitives>::gather_type_info(),
+        ));
+
+        assert!(idl.starts_with("["));
+        assert!(idl.contains("VARIANT_BOOL Toggle([in] VARIANT_BOOL on);"));
+        assert!(idl.contains("bool Toggle([in] bool on);"));
+
+        assert!(idl.contains("typedef float f32;"));
+        assert!(idl.contains("typedef double f64;"));
+        assert!(idl.contains("f64 Scale([in] f64 x);"));
+        assert!(idl.contains("f32 ScaleSingle([in] f32 x);"));
+
+        // The same MIDL spelling as the int32 and uint32 typedefs.
+        assert!(idl.contains("typedef int32 i32;"));
+        assert!(idl.contains("typedef int64 i64;"));
+        assert!(idl.contains("typedef uint64 u64;"));
+        assert!(idl.contains("HRESULT GetOffset([out, retval] u64* __out);"));
+        assert!(idl.contains("HRESULT Seek([in] i64 delta, [out, retval] i64* __out);"));
+
+        assert!(idl.contains("HRESULT List([out, retval] SAFEARRAY(BSTR)* __out);"));
+        assert!(idl.contains("HRESULT Append([in, out] BSTR* text);"));
+    }
+
+    #[intercom::com_interface]
+    trait IBuffers
+    {
+        unsafe fn read(
+            &self,
+            buffer: *mut u8,
+            #[com_arg(length_of = "buffer")] capacity: u32,
+            #[com_arg(length_of = "buffer")] out_count: &mut u32,
+        ) -> ComResult<()>;
+
+        #[com_return(ptr_and_len)]
+        fn read_all(&self) -> ComResult<Vec<u8>>;
+
+        fn decode(&self, input: &[u8], output: &mut [u8]) -> ComResult<u32>;
+    }
+
+    #[test]
+    fn buffers_are_sized()
+    {
+        let idl = types_idl(<dyn IBuffers>::gather_type_info());
+
+        assert!(idl.contains(
+            "HRESULT Read([out, size_is(capacity), length_is(*out_count)] u8* buffer, \
+             [in] u32 capacity, [out] u32* out_count);"
+        ));
+        assert!(idl.contains(
+            "HRESULT ReadAll([out, size_is(, *__out_len)] u8** __out, [out] u32* __out_len);"
+        ));
+        assert!(idl.contains("HRESULT ReadAll([out, retval] SAFEARRAY(u8)* __out);"));
+        assert!(idl.contains(
+            "HRESULT Decode([in, size_is(input_count)] u8* input, [in] u32 input_count, \
+             [out, size_is(output_count)] u8* output, [in] u32 output_count, \
+             [out, retval] u32* __out);"
+        ));
+    }
+
+    #[intercom::com_interface]
+    trait ILabel
+    {
+        fn text(&self) -> ComResult<String>;
+        fn set_text(&self, text: String) -> ComResult<()>;
+        fn get_width(&self) -> ComResult<u32>;
+        fn set_width(&self, width: u32) -> ComResult<()>;
+        fn set_font(&self, font: String) -> ComResult<()>;
+    }
+
+    #[test]
+    fn property_pairs_follow_naming()
+    {
+        let lib = test_lib("label_lib", <dyn ILabel>::gather_type_info());
+        let render = |naming: PropertyNaming| {
+            let mut opts = ModelOptions::default();
+            opts.property_naming = naming;
+            render(&lib, &opts).unwrap()
+        };
+
+        let idl = render(PropertyNaming::None);
+        assert!(!idl.contains("propget"));
+        assert!(!idl.contains("propput"));
+
+        let idl = render(PropertyNaming::Noun);
+        assert!(idl.contains("[id(0), propget]\n        HRESULT Text([out, retval] BSTR* __out);"));
+        assert!(idl.contains("[id(0), propput]\n        HRESULT Text([in] BSTR text);"));
+        assert!(idl.contains("[id(2)]\n        HRESULT GetWidth("));
+        assert!(idl.contains("[id(3)]\n        HRESULT SetWidth("));
+        assert!(idl.contains("[id(4)]\n        HRESULT SetFont("));
+
+        let idl = render(PropertyNaming::GetSet);
+        assert!(idl.contains("[id(0)]\n        HRESULT Text("));
+        assert!(idl.contains("[id(2), propget]\n        HRESULT Width([out, retval] u32* __out);"));
+        assert!(idl.contains("[id(2), propput]\n        HRESULT Width([in] u32 width);"));
+        assert!(idl.contains("[id(4)]\n        HRESULT SetFont("));
+    }
+
+    #[intercom::com_interface]
+    trait IBadge
+    {
+        #[com_property(get)]
+        fn caption(&self) -> ComResult<String>;
+        #[com_property(put)]
+        fn put_caption(&self, caption: String) -> ComResult<()>;
+        #[com_property(get)]
+        fn get_count(&self) -> ComResult<u32>;
+        #[com_method(newenum)]
+        fn tracks(&self) -> ComResult<intercom::ComRc<dyn intercom::IUnknown>>;
+        #[com_method(hidden, restricted)]
+        fn reset(&self) -> ComResult<()>;
+    }
+
+    #[test]
+    fn method_attributes_are_described()
+    {
+        let idl = types_idl(<dyn IBadge>::gather_type_info());
+
+        assert!(idl.contains("[id(0), propget]\n        HRESULT Caption("));
+        assert!(idl.contains("[id(0), propput]\n        HRESULT Caption([in] BSTR caption);"));
+        assert!(idl.contains("[id(2), propget]\n        HRESULT Count("));
+        assert!(idl.contains("[id(-4)]\n        HRESULT Tracks("));
+        assert!(idl.contains("[id(4), restricted, hidden]\n        HRESULT Reset("));
+    }
+
+    #[intercom::com_interface(dual)]
+    trait IScore
+    {
+        fn reset(&self) -> ComResult<()>;
+        #[com_property(get)]
+        fn get_points(&self) -> ComResult<u32>;
+        #[com_property(put)]
+        fn set_points(&self, points: u32) -> ComResult<()>;
+    }
+
+    #[test]
+    fn dual_interfaces_derive_from_idispatch()
+    {
+        let idl = types_idl(<dyn IScore>::gather_type_info());
+
+        let end = idl.find("interface IScore_Automation :").unwrap();
+        let start = idl[..end].rfind('[').unwrap();
+        assert!(idl[start..end].contains("dual,\n        oleautomation,"));
+        assert!(idl.contains("interface IScore_Automation : IDispatch"));
+        assert!(idl.contains("interface IScore_Raw : IDispatch"));
+
+        // The DISPID 0 is left for the default member.
+        assert!(idl.contains("[id(1)]\n        HRESULT Reset("));
+        assert!(idl.contains("[id(2), propget]\n        HRESULT Points("));
+        assert!(idl.contains("[id(2), propput]\n        HRESULT Points([in] u32 points);"));
+    }
+
+    #[intercom::com_interface(dispinterface)]
+    trait IScript
+    {
+        fn run(&self, code: String) -> ComResult<i32>;
+        #[com_property(get)]
+        fn get_name(&self) -> ComResult<String>;
+        #[com_property(put)]
+        fn set_name(&self, name: String) -> ComResult<()>;
+    }
+
+    #[intercom::com_class(clsid = "3d5f7b9a-1c2e-4f6a-8b0d-2e4f6a8c0e1a", IScript)]
+    #[derive(Default)]
+    pub struct Script;
+
+    impl IScript for Script
+    {
+        fn run(&self, _code: String) -> ComResult<i32>
+        {
+            Ok(0)
+        }
+
+        fn get_name(&self) -> ComResult<String>
+        {
+            Ok("script".into())
+        }
+
+        fn set_name(&self, _name: String) -> ComResult<()>
+        {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn dispinterfaces_have_no_vtable()
+    {
+        let idl = to_idl(&test_lib("script_lib", Script::gather_type_info()));
+
+        assert!(idl.contains("    dispinterface IScript_Automation;\n"));
+        assert!(idl.contains(
+            "    dispinterface IScript_Automation\n    {\n    properties:\n    methods:"
+        ));
+        assert!(idl.contains("[id(1)]\n        i32 Run([in] BSTR code);"));
+        assert!(idl.contains("[id(2), propget]\n        BSTR Name();"));
+        assert!(idl.contains("[id(2), propput]\n        void Name([in] BSTR name);"));
+        assert!(idl.contains("        [default] dispinterface IScript_Automation;\n    }"));
+
+        // The clients see only the IDispatch.
+        assert!(!idl.contains("interface IScript_Automation :"));
+        assert!(!idl.contains("IScript_Raw"));
+    }
+
+    #[test]
+    fn secondary_variants_are_hidden()
+    {
+        let lib = test_lib("primitives_lib", <dyn IPrimitives>::gather_type_info());
+        let attributes = |ts: Option<TypeSystemName>, name: &str| {
+            let opts = ts.map_or_else(ModelOptions::default, ModelOptions::with_primary);
+            let idl = render(&lib, &opts).unwrap();
+            let end = idl.find(&format!("interface {} :", name)).unwrap();
+            let start = idl[..end].rfind('[').unwrap();
+            idl[start..end].to_string()
+        };
+
+        let automation = Some(TypeSystemName::Automation);
+        assert!(!attributes(automation, "IPrimitives_Automation").contains("hidden"));
+        assert!(attributes(automation, "IPrimitives_Raw").contains("hidden,"));
+
+        let raw = Some(TypeSystemName::Raw);
+        assert!(attributes(raw, "IPrimitives_Automation").contains("hidden,"));
+        assert!(!attributes(raw, "IPrimitives_Raw").contains("hidden"));
+
+        assert!(!to_idl(&lib).contains("hidden"));
+    }
+
+    #[derive(Clone, Copy, ExternType, ExternInput, ExternOutput)]
+    #[repr(C)]
+    enum Color
+    {
+        Red = 1,
+        Blue = 5,
+    }
+
+    #[derive(Clone, Copy, ExternType, ExternInput, ExternOutput)]
+    #[repr(u8)]
+    enum Shade
+    {
+        Light = 1,
+        Dark = 2,
+    }
+
+    #[derive(Clone, Copy, ExternType, ExternInput, ExternOutput)]
+    #[repr(i16)]
+    #[v1_enum]
+    enum Hue
+    {
+        Warm = 1,
+        Cool = 2,
+    }
+
+    #[intercom::com_interface]
+    trait IPalette
+    {
+        fn favorite(&self) -> ComResult<Color>;
+        fn complement(&self, color: Color) -> ComResult<Color>;
+        fn mix(&self, shade: Shade, hue: Hue) -> ComResult<()>;
+        fn move_to(&self, point: (i32, i32)) -> ComResult<()>;
+    }
+
+    #[test]
+    fn enums_and_structs_are_named()
+    {
+        let idl = types_idl(<dyn IPalette>::gather_type_info());
+
+        assert!(idl.contains(
+            "    typedef [v1_enum] enum Color
+    {
+        Red = 1,
+        Blue = 5
+    } Color;"
+        ));
+        assert!(!idl.contains("typedef i32 Color;"));
+        assert!(idl.contains("HRESULT Favorite([out, retval] Color* __out);"));
+        assert!(idl.contains("HRESULT Complement([in] Color color, [out, retval] Color* __out);"));
+
+        // Only the enums wider than 16 bits need the v1_enum.
+        assert!(idl.contains("    typedef [v1_enum] enum Hue\n"));
+        assert!(idl.contains("    typedef enum Shade\n"));
+
+        assert!(idl.contains(
+            "    typedef struct Tuple_i32_i32
+    {
+        i32 item1;
+        i32 item2;
+    } Tuple_i32_i32;"
+        ));
+        assert_eq!(idl.matches("typedef struct Tuple_i32_i32").count(), 1);
+        assert!(idl.contains("HRESULT MoveTo([in] Tuple_i32_i32 point);"));
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, ExternType, ExternInput, ExternOutput)]
+    #[repr(transparent)]
+    #[newtype]
+    struct UserId(u64);
+
+    #[intercom::com_interface]
+    trait IUsers
+    {
+        fn current(&self) -> ComResult<UserId>;
+        fn next(&self, id: UserId) -> UserId;
+    }
+
+    #[intercom::com_class(clsid = None, IUsers)]
+    struct Users;
+
+    impl IUsers for Users
+    {
+        fn current(&self) -> ComResult<UserId>
+        {
+            Ok(UserId(42))
+        }
+
+        fn next(&self, id: UserId) -> UserId
+        {
+            UserId(id.0 + 1)
+        }
+    }
+
+    #[test]
+    fn newtype_is_typedefd()
+    {
+        let users = intercom::ComRc::<dyn IUsers>::from(ComBox::new(Users));
+        assert_eq!(users.current().unwrap(), UserId(42));
+        assert_eq!(users.next(UserId(7)), UserId(8));
+
+        let idl = types_idl(<dyn IUsers>::gather_type_info());
+
+        assert!(idl.contains("typedef u64 UserId;"));
+        assert!(idl.contains("HRESULT Current([out, retval] UserId* __out);"));
+        assert!(idl.contains("UserId Next([in] UserId id);"));
+    }
+
+    #[test]
+    fn systemtime_imports_oaidl()
+    {
+        let time = arg("time", "SYSTEMTIME", Direction::In);
+        let idl = to_idl(&test_lib(
+            "scheduler_lib",
+            vec![interface("IScheduler", "schedule", vec![time])],
+        ));
+
+        assert!(idl.starts_with("import \"oaidl.idl\";\n["));
+        assert!(idl.contains("HRESULT Schedule([in] SYSTEMTIME time);"));
+    }
+
+    #[intercom::com_interface]
+    trait IListener
+    {
+        fn notify(&self) -> ComResult<()>;
+    }
+
+    #[intercom::com_interface]
+    trait IListenerRegistry
+    {
+        fn register(&self, listeners: &[ComItf<dyn IListener>]) -> ComResult<()>;
+    }
+
+    #[test]
+    fn interface_slice_is_safearray()
+    {
+        let mut types = <dyn IListener>::gather_type_info();
+        types.extend(<dyn IListenerRegistry>::gather_type_info());
+
+        let idl = to_idl(&test_lib("listener_lib", types));
+
+        assert!(idl.contains("HRESULT Register([in] SAFEARRAY(IListener_Automation*) listeners);"));
+        assert!(idl.contains(
+            "HRESULT Register([in] IListener_Raw** listeners, [in] u32 listeners_count);"
+        ));
+    }
+
+    #[intercom::com_dll_module(dllname = "exports.dll")]
+    mod exports
+    {
+        /// Adds two numbers.
+        pub extern "system" fn exports_add_numbers(a: i32, b: i32) -> i32
+        {
+            a + b
+        }
+
+        pub const MAX_COUNT: u32 = 10;
+    }
+
+    #[test]
+    fn dll_module_is_described()
+    {
+        let idl = types_idl(exports::__gather_module_types());
+
+        assert!(idl.contains(
+            r#"    [
+        dllname("exports.dll")
+    ]
+    module Exports
+    {
+        const u32 MAX_COUNT = 10;
+        [entry("exports_add_numbers")] i32 __stdcall ExportsAddNumbers([in] i32 a, [in] i32 b);
+    }"#
+        ));
+        assert_eq!(exports::exports_add_numbers(1, 2), 3);
+        assert_eq!(exports::MAX_COUNT, 10);
+    }
+
+    #[intercom::com_class(clsid = "8d3e9c1a-5b7f-4a2e-9c6d-1e0f2a3b4c5d", no_class_factory, Self)]
+    pub struct InternalClass;
+
+    #[intercom::com_interface]
+    impl InternalClass
+    {
+        fn get_value(&self) -> u32
+        {
+            1
+        }
+    }
+
+    #[test]
+    fn no_class_factory_is_noncreatable()
+    {
+        let idl = types_idl(<InternalClass as ComClassTypeInfo>::gather_type_info());
+
+        assert!(idl.contains(
+            r#"    [
+        uuid( 8D3E9C1A-5B7F-4A2E-9C6D-1E0F2A3B4C5D ),
+        noncreatable
+    ]
+    coclass InternalClass"#
+        ));
+    }
+
+    #[intercom::com_interface]
+    trait IApplication
+    {
+        fn get_version(&self) -> u32;
+    }
+
+    #[intercom::com_class(
+        clsid = "5a1c3e7b-9d2f-4b6a-8e0c-7f1a2b3c4d5e",
+        appobject,
+        IApplication
+    )]
+    #[derive(Default)]
+    pub struct Application;
+
+    #[intercom::com_class(
+        clsid = "6b2d4f8c-0e3a-4c7b-9f1d-8a2b3c4d5e6f",
+        appobject,
+        IApplication
+    )]
+    #[derive(Default)]
+    pub struct OtherApplication;
+
+    impl IApplication for Application
+    {
+        fn get_version(&self) -> u32
+        {
+            1
+        }
+    }
+
+    impl IApplication for OtherApplication
+    {
+        fn get_version(&self) -> u32
+        {
+            2
+        }
+    }
+
+    #[test]
+    fn appobject_is_marked()
+    {
+        let idl = to_idl(&test_lib("app_lib", Application::gather_type_info()));
+
+        assert!(idl.contains(
+            r#"    [
+        uuid( 5A1C3E7B-9D2F-4B6A-8E0C-7F1A2B3C4D5E ),
+        appobject
+    ]
+    coclass Application"#
+        ));
+
+        let mut types = Application::gather_type_info();
+        types.extend(OtherApplication::gather_type_info());
+        assert_eq!(
+            library_error(&test_lib("app_lib", types)),
+            "Multiple appobject classes: Application, OtherApplication"
+        );
+    }
+
+    #[intercom::com_interface]
+    trait IPrimary
+    {
+        fn primary(&self) -> u32;
+    }
+
+    #[intercom::com_class(
+        clsid = "7c3e5a9b-2d4f-4a6c-8e1b-3f5a7c9e1b2d",
+        IPrimary,
+        IApplication,
+        default_interface = IApplication
+    )]
+    #[derive(Default)]
+    pub struct Pair;
+
+    impl IPrimary for Pair
+    {
+        fn primary(&self) -> u32
+        {
+            1
+        }
+    }
+
+    impl IApplication for Pair
+    {
+        fn get_version(&self) -> u32
+        {
+            3
+        }
+    }
+
+    #[test]
+    fn coclass_lists_default_interface_first()
+    {
+        let idl = to_idl(&test_lib("pair_lib", Pair::gather_type_info()));
+
+        assert!(idl.contains(
+            r#"    coclass Pair
+    {
+    
+        [default] interface IApplication_Automation;
+        interface IApplication_Raw;
+        interface IPrimary_Automation;
+        interface IPrimary_Raw;
+    }"#
+        ));
+    }
+
+    #[test]
+    fn library_version_is_described()
+    {
+        let lib = TypeLib::__new(
+            "versioned_lib".into(),
+            GUID::parse(LIBID).unwrap(),
+            "2.13".into(),
+            vec![],
+            <dyn IPrimary>::gather_type_info(),
+        );
+
+        assert!(to_idl(&lib).contains(
+            r#"[
+    uuid( 2F6C8A4E-1B3D-4E5F-8A7B-9C0D1E2F3A4B ),
+    version( 2.13 )
+]
+library VersionedLib"#
+        ));
+    }
+
+    #[test]
+    fn invalid_library_version_is_reported()
+    {
+        for version in &["", "1", "1.2.3", "1.x", "70000.0"] {
+            let lib = TypeLib::__new(
+                "versioned_lib".into(),
+                GUID::zero_guid(),
+                version.to_string().into(),
+                vec![],
+                vec![],
+            );
+
+            assert_eq!(
+                library_error(&lib),
+                format!(
+                    "Invalid library version \"{}\", expected major.minor",
+                    version
+                )
+            );
+        }
+    }
+
+    /// Stores the "notes"
+    /// of a user.
+    #[intercom::com_interface]
+    trait INotes
+    {
+        /// Path of the notes, such as `C:\notes`.
+        fn get_path(&self) -> u32;
+
+        fn clear(&self);
+    }
+
+    /// Notes of the current user.
+    #[intercom::com_class(clsid = "7c3e5a9d-1f4b-4d8c-a0e2-9b3c4d5e6f70", INotes)]
+    #[derive(Default)]
+    pub struct Notes;
+
+    impl INotes for Notes
+    {
+        fn get_path(&self) -> u32
+        {
+            0
+        }
+
+        fn clear(&self) {}
+    }
+
+    #[test]
+    fn doc_comments_are_helpstrings()
+    {
+        let idl = types_idl(Notes::gather_type_info());
+
+        assert!(idl.contains(
+            r#"
+        helpstring("Stores the \"notes\" of a user."),
+        nonextensible,
+        pointer_default(unique)
+    ]
+    interface INotes_Automation : IUnknown"#
+        ));
+        assert!(idl.contains(
+            r#"[id(0), helpstring("Path of the notes, such as `C:\\notes`.")]
+        u32 GetPath();"#
+        ));
+        assert!(idl.contains("[id(1)]\n        void Clear();"));
+        assert!(idl.contains(
+            r#"    [
+        uuid( 7C3E5A9D-1F4B-4D8C-A0E2-9B3C4D5E6F70 ),
+        helpstring("Notes of the current user.")
+    ]
+    coclass Notes"#
+        ));
+    }
+
+    #[test]
+    fn guid_constants_match_idl()
+    {
+        let lib = test_lib("notes_lib", Notes::gather_type_info());
+
+        let idl = to_idl(&lib);
+
+        let mut out = vec![];
+        write_guids(&lib, &ModelOptions::default(), &mut out).unwrap();
+        let guids = String::from_utf8(out).unwrap();
+
+        let constants = guids
+            .lines()
+            .filter_map(|line| line.strip_prefix("pub const "))
+            .map(|line| {
+                let name = &line[..line.find(':').unwrap()];
+                let values = line[line.find('{').unwrap()..]
+                    .split(|c: char| !c.is_ascii_alphanumeric())
+                    .filter_map(|v| v.strip_prefix("0x"))
+                    .map(|v| u32::from_str_radix(v, 16).unwrap())
+                    .collect::<Vec<_>>();
+                let mut data4 = [0u8; 8];
+                for (d, v) in data4.iter_mut().zip(&values[3..]) {
+                    *d = *v as u8;
+                }
+                let guid = GUID {
+                    data1: values[0],
+                    data2: values[1] as u16,
+                    data3: values[2] as u16,
+                    data4,
+                };
+                (name.to_string(), guid)
+            })
+            .collect::<Vec<_>>();
+
+        let names = constants
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "LIBID_NotesLib",
+                "IID_INotes_Automation",
+                "IID_INotes_Raw",
+                "CLSID_Notes"
+            ]
+        );
+
+        // Each GUID is the uuid of the IDL item the constant is named after.
+        for (name, guid) in &constants {
+            let item = &name[name.find('_').unwrap() + 1..];
+            let uuid = idl.find(&format!("uuid( {:-X} )", guid)).unwrap();
+            let header = idl[uuid..]
+                .lines()
+                .map(str::trim_start)
+                .find(|line| {
+                    line.starts_with("library ")
+                        || line.starts_with("interface ")
+                        || line.starts_with("coclass ")
+                })
+                .unwrap();
+            assert_eq!(header.split_whitespace().nth(1), Some(item), "{}", name);
+        }
+        assert_eq!(constants[0].1, GUID::parse(LIBID).unwrap());
+        assert_eq!(
+            constants[3].1,
+            GUID::parse("7c3e5a9d-1f4b-4d8c-a0e2-9b3c4d5e6f70").unwrap()
+        );
+    }
+
+    #[intercom::com_interface(
+        com_iid = "6E1A50B2-3C4D-4E5F-8A9B-0C1D2E3F4A5B",
+        raw_iid = "6E1A50B2-3C4D-4E5F-8A9B-0C1D2E3F4A5C"
+    )]
+    trait IConditional
+    {
+        fn always(&self) -> u32;
+
+        #[cfg(any())]
+        fn never(&self) -> u32;
+
+        #[cfg(test)]
+        fn enabled(&self) -> u32;
+    }
+
+    #[intercom::com_class(clsid = None, IConditional)]
+    struct Conditional;
+
+    impl IConditional for Conditional
+    {
+        fn always(&self) -> u32
+        {
+            1
+        }
+
+        #[cfg(any())]
+        fn never(&self) -> u32
+        {
+            2
+        }
+
+        #[cfg(test)]
+        fn enabled(&self) -> u32
+        {
+            3
+        }
+    }
+
+    #[test]
+    fn excluded_methods_are_left_out()
+    {
+        let idl = to_idl(&test_lib("lib", <dyn IConditional>::gather_type_info()));
+
+        assert!(idl.contains("[id(0)]\n        u32 Always();"));
+        assert!(idl.contains("[id(1)]\n        u32 Enabled();"));
+        assert!(!idl.contains("Never"));
+
+        let rc = intercom::ComRc::<dyn IConditional>::from(ComBox::new(Conditional));
+        assert_eq!(rc.enabled(), 3);
+    }
+
+    #[test]
+    fn method_attributes_default_to_index()
+    {
+        assert_eq!(MethodAttributes::default().render(3).unwrap(), "id(3)");
+    }
+
+    #[test]
+    fn method_attributes_canonical_order()
+    {
+        let attrs = MethodAttributes {
+            dispid: Some(10),
+            property: PropertyKind::Get,
+            hidden: true,
+            restricted: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            attrs.render(0).unwrap(),
+            "id(10), propget, restricted, hidden"
+        );
+
+        let attrs = MethodAttributes {
+            property: PropertyKind::PutRef,
+            hidden: true,
+            ..Default::default()
+        };
+        assert_eq!(attrs.render(2).unwrap(), "id(2), propputref, hidden");
+
+        let attrs = MethodAttributes {
+            property: PropertyKind::Get,
+            newenum: true,
+            restricted: true,
+            ..Default::default()
+        };
+        assert_eq!(attrs.render(1).unwrap(), "id(-4), propget, restricted");
+
+        let attrs = MethodAttributes {
+            vararg: true,
+            hidden: true,
+            ..Default::default()
+        };
+        assert_eq!(attrs.render(4).unwrap(), "id(4), vararg, hidden");
+    }
+
+    #[test]
+    fn method_attributes_invalid_combinations()
+    {
+        let invalid = [
+            MethodAttributes {
+                property: PropertyKind::Put,
+                newenum: true,
+                ..Default::default()
+            },
+            MethodAttributes {
+                dispid: Some(5),
+                newenum: true,
+                ..Default::default()
+            },
+            MethodAttributes {
+                dispid: Some(-1),
+                ..Default::default()
+            },
+        ];
+        for attrs in &invalid {
+            assert!(attrs.render(0).is_err());
+        }
+    }
+}
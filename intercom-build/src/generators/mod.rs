@@ -36,12 +36,74 @@ impl From<String> for GeneratorError
 pub struct ModelOptions
 {
     pub type_systems: Vec<TypeSystemOptions>,
+
+    /// Naming convention of the methods treated as property accessors in
+    /// addition to the ones marked with `#[com_property]`.
+    pub property_naming: PropertyNaming,
+}
+
+/// Naming conventions for recognizing property accessor pairs.
+///
+/// A getter is recognized only if a setter for the same property exists. The
+/// getters take no parameters and the setters take the value as their only
+/// parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PropertyNaming
+{
+    /// Only the methods marked with `#[com_property]` are property accessors.
+    None,
+
+    /// `get_name` and `set_name` access the property `Name`.
+    GetSet,
+
+    /// `name` and `set_name` access the property `Name`.
+    Noun,
+}
+
+impl Default for ModelOptions
+{
+    /// Includes both type systems using their full names.
+    fn default() -> Self
+    {
+        ModelOptions {
+            type_systems: vec![
+                TypeSystemOptions {
+                    ts: TypeSystemName::Automation,
+                    use_full_name: true,
+                    hidden: false,
+                },
+                TypeSystemOptions {
+                    ts: TypeSystemName::Raw,
+                    use_full_name: true,
+                    hidden: false,
+                },
+            ],
+            property_naming: PropertyNaming::None,
+        }
+    }
+}
+
+impl ModelOptions
+{
+    /// Includes both type systems with the interfaces of the type systems
+    /// other than the `primary` one hidden from the object browsers.
+    pub fn with_primary(primary: TypeSystemName) -> Self
+    {
+        let mut opts = Self::default();
+        for ts_opts in &mut opts.type_systems {
+            ts_opts.hidden = ts_opts.ts != primary;
+        }
+        opts
+    }
 }
 
 pub struct TypeSystemOptions
 {
     pub ts: TypeSystemName,
     pub use_full_name: bool,
+
+    /// Marks the interfaces of the type system `[hidden]` in the IDL.
+    pub hidden: bool,
 }
 
 pub struct LibraryContext<'a>
@@ -85,6 +147,12 @@ impl<'a> LibraryContext<'a>
     }
 }
 
+/// Gets the element interface of a `SAFEARRAY(IFoo*)` type name.
+pub fn interface_array_element(ty: &str) -> Option<&str>
+{
+    ty.strip_prefix("SAFEARRAY(")?.strip_suffix("*)")
+}
+
 /// Convert the Rust identifier from `snake_case` to `PascalCase`
 pub fn pascal_case<T: AsRef<str>>(input: T) -> String
 {
@@ -119,4 +187,10 @@ pub fn pascal_case<T: AsRef<str>>(input: T) -> String
 }
 
 pub mod cpp;
+pub mod cpp_wrappers;
+pub mod def;
 pub mod idl;
+pub mod rgs;
+
+#[cfg(feature = "experimental-winmd")]
+pub mod winmd;
@@ -0,0 +1,153 @@
+//! Enables the generation of the registrar scripts, `.rgs`.
+//!
+//! The script describes the same registry keys `DllRegisterServer` writes
+//! for the classes, in the format understood by the ATL registrar. The
+//! `%MODULE%` replacement stands for the path of the library. The type
+//! library is registered separately by the registrar and is not part of the
+//! script.
+
+use std::io::Write;
+
+use super::{pascal_case, GeneratorError};
+
+use handlebars::Handlebars;
+use serde_derive::Serialize;
+
+use intercom::typelib::{CoClass, TypeInfo, TypeLib};
+
+#[derive(Debug, Serialize)]
+struct RgsScript
+{
+    pub libid: String,
+    pub classes: Vec<RgsClass>,
+}
+
+#[derive(Debug, Serialize)]
+struct RgsClass
+{
+    pub clsid: String,
+    pub description: String,
+    pub prog_id: String,
+    pub cur_ver: String,
+}
+
+impl RgsScript
+{
+    fn from(lib: &TypeLib) -> Self
+    {
+        // Format the names the same way the registration does.
+        let lib_name = pascal_case(&lib.name);
+        let lib_version = lib.version.replace(".", "_");
+
+        // The classes without a class factory cannot be created through COM
+        // so they are not registered.
+        let classes = lib
+            .types
+            .iter()
+            .filter_map(|t| match t {
+                TypeInfo::Class(cls) if !cls.options.no_class_factory => Some(cls),
+                _ => None,
+            })
+            .map(|cls| RgsClass::from(cls, &lib_name, &lib_version))
+            .collect();
+
+        Self {
+            libid: lib.libid.to_string(),
+            classes,
+        }
+    }
+}
+
+impl RgsClass
+{
+    fn from(cls: &CoClass, lib_name: &str, lib_version: &str) -> Self
+    {
+        Self {
+            clsid: cls.clsid.to_string(),
+            description: format!("{} {} Class", lib_name, cls.name),
+            prog_id: format!("{}.{}", lib_name, cls.name),
+            cur_ver: format!("{}.{}.{}", lib_name, cls.name, lib_version),
+        }
+    }
+}
+
+/// Generates the registrar script.
+pub fn write(lib: &TypeLib, out: &mut dyn Write) -> Result<(), GeneratorError>
+{
+    let mut reg = Handlebars::new();
+    reg.register_template_string("rgs", include_str!("rgs.hbs"))
+        .expect("Error in the built-in registrar script template.");
+
+    let rendered = reg
+        .render("rgs", &RgsScript::from(lib))
+        .expect("Rendering a valid ComCrate to registrar script failed");
+    write!(out, "{}", rendered)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    use intercom::typelib::ClassOptions;
+    use intercom::{ComBox, GUID};
+
+    fn class(name: &str, clsid: &str, no_class_factory: bool) -> TypeInfo
+    {
+        let mut cls = CoClass::__new(name.to_string().into(), GUID::parse(clsid).unwrap(), vec![]);
+        cls.options = ClassOptions {
+            no_class_factory,
+            ..ClassOptions::default()
+        };
+        TypeInfo::Class(ComBox::new(cls))
+    }
+
+    #[test]
+    fn registers_classes_with_class_factories()
+    {
+        let lib = TypeLib::__new(
+            "fixture_lib".into(),
+            GUID::parse("c7a6a0f6-4d2b-4c4a-9c6d-1f1b1e1f0bb1").unwrap(),
+            "1.0".into(),
+            vec![],
+            vec![
+                class("Calculator", "3e2b1a4c-5d6e-4f70-8a9b-0c1d2e3f4a5b", false),
+                class("Result", "4f3c2b5d-6e7f-4081-9bac-1d2e3f4a5b6c", true),
+            ],
+        );
+
+        let mut rgs = vec![];
+        write(&lib, &mut rgs).unwrap();
+
+        assert_eq!(
+            String::from_utf8(rgs).unwrap(),
+            r"HKCR
+{
+    FixtureLib.Calculator.1_0 = s 'FixtureLib Calculator Class'
+    {
+        CLSID = s '{3E2B1A4C-5D6E-4F70-8A9B-0C1D2E3F4A5B}'
+    }
+    FixtureLib.Calculator = s 'FixtureLib Calculator Class'
+    {
+        CurVer = s 'FixtureLib.Calculator.1_0'
+    }
+    NoRemove CLSID
+    {
+        ForceRemove {3E2B1A4C-5D6E-4F70-8A9B-0C1D2E3F4A5B} = s 'FixtureLib Calculator Class'
+        {
+            InprocServer32 = s '%MODULE%'
+            {
+                val ThreadingModel = s 'Both'
+            }
+            ProgID = s 'FixtureLib.Calculator.1_0'
+            TypeLib = s '{C7A6A0F6-4D2B-4C4A-9C6D-1F1B1E1F0BB1}'
+            VersionIndependentProgID = s 'FixtureLib.Calculator'
+        }
+    }
+}
+"
+        );
+    }
+}
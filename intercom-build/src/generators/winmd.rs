@@ -0,0 +1,471 @@
+//! Enables the generation of a simplified, `winmd`-like metadata description.
+//!
+//! **Experimental.** Available only with the `experimental-winmd` feature.
+//! The output format may change between releases.
+//!
+//! The generator does not produce the binary ECMA-335 metadata of a real
+//! `.winmd` file. Instead it writes a textual description of the same
+//! information, resembling the view projection tools present of the
+//! Windows Runtime metadata:
+//!
+//! - The library becomes a `namespace` with the library name.
+//! - Each interface variant becomes an `interface` with a `guid` attribute.
+//! - Each class becomes a `class` listing the interfaces it implements.
+//! - Methods are described in their projected form: the `HRESULT` return
+//!   value and the `[retval]` parameter are folded into the method return
+//!   type, the `out` parameters are marked with `out` and the interface
+//!   pointers are described as reference types without the indirection.
+//! - The primitive types are named using their metadata names, such as
+//!   `Int32`, `Double` and `String`.
+//!
+//! Only the interfaces, methods and classes are described. Parameter
+//! attributes that have no counterpart in the Windows Runtime type system
+//! are omitted.
+
+use std::borrow::Cow;
+use std::io::Write;
+
+use super::GeneratorError;
+use super::{
+    interface_array_element, pascal_case, LibraryContext, ModelOptions, TypeSystemOptions,
+};
+
+use handlebars::Handlebars;
+use serde_derive::Serialize;
+
+use intercom::typelib::{
+    Arg, CoClass, Direction, Interface, InterfaceVariant, Method, TypeInfo, TypeLib,
+};
+
+#[derive(Debug, Serialize)]
+struct WinmdLibrary
+{
+    pub namespace: String,
+    pub version: String,
+    pub interfaces: Vec<WinmdInterface>,
+    pub classes: Vec<WinmdClass>,
+}
+
+#[derive(Debug, Serialize)]
+struct WinmdInterface
+{
+    pub name: String,
+    pub guid: String,
+    pub methods: Vec<WinmdMethod>,
+}
+
+#[derive(Debug, Serialize)]
+struct WinmdMethod
+{
+    pub name: String,
+    pub ret_type: String,
+    pub args: Vec<WinmdArg>,
+}
+
+#[derive(Debug, Serialize)]
+struct WinmdArg
+{
+    pub name: String,
+    pub arg_type: String,
+    pub direction: String,
+}
+
+#[derive(Debug, Serialize)]
+struct WinmdClass
+{
+    pub name: String,
+    pub guid: String,
+    pub interfaces: Vec<String>,
+}
+
+impl WinmdLibrary
+{
+    fn try_from(lib: &TypeLib, opts: &ModelOptions) -> Result<Self, GeneratorError>
+    {
+        let ctx = LibraryContext::from(lib);
+
+        let mut interfaces = vec![];
+        let mut classes = vec![];
+        for t in &lib.types {
+            match t {
+                TypeInfo::Class(cls) => classes.push(WinmdClass::from(cls.as_ref(), opts, &ctx)),
+                TypeInfo::Interface(itf) => {
+                    for ts_opts in &opts.type_systems {
+                        if let Some(v) = itf.variants.iter().find(|v| v.as_ref().ts == ts_opts.ts) {
+                            interfaces.push(WinmdInterface::try_from(
+                                itf.as_ref(),
+                                v.as_ref(),
+                                ts_opts,
+                                &ctx,
+                            )?);
+                        }
+                    }
+                }
+
+                // The enums are described by their discriminant types.
+                TypeInfo::Enum(..) => {}
+
+                // The DLL entry points have no counterpart in the metadata.
+                TypeInfo::Module(..) => {}
+
+                // The value types are not described in the metadata yet.
+                TypeInfo::Struct(..) => {}
+            }
+        }
+
+        Ok(Self {
+            namespace: pascal_case(&lib.name),
+            version: lib.version.to_string(),
+            interfaces,
+            classes,
+        })
+    }
+}
+
+impl WinmdInterface
+{
+    fn try_from(
+        itf: &Interface,
+        itf_variant: &InterfaceVariant,
+        ts_opts: &TypeSystemOptions,
+        ctx: &LibraryContext,
+    ) -> Result<Self, GeneratorError>
+    {
+        // The Windows Runtime has no late binding.
+        if itf.options.dual {
+            let kind = match itf.options.dispinterface {
+                true => "Dispinterface",
+                false => "Dual interface",
+            };
+            return Err(format!(
+                "{} {} is not supported in the Windows Runtime",
+                kind, itf.name
+            )
+            .into());
+        }
+
+        Ok(Self {
+            name: Self::final_name(itf, ts_opts),
+            guid: format!("{:-X}", itf_variant.iid),
+            methods: itf_variant
+                .methods
+                .iter()
+                .map(|m| WinmdMethod::try_from(m.as_ref(), ts_opts, ctx))
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+
+    fn final_name(itf: &Interface, opts: &TypeSystemOptions) -> String
+    {
+        let base_name = if itf.options.class_impl_interface {
+            Cow::from(format!("I{}", itf.name))
+        } else {
+            itf.name.clone()
+        };
+
+        match opts.use_full_name {
+            true => format!("{}_{:?}", base_name, opts.ts),
+            false => base_name.to_string(),
+        }
+    }
+}
+
+impl WinmdMethod
+{
+    fn try_from(
+        method: &Method,
+        opts: &TypeSystemOptions,
+        ctx: &LibraryContext,
+    ) -> Result<Self, GeneratorError>
+    {
+        // Methods returning HRESULT are projected as methods returning their
+        // [retval] parameter, or nothing at all if there is none.
+        let mut params = method.parameters.iter().collect::<Vec<_>>();
+        let ret_type = match method.return_type.ty.as_ref() {
+            "HRESULT" => match params.last() {
+                Some(arg) if arg.direction == Direction::Retval => {
+                    let ret = WinmdArg::winmd_type(arg, opts, ctx);
+                    params.pop();
+                    ret
+                }
+                _ => "void".to_string(),
+            },
+            _ => WinmdArg::winmd_type(&method.return_type, opts, ctx),
+        };
+
+        Ok(Self {
+            name: pascal_case(&method.name),
+            ret_type,
+            args: params
+                .into_iter()
+                .map(|arg| WinmdArg::try_from(arg, opts, ctx))
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+}
+
+impl WinmdArg
+{
+    fn try_from(
+        arg: &Arg,
+        opts: &TypeSystemOptions,
+        ctx: &LibraryContext,
+    ) -> Result<Self, GeneratorError>
+    {
+        let direction = match arg.direction {
+            Direction::In => "in",
+            Direction::Out => "out",
+            Direction::InOut => {
+                return Err(format!(
+                    "In-out parameter {} is not supported in the Windows Runtime",
+                    arg.name
+                )
+                .into());
+            }
+            Direction::Retval => {
+                return Err("Only the last argument may be a [retval] argument"
+                    .to_string()
+                    .into());
+            }
+            Direction::Return => {
+                return Err("Direction::Return is invalid direction for arguments"
+                    .to_string()
+                    .into());
+            }
+        };
+
+        Ok(Self {
+            name: arg.name.to_string(),
+            arg_type: Self::winmd_type(arg, opts, ctx),
+            direction: direction.to_string(),
+        })
+    }
+
+    /// Gets the projected type name of the argument.
+    ///
+    /// The indirection implied by `out` parameters and the return values is
+    /// not included in the type.
+    fn winmd_type(arg: &Arg, opts: &TypeSystemOptions, ctx: &LibraryContext) -> String
+    {
+        let (base_name, is_reference) = match ctx.itfs_by_name.get(arg.ty.as_ref()) {
+            Some(itf) => (WinmdInterface::final_name(itf, opts), true),
+            None => match arg.ty.as_ref() {
+                "IUnknown" => ("Object".to_string(), true),
+                other if interface_array_element(other).is_some() => {
+                    let elem = interface_array_element(other).unwrap();
+                    let elem = match ctx.itfs_by_name.get(elem) {
+                        Some(itf) => WinmdInterface::final_name(itf, opts),
+                        None => elem.to_string(),
+                    };
+                    (format!("{}[]", elem), false)
+                }
+                other => (Self::primitive_name(other).to_string(), false),
+            },
+        };
+
+        // Interfaces are reference types so the first level of indirection
+        // is implied by the type itself.
+        let indirection = match is_reference {
+            true => arg.indirection_level.saturating_sub(1),
+            false => arg.indirection_level,
+        };
+
+        format!("{}{}", base_name, "*".repeat(indirection as usize))
+    }
+
+    fn primitive_name(ty: &str) -> &str
+    {
+        match ty {
+            "std::ffi::c_void" => "void",
+            "i8" => "Int8",
+            "u8" => "UInt8",
+            "i16" => "Int16",
+            "u16" => "UInt16",
+            "i32" => "Int32",
+            "u32" => "UInt32",
+            "i64" => "Int64",
+            "u64" => "UInt64",
+            "f32" => "Single",
+            "f64" => "Double",
+            "usize" => "UIntPtr",
+            "bool" | "VARIANT_BOOL" => "Boolean",
+            "BSTR" => "String",
+            "Variant" => "Object",
+            "SAFEARRAY(VARIANT)" => "Object[]",
+            "SAFEARRAY(BSTR)" => "String[]",
+            "SAFEARRAY(VARIANT_BOOL)" => "Boolean[]",
+            "SAFEARRAY(i8)" => "Int8[]",
+            "SAFEARRAY(u8)" => "UInt8[]",
+            "SAFEARRAY(i16)" => "Int16[]",
+            "SAFEARRAY(u16)" => "UInt16[]",
+            "SAFEARRAY(i32)" => "Int32[]",
+            "SAFEARRAY(u32)" => "UInt32[]",
+            "SAFEARRAY(i64)" => "Int64[]",
+            "SAFEARRAY(u64)" => "UInt64[]",
+            "SAFEARRAY(f32)" => "Single[]",
+            "SAFEARRAY(f64)" => "Double[]",
+            "GUID" => "Guid",
+            "HRESULT" => "HResult",
+            other => other,
+        }
+    }
+}
+
+impl WinmdClass
+{
+    fn from(cls: &CoClass, opts: &ModelOptions, ctx: &LibraryContext) -> Self
+    {
+        let interfaces = cls
+            .interfaces
+            .iter()
+            .flat_map(|itf_ref| {
+                opts.type_systems
+                    .iter()
+                    .map(|opt| {
+                        let itf = ctx.itfs_by_ref[itf_ref.name.as_ref()];
+                        WinmdInterface::final_name(itf, opt)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        WinmdClass {
+            name: cls.name.to_string(),
+            guid: format!("{:-X}", cls.clsid),
+            interfaces,
+        }
+    }
+}
+
+/// Generates the simplified metadata description.
+///
+/// - `out` - The writer to use for output.
+pub fn write(
+    lib: &intercom::typelib::TypeLib,
+    opts: &ModelOptions,
+    out: &mut dyn Write,
+) -> Result<(), GeneratorError>
+{
+    let mut reg = Handlebars::new();
+    reg.register_template_string("winmd", include_str!("winmd.hbs"))
+        .expect("Error in the built-in winmd template.");
+
+    let winmd_model = WinmdLibrary::try_from(lib, opts)?;
+
+    let rendered = reg
+        .render("winmd", &winmd_model)
+        .expect("Rendering a valid ComCrate to winmd failed");
+    write!(out, "{}", rendered)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    use crate::generators::PropertyNaming;
+    use intercom::type_system::TypeSystemName;
+    use intercom::typelib::{ClassOptions, InterfaceOptions, InterfaceRef, MethodKind};
+    use intercom::{ComBox, GUID};
+
+    fn arg(name: &str, ty: &str, indirection_level: u32, direction: Direction) -> Arg
+    {
+        Arg {
+            name: name.to_string().into(),
+            ty: ty.to_string().into(),
+            indirection_level,
+            direction,
+            idl_ty: None,
+            length_of: None,
+        }
+    }
+
+    #[test]
+    fn methods_are_projected()
+    {
+        let methods = vec![
+            Method {
+                name: "get_name".into(),
+                return_type: arg("", "HRESULT", 0, Direction::Return),
+                parameters: vec![
+                    arg("id", "i32", 0, Direction::In),
+                    arg("__out", "BSTR", 0, Direction::Retval),
+                ],
+                doc: None,
+                kind: MethodKind::Method,
+                options: Default::default(),
+            },
+            Method {
+                name: "link".into(),
+                return_type: arg("", "HRESULT", 0, Direction::Return),
+                parameters: vec![
+                    arg("other", "IUser", 1, Direction::In),
+                    arg("previous", "IUser", 1, Direction::Out),
+                ],
+                doc: None,
+                kind: MethodKind::Method,
+                options: Default::default(),
+            },
+            Method {
+                name: "count".into(),
+                return_type: arg("", "u32", 0, Direction::Return),
+                parameters: vec![],
+                doc: None,
+                kind: MethodKind::Method,
+                options: Default::default(),
+            },
+        ];
+        let itf = Interface {
+            name: "IUser".into(),
+            options: InterfaceOptions::default(),
+            variants: vec![ComBox::new(InterfaceVariant {
+                ts: TypeSystemName::Automation,
+                iid: GUID::parse("5b1d3e0c-7f4a-4b6e-9d2c-8a1f3e5b7c9d").unwrap(),
+                methods: methods.into_iter().map(ComBox::new).collect(),
+            })],
+            doc: None,
+        };
+        let cls = CoClass {
+            name: "User".into(),
+            clsid: GUID::parse("0c8b7e4a-69d3-4f1e-b2a5-3d6c9e8f1a2b").unwrap(),
+            interfaces: vec![InterfaceRef {
+                name: "IUser".into(),
+                iid_automation: GUID::zero_guid(),
+                iid_raw: GUID::zero_guid(),
+            }],
+            options: ClassOptions::default(),
+            doc: None,
+        };
+        let lib = TypeLib::__new(
+            "user_lib".into(),
+            GUID::parse("2f6c8a4e-1b3d-4e5f-8a7b-9c0d1e2f3a4b").unwrap(),
+            "1.0".into(),
+            vec![],
+            vec![
+                TypeInfo::Class(ComBox::new(cls)),
+                TypeInfo::Interface(ComBox::new(itf)),
+            ],
+        );
+
+        let opts = ModelOptions {
+            type_systems: vec![TypeSystemOptions {
+                ts: TypeSystemName::Automation,
+                use_full_name: false,
+                hidden: false,
+            }],
+            property_naming: PropertyNaming::None,
+        };
+        let mut out = vec![];
+        write(&lib, &opts, &mut out).unwrap();
+        let winmd = String::from_utf8(out).unwrap();
+
+        assert!(winmd.contains("namespace UserLib"));
+        assert!(winmd.contains("[guid(5B1D3E0C-7F4A-4B6E-9D2C-8A1F3E5B7C9D)]"));
+        assert!(winmd.contains("interface IUser"));
+        assert!(winmd.contains("String GetName(in Int32 id);"));
+        assert!(winmd.contains("void Link(in IUser other, out IUser previous);"));
+        assert!(winmd.contains("UInt32 Count();"));
+        assert!(winmd.contains("class User : IUser;"));
+    }
+}
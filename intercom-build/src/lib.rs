@@ -0,0 +1,276 @@
+//! Build utilities for crates using Intercom.
+//!
+//! The functions in this crate are meant to be invoked from `build.rs`
+//! scripts so that the artifacts, such as IDL files, are produced as part of
+//! the normal cargo build. The `intercom-cli` command line utility is
+//! implemented on top of the same functionality.
+//!
+//! ```rust,ignore
+//! // build.rs
+//! fn main()
+//! {
+//!     intercom_build::generate_idl(
+//!         "path/to/intercom_lib.dll",
+//!         "target/intercom_lib.idl",
+//!         intercom_build::generators::ModelOptions::default(),
+//!     )
+//!     .unwrap();
+//! }
+//! ```
+
+#![allow(clippy::match_bool)]
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[macro_use]
+extern crate failure;
+
+pub mod generators;
+pub mod typelib;
+
+/// Artifacts that can be generated from a compiled intercom library.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Artifact
+{
+    /// IDL file, `<lib>.idl`.
+    Idl,
+
+    /// C++ header and source files, `<lib>.hpp` and `<lib>.cpp`.
+    Cpp,
+
+    /// Module definition file, `<lib>.def`.
+    Def,
+
+    /// Registrar script, `<lib>.rgs`.
+    Rgs,
+
+    /// Experimental simplified metadata description, `<lib>.winmd.txt`.
+    #[cfg(feature = "experimental-winmd")]
+    WinmdLite,
+}
+
+impl FromStr for Artifact
+{
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s {
+            "idl" => Ok(Artifact::Idl),
+            "cpp" => Ok(Artifact::Cpp),
+            "def" => Ok(Artifact::Def),
+            "rgs" => Ok(Artifact::Rgs),
+            #[cfg(feature = "experimental-winmd")]
+            "winmd-lite" => Ok(Artifact::WinmdLite),
+            other => Err(format_err!(
+                "Unsupported artifact `{}`, expected `idl`, `cpp`, `def` or `rgs`",
+                other
+            )),
+        }
+    }
+}
+
+/// Generates the IDL file for a compiled intercom library.
+///
+/// - `lib_path` - Path to the compiled intercom library.
+/// - `out_path` - Path of the IDL file to write.
+/// - `options` - Options specifying the type systems included in the IDL.
+pub fn generate_idl<P1, P2>(
+    lib_path: P1,
+    out_path: P2,
+    options: generators::ModelOptions,
+) -> Result<(), failure::Error>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let lib = typelib::read_typelib(lib_path.as_ref())?;
+    write_idl_file(&lib, out_path.as_ref(), &options)
+}
+
+/// Generates several artifacts for a compiled intercom library.
+///
+/// The library is loaded only once and all the artifacts are generated from
+/// the same type library.
+///
+/// - `lib_path` - Path to the compiled intercom library.
+/// - `out_dir` - Directory for the generated files. Created if missing.
+/// - `artifacts` - Artifacts to generate.
+/// - `options` - Options specifying the type systems included in the files.
+///
+/// Returns the paths of the generated files.
+pub fn generate_artifacts<P1, P2>(
+    lib_path: P1,
+    out_dir: P2,
+    artifacts: &[Artifact],
+    options: generators::ModelOptions,
+) -> Result<Vec<PathBuf>, failure::Error>
+where
+    P1: AsRef<Path>,
+    P2: AsRef<Path>,
+{
+    let lib = typelib::read_typelib(lib_path.as_ref())?;
+    write_artifacts(&lib, out_dir.as_ref(), artifacts, &options)
+}
+
+/// Writes the IDL for an already loaded type library into a file.
+fn write_idl_file(
+    lib: &intercom::typelib::TypeLib,
+    out_path: &Path,
+    options: &generators::ModelOptions,
+) -> Result<(), failure::Error>
+{
+    let mut file = File::create(out_path)?;
+    generators::idl::write(lib, options, &mut file)?;
+    Ok(())
+}
+
+/// Writes the artifacts for an already loaded type library into a directory.
+fn write_artifacts(
+    lib: &intercom::typelib::TypeLib,
+    out_dir: &Path,
+    artifacts: &[Artifact],
+    options: &generators::ModelOptions,
+) -> Result<Vec<PathBuf>, failure::Error>
+{
+    std::fs::create_dir_all(out_dir)?;
+
+    let mut written = vec![];
+    for artifact in artifacts {
+        match artifact {
+            Artifact::Idl => {
+                let path = out_dir.join(format!("{}.idl", lib.name));
+                write_idl_file(lib, &path, options)?;
+                written.push(path);
+            }
+            Artifact::Cpp => {
+                let header_path = out_dir.join(format!("{}.hpp", lib.name));
+                let source_path = out_dir.join(format!("{}.cpp", lib.name));
+                let mut header = File::create(&header_path)?;
+                let mut source = File::create(&source_path)?;
+                generators::cpp::write(lib, options, Some(&mut header), Some(&mut source))?;
+                written.push(header_path);
+                written.push(source_path);
+            }
+            Artifact::Def => {
+                let path = out_dir.join(format!("{}.def", lib.name));
+                let mut file = File::create(&path)?;
+                generators::def::write(lib, &mut file)?;
+                written.push(path);
+            }
+            Artifact::Rgs => {
+                let path = out_dir.join(format!("{}.rgs", lib.name));
+                let mut file = File::create(&path)?;
+                generators::rgs::write(lib, &mut file)?;
+                written.push(path);
+            }
+            #[cfg(feature = "experimental-winmd")]
+            Artifact::WinmdLite => {
+                let path = out_dir.join(format!("{}.winmd.txt", lib.name));
+                let mut file = File::create(&path)?;
+                generators::winmd::write(lib, options, &mut file)?;
+                written.push(path);
+            }
+        }
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    #[test]
+    fn write_idl_file_creates_idl()
+    {
+        // Use the types built into intercom as the fixture library.
+        let lib = intercom::typelib::TypeLib::__new(
+            "FixtureLib".into(),
+            intercom::GUID::parse("c7a6a0f6-4d2b-4c4a-9c6d-1f1b1e1f0bb1").unwrap(),
+            "1.0".into(),
+            vec![],
+            intercom::__gather_module_types(),
+        );
+
+        let out_path = std::env::temp_dir().join("intercom_build_write_idl_file_creates_idl.idl");
+        write_idl_file(&lib, &out_path, &generators::ModelOptions::default()).unwrap();
+
+        let idl = std::fs::read_to_string(&out_path).unwrap();
+        std::fs::remove_file(&out_path).unwrap();
+
+        assert!(idl.contains("library FixtureLib"));
+        assert!(idl.contains("interface IErrorStore_Automation"));
+        assert!(idl.contains("coclass ErrorStore"));
+    }
+
+    #[test]
+    fn write_artifacts_creates_all_files()
+    {
+        let lib = intercom::typelib::TypeLib::__new(
+            "fixture_lib".into(),
+            intercom::GUID::parse("c7a6a0f6-4d2b-4c4a-9c6d-1f1b1e1f0bb1").unwrap(),
+            "1.0".into(),
+            vec![],
+            intercom::__gather_module_types(),
+        );
+
+        let out_dir = std::env::temp_dir().join("intercom_build_write_artifacts_creates_all_files");
+        let written = write_artifacts(
+            &lib,
+            &out_dir,
+            &[Artifact::Idl, Artifact::Cpp, Artifact::Def, Artifact::Rgs],
+            &generators::ModelOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            written,
+            vec![
+                out_dir.join("fixture_lib.idl"),
+                out_dir.join("fixture_lib.hpp"),
+                out_dir.join("fixture_lib.cpp"),
+                out_dir.join("fixture_lib.def"),
+                out_dir.join("fixture_lib.rgs"),
+            ]
+        );
+        let idl = std::fs::read_to_string(out_dir.join("fixture_lib.idl")).unwrap();
+        let cpp = std::fs::read_to_string(out_dir.join("fixture_lib.hpp")).unwrap()
+            + &std::fs::read_to_string(out_dir.join("fixture_lib.cpp")).unwrap();
+        let def = std::fs::read_to_string(out_dir.join("fixture_lib.def")).unwrap();
+        let rgs = std::fs::read_to_string(out_dir.join("fixture_lib.rgs")).unwrap();
+        std::fs::remove_dir_all(&out_dir).unwrap();
+
+        assert!(def.contains("LIBRARY fixture_lib"));
+        assert!(rgs.contains(&format!("TypeLib = s '{}'", lib.libid)));
+
+        // All the artifacts must describe the classes with the same GUIDs.
+        let clsids = lib
+            .types
+            .iter()
+            .filter_map(|t| match t {
+                intercom::typelib::TypeInfo::Class(cls) => Some(cls.clsid.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(!clsids.is_empty());
+        for clsid in clsids {
+            assert!(idl.contains(&format!("{:-X}", clsid)));
+            assert!(cpp.contains(&generators::cpp::guid_as_struct(&clsid)));
+            assert!(rgs.contains(&format!("ForceRemove {}", clsid)));
+        }
+    }
+
+    #[test]
+    fn parse_artifact()
+    {
+        assert_eq!("idl".parse::<Artifact>().unwrap(), Artifact::Idl);
+        assert_eq!("cpp".parse::<Artifact>().unwrap(), Artifact::Cpp);
+        assert_eq!("def".parse::<Artifact>().unwrap(), Artifact::Def);
+        assert_eq!("rgs".parse::<Artifact>().unwrap(), Artifact::Rgs);
+        assert!("tlb".parse::<Artifact>().is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::generators::{idl, ModelOptions};
+use intercom_build::generators::{idl, ModelOptions};
 use std::env;
 use std::fs::File;
 use std::io::Read;
@@ -26,11 +26,11 @@ pub fn embed_typelib(path: &Path, opts: ModelOptions) -> Result<(), failure::Err
     let dll_name = "test_lib.dll";
     let manifest_path = Path::new("crate.manifest");
 
-    let lib = crate::typelib::read_typelib(path)?;
+    let lib = intercom_build::typelib::read_typelib(path)?;
 
     {
         let mut idl_file = File::create(&idl_path).unwrap();
-        idl::write(lib, opts, &mut idl_file)?;
+        idl::write(&lib, &opts, &mut idl_file)?;
     }
 
     let paths = setup_configuration::get_tool_paths().map_err(EmbedError)?;
@@ -1,8 +1,8 @@
 #![allow(clippy::match_bool)]
 
 use std::fs::File;
-use std::io;
-use std::path::Path;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 #[macro_use]
 extern crate failure;
@@ -14,8 +14,9 @@ use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 #[cfg(windows)]
 mod embed;
 
-mod generators;
-mod typelib;
+use intercom::type_system::TypeSystemName;
+use intercom::typelib::{TypeInfo, TypeLib};
+use intercom_build::{generators, typelib};
 
 /// Main entry point.
 fn main()
@@ -41,14 +42,117 @@ fn main()
                 .about("Generates IDL file from the Rust crate")
                 .arg(
                     Arg::with_name("path")
-                        .help("Path to the crate to process")
+                        .help(
+                            "Paths to the libraries or the Cargo manifests of the crates to \
+                             process.{n}A virtual workspace manifest stands for all the \
+                             members building a cdylib.{n}Several libraries are merged into \
+                             the library of the first one.",
+                        )
                         .default_value(".")
+                        .multiple(true)
                         .index(1),
                 )
+                .arg(
+                    Arg::with_name("release")
+                        .long("release")
+                        .help("Read the release builds of the crates given by their manifests."),
+                )
                 .arg(Arg::with_name("all").long("all").help(
                     "Include both Automation and Raw type systems in the IDL.{n}\
                      Normally the IDL only includes the Automation type system interfaces.",
-                )),
+                ))
+                .arg(
+                    Arg::with_name("exclude")
+                        .long("exclude")
+                        .value_name("pattern")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help(
+                            "Skip the interfaces and classes with names matching the glob \
+                             pattern.{n}May be specified multiple times.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("warn-missing-helpstring")
+                        .long("warn-missing-helpstring")
+                        .help(
+                            "Report the interfaces and methods without a doc comment for the \
+                             helpstring.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("warnings-as-errors")
+                        .long("warnings-as-errors")
+                        .help("Fail instead of generating the IDL if there are warnings."),
+                )
+                .arg(Arg::with_name("strict-guids").long("strict-guids").help(
+                    "Fail if any interface or class has a GUID derived from its name \
+                             instead of an explicit one.",
+                ))
+                .arg(
+                    Arg::with_name("version-override")
+                        .long("version-override")
+                        .value_name("major.minor")
+                        .help(
+                            "Use the version instead of the one the library was built \
+                             with.{n}Useful for reproducible builds.",
+                        ),
+                )
+                .arg(Arg::with_name("hide-variants").long("hide-variants").help(
+                    "Mark the interfaces of the type systems other than the primary \
+                             one [hidden].",
+                ))
+                .arg(
+                    Arg::with_name("primary")
+                        .long("primary")
+                        .value_name("type system")
+                        .possible_values(&["automation", "raw"])
+                        .default_value("automation")
+                        .help("The type system left visible with --hide-variants."),
+                )
+                .arg(
+                    Arg::with_name("properties")
+                        .long("properties")
+                        .value_name("naming")
+                        .possible_values(&["none", "get-set", "noun"])
+                        .default_value("none")
+                        .help(
+                            "Emit the getter and setter method pairs as [propget] and \
+                             [propput] accessors.{n}get-set pairs get_x with set_x, noun \
+                             pairs x with set_x.",
+                        ),
+                )
+                .arg(Arg::with_name("dump-model").long("dump-model").help(
+                    "Print the interface and class model the IDL is generated from to stderr.",
+                ))
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("file")
+                        .help(
+                            "Write the IDL into the file instead of stdout.{n}\
+                             The file is replaced only if the generation succeeds.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("emit-guids")
+                        .long("emit-guids")
+                        .value_name("file")
+                        .help(
+                            "Write the GUIDs of the IDL into the file as Rust constants \
+                             for include!.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("assert-stable")
+                        .long("assert-stable")
+                        .value_name("snapshot")
+                        .help(
+                            "Fail with a diff if the IDL differs from the snapshot file.{n}\
+                             Line endings are ignored in the comparison.",
+                        ),
+                ),
         )
         .subcommand(
             SubCommand::with_name("manifest")
@@ -88,6 +192,56 @@ fn main()
                     "Include both Automation and Raw type systems in the C++ implementation.{n}\
                      Normally the implementation only includes the Raw type system interfaces.",
                 )),
+        )
+        .subcommand(
+            SubCommand::with_name("cpp-header")
+                .about("Generates a C++ interface header from the Rust crate to stdout")
+                .arg(
+                    Arg::with_name("path")
+                        .help("Path to the crate to process")
+                        .default_value(".")
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("cpp-wrappers")
+                .about(
+                    "Generates C++ smart pointer wrappers similar to the #import{n}\
+                     .tlh/.tli files from the Rust crate",
+                )
+                .arg(
+                    Arg::with_name("path")
+                        .help("Path to the crate to process")
+                        .default_value(".")
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("all")
+                .about("Generates several artifacts from the Rust crate at once")
+                .arg(
+                    Arg::with_name("path")
+                        .help("Path to the crate to process")
+                        .default_value(".")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("out-dir")
+                        .long("out-dir")
+                        .value_name("dir")
+                        .required(true)
+                        .help("Directory for the generated files."),
+                )
+                .arg(
+                    Arg::with_name("emit")
+                        .long("emit")
+                        .value_name("artifacts")
+                        .default_value("idl,cpp")
+                        .help(
+                            "Comma separated list of the artifacts to generate: idl, cpp, def,{n}\
+                             rgs. The experimental-winmd feature adds winmd-lite.",
+                        ),
+                ),
         );
 
     #[cfg(windows)]
@@ -106,18 +260,7 @@ fn main()
 
 fn run_cmd(matches: &ArgMatches) -> Result<(), failure::Error>
 {
-    let opts = generators::ModelOptions {
-        type_systems: vec![
-            generators::TypeSystemOptions {
-                ts: intercom::type_system::TypeSystemName::Automation,
-                use_full_name: true,
-            },
-            generators::TypeSystemOptions {
-                ts: intercom::type_system::TypeSystemName::Raw,
-                use_full_name: true,
-            },
-        ],
-    };
+    let opts = generators::ModelOptions::default();
 
     match matches.subcommand() {
         ("read-typelib", Some(args)) => {
@@ -129,9 +272,78 @@ fn run_cmd(matches: &ArgMatches) -> Result<(), failure::Error>
             embed::embed_typelib(Path::new(args.value_of("path").unwrap()), opts)?;
         }
         ("idl", Some(args)) => {
-            let path = Path::new(args.value_of("path").unwrap());
-            let lib = typelib::read_typelib(path)?;
-            generators::idl::write(lib, opts, &mut io::stdout())?;
+            let patterns = args
+                .values_of("exclude")
+                .into_iter()
+                .flatten()
+                .map(glob::Pattern::new)
+                .collect::<Result<Vec<_>, _>>()?;
+            let profile = match args.is_present("release") {
+                true => "release",
+                false => "debug",
+            };
+            let mut libs = vec![];
+            for path in args.values_of("path").unwrap() {
+                for lib_path in library_paths(Path::new(path), profile)? {
+                    libs.push(typelib::read_typelib(&lib_path)?);
+                }
+            }
+            let mut lib = merge_typelibs(libs)?;
+            exclude_types(&mut lib, &patterns);
+            if let Some(version) = args.value_of("version-override") {
+                lib.version = version.to_string().into();
+            }
+            if args.is_present("warn-missing-helpstring") {
+                let warnings = missing_helpstrings(&lib);
+                for warning in &warnings {
+                    eprintln!("warning: {}", warning);
+                }
+                if args.is_present("warnings-as-errors") && !warnings.is_empty() {
+                    return Err(format_err!("{} item(s) lack a helpstring", warnings.len()));
+                }
+            }
+            if args.is_present("strict-guids") {
+                let errors = generated_guids(&lib);
+                for error in &errors {
+                    eprintln!("error: {}", error);
+                }
+                if !errors.is_empty() {
+                    return Err(format_err!(
+                        "{} item(s) lack an explicit GUID",
+                        errors.len()
+                    ));
+                }
+            }
+            if args.is_present("dump-model") {
+                generators::idl::write_model(&lib, &mut io::stderr())?;
+            }
+            let mut opts = match (args.is_present("hide-variants"), args.value_of("primary")) {
+                (true, Some("raw")) => generators::ModelOptions::with_primary(TypeSystemName::Raw),
+                (true, _) => generators::ModelOptions::with_primary(TypeSystemName::Automation),
+                (false, _) => opts,
+            };
+            opts.property_naming = match args.value_of("properties") {
+                Some("get-set") => generators::PropertyNaming::GetSet,
+                Some("noun") => generators::PropertyNaming::Noun,
+                _ => generators::PropertyNaming::None,
+            };
+
+            // Generate the whole IDL before touching the file so a failure
+            // doesn't leave a partial file behind.
+            let mut idl = vec![];
+            generators::idl::write(&lib, &opts, &mut idl)?;
+            if let Some(snapshot) = args.value_of("assert-stable") {
+                assert_stable(Path::new(snapshot), &idl)?;
+            }
+            match args.value_of("output") {
+                Some(output) => replace_file(Path::new(output), &idl)?,
+                None => io::stdout().write_all(&idl)?,
+            }
+            if let Some(guids_path) = args.value_of("emit-guids") {
+                let mut guids = vec![];
+                generators::idl::write_guids(&lib, &opts, &mut guids)?;
+                replace_file(Path::new(guids_path), &guids)?;
+            }
         }
         ("cpp", Some(args)) => {
             let path = Path::new(args.value_of("path").unwrap());
@@ -162,15 +374,941 @@ fn run_cmd(matches: &ArgMatches) -> Result<(), failure::Error>
                 let mut header_writer = header_writer?;
                 let mut source_writer = source_writer?;
                 return Ok(generators::cpp::write(
-                    lib,
-                    opts,
+                    &lib,
+                    &opts,
                     header_writer.as_mut().map(|b| b as &mut dyn io::Write),
                     source_writer.as_mut().map(|b| b as &mut dyn io::Write),
                 )?);
             }
         }
+        ("cpp-header", Some(args)) => {
+            let path = Path::new(args.value_of("path").unwrap());
+            let lib = typelib::read_typelib(path)?;
+            generators::cpp::write(&lib, &opts, Some(&mut io::stdout()), None)?;
+        }
+        ("cpp-wrappers", Some(args)) => {
+            let path = Path::new(args.value_of("path").unwrap());
+            let lib = typelib::read_typelib(path)?;
+            for skipped in generators::cpp_wrappers::write(&lib, &opts, &mut io::stdout())? {
+                eprintln!("warning: {}", skipped);
+            }
+        }
+        ("all", Some(args)) => {
+            let path = Path::new(args.value_of("path").unwrap());
+            let out_dir = Path::new(args.value_of("out-dir").unwrap());
+            let artifacts = args
+                .value_of("emit")
+                .unwrap()
+                .split(',')
+                .map(|a| a.trim().parse())
+                .collect::<Result<Vec<intercom_build::Artifact>, _>>()?;
+            for written in intercom_build::generate_artifacts(path, out_dir, &artifacts, opts)? {
+                println!("{}", written.display());
+            }
+        }
         _ => unreachable!(),
     }
 
     Ok(())
 }
+
+/// Replaces the file with the content.
+///
+/// The content is written into a temporary file next to the target first and
+/// then renamed over the target so the target is never left half-written. The
+/// missing parent directories are created.
+fn replace_file(path: &Path, content: &[u8]) -> Result<(), failure::Error>
+{
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format_err!("Output path `{}` is not a file", path.display()))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let written =
+        std::fs::write(&temp_path, content).and_then(|_| std::fs::rename(&temp_path, path));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    Ok(written?)
+}
+
+/// Fails if the IDL differs from the snapshot.
+///
+/// The error lists the differing lines. The snapshot is compared line by line
+/// so its line endings don't matter.
+fn assert_stable(snapshot: &Path, idl: &[u8]) -> Result<(), failure::Error>
+{
+    let expected = std::fs::read_to_string(snapshot).map_err(|e| {
+        format_err!(
+            "Failed to read the snapshot `{}`: {}",
+            snapshot.display(),
+            e
+        )
+    })?;
+    match diff_lines(&expected, &String::from_utf8_lossy(idl)) {
+        None => Ok(()),
+        Some(diff) => Err(format_err!(
+            "IDL differs from the snapshot `{}`:\n{}",
+            snapshot.display(),
+            diff
+        )),
+    }
+}
+
+/// Formats the differences between the lines of the texts.
+///
+/// Returns `None` if the lines are the same. The removed lines are marked with
+/// `-` and the added ones with `+`. The unchanged lines are shown only around
+/// the changes.
+fn diff_lines(expected: &str, actual: &str) -> Option<String>
+{
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+    if expected == actual {
+        return None;
+    }
+
+    // Lengths of the longest common subsequences of the remaining lines.
+    let mut lcs = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lcs[i][j] = match expected[i] == actual[j] {
+                true => lcs[i + 1][j + 1] + 1,
+                false => lcs[i + 1][j].max(lcs[i][j + 1]),
+            };
+        }
+    }
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            lines.push((' ', expected[i]));
+            i += 1;
+            j += 1;
+        } else if j == actual.len() || (i < expected.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(('-', expected[i]));
+            i += 1;
+        } else {
+            lines.push(('+', actual[j]));
+            j += 1;
+        }
+    }
+
+    // Skipped lines are marked with `...`.
+    const CONTEXT: usize = 2;
+    let changes = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, (tag, _))| *tag != ' ')
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+    let mut diff = String::new();
+    let mut previous = None;
+    for (idx, (tag, line)) in lines.iter().enumerate() {
+        if !changes
+            .iter()
+            .any(|c| idx + CONTEXT >= *c && idx <= c + CONTEXT)
+        {
+            continue;
+        }
+        if previous.map(|p| p + 1 != idx).unwrap_or(idx != 0) {
+            diff.push_str("...\n");
+        }
+        diff.push_str(format!("{} {}", tag, line).trim_end());
+        diff.push('\n');
+        previous = Some(idx);
+    }
+    Some(diff)
+}
+
+/// Resolves the compiled libraries the path stands for.
+///
+/// The libraries are used as they are. A Cargo manifest, or a directory
+/// containing one, stands for the dynamic library the package builds into
+/// its target directory. A virtual workspace manifest stands for the
+/// libraries of all its members that build one.
+fn library_paths(path: &Path, profile: &str) -> Result<Vec<PathBuf>, failure::Error>
+{
+    let manifest_path = if path.is_dir() {
+        path.join("Cargo.toml")
+    } else if path.file_name() == Some("Cargo.toml".as_ref()) {
+        path.to_path_buf()
+    } else {
+        return Ok(vec![path.to_path_buf()]);
+    };
+    let manifest_path = manifest_path
+        .canonicalize()
+        .map_err(|e| format_err!("Failed to read `{}`: {}", manifest_path.display(), e))?;
+    let metadata = cargo_metadata(&manifest_path)?;
+    let target_dir = metadata["target_directory"]
+        .as_str()
+        .map(|dir| Path::new(dir).join(profile))
+        .ok_or_else(|| format_err!("Cargo metadata is missing the target directory"))?;
+    let packages = metadata["packages"]
+        .as_array()
+        .ok_or_else(|| format_err!("Cargo metadata is missing the packages"))?;
+
+    // The packages include the whole workspace, the package manifest stands
+    // only for its own package.
+    if let Some(package) = packages
+        .iter()
+        .find(|p| p["manifest_path"].as_str().map(Path::new) == Some(&manifest_path))
+    {
+        return match package_library(package, &target_dir) {
+            Some(lib) => Ok(vec![lib]),
+            None => Err(format_err!(
+                "`{}` doesn't build a cdylib",
+                manifest_path.display()
+            )),
+        };
+    }
+
+    let libs: Vec<_> = packages
+        .iter()
+        .filter_map(|p| package_library(p, &target_dir))
+        .collect();
+    if libs.is_empty() {
+        return Err(format_err!(
+            "No member of `{}` builds a cdylib",
+            manifest_path.display()
+        ));
+    }
+    Ok(libs)
+}
+
+/// Reads the workspace of the manifest with `cargo metadata`.
+///
+/// The dependencies are not resolved; only the workspace members and the
+/// target directory are needed.
+fn cargo_metadata(manifest_path: &Path) -> Result<serde_json::Value, failure::Error>
+{
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let output = std::process::Command::new(cargo)
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .arg("--manifest-path")
+        .arg(manifest_path)
+        .output()
+        .map_err(|e| format_err!("Failed to run `cargo metadata`: {}", e))?;
+    if !output.status.success() {
+        return Err(format_err!(
+            "Failed to read `{}`: {}",
+            manifest_path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// Locates the dynamic library the package builds into the target directory.
+fn package_library(package: &serde_json::Value, target_dir: &Path) -> Option<PathBuf>
+{
+    let lib = package["targets"].as_array()?.iter().find(|target| {
+        target["crate_types"]
+            .as_array()
+            .map(|types| types.iter().any(|t| t == "cdylib"))
+            .unwrap_or(false)
+    })?;
+
+    let name = lib["name"].as_str()?.replace('-', "_");
+    Some(target_dir.join(format!(
+        "{}{}{}",
+        std::env::consts::DLL_PREFIX,
+        name,
+        std::env::consts::DLL_SUFFIX
+    )))
+}
+
+/// Merges the type libraries into the first one.
+///
+/// The libraries share the types with the same name and definition, such as
+/// the ones built into intercom. The interfaces and classes are identified
+/// by their GUIDs while the enums, structs and modules are compared by their
+/// contents. A name used for different definitions is an error.
+fn merge_typelibs(libs: Vec<TypeLib>) -> Result<TypeLib, failure::Error>
+{
+    let mut libs = libs.into_iter();
+    let mut merged = libs
+        .next()
+        .ok_or_else(|| format_err!("No libraries to merge"))?;
+    for lib in libs {
+        for import in lib.imports {
+            if !merged.imports.contains(&import) {
+                merged.imports.push(import);
+            }
+        }
+        for t in lib.types {
+            match merged.types.iter().find(|m| type_name(m) == type_name(&t)) {
+                None => merged.types.push(t),
+                Some(existing) if type_identity(existing) == type_identity(&t) => {}
+                Some(_) => {
+                    return Err(format_err!(
+                        "`{}` is defined by both `{}` and `{}`",
+                        type_name(&t),
+                        merged.name,
+                        lib.name
+                    ))
+                }
+            }
+        }
+    }
+    Ok(merged)
+}
+
+fn type_name(t: &TypeInfo) -> &str
+{
+    match t {
+        TypeInfo::Class(cls) => &cls.name,
+        TypeInfo::Interface(itf) => &itf.name,
+        TypeInfo::Enum(e) => &e.name,
+        TypeInfo::Module(m) => &m.name,
+        TypeInfo::Struct(s) => &s.name,
+    }
+}
+
+/// Describes what makes the types with the same name the same type.
+fn type_identity(t: &TypeInfo) -> Vec<String>
+{
+    match t {
+        TypeInfo::Class(cls) => vec![cls.clsid.to_string()],
+        TypeInfo::Interface(itf) => itf.variants.iter().map(|v| v.iid.to_string()).collect(),
+        TypeInfo::Enum(e) => std::iter::once(format!("v1_enum: {}", e.v1_enum))
+            .chain(e.values.iter().map(|v| format!("{} = {}", v.name, v.value)))
+            .collect(),
+        TypeInfo::Struct(s) => s
+            .fields
+            .iter()
+            .map(|f| {
+                format!(
+                    "{}: {}{}",
+                    f.name,
+                    f.ty,
+                    "*".repeat(f.indirection_level as usize)
+                )
+            })
+            .collect(),
+        TypeInfo::Module(m) => std::iter::once(format!("dll: {}", m.dll_name))
+            .chain(
+                m.constants
+                    .iter()
+                    .map(|c| format!("{}: {} = {}", c.name, c.ty, c.value)),
+            )
+            .chain(m.functions.iter().map(|f| {
+                let params = f
+                    .parameters
+                    .iter()
+                    .map(|p| {
+                        format!(
+                            "{:?} {}{}",
+                            p.direction,
+                            p.ty,
+                            "*".repeat(p.indirection_level as usize)
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                format!("{}({}) -> {}", f.name, params.join(", "), f.return_type.ty)
+            }))
+            .collect(),
+    }
+}
+
+/// Removes the interfaces and classes with names matching any of the patterns.
+///
+/// The excluded interfaces are also removed from the classes implementing
+/// them.
+fn exclude_types(lib: &mut TypeLib, patterns: &[glob::Pattern])
+{
+    let is_excluded = |name: &str| patterns.iter().any(|p| p.matches(name));
+    lib.types.retain(|t| !is_excluded(type_name(t)));
+    for t in &mut lib.types {
+        if let TypeInfo::Class(cls) = t {
+            cls.interfaces.retain(|itf| !is_excluded(&itf.name));
+        }
+    }
+}
+
+/// Lists the interfaces and methods that have no doc comment for the
+/// helpstring.
+///
+/// Rust doesn't support doc comments on parameters so the parameters are not
+/// checked.
+fn missing_helpstrings(lib: &TypeLib) -> Vec<String>
+{
+    let mut warnings = vec![];
+    for t in &lib.types {
+        let itf = match t {
+            TypeInfo::Interface(itf) => itf,
+            _ => continue,
+        };
+
+        if itf.doc.is_none() {
+            warnings.push(format!("interface `{}` has no doc comment", itf.name));
+        }
+
+        // The variants share the methods so checking one of them is enough.
+        if let Some(variant) = itf.variants.first() {
+            for method in &variant.methods {
+                if method.doc.is_none() {
+                    warnings.push(format!(
+                        "method `{}::{}` has no doc comment",
+                        itf.name, method.name
+                    ));
+                }
+            }
+        }
+    }
+    warnings
+}
+
+/// Lists the interfaces and classes that rely on GUIDs derived from their
+/// names.
+///
+/// Such GUIDs change whenever the item is renamed, which breaks the existing
+/// clients of the library.
+fn generated_guids(lib: &TypeLib) -> Vec<String>
+{
+    let mut errors = vec![];
+    for t in &lib.types {
+        match t {
+            TypeInfo::Interface(itf) if itf.options.generated_iid => {
+                errors.push(format!("interface `{}` has a name-derived IID", itf.name))
+            }
+            TypeInfo::Class(cls) if cls.options.generated_clsid => {
+                errors.push(format!("class `{}` has a name-derived CLSID", cls.name))
+            }
+            _ => {}
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    use intercom::typelib::{
+        ClassOptions, CoClass, Enum, EnumValue, Interface, InterfaceOptions, InterfaceRef,
+        InterfaceVariant,
+    };
+    use intercom::{ComBox, GUID};
+
+    fn interface(name: &str) -> TypeInfo
+    {
+        interface_with_iid(name, GUID::zero_guid())
+    }
+
+    fn interface_with_iid(name: &str, iid: GUID) -> TypeInfo
+    {
+        TypeInfo::Interface(ComBox::new(Interface {
+            name: name.to_string().into(),
+            options: InterfaceOptions::default(),
+            variants: vec![ComBox::new(InterfaceVariant {
+                ts: TypeSystemName::Automation,
+                iid,
+                methods: vec![],
+            })],
+            doc: None,
+        }))
+    }
+
+    fn class(name: &str, interfaces: &[&str]) -> TypeInfo
+    {
+        TypeInfo::Class(ComBox::new(CoClass {
+            name: name.to_string().into(),
+            clsid: GUID::zero_guid(),
+            interfaces: interfaces
+                .iter()
+                .map(|itf| InterfaceRef {
+                    name: itf.to_string().into(),
+                    iid_automation: GUID::zero_guid(),
+                    iid_raw: GUID::zero_guid(),
+                })
+                .collect(),
+            options: ClassOptions::default(),
+            doc: None,
+        }))
+    }
+
+    #[test]
+    fn replace_file_creates_directories()
+    {
+        let dir = std::env::temp_dir().join("intercom_cli_replace_file_creates_directories");
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("nested").join("lib.idl");
+
+        replace_file(&path, b"first").unwrap();
+        replace_file(&path, b"second").unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert_eq!(std::fs::read_dir(dir.join("nested")).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    mod v1
+    {
+        #[intercom::com_interface]
+        pub trait IStable
+        {
+            fn sides(&self) -> u32;
+        }
+    }
+
+    mod v2
+    {
+        #[intercom::com_interface]
+        pub trait IStable
+        {
+            fn sides(&self) -> u32;
+            fn corners(&self) -> u32;
+        }
+    }
+
+    #[test]
+    fn changed_interface_fails_assert_stable()
+    {
+        use intercom::attributes::ComInterfaceTypeInfo;
+
+        let idl = |types| {
+            let lib = TypeLib::__new("lib".into(), GUID::zero_guid(), "1.0".into(), vec![], types);
+            let mut idl = vec![];
+            generators::idl::write(&lib, &generators::ModelOptions::default(), &mut idl).unwrap();
+            idl
+        };
+        let v1 = idl(<dyn v1::IStable>::gather_type_info());
+        let v2 = idl(<dyn v2::IStable>::gather_type_info());
+
+        let dir = std::env::temp_dir().join("intercom_cli_changed_interface_fails_assert_stable");
+        let _ = std::fs::remove_dir_all(&dir);
+        let snapshot = dir.join("lib.idl");
+        replace_file(&snapshot, &v1).unwrap();
+
+        assert_stable(&snapshot, &v1).unwrap();
+        let err = assert_stable(&snapshot, &v2).unwrap_err().to_string();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Both type systems gain the method.
+        assert!(err.starts_with("IDL differs from the snapshot `"));
+        let added = err
+            .lines()
+            .filter(|line| line.starts_with('+'))
+            .collect::<Vec<_>>();
+        assert_eq!(added.len(), 6);
+        assert_eq!(added[1], "+         u32 Corners();");
+        assert!(!err.lines().any(|line| line.starts_with('-')));
+    }
+
+    #[test]
+    fn diff_shows_context()
+    {
+        let expected = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let actual = "a\nb\nc\nd\ne\r\nF\ng\nh\n";
+        assert_eq!(
+            diff_lines(expected, expected.replace('\n', "\r\n").as_str()),
+            None
+        );
+        assert_eq!(
+            diff_lines(expected, actual).unwrap(),
+            "...\n  d\n  e\n- f\n+ F\n  g\n  h\n"
+        );
+    }
+
+    #[test]
+    fn exclude_test_interfaces()
+    {
+        let mut lib = TypeLib::__new(
+            "lib".into(),
+            GUID::zero_guid(),
+            "1.0".into(),
+            vec![],
+            vec![
+                interface("IPublic"),
+                interface("IMockTest"),
+                class("Public", &["IPublic", "IMockTest"]),
+                class("PublicTest", &["IPublic"]),
+            ],
+        );
+
+        exclude_types(&mut lib, &[glob::Pattern::new("*Test").unwrap()]);
+
+        let mut idl = vec![];
+        generators::idl::write(&lib, &generators::ModelOptions::default(), &mut idl).unwrap();
+        let idl = String::from_utf8(idl).unwrap();
+
+        assert!(idl.contains("interface IPublic"));
+        assert!(idl.contains("coclass Public"));
+        assert!(!idl.contains("IMockTest"));
+        assert!(!idl.contains("PublicTest"));
+    }
+
+    #[test]
+    fn merge_shares_identical_types()
+    {
+        let lib = |name: &str, types| {
+            TypeLib::__new(
+                name.to_string().into(),
+                GUID::zero_guid(),
+                "1.0".into(),
+                vec![],
+                types,
+            )
+        };
+        let merged = merge_typelibs(vec![
+            lib("first", vec![interface("IShared"), interface("IFirst")]),
+            lib(
+                "second",
+                vec![interface("IShared"), class("Second", &["IShared"])],
+            ),
+        ])
+        .unwrap();
+
+        assert_eq!(merged.name, "first");
+        let names = merged.types.iter().map(type_name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["IFirst", "IShared", "Second"]);
+    }
+
+    #[test]
+    fn merge_fails_on_duplicate_interface()
+    {
+        let other = interface_with_iid(
+            "IShared",
+            GUID::parse("7d1e3a5c-9b2f-4e6d-8a0c-1e3f5a7c9b2d").unwrap(),
+        );
+        let lib = |name: &str, types| {
+            TypeLib::__new(
+                name.to_string().into(),
+                GUID::zero_guid(),
+                "1.0".into(),
+                vec![],
+                types,
+            )
+        };
+        let err = merge_typelibs(vec![
+            lib("first", vec![interface("IShared")]),
+            lib("second", vec![other]),
+        ])
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`IShared` is defined by both `first` and `second`"
+        );
+    }
+
+    fn color(values: &[(&'static str, i64)]) -> TypeInfo
+    {
+        TypeInfo::Enum(ComBox::new(Enum {
+            name: "Color".into(),
+            values: values
+                .iter()
+                .map(|(name, value)| EnumValue {
+                    name: (*name).into(),
+                    value: *value,
+                })
+                .collect(),
+            v1_enum: false,
+        }))
+    }
+
+    #[test]
+    fn merge_compares_enum_definitions()
+    {
+        let lib = |name: &str, types| {
+            TypeLib::__new(
+                name.to_string().into(),
+                GUID::zero_guid(),
+                "1.0".into(),
+                vec![],
+                types,
+            )
+        };
+        let merged = merge_typelibs(vec![
+            lib("first", vec![color(&[("Red", 1), ("Blue", 2)])]),
+            lib("second", vec![color(&[("Red", 1), ("Blue", 2)])]),
+        ])
+        .unwrap();
+        assert_eq!(merged.types.len(), 1);
+
+        let err = merge_typelibs(vec![
+            lib("first", vec![color(&[("Red", 1), ("Blue", 2)])]),
+            lib("second", vec![color(&[("Red", 1), ("Green", 2)])]),
+        ])
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`Color` is defined by both `first` and `second`"
+        );
+    }
+
+    #[test]
+    fn virtual_workspace_lists_member_libraries()
+    {
+        let dir =
+            std::env::temp_dir().join("intercom_cli_virtual_workspace_lists_member_libraries");
+        let _ = std::fs::remove_dir_all(&dir);
+        let write = |path: &str, content: &str| {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\n    \"server\", # The COM server, not [\"client\"].\n    \"crates/*\",\n]\n",
+        );
+        write(
+            "server/Cargo.toml",
+            "[package]\nname = \"com-server\"\nversion = \"0.1.0\"\n\n\
+             [dependencies]\nlog = { version = \"0.4\", features = [\"std\"] }\n\n\
+             [lib]\ncrate-type = [\"cdylib\"]\n\n\
+             [[bin]]\nname = \"register\"\npath = \"src/lib.rs\"\n",
+        );
+        write("server/src/lib.rs", "");
+        write(
+            "crates/plugin/Cargo.toml",
+            "[package]\nname = \"plugin\"\nversion = \"0.1.0\"\n\n\
+             [lib]\nname = 'plugin_lib'\ncrate-type = [\n    \"rlib\", # For the tests.\n    \"cdylib\",\n]\n",
+        );
+        write("crates/plugin/src/lib.rs", "");
+        write(
+            "crates/shared/Cargo.toml",
+            "[package]\nname = \"shared\"\nversion = \"0.1.0\"\n",
+        );
+        write("crates/shared/src/lib.rs", "");
+
+        let target = dir.canonicalize().unwrap().join("target").join("release");
+        let lib = |name: &str| {
+            target.join(format!(
+                "{}{}{}",
+                std::env::consts::DLL_PREFIX,
+                name,
+                std::env::consts::DLL_SUFFIX
+            ))
+        };
+        let mut libs = library_paths(&dir, "release").unwrap();
+        libs.sort();
+        let mut expected = vec![lib("com_server"), lib("plugin_lib")];
+        expected.sort();
+        assert_eq!(libs, expected);
+
+        // The members share the target directory of the workspace.
+        assert_eq!(
+            library_paths(&dir.join("server").join("Cargo.toml"), "release").unwrap(),
+            vec![lib("com_server")]
+        );
+        assert!(library_paths(&dir.join("crates").join("shared"), "release").is_err());
+
+        // The libraries are used as they are.
+        assert_eq!(
+            library_paths(Path::new("lib.dll"), "debug").unwrap(),
+            vec![PathBuf::from("lib.dll")]
+        );
+    }
+
+    #[test]
+    fn root_package_lists_its_own_library()
+    {
+        let dir = std::env::temp_dir().join("intercom_cli_root_package_lists_its_own_library");
+        let _ = std::fs::remove_dir_all(&dir);
+        let write = |path: &str, content: &str| {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "Cargo.toml",
+            "[package]\nname = \"com-server\"\nversion = \"0.1.0\"\n\n\
+             [lib]\ncrate-type = [\"cdylib\"]\n\n\
+             [workspace]\nmembers = [\"plugin\"]\n",
+        );
+        write("src/lib.rs", "");
+        write(
+            "plugin/Cargo.toml",
+            "[package]\nname = \"plugin\"\nversion = \"0.1.0\"\n\n\
+             [lib]\ncrate-type = [\"cdylib\"]\n",
+        );
+        write("plugin/src/lib.rs", "");
+
+        // The root manifest stands for its own package only.
+        let target = dir.canonicalize().unwrap().join("target").join("debug");
+        assert_eq!(
+            library_paths(&dir, "debug").unwrap(),
+            vec![target.join(format!(
+                "{}com_server{}",
+                std::env::consts::DLL_PREFIX,
+                std::env::consts::DLL_SUFFIX
+            ))]
+        );
+    }
+
+    /// Documented interface.
+    #[intercom::com_interface]
+    trait IPartlyDocumented
+    {
+        /// Documented method.
+        fn documented(&self);
+
+        fn undocumented(&self);
+    }
+
+    #[intercom::com_interface]
+    trait ICalculator
+    {
+        fn add(&self, a: i32, b: i32) -> i32;
+        fn describe(&self, value: u32) -> intercom::ComResult<String>;
+    }
+
+    #[test]
+    fn dump_model_lists_methods()
+    {
+        use intercom::attributes::ComInterfaceTypeInfo;
+
+        let lib = TypeLib::__new(
+            "lib".into(),
+            GUID::zero_guid(),
+            "1.0".into(),
+            vec![],
+            <dyn ICalculator>::gather_type_info(),
+        );
+
+        let mut model = vec![];
+        generators::idl::write_model(&lib, &mut model).unwrap();
+        let model = String::from_utf8(model).unwrap();
+
+        let automation = model
+            .lines()
+            .skip_while(|line| !line.starts_with("interface ICalculator (Automation)"))
+            .skip(1)
+            .take_while(|line| line.starts_with("    "))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            automation,
+            vec![
+                "    add(in a: i32, in b: i32) -> i32",
+                "    describe(in value: u32, retval __out: BSTR) -> HRESULT",
+            ]
+        );
+        assert!(model.contains("interface ICalculator (Raw)"));
+    }
+
+    #[test]
+    fn undocumented_method_is_reported()
+    {
+        use intercom::attributes::ComInterfaceTypeInfo;
+
+        let lib = TypeLib::__new(
+            "lib".into(),
+            GUID::zero_guid(),
+            "1.0".into(),
+            vec![],
+            <dyn IPartlyDocumented>::gather_type_info(),
+        );
+        assert_eq!(
+            missing_helpstrings(&lib),
+            vec!["method `IPartlyDocumented::undocumented` has no doc comment"]
+        );
+
+        let lib = TypeLib::__new(
+            "lib".into(),
+            GUID::zero_guid(),
+            "1.0".into(),
+            vec![],
+            vec![interface("IUndocumented")],
+        );
+        assert_eq!(
+            missing_helpstrings(&lib),
+            vec!["interface `IUndocumented` has no doc comment"]
+        );
+    }
+
+    mod shapes
+    {
+        #[intercom::com_interface]
+        pub trait IShape
+        {
+            fn sides(&self) -> u32;
+        }
+    }
+
+    pub use self::shapes::IShape as IPolygon;
+
+    #[intercom::com_class(clsid = "{5d3c7e21-8f4a-4b6c-9d0e-1a2b3c4d5e6f}", IPolygon)]
+    struct Square;
+
+    impl IPolygon for Square
+    {
+        fn sides(&self) -> u32
+        {
+            4
+        }
+    }
+
+    #[test]
+    fn reexported_interface_uses_defined_name()
+    {
+        use intercom::attributes::ComClassTypeInfo;
+
+        let lib = TypeLib::__new(
+            "lib".into(),
+            GUID::zero_guid(),
+            "1.0".into(),
+            vec![],
+            Square::gather_type_info(),
+        );
+
+        let mut idl = vec![];
+        generators::idl::write(&lib, &generators::ModelOptions::default(), &mut idl).unwrap();
+        let idl = String::from_utf8(idl).unwrap();
+
+        assert!(idl.contains("interface IShape"));
+        assert!(idl.contains("coclass Square"));
+        assert!(!idl.contains("IPolygon"));
+    }
+
+    #[intercom::com_interface]
+    trait IUnpinned
+    {
+        fn value(&self) -> u32;
+    }
+
+    #[intercom::com_interface(
+        com_iid = "3a5e1c7d-0b2f-4d48-9e61-7f8a9b0c1d2e",
+        raw_iid = "4b6f2d8e-1c3a-4e59-8f72-8a9b0c1d2e3f"
+    )]
+    trait IPinned
+    {
+        fn value(&self) -> u32;
+    }
+
+    #[test]
+    fn unpinned_interface_fails_strict_guids()
+    {
+        use intercom::attributes::ComInterfaceTypeInfo;
+
+        let lib = TypeLib::__new(
+            "lib".into(),
+            GUID::zero_guid(),
+            "1.0".into(),
+            vec![],
+            <dyn IUnpinned>::gather_type_info(),
+        );
+        assert_eq!(
+            generated_guids(&lib),
+            vec!["interface `IUnpinned` has a name-derived IID"]
+        );
+
+        let lib = TypeLib::__new(
+            "lib".into(),
+            GUID::zero_guid(),
+            "1.0".into(),
+            vec![],
+            <dyn IPinned>::gather_type_info(),
+        );
+        assert!(generated_guids(&lib).is_empty());
+    }
+}
@@ -27,6 +27,21 @@ pub fn expand_com_class(
     let cls = model::ComClass::parse(&lib_name(), attr_tokens.into(), item_tokens.clone().into())?;
     let cls_ident = &cls.name;
     let cls_name = cls_ident.to_string();
+
+    // The COM clients control the lifetime of the objects so the objects
+    // cannot borrow any data. Report this instead of the less descriptive
+    // errors the lifetimes would result in.
+    if let Some(lt) = cls.generics.lifetimes().next() {
+        let msg = format!(
+            "COM class `{}` cannot have lifetime parameters: COM objects are owned \
+             by their clients and must own their data, consider replacing the `{}` \
+             references with owned values or `Arc`",
+            cls_name, lt.lifetime
+        );
+        output.push(quote_spanned!(lt.span() => compile_error!(#msg);));
+        return Ok(tokens_to_tokenstream(item_tokens, output));
+    }
+
     let (impl_generics, ty_generics, where_clause) = cls.generics.split_for_impl();
 
     // IUnknown vtable match. As the primary query_interface is implemented
@@ -161,10 +176,27 @@ pub fn expand_com_class(
                 } else
             ));
 
+            // The first dual interface serves the IDispatch.
+            if ts == ModelTypeSystem::Automation {
+                query_interface_match_arms.push(quote!(
+                    if <#maybe_dyn #itf as intercom::attributes::ComInterface>::DUAL
+                        && riid == <dyn intercom::IDispatch as intercom::attributes::ComInterfaceVariant<intercom::type_system::AutomationTypeSystem>>::iid() {
+                        let ptr = &vtables.#itf_variant
+                            as *const &#itf_attrib_data::VTable
+                            as *mut &#itf_attrib_data::VTable
+                            as intercom::raw::RawComPtr;
+                        intercom::logging::trace(|l| l(module_path!(), format_args!(
+                            "[{:p}] {}::query_interface({:-X}) -> IDispatch ({}) [{:p}]",
+                            vtables, #cls_name, riid, #itf_name, ptr)));
+                        ptr
+                    } else
+                ));
+            }
+
             // Define the support error info match arms.
             support_error_info_match_arms.push(quote!(
                 if riid == #itf_attrib_data::iid() {
-                    true
+                    <#maybe_dyn #itf as intercom::attributes::ComInterface>::SUPPORTS_ERROR_INFO
                 } else
             ));
         }
@@ -211,6 +243,32 @@ pub fn expand_com_class(
         }
     ));
 
+    // Lock guarding the method calls. The classes without a locking policy
+    // use `()`, which takes no space in the `ComBoxData`.
+    let lock = match &cls.locking {
+        Some(locking) => quote_spanned!(locking.span() => std::sync::#locking<()>),
+        None => quote!(()),
+    };
+
+    // Cleanup hook for the final release.
+    let on_release = cls.on_release.as_ref().map(|method| {
+        quote_spanned!(method.span() =>
+            fn on_release(&mut self) {
+                self.#method()
+            }
+        )
+    });
+
+    // The Debug implementation of the class is preferred over the class name.
+    let fmt_debug = quote!(
+        fn fmt_debug(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+        {
+            #[allow(unused_imports)]
+            use intercom::attributes::{FmtWithDebug as _, FmtWithName as _};
+            (&intercom::attributes::DebugSelector(self)).fmt_debug(f)
+        }
+    );
+
     // The actual ComClass implementation.
     let vtable_static_ident = Ident::new(
         &format!("Static{}", vtable_list_ident),
@@ -228,6 +286,9 @@ pub fn expand_com_class(
             const VTABLE : Self::VTableList = #vtable_list_ident {
                 #( #vtable_list_field_ptrs ),*
             };
+            type Lock = #lock;
+            #on_release
+            #fmt_debug
             fn query_interface(
                 vtables : &Self::VTableList,
                 riid : intercom::REFIID,
@@ -277,6 +338,30 @@ pub fn expand_com_class(
             pub const #clsid_ident : intercom::CLSID = #clsid_guid_tokens;
         );
         output.push(clsid_const);
+
+        // Class factory used by the `com_library`. The class type is given
+        // as a type parameter so the `Default` requirement of the factory
+        // applies only to the classes that are listed in the library.
+        let factory_ident = idents::class_factory(cls_ident);
+        let (factory_bounds, create_factory) = match cls.no_class_factory {
+            false => (
+                quote!(Default + intercom::attributes::ComClass),
+                quote!(Some(intercom::ClassFactory::<T>::create(riid, pout))),
+            ),
+            true => (quote!(intercom::attributes::ComClass), quote!(None)),
+        };
+        output.push(quote!(
+            #[allow(non_snake_case)]
+            #[allow(dead_code)]
+            #[doc(hidden)]
+            pub unsafe fn #factory_ident<T: #factory_bounds>(
+                riid: intercom::REFIID,
+                pout: *mut intercom::raw::RawComPtr,
+            ) -> Option<intercom::raw::HRESULT>
+            {
+                #create_factory
+            }
+        ));
     }
 
     output.push(
@@ -305,17 +390,33 @@ fn create_get_typeinfo_function(cls: &model::ComClass) -> Result<TokenStream, St
         }
     };
     let clsid_tokens = utils::get_guid_tokens(&clsid, Span::call_site());
+    let no_class_factory = cls.no_class_factory;
+    let generated_clsid = cls.generated_clsid;
+    let appobject = cls.appobject;
+    let doc = doc_tokens(&cls.doc, Span::call_site());
     let (impl_generics, ty_generics, where_clause) = cls.generics.split_for_impl();
-    let (interfaces, interface_info): (Vec<_>, Vec<_>) = cls
-        .interfaces
-        .iter()
+
+    // The default interface is listed first.
+    let mut itf_paths = cls.interfaces.iter().collect::<Vec<_>>();
+    if !itf_paths.is_empty() {
+        let default_interface = itf_paths.remove(cls.default_interface);
+        itf_paths.insert(0, default_interface);
+    }
+    let (interfaces, interface_info): (Vec<_>, Vec<_>) = itf_paths
+        .into_iter()
         .map(|itf_path| {
-            let itf_name = itf_path.get_some_ident().expect("#[com_interface] had no ident").to_string();
             let maybe_dyn = match cls.is_self_path(itf_path) {
                 true => quote!(),
                 false => quote_spanned!(itf_path.span() => dyn),
             };
 
+            // The path may name the interface through a `use` alias. Refer to
+            // the interface by the name it was defined with so the reference
+            // matches the interface type info.
+            let itf_name = quote!(
+                <#maybe_dyn #itf_path as intercom::type_system::ForeignType>::type_name()
+            );
+
             // Access the ComInterfaceVariant through the ComInterface trait to
             // ensure the error messages will first report missing ComInterface
             // trait.
@@ -340,12 +441,17 @@ fn create_get_typeinfo_function(cls: &model::ComClass) -> Result<TokenStream, St
         {
             fn gather_type_info() -> Vec<intercom::typelib::TypeInfo>
             {
+                let mut cls = intercom::typelib::CoClass::__new(
+                    #cls_name.into(),
+                    #clsid_tokens,
+                    vec![ #( #interfaces ),* ]
+                );
+                cls.options.no_class_factory = #no_class_factory;
+                cls.options.generated_clsid = #generated_clsid;
+                cls.options.appobject = #appobject;
+                cls.doc = #doc;
                 let mut r = vec![ intercom::typelib::TypeInfo::Class(
-                    intercom::ComBox::new( intercom::typelib::CoClass::__new(
-                        #cls_name.into(),
-                        #clsid_tokens,
-                        vec![ #( #interfaces ),* ]
-                    ) ) )
+                    intercom::ComBox::new( cls ) )
                 ];
                 #( #interface_info )*
                 r
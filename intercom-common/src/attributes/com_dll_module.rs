@@ -0,0 +1,133 @@
+use super::common::*;
+use crate::prelude::*;
+
+use crate::model;
+
+use quote::ToTokens;
+
+/// Expands the `com_dll_module` attribute.
+///
+/// The attribute expansion results in the following items:
+///
+/// - `#[no_mangle]` attribute for the entry points that lack one.
+/// - `__gather_module_types` function describing the entry points and the
+///   constants.
+/// - `__get_module_class_factory` function so that the module can be listed
+///   in the `com_library!` as a `module`.
+pub fn expand_com_dll_module(
+    attr_tokens: TokenStreamNightly,
+    item_tokens: TokenStreamNightly,
+) -> Result<TokenStreamNightly, model::ParseError>
+{
+    let module = model::ComDllModule::parse(&lib_name(), attr_tokens.into(), item_tokens.into())?;
+
+    let module_name = module.name.to_string();
+    let dll_name = &module.dll_name;
+    let functions = module.functions.iter().map(|f| {
+        let entry_name = f.name.to_string();
+        let return_type = match &f.return_type {
+            Some(ty) => quote_spanned!(f.span => intercom::typelib::Arg {
+                name: "".into(),
+                ty: <#ty as intercom::type_system::ForeignType>::type_name().into(),
+                indirection_level: <#ty as intercom::type_system::ForeignType>::indirection_level(),
+                direction: intercom::typelib::Direction::Return,
+                idl_ty: None,
+                length_of: None,
+            }),
+            None => quote_spanned!(f.span => intercom::typelib::Arg {
+                name: "".into(),
+                ty: "void".into(),
+                indirection_level: 0,
+                direction: intercom::typelib::Direction::Return,
+                idl_ty: None,
+                length_of: None,
+            }),
+        };
+        let params = f.args.iter().map(|(name, ty)| {
+            let arg_name = name.to_string();
+            quote_spanned!(f.span => intercom::typelib::Arg {
+                name: #arg_name.into(),
+                ty: <#ty as intercom::type_system::ForeignType>::type_name().into(),
+                indirection_level: <#ty as intercom::type_system::ForeignType>::indirection_level(),
+                direction: intercom::typelib::Direction::In,
+                idl_ty: None,
+                length_of: None,
+            })
+        });
+        let doc = doc_tokens(&f.doc, f.span);
+        quote_spanned!(f.span =>
+            intercom::ComBox::new(intercom::typelib::Method {
+                name: #entry_name.into(),
+                return_type: #return_type,
+                parameters: vec![ #( #params ),* ],
+                doc: #doc,
+                kind: intercom::typelib::MethodKind::Method,
+                options: Default::default(),
+            })
+        )
+    });
+    let constants = module.constants.iter().map(|c| {
+        let name = c.name.to_string();
+        let value = &c.value;
+        let ty = &c.ty;
+        let type_name = match &c.idl_ty {
+            Some(idl_ty) => quote_spanned!(c.span => #idl_ty),
+            None => quote_spanned!(c.span =>
+                <#ty as intercom::type_system::ForeignType>::type_name()),
+        };
+        quote_spanned!(c.span =>
+            intercom::typelib::Constant {
+                name: #name.into(),
+                ty: #type_name.into(),
+                value: #value.into(),
+            }
+        )
+    });
+
+    let generated: Vec<syn::Item> = vec![
+        syn::parse_quote!(
+            #[allow(dead_code)]
+            #[doc(hidden)]
+            pub unsafe fn __get_module_class_factory(
+                _rclsid: intercom::REFCLSID,
+                _riid: intercom::REFIID,
+                _pout: *mut intercom::raw::RawComPtr,
+            ) -> Option<intercom::raw::HRESULT>
+            {
+                // The DLL modules do not contain any classes.
+                None
+            }
+        ),
+        syn::parse_quote!(
+            #[allow(dead_code)]
+            #[doc(hidden)]
+            pub fn __gather_module_types() -> Vec<intercom::typelib::TypeInfo>
+            {
+                vec![intercom::typelib::TypeInfo::Module(intercom::ComBox::new(
+                    intercom::typelib::Module {
+                        name: #module_name.into(),
+                        dll_name: #dll_name.into(),
+                        functions: vec![ #( #functions ),* ],
+                        constants: vec![ #( #constants ),* ],
+                    }
+                ))]
+            }
+        ),
+    ];
+
+    // The entry points must be exported with their Rust names as these are
+    // the names the `entry` attributes refer to.
+    let mut item = module.item;
+    if let Some((_, content)) = &mut item.content {
+        for module_item in content.iter_mut() {
+            if let syn::Item::Fn(f) = module_item {
+                if f.sig.abi.is_some() && !f.attrs.iter().any(|a| a.path.is_ident("no_mangle")) {
+                    f.attrs.push(syn::parse_quote!(#[no_mangle]));
+                }
+            }
+        }
+        content.extend(generated);
+    }
+
+    Ok(item.into_token_stream().into())
+}
@@ -64,6 +64,75 @@ pub fn expand_com_interface(
     let itf_path = &itf.path;
     let itf_name = itf.ident.to_string();
     let itf_ref = &itf.itf_ref;
+    let item_tokens = strip_com_arg_attributes(item_tokens);
+
+    // The `&[Variant]` parameters are vararg parameters for the automation
+    // clients and the vararg parameter must be the last one.
+    if let Some(variant) = itf.variants.values().next() {
+        for method in &variant.methods {
+            let leading_args = method.args.len().saturating_sub(1);
+            if let Some(arg) = method.args[..leading_args]
+                .iter()
+                .find(|arg| utils::is_variant_slice(&arg.ty))
+            {
+                let msg = format!(
+                    "`&[Variant]` parameter `{}` must be the last parameter of `{}`",
+                    arg.name, method.name
+                );
+                output.push(quote_spanned!(arg.span => compile_error!(#msg);));
+            }
+
+            // The Rust callers of the trait interfaces would have no way to
+            // receive the errors of the calls returning bare tuples or
+            // optional values.
+            let is_tuple =
+                matches!(&method.rust_return_ty, syn::Type::Tuple(t) if !t.elems.is_empty());
+            let is_option = utils::get_option_ty(&method.rust_return_ty).is_some();
+            if itf.item_type == utils::InterfaceType::Trait {
+                let msg = match (is_tuple, is_option) {
+                    (true, _) => Some(format!(
+                        "`{}` returns a tuple, which cannot carry the errors of the call: \
+                         return `ComResult<(..)>` instead",
+                        method.name
+                    )),
+                    (_, true) => Some(format!(
+                        "`{}` returns an `Option`, which cannot carry the errors of the call: \
+                         return `ComResult<Option<..>>` instead",
+                        method.name
+                    )),
+                    _ => None,
+                };
+                if let Some(msg) = msg {
+                    output.push(
+                        quote_spanned!(method.rust_return_ty.span() => compile_error!(#msg);),
+                    );
+                }
+            }
+
+            // The width of `usize` and `isize` may differ between the client
+            // and the server so these must be chosen explicitly.
+            let types = method
+                .args
+                .iter()
+                .map(|arg| &arg.ty)
+                .chain(std::iter::once(&method.rust_return_ty))
+                .filter(|_| !itf.target_pointer_width);
+            for ty in types {
+                if let Some(ident) = utils::find_pointer_width_ty(ty) {
+                    let msg = format!(
+                        "`{}` in `{}` is pointer-width and may differ between the COM \
+                         client and server: use a fixed width type such as `u32` or `u64`, \
+                         or specify `pointer_width = \"target\"` on the interface",
+                        ident, method.name
+                    );
+                    output.push(quote_spanned!(ident.span() => compile_error!(#msg);));
+                }
+            }
+        }
+    }
+    if !output.is_empty() {
+        return Ok(tokens_to_tokenstream(item_tokens, output));
+    }
 
     let mut itf_output = InterfaceOutput::default();
     for (ts, itf_variant) in &itf.variants {
@@ -104,9 +173,11 @@ pub fn expand_com_interface(
             };
             let self_arg = &method.info.rust_self_arg;
             let return_ty = &method.info.rust_return_ty;
+            let cfgs = &method.info.cfgs;
 
             // Rust to COM implementation.
             impls.push(quote_spanned!(method.info.signature_span =>
+                #( #cfgs )*
                 #unsafety fn #method_rust_ident(
                     #self_arg, #( #impl_args ),*
                 ) -> #return_ty {
@@ -146,6 +217,14 @@ pub fn expand_com_interface(
 
     // Implement the ComInterface for the trait.
     let iid_arms = itf_output.iid_arms;
+    let primary_iid = itf
+        .variants
+        .get(&ModelTypeSystem::Automation)
+        .or_else(|| itf.variants.values().next())
+        .map(|variant| utils::get_guid_tokens(&variant.iid, itf.span))
+        .ok_or_else(|| {
+            model::ParseError::ComInterface(itf.ident.to_string(), "No type systems".into())
+        })?;
     let (deref_impl, deref_ret) = if itf.item_type == utils::InterfaceType::Trait {
         (
             quote_spanned!(itf.span => com_itf),
@@ -164,7 +243,7 @@ pub fn expand_com_interface(
         (
             quote_spanned!(itf.span =>
                 let some_iunk : &intercom::ComItf<dyn intercom::interfaces::RawIUnknown> = com_itf.as_raw_iunknown();
-                let iunknown_iid = intercom::IUnknown::iid(
+                let iunknown_iid = <dyn intercom::IUnknown>::iid(
                         intercom::type_system::TypeSystemName::Automation )
                             .expect( "IUnknown must have Automation IID" );
                 let primary_iunk = some_iunk.query_interface( iunknown_iid )
@@ -188,11 +267,25 @@ pub fn expand_com_interface(
         )
     };
 
+    let dual = itf.dual;
+    let own_error_info = itf.error_info;
+    let supports_error_info = match &itf.base_interface {
+        Some(base) => quote!(
+            #own_error_info || <dyn #base as intercom::attributes::ComInterface>::SUPPORTS_ERROR_INFO
+        ),
+        None => quote!(#own_error_info),
+    };
     output.push(quote_spanned!(itf.span =>
         impl intercom::attributes::ComInterface for #itf_ref {
 
             type TSelf = #itf_ref;
 
+            const IID: intercom::IID = #primary_iid;
+
+            const DUAL: bool = #dual;
+
+            const SUPPORTS_ERROR_INFO: bool = #supports_error_info;
+
             #[doc = "Returns the IID of the requested interface."]
             fn iid_ts<TS: intercom::type_system::TypeSystem>() -> &'static intercom::IID
                 where Self: intercom::attributes::ComInterfaceVariant<TS>
@@ -217,6 +310,15 @@ pub fn expand_com_interface(
         }
     ));
 
+    // Serve the late bound calls of the dual interfaces.
+    if let Some(variant) = itf
+        .variants
+        .get(&ModelTypeSystem::Automation)
+        .filter(|_| itf.dual)
+    {
+        output.push(create_dispatch_impl(&itf, variant));
+    }
+
     // Implement type info for the interface.
     output.push(quote_spanned!(itf.span =>
 
@@ -317,6 +419,31 @@ fn process_itf_variant(
         utils::InterfaceType::Trait if itf.implemented_by.is_some() => quote!(),
         utils::InterfaceType::Trait => quote!(+ #itf_ident),
     };
+    let dispatch_bound = match itf.dual {
+        true => quote!(+ intercom::attributes::ComDispatch),
+        false => quote!(),
+    };
+    let unpin_bound = match itf_variant.methods.iter().any(|m| requires_unpin(itf, m)) {
+        true => quote!(+ Unpin),
+        false => quote!(),
+    };
+    // The struct interfaces are implemented by a single class, which the
+    // virtual methods check directly.
+    let requires_lock = itf_variant
+        .methods
+        .iter()
+        .any(|m| requires_exclusive_lock(itf, m));
+    let lock_bound = match itf.item_type {
+        utils::InterfaceType::Trait if requires_lock => {
+            quote!(<S as intercom::attributes::ComClass>::Lock: intercom::attributes::ExclusiveClassLock,)
+        }
+        _ => quote!(),
+    };
+    let base_bound = match &itf.base_interface {
+        Some(base) => quote_spanned!(itf.span =>
+            dyn #base: intercom::attributes::ComInterfaceVTableFor<I, S, #ts_type_tokens>,),
+        None => quote!(),
+    };
     if itf.vtable_of.is_none() {
         output.push(quote_spanned!(itf.span =>
             #[allow(non_camel_case_types)]
@@ -329,8 +456,10 @@ fn process_itf_variant(
 
             #[allow(unused)]
             impl<I, S> intercom::attributes::ComInterfaceVTableFor<I, S, #ts_type_tokens> for #itf_ref
-            where I: ?Sized,
-                  S: intercom::attributes::ComClassInterface<I, #ts_type_tokens> + intercom::attributes::ComClass #itf_bound,
+            where I: ?Sized #dispatch_bound,
+                  S: intercom::attributes::ComClassInterface<I, #ts_type_tokens> + intercom::attributes::ComClass #itf_bound #unpin_bound,
+                  #lock_bound
+                  #base_bound
             {
                 const VTABLE: #vtable_path = #vtable_path {
                     #( #vtbl_values, )*
@@ -354,6 +483,153 @@ fn process_itf_variant(
     ));
 }
 
+/// Creates the `ComDispatch` implementation serving the `IDispatch` calls of
+/// a dual interface.
+///
+/// The calls are made through the `ComItf` of the interface. The methods
+/// taking parameters that can't be read from a `Variant`, such as the
+/// out-parameters, or returning values that can't be written in one, are
+/// not callable through `IDispatch`.
+fn create_dispatch_impl(
+    itf: &model::ComInterface,
+    itf_variant: &model::ComInterfaceVariant,
+) -> TokenStream
+{
+    let itf_path = &itf.path;
+    let itf_ref = &itf.itf_ref;
+    let dispids = itf_variant.dispids();
+
+    // The names are matched ignoring the case and the underscores.
+    let normalize = |name: &str| name.replace('_', "").to_lowercase();
+    let mut name_arms = vec![];
+    for (method, dispid) in itf_variant.methods.iter().zip(&dispids) {
+        let names = iter::once(method.name.to_string()).chain(method.property_name());
+        for name in names.map(|n| normalize(&n)) {
+            name_arms.push(quote_spanned!(method.signature_span => #name => Some(#dispid)));
+        }
+    }
+
+    let mut invoke_arms = vec![];
+    for (method, dispid) in itf_variant.methods.iter().zip(&dispids) {
+        if let Some(arm) = dispatch_invoke_arm(itf_path, method, *dispid) {
+            invoke_arms.push(arm);
+        }
+    }
+
+    quote_spanned!(itf.span =>
+        #[allow(clippy::all)]
+        impl intercom::attributes::ComDispatch for #itf_ref {
+            fn dispid_of_name(name: &str) -> Option<i32>
+            {
+                #[allow(unreachable_patterns)]
+                match name.replace('_', "").to_lowercase().as_str() {
+                    #( #name_arms, )*
+                    _ => None,
+                }
+            }
+
+            #[allow(unused_variables, unused_mut)]
+            fn invoke(
+                __itf: &mut intercom::ComItf<Self>,
+                __dispid: i32,
+                __flags: u16,
+                __args: Vec<intercom::Variant>,
+            ) -> intercom::ComResult<intercom::Variant>
+            {
+                let __is_put = __flags
+                    & (intercom::raw::DISPATCH_PROPERTYPUT | intercom::raw::DISPATCH_PROPERTYPUTREF)
+                    != 0;
+                let mut __args = __args.into_iter();
+                #[allow(unreachable_patterns, unused_unsafe)]
+                match (__dispid, __is_put) {
+                    #( #invoke_arms )*
+                    _ => Err(intercom::ComError::from(intercom::raw::DISP_E_MEMBERNOTFOUND)),
+                }
+            }
+        }
+    )
+}
+
+/// Creates the `ComDispatch::invoke` match arm calling the method.
+///
+/// Returns `None` if the method isn't callable through `IDispatch`.
+fn dispatch_invoke_arm(
+    itf_path: &syn::Path,
+    method: &ComMethodInfo,
+    dispid: i32,
+) -> Option<TokenStream>
+{
+    if method.is_future || method.is_operation {
+        return None;
+    }
+    let (value_ty, fallible) = match (&method.retval_type, &method.return_type) {
+        (Some(retval), _) => (retval.clone(), true),
+        (None, Some(ret)) => (ret.clone(), false),
+        (None, None) => (utils::unit_ty(method.signature_span), false),
+    };
+    if !utils::is_dispatch_return_ty(&value_ty) {
+        return None;
+    }
+
+    // The trailing `&[Variant]` receives the remaining arguments.
+    let mut reads = vec![];
+    let mut params = vec![];
+    let mut arg_count = 0usize;
+    let mut vararg = false;
+    for (idx, arg) in method.args.iter().enumerate() {
+        let name = &arg.name;
+        if idx + 1 == method.args.len() && utils::is_variant_slice(&arg.ty) {
+            reads.push(quote_spanned!(arg.span =>
+                let #name: Vec<intercom::Variant> = __args.by_ref().collect();
+            ));
+            params.push(quote_spanned!(arg.span => &#name));
+            vararg = true;
+            continue;
+        }
+        if arg.dir != Direction::In {
+            return None;
+        }
+        let ty = utils::get_dispatch_arg_ty(&arg.ty)?;
+        reads.push(quote_spanned!(arg.span =>
+            let #name: #ty = std::convert::TryFrom::try_from(__args.next().unwrap())
+                .map_err(|_| intercom::ComError::from(intercom::raw::DISP_E_TYPEMISMATCH))?;
+        ));
+        params.push(match arg.ty {
+            syn::Type::Reference(..) => quote_spanned!(arg.span => &#name),
+            _ => quote_spanned!(arg.span => #name),
+        });
+        arg_count += 1;
+    }
+
+    let count_check = match vararg {
+        true => quote!(__args.len() < #arg_count),
+        false => quote!(__args.len() != #arg_count),
+    };
+    let is_put = method.kind == crate::methodinfo::MethodKind::PropertyPut;
+    let method_ident = &method.name;
+    let call = quote_spanned!(method.signature_span =>
+        <intercom::ComItf<dyn #itf_path> as #itf_path>::#method_ident(__itf, #( #params ),*));
+    let call = match method.is_unsafe {
+        true => quote_spanned!(method.signature_span => unsafe { #call }),
+        false => call,
+    };
+    let value = match fallible {
+        true => quote_spanned!(method.signature_span =>
+            #call.map_err(Into::<intercom::ComError>::into)?),
+        false => call,
+    };
+    Some(quote_spanned!(method.signature_span =>
+        (#dispid, #is_put) => {
+            if #count_check {
+                return Err(intercom::ComError::from(intercom::raw::DISP_E_BADPARAMCOUNT));
+            }
+            #( #reads )*
+            let value: #value_ty = #value;
+            Ok(intercom::Variant::from(value))
+        }
+    ))
+}
+
 /// Creates the functions responsible for delegating calls from Rust to COM
 /// interfaces.
 ///
@@ -372,7 +648,7 @@ fn rust_to_com_delegate(
     // require temporary variables during the COM call. Format their
     // declarations.
     let infallible = method_info.returnhandler.is_infallible();
-    let out_arg_declarations = method_info
+    let mut out_arg_declarations = method_info
         .returnhandler
         .com_out_args()
         .iter()
@@ -384,22 +660,89 @@ fn rust_to_com_delegate(
         })
         .collect::<Vec<_>>();
 
-    // Format the in and out parameters for the COM call.
-    let params: Vec<_> = method_info
-        .raw_com_args()
-        .into_iter()
-        .map(|com_arg| {
-            let name = com_arg.name;
-            match com_arg.dir {
-                Direction::In => {
-                    com_arg
-                        .handler
-                        .rust_to_com(&name, com_arg.span, Direction::In, infallible)
-                }
-                Direction::Out | Direction::Retval => quote_spanned!(com_arg.span => &mut #name ),
+    // Format the in and out parameters for the COM call. The explicit
+    // out-parameters are received into temporaries and written to the Rust
+    // references only if the call succeeds. The in-out parameters pass a copy
+    // of the value to the callee. The value written back is always taken
+    // over, but it is stored in the Rust reference only if the call succeeds.
+    let return_ident = Ident::new("__result", Span::call_site());
+    let mut params = vec![];
+    let mut out_arg_writes = vec![];
+    for arg in &method_info.args {
+        let name = &arg.name;
+        match arg.dir {
+            Direction::In if arg.handler.is_counted() => {
+                let count = idents::element_count(name);
+                let lease = Ident::new(&format!("__{}_lease", name), arg.span);
+                let value = arg.handler.rust_to_com_counted(name, arg.span);
+                out_arg_declarations.push(quote_spanned!(arg.span =>
+                    let (#name, #count, #lease) = #value;
+                ));
+                params.push(quote_spanned!(arg.span => #name));
+                params.push(quote_spanned!(arg.span => #count));
             }
-        })
-        .collect();
+            Direction::In => {
+                params.push(
+                    arg.handler
+                        .rust_to_com(name, arg.span, Direction::In, infallible),
+                );
+            }
+            Direction::Out | Direction::Retval | Direction::InOut => {
+                let value = arg.out_value_ident();
+                let ty = arg.handler.com_ty(arg.span);
+                let rust_value =
+                    arg.handler
+                        .com_to_rust(&value, arg.span, Direction::Out, infallible);
+                let unwrap = match infallible {
+                    true => quote!(),
+                    false => quote!(?),
+                };
+                let write = match arg.dir {
+                    Direction::InOut => {
+                        let com_value =
+                            arg.handler
+                                .rust_to_com(&value, arg.span, Direction::InOut, infallible);
+                        out_arg_declarations.push(quote_spanned!(arg.span =>
+                            let mut #value : #ty = {
+                                let #value = (*#name).clone();
+                                #com_value
+                            };
+                        ));
+                        out_arg_writes.push(quote_spanned!(arg.span =>
+                            let #value = #rust_value;
+                        ));
+                        quote_spanned!(arg.span => *#name = #value#unwrap;)
+                    }
+                    _ => {
+                        let default = arg.handler.default_value();
+                        out_arg_declarations.push(quote_spanned!(arg.span =>
+                            let mut #value : #ty = #default;
+                        ));
+                        quote_spanned!(arg.span => *#name = #rust_value#unwrap;)
+                    }
+                };
+                out_arg_writes.push(match method_info.retval_type {
+                    Some(_) => quote_spanned!(arg.span =>
+                        if __intercom_return.is_ok() {
+                            #write
+                        }
+                    ),
+                    None => write,
+                });
+                params.push(quote_spanned!(arg.span => &mut #value));
+            }
+        }
+    }
+    params.extend(
+        method_info
+            .returnhandler
+            .com_out_args()
+            .into_iter()
+            .map(|com_arg| {
+                let name = com_arg.name;
+                quote_spanned!(com_arg.span => &mut #name )
+            }),
+    );
 
     // Combine the parameters into the final parameter list.
     // This includes the 'this' pointer and both the IN and OUT
@@ -409,7 +752,6 @@ fn rust_to_com_delegate(
             .chain(params);
 
     // Create the return statement.
-    let return_ident = Ident::new("__result", Span::call_site());
     let return_statement = method_info.returnhandler.com_to_rust_return(&return_ident);
 
     // Resolve some of the fields needed for quote.
@@ -433,7 +775,9 @@ fn rust_to_com_delegate(
 
                 let __intercom_iid = #iid_tokens;
                 #[allow(unused_braces)]
-                return { #return_statement };
+                let __intercom_return = { #return_statement };
+                #( #out_arg_writes )*
+                return __intercom_return;
             }
         )
     } else {
@@ -452,7 +796,9 @@ fn rust_to_com_delegate(
 
                 let __intercom_iid = #iid_tokens;
                 #[allow(unused_braces)]
-                Ok( { #return_statement } )
+                let __intercom_return = { #return_statement };
+                #( #out_arg_writes )*
+                Ok( __intercom_return )
             } )();
 
             return match __intercom_result {
@@ -478,11 +824,14 @@ fn format_method_vtable_entries(
         utils::InterfaceType::Trait => quote!(::<I, S>),
     };
     let params = method_info.get_parameters_tokenstream();
+    let cfgs = &method_info.cfgs;
 
     (
         quote_spanned!(method_info.signature_span =>
+            #( #cfgs )*
             pub #method_ident : unsafe extern "system" fn(#params) -> #ret_ty),
         quote_spanned!(method_info.signature_span =>
+            #( #cfgs )*
             #method_ident: #method_impl_ident #generics),
     )
 }
@@ -508,9 +857,14 @@ fn create_virtual_method(
     let in_args: Vec<_> = method_info
         .args
         .iter()
-        .map(|ca| {
-            ca.handler
-                .com_to_rust(&ca.name, ca.span, Direction::In, infallible)
+        .map(|ca| match ca.dir {
+            Direction::In => ca
+                .handler
+                .com_to_rust(&ca.name, ca.span, Direction::In, infallible),
+            Direction::Out | Direction::Retval | Direction::InOut => {
+                let value = ca.out_value_ident();
+                quote_spanned!(ca.span => &mut #value)
+            }
         })
         .collect();
 
@@ -518,11 +872,91 @@ fn create_virtual_method(
     let return_statement = method_info.returnhandler.rust_to_com_return(&return_ident);
     let ret_ty = method_info.returnhandler.com_ty();
 
+    // The explicit out-parameters are cleared before the call so that they
+    // are left zeroed on failure and written only once the call succeeds.
+    let mut out_arg_declarations = vec![];
+    let mut out_arg_writes = vec![];
+    let mut out_count_checks = vec![];
+    for arg in method_info.args.iter().filter(|a| a.dir == Direction::Out) {
+        let name = &arg.name;
+        let value = arg.out_value_ident();
+        let ty = arg.handler.rust_ty();
+        let default = arg.handler.default_value();
+        let ok_value = arg
+            .handler
+            .rust_to_com(&value, arg.span, Direction::Out, infallible);
+        let condition = match method_info.retval_type {
+            Some(_) => quote_spanned!(arg.span => #return_ident.is_ok() && !#name.is_null()),
+            None => quote_spanned!(arg.span => !#name.is_null()),
+        };
+        out_arg_declarations.push(quote_spanned!(arg.span =>
+            if !#name.is_null() {
+                *#name = #default;
+            }
+            let mut #value : #ty = Default::default();
+        ));
+        // The element count written to the caller must stay within the
+        // capacity the caller gave for the same buffer.
+        let capacity = method_info.args.iter().find(|a| {
+            a.dir == Direction::In && a.length_of.is_some() && a.length_of == arg.length_of
+        });
+        if let (Some(capacity), Some(buffer)) = (capacity, &arg.length_of) {
+            let capacity = &capacity.name;
+            let msg = format!("{} exceeds the capacity {} of {}", name, capacity, buffer);
+            out_count_checks.push(quote_spanned!(arg.span =>
+                if #condition && #value > #capacity {
+                    return Err(intercom::ComError::E_FAIL.with_message(#msg));
+                }
+            ));
+        }
+        out_arg_writes.push(quote_spanned!(arg.span =>
+            if #condition {
+                *#name = #ok_value;
+            }
+        ));
+    }
+
+    // The counts are checked before any of the values are written.
+    out_count_checks.append(&mut out_arg_writes);
+    let mut out_arg_writes = out_count_checks;
+
+    // The in-out parameters take over the value passed by the caller and
+    // always hand back the value held after the call, even if the call
+    // fails. This allows the callee to reallocate values such as `BSTR`s.
+    for arg in method_info
+        .args
+        .iter()
+        .filter(|a| a.dir == Direction::InOut)
+    {
+        let name = &arg.name;
+        let value = arg.out_value_ident();
+        let ty = arg.handler.rust_ty();
+        let rust_value = arg
+            .handler
+            .com_to_rust(name, arg.span, Direction::InOut, infallible);
+        let com_value = arg
+            .handler
+            .rust_to_com(&value, arg.span, Direction::InOut, infallible);
+        out_arg_declarations.push(quote_spanned!(arg.span =>
+            let mut #value : #ty = match #name.is_null() {
+                true => Default::default(),
+                false => #rust_value,
+            };
+        ));
+        out_arg_writes.push(quote_spanned!(arg.span =>
+            if !#name.is_null() {
+                *#name = #com_value;
+            }
+        ));
+    }
+
     // Figure out how to get the self struct reference.
     let self_struct_expr = if itf.implemented_by.is_some() {
         quote!(&*self_combox)
     } else if method_info.is_const {
         quote!(&**self_combox)
+    } else if method_info.is_pinned {
+        quote!(intercom::ComBoxData::pin_mut(&mut *self_combox))
     } else {
         quote!(&mut **self_combox)
     };
@@ -544,24 +978,96 @@ fn create_virtual_method(
     // Since "+ Struct" is an invalid bound and wouldn't really make sense
     // anywya, we won't use generic parameters on struct impl based implicit
     // interfaces.
+    let requires_unpin = requires_unpin(itf, method_info);
+    let self_span = method_info.rust_self_arg.span();
     let (generics, bounds, s_ref, i_ref) = match itf.item_type {
-        utils::InterfaceType::Struct => (quote!(), quote!(), quote!(#itf_path), quote!(#itf_path)),
+        utils::InterfaceType::Struct => (
+            quote!(),
+            match requires_unpin {
+                true => quote_spanned!(self_span => where #itf_path: Unpin),
+                false => quote!(),
+            },
+            quote!(#itf_path),
+            quote!(#itf_path),
+        ),
         utils::InterfaceType::Trait => (
             quote!(<I, S>),
-            quote!(
-                where I: ?Sized,
-                      S: #attr_comclassinterface<I, #ts_type_tokens> + intercom::attributes::ComClass #required_itf
-            ),
+            {
+                let unpin = match requires_unpin {
+                    true => quote_spanned!(self_span => + Unpin),
+                    false => quote!(),
+                };
+                let exclusive = match requires_exclusive_lock(itf, method_info) {
+                    true => quote_spanned!(self_span =>
+                        , <S as intercom::attributes::ComClass>::Lock: intercom::attributes::ExclusiveClassLock),
+                    false => quote!(),
+                };
+                quote!(
+                    where I: ?Sized,
+                          S: #attr_comclassinterface<I, #ts_type_tokens> + intercom::attributes::ComClass #required_itf #unpin
+                          #exclusive
+                )
+            },
             quote!(S),
             quote!(I),
         ),
     };
 
+    // The operations convert the parameters during the call and run the
+    // method itself on the intercom thread pool.
+    let call = match method_info.is_operation {
+        true => {
+            let names = method_info.args.iter().map(|a| &a.name).collect::<Vec<_>>();
+            quote_spanned!(method_info.signature_span => {
+                #( let #names = #in_args; )*
+                Ok::<_, intercom::ComError>(intercom::operation::spawn_method(
+                    self_struct,
+                    move |self_struct: &#s_ref| self_struct.#method_ident( #( #names ),* ),
+                ))
+            })
+        }
+        false => call,
+    };
+
+    // Figure out the lock to hold during the call. The `&mut self` methods
+    // require the class to specify a locking policy. The classes and the
+    // interfaces are expanded separately so the policy is checked by the
+    // compiler through the `ExclusiveClassLock` bound on the class lock.
+    let lock_expr = if itf.implemented_by.is_some() {
+        quote!(())
+    } else if !requires_exclusive_lock(itf, method_info) {
+        quote!((*self_combox).lock_shared())
+    } else {
+        quote_spanned!(method_info.rust_self_arg.span() => (*self_combox).lock_exclusive())
+    };
+
+    // The errors raised outside the return handler, such as a failed
+    // parameter conversion or a panic, skip the out-value writes. The values
+    // are cleared so the caller never frees a pointer it doesn't own.
+    let out_arg_clears = method_info
+        .returnhandler
+        .com_out_args()
+        .into_iter()
+        .filter(|ca| ca.dir != Direction::In)
+        .map(|ca| {
+            let name = &ca.name;
+            let default = ca.handler.default_value();
+            quote_spanned!(ca.span =>
+                if !#name.is_null() {
+                    *#name = #default;
+                }
+            )
+        })
+        .collect::<Vec<_>>();
+
     // Format the payload depending on whether the method is infallible or not.
     let payload = if infallible {
         quote!(
+            let __intercom_lock = #lock_expr;
             let self_struct = #self_struct_expr;
+            #( #out_arg_declarations )*
             let #return_ident = #call;
+            #( #out_arg_writes )*
 
             intercom::logging::trace(|l| l(module_path!(), format_args!(
                 "[{:p}, through {:p}] Serving {}::{}, OK",
@@ -571,12 +1077,19 @@ fn create_virtual_method(
         )
     } else {
         // Fallible methods require an error-catching closure and error handling.
+        // The panics are caught as well to report them as errors instead of
+        // unwinding into the caller.
         quote!(
-            let result : Result< #ret_ty, intercom::ComError > = ( || {
-                let self_struct = #self_struct_expr;
-                let #return_ident = #call;
-                Ok( { #return_statement } )
-            } )();
+            let result : Result< #ret_ty, intercom::ComError > = std::panic::catch_unwind(
+                std::panic::AssertUnwindSafe( || {
+                    let __intercom_lock = #lock_expr;
+                    let self_struct = #self_struct_expr;
+                    #( #out_arg_declarations )*
+                    let #return_ident = #call;
+                    #( #out_arg_writes )*
+                    Ok( { #return_statement } )
+                } )
+            ).unwrap_or_else( |payload| Err( intercom::error::panic_error( payload ) ) );
 
             match result {
                 Ok( v ) => {
@@ -591,6 +1104,7 @@ fn create_virtual_method(
                         "[{:p}, through {:p}] Serving {}::{}, ERROR",
                         self_combox, self_vtable,
                         std::any::type_name::<#s_ref>(), #method_name)));
+                    #( #out_arg_clears )*
                     <#ret_ty as intercom::ErrorValue>::from_error(
                         intercom::store_error(err))
                 },
@@ -598,7 +1112,9 @@ fn create_virtual_method(
         )
     };
 
+    let cfgs = &method_info.cfgs;
     quote!(
+        #( #cfgs )*
         #[allow(non_snake_case)]
         #[allow(dead_code)]
         #[doc(hidden)]
@@ -623,15 +1139,57 @@ fn create_virtual_method(
     )
 }
 
+/// Checks whether the method requires the class to be `Unpin`.
+///
+/// The `&mut self` methods would allow moving the value so the classes that
+/// aren't `Unpin` must use the `self: Pin<&mut Self>` receiver instead.
+fn requires_unpin(itf: &model::ComInterface, method_info: &ComMethodInfo) -> bool
+{
+    itf.implemented_by.is_none() && !method_info.is_const && !method_info.is_pinned
+}
+
+/// Checks whether the method requires an exclusive lock on the class.
+///
+/// The `&mut self` and `self: Pin<&mut Self>` methods require the class to
+/// specify a locking policy.
+fn requires_exclusive_lock(itf: &model::ComInterface, method_info: &ComMethodInfo) -> bool
+{
+    itf.implemented_by.is_none() && !method_info.is_const
+}
+
 fn create_get_typeinfo_function(itf: &model::ComInterface) -> Result<TokenStream, String>
 {
     let itf_name = itf.ident.to_string();
     let itf_ref = &itf.itf_ref;
     let mut variant_tokens = vec![];
+    let mut type_info_tokens = vec![];
     for (ts, variant) in &itf.variants {
         variant_tokens.push(create_typeinfo_for_variant(itf, *ts, &variant)?);
+
+        // The types described in the type library, such as enums, are
+        // included with the interface that uses them. The counted parameters
+        // are pointers to the elements.
+        let ts_type = ts.as_typesystem_type(itf.span);
+        for m in &variant.methods {
+            let arg_tys = m
+                .raw_com_args()
+                .into_iter()
+                .filter(|arg| !arg.handler.is_counted())
+                .map(|arg| arg.handler.extern_ty());
+            let return_ty = m.return_type.as_ref().map(|_| m.returnhandler.rust_ty());
+            let cfgs = &m.cfgs;
+            for ty in return_ty.into_iter().chain(arg_tys) {
+                type_info_tokens.push(quote_spanned!(itf.span =>
+                    #( #cfgs )*
+                    <#ty as intercom::type_system::ExternType<#ts_type>>::type_info()));
+            }
+        }
     }
     let is_impl_interface = itf.item_type == utils::InterfaceType::Struct;
+    let generated_iid = itf.generated_iid;
+    let dual = itf.dual;
+    let dispinterface = itf.dispinterface;
+    let itf_doc = doc_tokens(&itf.doc, itf.span);
 
     Ok(quote_spanned!(itf.span =>
         #[allow(non_snake_case)]
@@ -642,16 +1200,24 @@ fn create_get_typeinfo_function(itf: &model::ComInterface) -> Result<TokenStream
             {
                 let variants = vec![ #( #variant_tokens ),* ];
 
-                vec![ intercom::typelib::TypeInfo::Interface(
+                let mut types = vec![ intercom::typelib::TypeInfo::Interface(
                     intercom::ComBox::new( intercom::typelib::Interface {
                         name: #itf_name.into(),
                         variants,
                         options: intercom::typelib::InterfaceOptions {
                             class_impl_interface: #is_impl_interface,
+                            generated_iid: #generated_iid,
+                            dual: #dual,
+                            dispinterface: #dispinterface,
                             ..Default::default()
-                        }
+                        },
+                        doc: #itf_doc,
                     })
-                ) ]
+                ) ];
+                let type_infos: Vec<Option<intercom::typelib::TypeInfo>> =
+                    vec![ #( #type_info_tokens ),* ];
+                types.extend( type_infos.into_iter().flatten() );
+                types
             }
         }
     ))
@@ -668,23 +1234,30 @@ fn create_typeinfo_for_variant(
     let iid_tokens = utils::get_guid_tokens(&itf_variant.iid, itf.span);
     let methods = itf_variant.methods.iter().map( |m| {
         let method_name = m.name.to_string();
-        let return_type = match &m.return_type {
+        // The return handler knows the Rust type behind the COM return value,
+        // which differs from the Rust return type for `Option<T>` methods.
+        let return_type = match m.return_type.as_ref().map(|_| m.returnhandler.rust_ty()) {
             Some(rt) => quote_spanned!(m.signature_span =>
                 intercom::typelib::Arg {
                     name: "".into(),
                     ty: <
                         <#rt as intercom::type_system::ExternType<#ts_type>>::ForeignType
-                        as intercom::type_system::ForeignType>::type_name().into(),
+                        as intercom::type_system::ForeignType>::type_library_name(),
                     indirection_level: <
                         <#rt as intercom::type_system::ExternType<#ts_type>>::ForeignType
                         as intercom::type_system::ForeignType>::indirection_level(),
                     direction: intercom::typelib::Direction::Return,
+                    idl_ty: <#rt as intercom::type_system::ExternType<#ts_type>>::idl_type_name()
+                        .map(Into::into),
+                    length_of: None,
                 }),
             None => quote_spanned!(m.signature_span => intercom::typelib::Arg {
                 name: "".into(),
                 ty: "void".into(),
                 indirection_level: 0,
                 direction: intercom::typelib::Direction::Return,
+                idl_ty: None,
+                length_of: None,
             } ),
         };
 
@@ -694,22 +1267,60 @@ fn create_typeinfo_for_variant(
             let dir_ident = Ident::new(match arg.dir {
                 Direction::In => "In",
                 Direction::Out => "Out",
-                Direction::Retval => "Retval"
+                Direction::Retval => "Retval",
+                Direction::InOut => "InOut",
             }, arg.span);
 
+            // The buffers the callee writes into are described as out
+            // parameters of the element type.
+            let (dir_ident, indirection) = match arg.handler.is_out_buffer() {
+                true => (Ident::new("Out", arg.span), quote_spanned!(arg.span => - 1)),
+                false => (dir_ident, quote!()),
+            };
+            let rust_ty = arg.handler.extern_ty();
+            let idl_ty = match arg.handler.idl_ty() {
+                Some(idl_ty) => quote_spanned!(arg.span => Some(#idl_ty.into())),
+                None if arg.handler.is_counted() => quote_spanned!(arg.span => None),
+                None => quote_spanned!(arg.span =>
+                    <#rust_ty as intercom::type_system::ExternType<#ts_type>>::idl_type_name()
+                        .map(Into::into)),
+            };
+            let length_of = match &arg.length_of {
+                Some(buffer) => quote_spanned!(arg.span => Some(#buffer.into())),
+                None => quote_spanned!(arg.span => None),
+            };
+
             quote_spanned!(arg.span => intercom::typelib::Arg {
                 name: #arg_name.into(),
-                ty: <#com_ty as intercom::type_system::ForeignType>::type_name().into(),
-                indirection_level: <#com_ty as intercom::type_system::ForeignType>::indirection_level(),
+                ty: <#com_ty as intercom::type_system::ForeignType>::type_library_name(),
+                indirection_level:
+                    <#com_ty as intercom::type_system::ForeignType>::indirection_level() #indirection,
                 direction: intercom::typelib::Direction::#dir_ident,
+                idl_ty: #idl_ty,
+                length_of: #length_of,
             })
         }).collect::<Vec<_>>();
 
+        let doc = doc_tokens(&m.doc, m.signature_span);
+        let kind = Ident::new(&format!("{:?}", m.kind), m.signature_span);
+        let hidden = m.is_hidden;
+        let restricted = m.is_restricted;
+        let newenum = m.is_newenum;
+        let cfgs = &m.cfgs;
         quote_spanned!(m.signature_span =>
+            #( #cfgs )*
             intercom::ComBox::new(intercom::typelib::Method {
                 name: #method_name.into(),
                 return_type: #return_type,
                 parameters: vec![ #( #params ),* ],
+                doc: #doc,
+                kind: intercom::typelib::MethodKind::#kind,
+                options: intercom::typelib::MethodOptions {
+                    hidden: #hidden,
+                    restricted: #restricted,
+                    newenum: #newenum,
+                    ..Default::default()
+                },
             })
         )
     }).collect::<Vec<_>>();
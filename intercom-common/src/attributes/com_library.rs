@@ -14,6 +14,8 @@ use syn::spanned::Spanned;
 ///
 /// - `DllGetClassObject` extern function implementation.
 /// - `IntercomListClassObjects` extern function implementation.
+///
+/// The classes marked with `no_class_factory` are left out of both.
 pub fn expand_com_module(
     arg_tokens: TokenStreamNightly,
     com_library: bool,
@@ -27,9 +29,10 @@ pub fn expand_com_module(
     for struct_path in &lib.coclasses {
         // Construct the match pattern.
         let clsid_path = idents::clsid_path(struct_path);
+        let factory_path = idents::class_factory_path(struct_path);
         match_arms.push(quote_spanned!(struct_path.span() =>
             #clsid_path =>
-                return Some(intercom::ClassFactory::<#struct_path>::create(riid, pout))
+                return #factory_path::<#struct_path>(riid, pout)
         ));
     }
 
@@ -169,6 +172,12 @@ fn create_gather_module_types(lib: &model::ComLibrary) -> Result<TokenStream, St
             <dyn #path as intercom::attributes::ComInterfaceTypeInfo>::gather_type_info()
         )
     });
+    let create_import_typeinfo = lib.imports.iter().map(|path| {
+        quote!(
+            intercom::typelib::TypeInfo::__imported(
+                <dyn #path as intercom::attributes::ComInterfaceTypeInfo>::gather_type_info())
+        )
+    });
     let gather_submodule_types = lib
         .submodules
         .iter()
@@ -180,6 +189,7 @@ fn create_gather_module_types(lib: &model::ComLibrary) -> Result<TokenStream, St
                 #( #create_class_typeinfo, )*
                 #( #gather_submodule_types, )*
                 #( #create_interface_typeinfo, )*
+                #( #create_import_typeinfo, )*
             ]
             .into_iter()
             .flatten()
@@ -191,7 +201,9 @@ fn create_gather_module_types(lib: &model::ComLibrary) -> Result<TokenStream, St
 fn create_get_typelib_function(lib: &model::ComLibrary) -> TokenStream
 {
     let lib_name = lib_name();
+    let lib_version = lib_version();
     let libid = utils::get_guid_tokens(&lib.libid, Span::call_site());
+    let imports = lib.uses.iter().collect::<Vec<_>>();
     quote!(
         #[no_mangle]
         pub unsafe extern "system" fn IntercomTypeLib(
@@ -202,7 +214,8 @@ fn create_get_typelib_function(lib: &model::ComLibrary) -> TokenStream
             let mut tlib = intercom::ComBox::new(intercom::typelib::TypeLib::__new(
                     #lib_name.into(),
                     #libid,
-                    "0.1".into(),
+                    #lib_version.into(),
+                    vec![ #( #imports.into() ),* ],
                     intercom::__gather_module_types()
                         .into_iter().chain(__gather_module_types())
                         .collect()
@@ -246,7 +259,11 @@ fn get_intercom_list_class_objects_function() -> TokenStream
                         .into_iter()
                         .chain(intercom::__gather_module_types())
                         .filter_map(|ty| match ty {
-                            intercom::typelib::TypeInfo::Class(cls) => Some(cls.clsid.clone()),
+                            intercom::typelib::TypeInfo::Class(cls)
+                                if !cls.options.no_class_factory =>
+                            {
+                                Some(cls.clsid.clone())
+                            }
                             _ => None,
                         })
                         .collect(),
@@ -270,7 +287,9 @@ fn get_intercom_list_class_objects_function() -> TokenStream
 fn get_register_server_function(lib: &model::ComLibrary) -> TokenStream
 {
     let lib_name = lib_name();
+    let lib_version = lib_version();
     let libid = utils::get_guid_tokens(&lib.libid, Span::call_site());
+    let imports = lib.uses.iter().collect::<Vec<_>>();
     quote!(
         #[no_mangle]
         #[allow(non_snake_case)]
@@ -281,7 +300,8 @@ fn get_register_server_function(lib: &model::ComLibrary) -> TokenStream
             let mut tlib = intercom::typelib::TypeLib::__new(
                     #lib_name.into(),
                     #libid,
-                    "0.1".into(),
+                    #lib_version.into(),
+                    vec![ #( #imports.into() ),* ],
                     intercom::__gather_module_types()
                         .into_iter().chain(__gather_module_types())
                         .collect()
@@ -301,7 +321,8 @@ fn get_register_server_function(lib: &model::ComLibrary) -> TokenStream
             let mut tlib = intercom::typelib::TypeLib::__new(
                     #lib_name.into(),
                     #libid,
-                    "0.1".into(),
+                    #lib_version.into(),
+                    vec![ #( #imports.into() ),* ],
                     intercom::__gather_module_types()
                         .into_iter().chain(__gather_module_types())
                         .collect()
@@ -1,4 +1,5 @@
 use crate::prelude::*;
+use quote::ToTokens;
 
 use std::env;
 use std::iter::FromIterator;
@@ -14,6 +15,29 @@ pub fn lib_name() -> String
     )
 }
 
+/// Resolve the type library version from the version of the package being
+/// compiled.
+///
+/// Type libraries are versioned with `major.minor` so the patch version is
+/// left out.
+pub fn lib_version() -> String
+{
+    let part = |name| {
+        env::var(name).unwrap_or_else(|_| {
+            panic!(
+                "Could not resolve package version. \
+                 Ensure {} environment variable is defined.",
+                name
+            )
+        })
+    };
+    format!(
+        "{}.{}",
+        part("CARGO_PKG_VERSION_MAJOR"),
+        part("CARGO_PKG_VERSION_MINOR")
+    )
+}
+
 pub fn tokens_to_tokenstream<T: IntoIterator<Item = TokenStream>>(
     original: TokenStreamNightly,
     tokens: T,
@@ -24,6 +48,64 @@ pub fn tokens_to_tokenstream<T: IntoIterator<Item = TokenStream>>(
     )
 }
 
+/// Creates the `Option` tokens for the type library documentation.
+pub fn doc_tokens(doc: &Option<String>, span: Span) -> TokenStream
+{
+    match doc {
+        Some(doc) => quote_spanned!(span => Some(#doc.into())),
+        None => quote_spanned!(span => None),
+    }
+}
+
+/// Removes the `#[com_arg(..)]` attributes from the method arguments and the
+/// `#[com_return(..)]`, `#[com_property(..)]` and `#[com_method(..)]`
+/// attributes from the methods.
+///
+/// The attributes are consumed by the intercom attributes and are unknown to
+/// the compiler.
+pub fn strip_com_arg_attributes(item_tokens: TokenStreamNightly) -> TokenStreamNightly
+{
+    let mut item: syn::Item = match syn::parse(item_tokens.clone()) {
+        Ok(item) => item,
+        Err(_) => return item_tokens,
+    };
+
+    let methods: Vec<(&mut Vec<syn::Attribute>, &mut syn::Signature)> = match &mut item {
+        syn::Item::Trait(t) => t
+            .items
+            .iter_mut()
+            .filter_map(|i| match i {
+                syn::TraitItem::Method(m) => Some((&mut m.attrs, &mut m.sig)),
+                _ => None,
+            })
+            .collect(),
+        syn::Item::Impl(i) => i
+            .items
+            .iter_mut()
+            .filter_map(|i| match i {
+                syn::ImplItem::Method(m) => Some((&mut m.attrs, &mut m.sig)),
+                _ => None,
+            })
+            .collect(),
+        _ => return item_tokens,
+    };
+
+    for (attrs, sig) in methods {
+        attrs.retain(|attr| {
+            !attr.path.is_ident("com_return")
+                && !attr.path.is_ident("com_property")
+                && !attr.path.is_ident("com_method")
+        });
+        for arg in sig.inputs.iter_mut() {
+            if let syn::FnArg::Typed(pat) = arg {
+                pat.attrs.retain(|attr| !attr.path.is_ident("com_arg"));
+            }
+        }
+    }
+
+    item.into_token_stream().into()
+}
+
 // These functions are left in for debugging purposes.
 //
 // They can be swapped in for the tokens_to_tokenstreams to get a printout of
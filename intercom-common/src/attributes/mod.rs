@@ -6,9 +6,15 @@ pub use self::com_class::expand_com_class;
 mod com_interface;
 pub use self::com_interface::expand_com_interface;
 
+mod com_dll_module;
+pub use self::com_dll_module::expand_com_dll_module;
+
 mod com_library;
 pub use self::com_library::expand_com_module;
 
+mod test_handler;
+pub use self::test_handler::expand_test_handler;
+
 mod type_info;
 pub use self::type_info::expand_bidirectional_type_info;
 pub use self::type_info::expand_derive_extern_input;
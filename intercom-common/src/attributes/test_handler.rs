@@ -0,0 +1,127 @@
+use crate::prelude::*;
+
+use crate::tyhandlers::{get_ty_handler, Direction, ModelTypeSystem, TypeContext};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+
+/// The arguments of the `__test_handler!` macro.
+struct TestHandlerArgs
+{
+    ty: syn::Type,
+    values: Punctuated<syn::Expr, syn::Token![,]>,
+}
+
+impl Parse for TestHandlerArgs
+{
+    fn parse(input: ParseStream) -> syn::Result<Self>
+    {
+        let ty = input.parse()?;
+        input.parse::<syn::Token![,]>()?;
+        let content;
+        syn::bracketed!(content in input);
+        let values = content.parse_terminated(syn::Expr::parse)?;
+        Ok(TestHandlerArgs { ty, values })
+    }
+}
+
+/// Expands the `__test_handler!` macro.
+///
+/// The values are converted with the code the type handlers generate for the
+/// stubs in each type system and direction:
+///
+/// - `Direction::In` passes the value as a parameter to a callee, which
+///   compares the owned value it receives.
+/// - `Direction::Out` and `Direction::Retval` write the value the way the
+///   callee does and read it back the way the caller does.
+/// - `Direction::InOut` passes the value in, takes it out of the pointer the
+///   way the callee does and writes it back again for the caller.
+pub fn expand_test_handler(args: TokenStreamNightly) -> Result<TokenStreamNightly, syn::Error>
+{
+    let args: TestHandlerArgs = syn::parse(args)?;
+    let ty = &args.ty;
+    let span = Span::call_site();
+    let ident = Ident::new("__value", span);
+
+    let mut checks = vec![];
+    for ts in &[ModelTypeSystem::Automation, ModelTypeSystem::Raw] {
+        let ts_name = format!("{:?}", ts);
+        let param = get_ty_handler(ty, TypeContext::parameter(*ts, None));
+        let retval = get_ty_handler(ty, TypeContext::new(*ts));
+        if param.is_counted() {
+            return Err(syn::Error::new_spanned(
+                ty,
+                "The counted parameters are not supported",
+            ));
+        }
+
+        let com_ty = param.com_ty(span);
+        let in_to_com = param.rust_to_com(&ident, span, Direction::In, false);
+        let in_to_rust = param.com_to_rust(&ident, span, Direction::In, false);
+        let in_out_to_com = param.rust_to_com(&ident, span, Direction::InOut, false);
+        let in_out_to_rust = param.com_to_rust(&ident, span, Direction::InOut, false);
+        let out_to_rust = param.com_to_rust(&ident, span, Direction::Out, false);
+        let in_msg = format!("Direction::In in {}", ts_name);
+        let entry_msg = format!("Direction::InOut in {} on entry", ts_name);
+        let return_msg = format!("Direction::InOut in {} on return", ts_name);
+        let outputs = [
+            (&param, Direction::Out, "Out"),
+            (&retval, Direction::Retval, "Retval"),
+        ]
+        .iter()
+        .map(|(handler, dir, dir_name)| {
+            let com_ty = handler.com_ty(span);
+            let to_com = handler.rust_to_com(&ident, span, *dir, false);
+            let to_rust = handler.com_to_rust(&ident, span, *dir, false);
+            let msg = format!("Direction::{} in {}", dir_name, ts_name);
+            quote!({
+                let #ident: #com_ty = {
+                    let #ident = value.clone();
+                    #to_com
+                };
+                let received: #ty = #to_rust?;
+                assert_eq!(received, value, #msg);
+            })
+        })
+        .collect::<Vec<_>>();
+
+        for value in &args.values {
+            checks.push(quote!(
+                (|| -> Result<(), intercom::ComError> { unsafe {
+                    let value: #ty = #value;
+
+                    // The lease of the parameter lives until the call returns.
+                    let callee = |#ident: #com_ty| -> Result<#ty, intercom::ComError> {
+                        let received: #ty = #in_to_rust;
+                        Ok(received)
+                    };
+                    let received = callee({
+                        let #ident = value.clone();
+                        #in_to_com
+                    })?;
+                    assert_eq!(received, value, #in_msg);
+
+                    #( #outputs )*
+
+                    let mut in_out: #com_ty = {
+                        let #ident = value.clone();
+                        #in_out_to_com
+                    };
+                    let #ident: *mut #com_ty = &mut in_out;
+                    let entry: #ty = #in_out_to_rust;
+                    assert_eq!(entry, value, #entry_msg);
+                    *#ident = {
+                        let #ident = entry;
+                        #in_out_to_com
+                    };
+                    let #ident = in_out;
+                    let returned: #ty = #out_to_rust?;
+                    assert_eq!(returned, value, #return_msg);
+
+                    Ok(())
+                } })().unwrap();
+            ));
+        }
+    }
+
+    Ok(quote!({ #( #checks )* }).into())
+}
@@ -39,9 +39,63 @@ pub fn expand_derive_extern_type(
 
     // Immpl requires the the generics in particular way.
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // The newtypes are passed as their field while the IDL names them.
+    if let Some((inner, _)) = get_newtype_info(&input)? {
+        let type_name = name.to_string();
+        let result = quote! {
+            unsafe impl<TS: intercom::type_system::TypeSystem> #impl_generics intercom::type_system::ExternType<TS> for #name #ty_generics
+                where #inner: intercom::type_system::ExternType<TS>
+            {
+                type ForeignType = <#inner as intercom::type_system::ExternType<TS>>::ForeignType;
+
+                fn idl_type_name() -> Option<String> {
+                    Some(#type_name.to_string())
+                }
+            }
+        };
+        return Ok(result.into());
+    }
+
+    // Enums are passed as their discriminant values so that the incoming
+    // values can be validated before they are turned into the Rust enum.
+    //
+    // The type library describes the enum so that the IDL can name the enum
+    // instead of the discriminant type.
+    let (foreign_ty, type_info) = match get_enum_info(&input)? {
+        Some((repr, variants)) => {
+            let enum_name = name.to_string();
+
+            // MIDL enums are 16 bits on the wire unless declared `v1_enum`.
+            // The 32-bit discriminants need it to match the Rust layout while
+            // the narrower ones may opt in with the `#[v1_enum]` attribute.
+            let v1_enum = match repr.to_string().as_str() {
+                "i32" | "u32" => true,
+                _ => input.attrs.iter().any(|attr| attr.path.is_ident("v1_enum")),
+            };
+            let value_names = variants.iter().map(|v| v.to_string());
+            let type_info = quote! {
+                fn type_info() -> Option<intercom::typelib::TypeInfo> {
+                    Some(intercom::typelib::TypeInfo::Enum(intercom::ComBox::new(
+                        intercom::typelib::Enum {
+                            name: #enum_name.into(),
+                            values: vec![ #( intercom::typelib::EnumValue {
+                                name: #value_names.into(),
+                                value: #name::#variants as i64,
+                            } ),* ],
+                            v1_enum: #v1_enum,
+                        }
+                    )))
+                }
+            };
+            (repr, type_info)
+        }
+        None => (quote!(#name), quote!()),
+    };
     let result = quote! {
         unsafe impl<TS: intercom::type_system::TypeSystem> #impl_generics intercom::type_system::ExternType<TS> for #name #ty_generics #where_clause {
-            type ForeignType = #name;
+            type ForeignType = #foreign_ty;
+            #type_info
         }
     };
 
@@ -63,6 +117,75 @@ pub fn expand_derive_extern_input(
 
     // Immpl requires the the generics in particular way.
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    if let Some((inner, field)) = get_newtype_info(&input)? {
+        let result = quote! {
+            unsafe impl<TS: intercom::type_system::TypeSystem> #impl_generics intercom::type_system::ExternInput<TS> for #name #ty_generics
+                where #inner: intercom::type_system::ExternInput<TS, Owned = #inner>
+            {
+                type Lease = <#inner as intercom::type_system::ExternInput<TS>>::Lease;
+
+                #[inline(always)]
+                unsafe fn into_foreign_parameter(self) -> intercom::ComResult<(Self::ForeignType, Self::Lease)> {
+                    <#inner as intercom::type_system::ExternInput<TS>>::into_foreign_parameter(self.#field)
+                }
+
+                type Owned = #name;
+
+                #[inline(always)]
+                unsafe fn from_foreign_parameter(source: Self::ForeignType) -> intercom::ComResult<Self::Owned> {
+                    <#inner as intercom::type_system::ExternInput<TS>>::from_foreign_parameter(source)
+                        .map(|value| #name { #field: value })
+                }
+            }
+
+            unsafe impl<TS: intercom::type_system::TypeSystem> #impl_generics intercom::type_system::InfallibleExternInput<TS> for #name #ty_generics
+                where #inner: intercom::type_system::InfallibleExternInput<TS, Owned = #inner>
+            {
+                type Lease = <#inner as intercom::type_system::InfallibleExternInput<TS>>::Lease;
+
+                #[inline(always)]
+                unsafe fn into_foreign_parameter(self) -> (Self::ForeignType, Self::Lease) {
+                    <#inner as intercom::type_system::InfallibleExternInput<TS>>::into_foreign_parameter(self.#field)
+                }
+
+                type Owned = #name;
+
+                #[inline(always)]
+                unsafe fn from_foreign_parameter(source: Self::ForeignType) -> Self::Owned {
+                    #name { #field: <#inner as intercom::type_system::InfallibleExternInput<TS>>::from_foreign_parameter(source) }
+                }
+            }
+        };
+        return Ok(result.into());
+    }
+
+    if let Some((repr, variants)) = get_enum_info(&input)? {
+        // Enums have no infallible conversion as the incoming discriminant
+        // might not match any of the variants. The methods taking enum
+        // parameters must return `ComResult` to report the invalid values.
+        let result = quote! {
+            unsafe impl<TS: intercom::type_system::TypeSystem> #impl_generics intercom::type_system::ExternInput<TS> for #name #ty_generics #where_clause {
+
+                type Lease = ();
+
+                #[inline(always)]
+                unsafe fn into_foreign_parameter(self) -> intercom::ComResult<(Self::ForeignType, Self::Lease)> {
+                    Ok((self as #repr, ()))
+                }
+
+                type Owned = #name;
+
+                #[inline(always)]
+                unsafe fn from_foreign_parameter(source: Self::ForeignType) -> intercom::ComResult<Self::Owned> {
+                    #( if source == #name::#variants as #repr { return Ok(#name::#variants); } )*
+                    Err(intercom::ComError::E_INVALIDARG)
+                }
+            }
+        };
+        return Ok(result.into());
+    }
+
     let result = quote! {
         unsafe impl<TS: intercom::type_system::TypeSystem> #impl_generics intercom::type_system::ExternInput<TS> for #name #ty_generics #where_clause {
 
@@ -117,6 +240,68 @@ pub fn expand_derive_extern_output(
 
     // Impl requires the the generics in particular way.
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    if let Some((inner, field)) = get_newtype_info(&input)? {
+        let result = quote! {
+            unsafe impl<TS: intercom::type_system::TypeSystem> #impl_generics intercom::type_system::ExternOutput<TS> for #name #ty_generics
+                where #inner: intercom::type_system::ExternOutput<TS>
+            {
+                #[inline(always)]
+                fn into_foreign_output(self) -> intercom::ComResult<Self::ForeignType> {
+                    <#inner as intercom::type_system::ExternOutput<TS>>::into_foreign_output(self.#field)
+                }
+
+                #[inline(always)]
+                unsafe fn from_foreign_output(source: Self::ForeignType) -> intercom::ComResult<Self> {
+                    <#inner as intercom::type_system::ExternOutput<TS>>::from_foreign_output(source)
+                        .map(|value| #name { #field: value })
+                }
+
+                #[inline(always)]
+                unsafe fn drop_foreign_output(source: Self::ForeignType) {
+                    <#inner as intercom::type_system::ExternOutput<TS>>::drop_foreign_output(source)
+                }
+            }
+
+            unsafe impl<TS: intercom::type_system::TypeSystem> #impl_generics intercom::type_system::InfallibleExternOutput<TS> for #name #ty_generics
+                where #inner: intercom::type_system::InfallibleExternOutput<TS>
+            {
+                #[inline(always)]
+                fn into_foreign_output(self) -> Self::ForeignType {
+                    <#inner as intercom::type_system::InfallibleExternOutput<TS>>::into_foreign_output(self.#field)
+                }
+
+                #[inline(always)]
+                unsafe fn from_foreign_output(source: Self::ForeignType) -> Self {
+                    #name { #field: <#inner as intercom::type_system::InfallibleExternOutput<TS>>::from_foreign_output(source) }
+                }
+            }
+        };
+        return Ok(result.into());
+    }
+
+    if let Some((repr, variants)) = get_enum_info(&input)? {
+        // Enums have no infallible conversion as the discriminant received
+        // from the server might not match any of the variants. The methods
+        // returning enums must return `ComResult` to report the invalid values.
+        let result = quote! {
+            unsafe impl<TS: intercom::type_system::TypeSystem> #impl_generics intercom::type_system::ExternOutput<TS> for #name #ty_generics #where_clause {
+
+                #[inline(always)]
+                fn into_foreign_output(self) -> intercom::ComResult<Self::ForeignType> {
+                    Ok(self as #repr)
+                }
+
+                #[inline(always)]
+                unsafe fn from_foreign_output(source: Self::ForeignType) -> intercom::ComResult<Self> {
+                    #( if source == #name::#variants as #repr { return Ok(#name::#variants); } )*
+                    Err(intercom::ComError::E_INVALIDARG)
+                }
+            }
+        };
+        return Ok(result.into());
+    }
+
     let result = quote! {
         unsafe impl<TS: intercom::type_system::TypeSystem> #impl_generics intercom::type_system::ExternOutput<TS> for #name #ty_generics #where_clause {
 
@@ -147,3 +332,116 @@ pub fn expand_derive_extern_output(
 
     Ok(result.into())
 }
+
+/// Resolves the discriminant type and the variants of a fieldless enum.
+///
+/// The discriminant type defaults to `i32` as a `repr(C)` enum is passed as
+/// a C `int`. Returns `None` if the type isn't an enum and an error if the
+/// enum can't be passed as its discriminant.
+fn get_enum_info(input: &syn::DeriveInput) -> syn::Result<Option<(TokenStream, Vec<&Ident>)>>
+{
+    let data = match &input.data {
+        syn::Data::Enum(data) => data,
+        _ => return Ok(None),
+    };
+
+    // The discriminants stand for the values so there must be no data.
+    if let Some(variant) = data
+        .variants
+        .iter()
+        .find(|v| !matches!(v.fields, syn::Fields::Unit))
+    {
+        return Err(syn::Error::new_spanned(
+            &variant.fields,
+            format!(
+                "Enum `{}` cannot be passed to COM: variant `{}` has fields, \
+                 only fieldless enums are supported",
+                input.ident, variant.ident
+            ),
+        ));
+    }
+
+    let reprs = get_reprs(input);
+
+    let repr = reprs.iter().find(|ident| {
+        matches!(
+            ident.to_string().as_str(),
+            "i8" | "u8" | "i16" | "u16" | "i32" | "u32" | "i64" | "u64" | "isize" | "usize"
+        )
+    });
+
+    // The layout of the enums without a `repr` is unspecified.
+    let repr = match repr {
+        Some(ident) => quote!(#ident),
+        None if reprs.iter().any(|ident| ident == "C") => quote!(i32),
+        None => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                format!(
+                    "Enum `{}` cannot be passed to COM without a `repr`, \
+                     add `#[repr(C)]` or an integer `repr` such as `#[repr(i32)]`",
+                    input.ident
+                ),
+            ))
+        }
+    };
+
+    Ok(Some((
+        repr,
+        data.variants.iter().map(|v| &v.ident).collect(),
+    )))
+}
+
+/// Gets the field type and the member of the `#[newtype]` structs.
+fn get_newtype_info(input: &syn::DeriveInput) -> syn::Result<Option<(syn::Type, syn::Member)>>
+{
+    if !input.attrs.iter().any(|attr| attr.path.is_ident("newtype")) {
+        return Ok(None);
+    }
+
+    let field = match &input.data {
+        syn::Data::Struct(data) if data.fields.len() == 1 => data.fields.iter().next().unwrap(),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                format!(
+                    "Newtype `{}` must be a struct with exactly one field",
+                    input.ident
+                ),
+            ))
+        }
+    };
+
+    // The newtype stands for the field so their layouts must match.
+    if !get_reprs(input).iter().any(|ident| ident == "transparent") {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            format!("Newtype `{}` must be `#[repr(transparent)]`", input.ident),
+        ));
+    }
+
+    let member = match &field.ident {
+        Some(ident) => syn::Member::Named(ident.clone()),
+        None => syn::Member::Unnamed(0.into()),
+    };
+    Ok(Some((field.ty.clone(), member)))
+}
+
+/// Gets the `repr` options of the type.
+fn get_reprs(input: &syn::DeriveInput) -> Vec<Ident>
+{
+    input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("repr"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|nested| match nested {
+            syn::NestedMeta::Meta(syn::Meta::Path(path)) => path.get_ident().cloned(),
+            _ => None,
+        })
+        .collect()
+}
@@ -28,6 +28,12 @@ pub fn com_to_rust_method_impl(itf: &Ident, method: &Ident, ts: ModelTypeSystem)
     Ident::new(&format!("__{}_{}_{:?}", itf, method, ts), method.span())
 }
 
+/// Name of the element count parameter that follows a counted parameter.
+pub fn element_count(arg: &Ident) -> Ident
+{
+    Ident::new(&format!("{}_count", arg), arg.span())
+}
+
 pub fn with_ts(ident: &Ident, ts: ModelTypeSystem) -> Ident
 {
     Ident::new(&format!("{}_{:?}", ident, ts), Span::call_site())
@@ -47,6 +53,20 @@ pub fn clsid(struct_name: &Ident) -> Ident
     new_ident(&format!("CLSID_{}", struct_name))
 }
 
+pub fn class_factory_path(struct_path: &Path) -> Path
+{
+    let mut factory_path = struct_path.clone();
+    if let Some(last) = factory_path.segments.last_mut() {
+        last.ident = class_factory(&last.ident);
+    }
+    factory_path
+}
+
+pub fn class_factory(struct_name: &Ident) -> Ident
+{
+    new_ident(&format!("__get_class_factory_{}", struct_name))
+}
+
 pub fn iid(itf_name: &Ident, span: Span) -> Ident
 {
     Ident::new(&format!("IID_{}", itf_name), span)
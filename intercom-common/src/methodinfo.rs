@@ -4,7 +4,8 @@ use std::rc::Rc;
 use syn::{spanned::Spanned, FnArg, PathArguments, Receiver, ReturnType, Signature, Type};
 
 use crate::ast_converters::*;
-use crate::returnhandlers::{get_return_handler, ReturnHandler};
+use crate::idents;
+use crate::returnhandlers::{get_future_handler, get_return_handler, ErrorPolicy, ReturnHandler};
 use crate::tyhandlers::{get_ty_handler, Direction, ModelTypeSystem, TypeContext, TypeHandler};
 use crate::utils;
 
@@ -17,6 +18,28 @@ pub enum ComMethodInfoError
     BadReturnType,
 }
 
+/// Property accessor a method is explicitly marked as with
+/// `#[com_property]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MethodKind
+{
+    Method,
+    PropertyGet,
+    PropertyPut,
+}
+
+/// Return scheme a method is explicitly marked with `#[com_return]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReturnKind
+{
+    /// The slice is returned as a `SAFEARRAY` over the data of the object.
+    BorrowArray,
+
+    /// The `Vec` is returned as a buffer and an element count in the Raw
+    /// type system.
+    PtrAndLen,
+}
+
 #[derive(Clone)]
 pub struct RustArg
 {
@@ -30,7 +53,16 @@ pub struct RustArg
     pub span: Span,
 
     /// Type handler.
+    ///
+    /// The handler of the `&mut T` out-parameters handles the `T` type.
     pub handler: Rc<TypeHandler>,
+
+    /// Argument direction. The `&mut T` arguments are out-parameters, or
+    /// in-out parameters with `#[com_arg(in_out)]`.
+    pub dir: Direction,
+
+    /// Name of the buffer parameter whose element count the argument holds.
+    pub length_of: Option<String>,
 }
 
 impl PartialEq for RustArg
@@ -51,16 +83,38 @@ impl ::std::fmt::Debug for RustArg
 
 impl RustArg
 {
-    pub fn new(name: Ident, ty: Type, span: Span, type_system: ModelTypeSystem) -> RustArg
+    pub fn new(
+        name: Ident,
+        ty: Type,
+        span: Span,
+        type_system: ModelTypeSystem,
+        idl_type: Option<String>,
+    ) -> RustArg
     {
-        let tyhandler = get_ty_handler(&ty, TypeContext::new(type_system));
+        // The mutable byte slices are buffers passed in for the callee to
+        // write into.
+        let (dir, handler_ty) = match &ty {
+            _ if crate::utils::is_mut_byte_slice(&ty) => (Direction::In, ty.clone()),
+            Type::Reference(r) if r.mutability.is_some() => (Direction::Out, (*r.elem).clone()),
+            _ => (Direction::In, ty.clone()),
+        };
+        let tyhandler = get_ty_handler(&handler_ty, TypeContext::parameter(type_system, idl_type));
         RustArg {
             name,
             ty,
             span,
             handler: tyhandler,
+            dir,
+            length_of: None,
         }
     }
+
+    /// Name of the temporary holding the value of an out-parameter during
+    /// the call.
+    pub fn out_value_ident(&self) -> Ident
+    {
+        Ident::new(&format!("__{}_value", self.name), self.span)
+    }
 }
 
 pub struct ComArg
@@ -79,6 +133,9 @@ pub struct ComArg
 
     /// Argument direction. COM uses OUT params while Rust uses return values.
     pub dir: Direction,
+
+    /// Name of the buffer parameter whose element count the argument holds.
+    pub length_of: Option<String>,
 }
 
 impl ComArg
@@ -98,18 +155,22 @@ impl ComArg
             dir,
             span,
             handler: tyhandler,
+            length_of: None,
         }
     }
 
-    pub fn from_rustarg(rustarg: RustArg, dir: Direction, type_system: ModelTypeSystem) -> ComArg
+    pub fn from_rustarg(rustarg: RustArg, type_system: ModelTypeSystem) -> ComArg
     {
-        let tyhandler = get_ty_handler(&rustarg.ty, TypeContext::new(type_system));
+        let idl_type = rustarg.handler.idl_ty().map(str::to_string);
+        let ty = rustarg.handler.rust_ty();
+        let tyhandler = get_ty_handler(&ty, TypeContext::parameter(type_system, idl_type));
         ComArg {
             name: rustarg.name,
-            ty: rustarg.ty,
-            dir,
+            ty,
+            dir: rustarg.dir,
             span: rustarg.span,
             handler: tyhandler,
+            length_of: rustarg.length_of,
         }
     }
 }
@@ -139,6 +200,9 @@ pub struct ComMethodInfo
     /// True if the self parameter is not mutable.
     pub is_const: bool,
 
+    /// True if the self parameter is `self: Pin<&mut Self>`.
+    pub is_pinned: bool,
+
     /// Rust self argument.
     pub rust_self_arg: Receiver,
 
@@ -168,6 +232,36 @@ pub struct ComMethodInfo
 
     /// Is the method infallible.
     pub infallible: bool,
+
+    /// True if the method returns a future completed through a completion
+    /// handler.
+    pub is_future: bool,
+
+    /// True if the method is marked with `#[com_method(operation)]` and runs
+    /// on the intercom thread pool, returning an `IOperation` to the caller.
+    pub is_operation: bool,
+
+    /// True if the method is marked with `#[com_method(hidden)]` and is
+    /// hidden from the object browsers.
+    pub is_hidden: bool,
+
+    /// True if the method is marked with `#[com_method(restricted)]` and is
+    /// not available to the scripting clients.
+    pub is_restricted: bool,
+
+    /// True if the method is marked with `#[com_method(newenum)]` and returns
+    /// the enumerator of the collection.
+    pub is_newenum: bool,
+
+    /// Documentation of the method.
+    pub doc: Option<String>,
+
+    /// Property accessor the method is marked as.
+    pub kind: MethodKind,
+
+    /// The `#[cfg]` attributes of the method. These are repeated on the
+    /// items generated for the method.
+    pub cfgs: Vec<syn::Attribute>,
 }
 
 impl PartialEq for ComMethodInfo
@@ -187,9 +281,13 @@ impl PartialEq for ComMethodInfo
 impl ComMethodInfo
 {
     /// Constructs new COM method info from a Rust method signature.
+    ///
+    /// The `error_policy` of the interface decides how the `Err` values of
+    /// `Result` methods are reported.
     pub fn new(
         decl: &Signature,
         type_system: ModelTypeSystem,
+        error_policy: ErrorPolicy,
     ) -> Result<ComMethodInfo, ComMethodInfoError>
     {
         // Process all the function arguments.
@@ -201,8 +299,19 @@ impl ComMethodInfo
         let mut iter = decl.inputs.iter();
         let rust_self_arg = iter.next().ok_or(ComMethodInfoError::TooFewArguments)?;
 
-        let (is_const, rust_self_arg) = match *rust_self_arg {
-            FnArg::Receiver(ref self_arg) => (self_arg.mutability.is_none(), self_arg.clone()),
+        let (is_const, is_pinned, rust_self_arg) = match *rust_self_arg {
+            FnArg::Receiver(ref self_arg) => {
+                (self_arg.mutability.is_none(), false, self_arg.clone())
+            }
+
+            // The pinned receivers are treated as `&mut self` apart from the
+            // way the value is passed to the method.
+            FnArg::Typed(ref pat) if is_pinned_self(pat) => {
+                match syn::parse2(quote_spanned!(pat.span() => &mut self)) {
+                    Ok(FnArg::Receiver(self_arg)) => (false, true, self_arg),
+                    _ => return Err(ComMethodInfoError::BadSelfArg),
+                }
+            }
             _ => return Err(ComMethodInfoError::BadSelfArg),
         };
 
@@ -215,8 +324,21 @@ impl ComMethodInfo
                 let ident = arg
                     .get_ident()
                     .map_err(|_| ComMethodInfoError::BadArg(Box::new(arg.clone())))?;
+                let options = get_com_arg_options(arg)
+                    .map_err(|_| ComMethodInfoError::BadArg(Box::new(arg.clone())))?;
 
-                Ok(RustArg::new(ident, ty, arg.span(), type_system))
+                let mut rust_arg =
+                    RustArg::new(ident, ty, arg.span(), type_system, options.idl_type);
+                rust_arg.length_of = options.length_of;
+
+                // Only the out-parameters may also pass a value in.
+                if options.in_out {
+                    if rust_arg.dir != Direction::Out {
+                        return Err(ComMethodInfoError::BadArg(Box::new(arg.clone())));
+                    }
+                    rust_arg.dir = Direction::InOut;
+                }
+                Ok(rust_arg)
             })
             .collect::<Result<_, _>>()?;
 
@@ -226,8 +348,17 @@ impl ComMethodInfo
             ReturnType::Type(_, ref ty) => (**ty).clone(),
         };
 
-        // Resolve the return type and retval type.
-        let (retval_type, return_type, retval_span) = if utils::is_unit(&rust_return_ty) {
+        // Resolve the return type and retval type. The futures deliver their
+        // output through a completion handler, which leaves only the HRESULT
+        // for the call itself.
+        let is_future = is_future(&rust_return_ty);
+        let (retval_type, return_type, retval_span) = if is_future {
+            (
+                None,
+                Some(hresult_ty(decl.output.span())),
+                decl.output.span(),
+            )
+        } else if utils::is_unit(&rust_return_ty) {
             (None, None, decl.span())
         } else if let Some((retval, ret)) = try_parse_result(&rust_return_ty) {
             (Some(retval), Some(ret), decl.output.span())
@@ -235,15 +366,24 @@ impl ComMethodInfo
             (None, Some(rust_return_ty.clone()), decl.output.span())
         };
 
-        let returnhandler =
-            get_return_handler(&retval_type, &return_type, retval_span, type_system)
-                .or(Err(ComMethodInfoError::BadReturnType))?;
+        let returnhandler = match is_future {
+            true => get_future_handler(retval_span, type_system),
+            false => get_return_handler(
+                &retval_type,
+                &return_type,
+                retval_span,
+                type_system,
+                error_policy,
+            )
+            .or(Err(ComMethodInfoError::BadReturnType))?,
+        };
         Ok(ComMethodInfo {
             name: n,
             infallible: returnhandler.is_infallible(),
             returnhandler: returnhandler.into(),
             signature_span: decl.span(),
             is_const,
+            is_pinned,
             rust_self_arg,
             rust_return_ty,
             retval_type,
@@ -251,18 +391,61 @@ impl ComMethodInfo
             args,
             is_unsafe: unsafety,
             type_system,
+            is_future,
+            is_operation: false,
+            is_hidden: false,
+            is_restricted: false,
+            is_newenum: false,
+            doc: None,
+            kind: MethodKind::Method,
+            cfgs: vec![],
         })
     }
 
+    /// Name of the property the method is marked to access.
+    pub fn property_name(&self) -> Option<String>
+    {
+        let name = self.name.to_string();
+        let prefixes: &[&str] = match self.kind {
+            MethodKind::Method => return None,
+            MethodKind::PropertyGet => &["get_"],
+            MethodKind::PropertyPut => &["set_", "put_"],
+        };
+        Some(
+            prefixes
+                .iter()
+                .find_map(|prefix| name.strip_prefix(prefix))
+                .unwrap_or(&name)
+                .to_string(),
+        )
+    }
+
     pub fn raw_com_args(&self) -> Vec<ComArg>
     {
-        let in_args = self
-            .args
-            .iter()
-            .map(|ca| ComArg::from_rustarg(ca.clone(), Direction::In, self.type_system));
+        // The counted parameters are followed by their element count. The
+        // IDL sizes the byte buffers by the count.
+        let rust_args = self.args.iter().flat_map(|ca| {
+            let count = match ca.handler.is_counted() {
+                true => {
+                    let mut count = ComArg::new(
+                        idents::element_count(&ca.name),
+                        syn::parse2(quote_spanned!(ca.span => u32)).unwrap(),
+                        ca.span,
+                        Direction::In,
+                        self.type_system,
+                    );
+                    if crate::utils::is_byte_slice(&ca.ty) {
+                        count.length_of = Some(ca.name.to_string());
+                    }
+                    Some(count)
+                }
+                false => None,
+            };
+            std::iter::once(ComArg::from_rustarg(ca.clone(), self.type_system)).chain(count)
+        });
         let out_args = self.returnhandler.com_out_args();
 
-        in_args.chain(out_args).collect()
+        rust_args.chain(out_args).collect()
     }
 
     pub fn get_parameters_tokenstream(&self) -> TokenStream
@@ -272,7 +455,9 @@ impl ComMethodInfo
             let com_ty = &com_arg.handler.com_ty(com_arg.span);
             let dir = match com_arg.dir {
                 Direction::In => quote!(),
-                Direction::Out | Direction::Retval => quote_spanned!(com_arg.span => *mut ),
+                Direction::Out | Direction::Retval | Direction::InOut => {
+                    quote_spanned!(com_arg.span => *mut )
+                }
             };
             quote_spanned!(com_arg.span => #name : #dir #com_ty )
         });
@@ -312,11 +497,111 @@ fn try_parse_result(ty: &Type) -> Option<(Type, Type)>
     None
 }
 
+/// Checks whether the argument is the `self: Pin<&mut Self>` receiver.
+fn is_pinned_self(pat: &syn::PatType) -> bool
+{
+    match &*pat.pat {
+        syn::Pat::Ident(ident) if ident.ident == "self" => {}
+        _ => return false,
+    }
+
+    let segment = match &*pat.ty {
+        Type::Path(p) => match p.path.segments.last() {
+            Some(segment) if segment.ident == "Pin" => segment,
+            _ => return false,
+        },
+        _ => return false,
+    };
+    let args = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => &args.args,
+        _ => return false,
+    };
+    match args.first() {
+        Some(syn::GenericArgument::Type(Type::Reference(r))) if args.len() == 1 => {
+            r.mutability.is_some()
+                && match &*r.elem {
+                    Type::Path(p) => p.qself.is_none() && p.path.is_ident("Self"),
+                    _ => false,
+                }
+        }
+        _ => false,
+    }
+}
+
+/// Checks whether the type is an `impl Future<..>`.
+fn is_future(ty: &Type) -> bool
+{
+    let bounds = match ty {
+        Type::ImplTrait(t) => &t.bounds,
+        _ => return false,
+    };
+    bounds.iter().any(|bound| match bound {
+        syn::TypeParamBound::Trait(t) => t
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident == "Future")
+            .unwrap_or(false),
+        _ => false,
+    })
+}
+
 fn hresult_ty(span: Span) -> Type
 {
     syn::parse2(quote_spanned!(span => intercom::raw::HRESULT)).unwrap()
 }
 
+/// Options given to an argument with the `#[com_arg(..)]` attributes.
+#[derive(Default)]
+struct ComArgOptions
+{
+    /// `idl_type = ".."`, the type name used in the IDL.
+    idl_type: Option<String>,
+
+    /// `length_of = ".."`, the buffer parameter the argument counts.
+    length_of: Option<String>,
+
+    /// `in_out`, the `&mut T` argument is an `[in, out]` parameter.
+    in_out: bool,
+}
+
+/// Reads the `#[com_arg(..)]` attributes of the argument.
+fn get_com_arg_options(arg: &FnArg) -> syn::Result<ComArgOptions>
+{
+    let attrs = match arg {
+        FnArg::Typed(pat) => &pat.attrs,
+        FnArg::Receiver(..) => return Ok(ComArgOptions::default()),
+    };
+
+    let mut options = ComArgOptions::default();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("com_arg")) {
+        let metas = attr.parse_args_with(
+            syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+        )?;
+        for meta in metas {
+            let meta = match meta {
+                syn::Meta::Path(path) if path.is_ident("in_out") && !options.in_out => {
+                    options.in_out = true;
+                    continue;
+                }
+                syn::Meta::NameValue(meta) => meta,
+                _ => return Err(syn::Error::new(meta.span(), "Bad com_arg attribute")),
+            };
+            let target = match meta.path.get_ident() {
+                Some(ident) if ident == "idl_type" => &mut options.idl_type,
+                Some(ident) if ident == "length_of" => &mut options.length_of,
+                _ => return Err(syn::Error::new(meta.span(), "Bad com_arg attribute")),
+            };
+            match &meta.lit {
+                syn::Lit::Str(s) if target.is_none() => *target = Some(s.value()),
+                _ => return Err(syn::Error::new(meta.span(), "Bad com_arg attribute")),
+            }
+        }
+    }
+
+    Ok(options)
+}
+
 #[cfg(test)]
 mod tests
 {
@@ -336,6 +621,23 @@ mod tests
         assert_eq!(info.args.len(), 0);
         assert_eq!(info.retval_type.is_none(), true);
         assert_eq!(info.return_type.is_none(), true);
+        assert_eq!(info.infallible, true);
+        assert_eq!(info.returnhandler.com_ty(), parse_quote!(()));
+    }
+
+    #[test]
+    fn unit_result_checks_hresult()
+    {
+        let info = test_info("fn foo( &self ) -> ComResult<()> {}", Automation);
+
+        assert_eq!(info.retval_type, Some(parse_quote!(())));
+        assert_eq!(info.infallible, false);
+        assert_eq!(info.returnhandler.com_out_args().len(), 0);
+
+        let result = Ident::new("__result", Span::call_site());
+        let tokens = info.returnhandler.com_to_rust_return(&result).to_string();
+        assert!(tokens.contains("S_OK"));
+        assert!(tokens.contains("load_error"));
     }
 
     #[test]
@@ -374,6 +676,139 @@ mod tests
         assert_eq!(info.return_type, Some(parse_quote!(intercom::raw::HRESULT)));
     }
 
+    #[test]
+    fn success_return_value()
+    {
+        let info = test_info("fn foo( &self ) -> ComResult<Success<u32>> {}", Automation);
+
+        assert_eq!(info.retval_type, Some(parse_quote!(Success<u32>)));
+        assert_eq!(info.infallible, false);
+
+        // The success status is returned as the HRESULT so only the value
+        // remains for the [retval].
+        let out_args = info.returnhandler.com_out_args();
+        assert_eq!(out_args.len(), 1);
+        assert_eq!(out_args[0].ty, parse_quote!(u32));
+        assert_eq!(out_args[0].dir, Direction::Retval);
+    }
+
+    #[test]
+    fn comresult_array_return_value()
+    {
+        let info = test_info("fn foo( &self ) -> ComResult<[u8; 32]> {}", Automation);
+
+        assert_eq!(info.retval_type, Some(parse_quote!([u8; 32])));
+
+        let out_args = info.returnhandler.com_out_args();
+        assert_eq!(out_args.len(), 1);
+        assert_eq!(out_args[0].ty, parse_quote!([u8; 32]));
+        assert_eq!(out_args[0].dir, Direction::Retval);
+    }
+
+    #[test]
+    fn comresult_interface_out_params()
+    {
+        let info = test_info(
+            "fn foo( &self ) -> ComResult<( ComRc<dyn IFoo>, ComRc<dyn IBar> )> {}",
+            Raw,
+        );
+
+        let out_args = info.returnhandler.com_out_args();
+        assert_eq!(out_args.len(), 2);
+        assert_eq!(out_args[0].ty, parse_quote!(ComRc<dyn IFoo>));
+        assert_eq!(out_args[0].dir, Direction::Out);
+        assert_eq!(out_args[1].ty, parse_quote!(ComRc<dyn IBar>));
+        assert_eq!(out_args[1].dir, Direction::Out);
+
+        // The out parameters are the interface pointers the callee writes to.
+        let com_ty = &out_args[0].handler.com_ty(Span::call_site());
+        assert_eq!(
+            quote!(#com_ty).to_string().replace(" ", ""),
+            "<ComRc<dynIFoo>asintercom::type_system::ExternType<\
+             intercom::type_system::RawTypeSystem>>::ForeignType"
+        );
+    }
+
+    #[test]
+    fn comresult_u64_return_value()
+    {
+        for ts in &[Automation, Raw] {
+            let info = test_info("fn get_offset( &self ) -> ComResult<u64> {}", *ts);
+
+            let out_args = info.returnhandler.com_out_args();
+            assert_eq!(out_args.len(), 1);
+            assert_eq!(out_args[0].ty, parse_quote!(u64));
+            assert_eq!(out_args[0].dir, Direction::Retval);
+
+            // 64-bit integers are passed as they are in both type systems.
+            let com_ty = &out_args[0].handler.com_ty(Span::call_site());
+            assert_eq!(
+                quote!(#com_ty).to_string().replace(" ", ""),
+                format!(
+                    "<u64asintercom::type_system::ExternType<\
+                     intercom::type_system::{:?}TypeSystem>>::ForeignType",
+                    ts
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn option_return_value()
+    {
+        let info = test_info("fn foo( &self ) -> Option<u32> {}", Automation);
+
+        assert_eq!(info.infallible, false);
+        assert_eq!(info.return_type, Some(parse_quote!(Option<u32>)));
+        assert_eq!(
+            info.returnhandler.rust_ty(),
+            parse_quote!(intercom::raw::HRESULT)
+        );
+
+        let out_args = info.returnhandler.com_out_args();
+        assert_eq!(out_args.len(), 1);
+        assert_eq!(out_args[0].ty, parse_quote!(u32));
+        assert_eq!(out_args[0].dir, Direction::Retval);
+
+        // The results yield the errors alongside the absent values.
+        let info = test_info("fn foo( &self ) -> ComResult<Option<u32>> {}", Automation);
+        assert_eq!(info.infallible, false);
+        assert_eq!(info.retval_type, Some(parse_quote!(Option<u32>)));
+        let out_args = info.returnhandler.com_out_args();
+        assert_eq!(out_args.len(), 1);
+        assert_eq!(out_args[0].ty, parse_quote!(u32));
+        assert_eq!(out_args[0].dir, Direction::Retval);
+
+        // The interfaces keep the Option to allow null pointers.
+        let info = test_info("fn foo( &self ) -> Option<ComRc<dyn IFoo>> {}", Raw);
+        let out_args = info.returnhandler.com_out_args();
+        assert_eq!(out_args.len(), 1);
+        assert_eq!(out_args[0].ty, parse_quote!(Option<ComRc<dyn IFoo>>));
+        assert_eq!(out_args[0].dir, Direction::Retval);
+    }
+
+    #[test]
+    fn tuple_return_value()
+    {
+        let info = test_info("fn foo( &self ) -> ( u32, String ) {}", Automation);
+
+        assert_eq!(info.infallible, false);
+        assert_eq!(info.retval_type, None);
+        assert_eq!(
+            info.returnhandler.rust_ty(),
+            parse_quote!(intercom::raw::HRESULT)
+        );
+
+        let out_args = info.returnhandler.com_out_args();
+        assert_eq!(out_args.len(), 2);
+        assert_eq!(out_args[0].name, Ident::new("__out1", Span::call_site()));
+        assert_eq!(out_args[0].ty, parse_quote!(u32));
+        assert_eq!(out_args[0].dir, Direction::Out);
+        assert_eq!(out_args[1].name, Ident::new("__out2", Span::call_site()));
+        assert_eq!(out_args[1].ty, parse_quote!(String));
+        assert_eq!(out_args[1].dir, Direction::Out);
+    }
+
     #[test]
     fn basic_arguments()
     {
@@ -393,6 +828,77 @@ mod tests
         assert_eq!(info.args[1].ty, parse_quote!(f32));
     }
 
+    #[test]
+    fn explicit_out_param_with_unit_result()
+    {
+        let info = test_info(
+            "fn write( &self, value : u32, written : &mut u32 ) -> ComResult<()> {}",
+            Raw,
+        );
+
+        assert_eq!(info.args[0].dir, Direction::In);
+        assert_eq!(info.args[1].dir, Direction::Out);
+        assert_eq!(info.args[1].ty, parse_quote!(&mut u32));
+
+        // The unit result has no out parameters of its own but the explicit
+        // parameter remains.
+        let com_args = info.raw_com_args();
+        assert_eq!(com_args.len(), 2);
+        assert_eq!(com_args[1].name, "written");
+        assert_eq!(com_args[1].ty, parse_quote!(u32));
+        assert_eq!(com_args[1].dir, Direction::Out);
+    }
+
+    #[test]
+    fn in_out_param()
+    {
+        let info = test_info(
+            "fn append( &self, #[com_arg(in_out)] text : &mut String ) -> ComResult<()> {}",
+            Automation,
+        );
+
+        assert_eq!(info.args[0].dir, Direction::InOut);
+        assert_eq!(info.raw_com_args()[0].dir, Direction::InOut);
+
+        // The in-out attribute requires a mutable reference.
+        let item = syn::parse_str::<Item>(
+            "fn append( &self, #[com_arg(in_out)] text : String ) -> ComResult<()> {}",
+        )
+        .unwrap();
+        let sig = match item {
+            Item::Fn(ref f) => &f.sig,
+            _ => panic!("Code isn't function"),
+        };
+        assert!(ComMethodInfo::new(sig, Automation, ErrorPolicy::default()).is_err());
+    }
+
+    #[test]
+    fn idl_type_override()
+    {
+        let info = test_info(
+            r#"fn foo( &self, #[com_arg(idl_type = "COLORREF")] a : u32, b : u32 ) {}"#,
+            Automation,
+        );
+
+        assert_eq!(info.args[0].handler.idl_ty(), Some("COLORREF"));
+        assert_eq!(info.args[1].handler.idl_ty(), None);
+
+        let com_args = info.raw_com_args();
+        assert_eq!(com_args[0].handler.idl_ty(), Some("COLORREF"));
+    }
+
+    #[test]
+    fn bad_com_arg_attribute()
+    {
+        let item: Item =
+            syn::parse_str(r#"fn foo( &self, #[com_arg(idl = "COLORREF")] a : u32 ) {}"#).unwrap();
+        let sig = match item {
+            Item::Fn(ref f) => &f.sig,
+            _ => panic!("Code isn't function"),
+        };
+        assert!(ComMethodInfo::new(sig, Automation, ErrorPolicy::default()).is_err());
+    }
+
     fn test_info(code: &str, ts: ModelTypeSystem) -> ComMethodInfo
     {
         let item = syn::parse_str(code).unwrap();
@@ -400,6 +906,6 @@ mod tests
             Item::Fn(ref f) => &f.sig,
             _ => panic!("Code isn't function"),
         };
-        ComMethodInfo::new(sig, ts).unwrap()
+        ComMethodInfo::new(sig, ts, ErrorPolicy::default()).unwrap()
     }
 }
@@ -3,11 +3,15 @@ use super::*;
 use crate::prelude::*;
 
 use crate::guid::GUID;
-use syn::{Generics, Path, Visibility};
+use syn::{Generics, LitStr, Path, Visibility};
 
 intercom_attribute!(
     ComClassAttr<ComClassAttrParam, Path> {
         clsid : StrOption,
+        clsid_namespace : LitStr,
+        locking : Ident,
+        on_release : LitStr,
+        default_interface : Path,
     }
 );
 
@@ -19,7 +23,24 @@ pub struct ComClass
     pub clsid: Option<GUID>,
     pub visibility: Visibility,
     pub interfaces: Vec<Path>,
+
+    /// Index of the default interface among the interfaces.
+    pub default_interface: usize,
     pub generics: Generics,
+    pub locking: Option<Ident>,
+    pub on_release: Option<Ident>,
+    pub no_class_factory: bool,
+
+    /// The class is the application object with its members accessible
+    /// without qualification.
+    pub appobject: bool,
+
+    /// The CLSID is derived from the class name instead of being specified
+    /// explicitly.
+    pub generated_clsid: bool,
+
+    /// Documentation of the class, if any.
+    pub doc: Option<String>,
 }
 
 impl ComClass
@@ -42,38 +63,120 @@ impl ComClass
             )
         })?;
 
+        // Automatic CLSIDs are derived from the namespace if one is given.
+        let clsid_namespace = attr
+            .clsid_namespace()
+            .map_err(|msg| ParseError::ComClass(item.ident.to_string(), msg))?
+            .map(|ns| {
+                GUID::parse(&ns.value()).map_err(|_| {
+                    ParseError::ComClass(
+                        item.ident.to_string(),
+                        "Bad CLSID namespace format".into(),
+                    )
+                })
+            })
+            .transpose()?;
+
         // First attribute parameter is the CLSID. Parse it.
         let clsid_attr = attr
             .clsid()
             .map_err(|msg| ParseError::ComClass(item.ident.to_string(), msg))?;
+        let generated_clsid = clsid_attr.is_none();
         let clsid = match clsid_attr {
-            None => Some(crate::utils::generate_clsid(
-                crate_name,
-                &item.ident.to_string(),
-            )),
+            None => Some(match &clsid_namespace {
+                Some(ns) => crate::utils::generate_namespaced_clsid(ns, &item.ident.to_string()),
+                None => crate::utils::generate_clsid(crate_name, &item.ident.to_string()),
+            }),
             Some(StrOption::Str(clsid)) => Some(GUID::parse(&clsid.value()).map_err(|_| {
                 ParseError::ComClass(item.ident.to_string(), "Bad CLSID format".into())
             })?),
             Some(StrOption::None) => None,
         };
 
+        // Locking policy for the `&mut self` methods.
+        let locking = attr
+            .locking()
+            .map_err(|msg| ParseError::ComClass(item.ident.to_string(), msg))?
+            .cloned();
+        if let Some(locking) = &locking {
+            if locking != "RwLock" {
+                return Err(ParseError::ComClass(
+                    item.ident.to_string(),
+                    format!("Unsupported locking policy {}, expected RwLock", locking),
+                ));
+            }
+        }
+
+        // Method invoked on the final release.
+        let on_release = attr
+            .on_release()
+            .map_err(|msg| ParseError::ComClass(item.ident.to_string(), msg))?
+            .map(|lit| {
+                lit.parse::<Ident>().map_err(|_| {
+                    ParseError::ComClass(item.ident.to_string(), "Bad on_release method".into())
+                })
+            })
+            .transpose()?;
+
+        // The `no_class_factory` and `appobject` flags are given without a
+        // value so they end up among the positional parameters.
+        let is_flag = |itf: &Path, flag: &str| itf.get_ident().map(|i| i == flag).unwrap_or(false);
+        let has_flag = |flag| attr.args().into_iter().any(|itf| is_flag(itf, flag));
+        let no_class_factory = has_flag("no_class_factory");
+        let appobject = has_flag("appobject");
+
         // Remaining parameters are coclasses.
         let name = item.ident.clone();
-        let interfaces = attr
+        let interfaces: Vec<Path> = attr
             .args()
             .into_iter()
+            .filter(|itf| !is_flag(itf, "no_class_factory") && !is_flag(itf, "appobject"))
             .map(|itf| match itf.get_ident() {
                 Some(ident) if ident == "Self" => parse_quote!(#name),
                 _ => itf.clone(),
             })
             .collect();
 
+        // The default interface is the first one unless chosen explicitly.
+        let default_interface = match attr
+            .default_interface()
+            .map_err(|msg| ParseError::ComClass(item.ident.to_string(), msg))?
+        {
+            None => 0,
+            Some(default) => {
+                let default = match default.get_ident() {
+                    Some(ident) if ident == "Self" => parse_quote!(#name),
+                    _ => default.clone(),
+                };
+                let same = |itf: &Path| quote!(#itf).to_string() == quote!(#default).to_string();
+                interfaces.iter().position(same).ok_or_else(|| {
+                    ParseError::ComClass(
+                        item.ident.to_string(),
+                        format!(
+                            "Default interface `{}` is not implemented by the class",
+                            quote!(#default).to_string().replace(' ', "")
+                        ),
+                    )
+                })?
+            }
+        };
+
+        // The doc comments become the help string in the type library.
+        let doc = crate::utils::get_doc(&item.attrs);
+
         Ok(ComClass {
             visibility: item.vis.clone(),
             generics: item.generics,
             name,
             clsid,
             interfaces,
+            default_interface,
+            locking,
+            on_release,
+            no_class_factory,
+            appobject,
+            generated_clsid,
+            doc,
         })
     }
 
@@ -120,6 +223,30 @@ mod test
         assert_eq!(cls.interfaces.len(), 2);
         assert_eq!(cls.interfaces[0], parse_quote!(Foo));
         assert_eq!(cls.interfaces[1], parse_quote!(Bar));
+        assert_eq!(cls.default_interface, 0);
+    }
+
+    #[test]
+    fn parse_com_class_with_default_interface()
+    {
+        let parse = |attr| {
+            ComClass::parse(
+                "not used",
+                attr,
+                quote!(
+                    struct S;
+                ),
+            )
+        };
+
+        let cls = parse(quote!(Foo, Bar, default_interface = Bar)).unwrap();
+        assert_eq!(cls.default_interface, 1);
+
+        let cls = parse(quote!(Foo, Self, default_interface = Self)).unwrap();
+        assert_eq!(cls.default_interface, 1);
+
+        let err = parse(quote!(Foo, default_interface = Baz)).unwrap_err();
+        assert!(format!("{}", err).contains("Default interface `Baz` is not implemented"));
     }
 
     #[test]
@@ -153,6 +280,51 @@ mod test
         assert_eq!(cls.interfaces[2], parse_quote!(IStuff));
     }
 
+    #[test]
+    fn parse_com_class_with_clsid_namespace()
+    {
+        let parse = |crate_name| {
+            ComClass::parse(
+                crate_name,
+                quote!(
+                    clsid_namespace = "2a5d8bb1-6b43-4a7a-9b0e-0e4d0e2c2f51",
+                    IThings
+                ),
+                quote!(
+                    struct MyStruct;
+                ),
+            )
+            .expect("com_class attribute parsing failed")
+        };
+
+        // The crate name doesn't affect the namespaced CLSIDs.
+        let cls = parse("crate_a");
+        assert_eq!(cls.clsid, parse("crate_b").clsid);
+        assert_eq!(
+            cls.clsid,
+            Some(GUID::parse("0F962511-51F2-5CC7-8F8E-E3AE15C87049").unwrap())
+        );
+        assert!(cls.generated_clsid);
+        assert_eq!(cls.interfaces, vec![parse_quote!(IThings)] as Vec<Path>);
+
+        // Explicit CLSIDs are not affected by the namespace.
+        let cls = ComClass::parse(
+            "not used",
+            quote!(
+                clsid = "12345678-1234-1234-1234-567890ABCDEF",
+                clsid_namespace = "2a5d8bb1-6b43-4a7a-9b0e-0e4d0e2c2f51"
+            ),
+            quote!(
+                struct MyStruct;
+            ),
+        )
+        .expect("com_class attribute parsing failed");
+        assert_eq!(
+            cls.clsid,
+            Some(GUID::parse("12345678-1234-1234-1234-567890ABCDEF").unwrap())
+        );
+    }
+
     #[test]
     fn parse_com_class_with_no_data()
     {
@@ -186,4 +358,88 @@ mod test
         assert_eq!(cls.clsid, None);
         assert_eq!(cls.interfaces.len(), 1);
     }
+
+    #[test]
+    fn parse_com_class_with_locking()
+    {
+        let cls = ComClass::parse(
+            "not used",
+            quote!(locking = RwLock, ITestInterface),
+            quote!(
+                struct LockedType;
+            ),
+        )
+        .expect("com_class attribute parsing failed");
+
+        assert_eq!(cls.name, "LockedType");
+        assert_eq!(cls.locking, Some(parse_quote!(RwLock)));
+        assert_eq!(cls.interfaces.len(), 1);
+
+        let result = ComClass::parse(
+            "not used",
+            quote!(locking = Mutex, ITestInterface),
+            quote!(
+                struct LockedType;
+            ),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_com_class_with_appobject()
+    {
+        let cls = ComClass::parse(
+            "not used",
+            quote!(appobject, ITestInterface),
+            quote!(
+                struct Application;
+            ),
+        )
+        .expect("com_class attribute parsing failed");
+
+        assert!(cls.appobject);
+        assert_eq!(cls.interfaces, vec![parse_quote!(ITestInterface)]);
+    }
+
+    #[test]
+    fn parse_com_class_with_on_release()
+    {
+        let cls = ComClass::parse(
+            "not used",
+            quote!(on_release = "cleanup", ITestInterface),
+            quote!(
+                struct CleanedType;
+            ),
+        )
+        .expect("com_class attribute parsing failed");
+
+        assert_eq!(cls.on_release, Some(parse_quote!(cleanup)));
+        assert_eq!(cls.interfaces.len(), 1);
+
+        let result = ComClass::parse(
+            "not used",
+            quote!(on_release = "not a method", ITestInterface),
+            quote!(
+                struct CleanedType;
+            ),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_com_class_with_no_class_factory()
+    {
+        let cls = ComClass::parse(
+            "not used",
+            quote!(no_class_factory, ITestInterface),
+            quote!(
+                struct InternalType;
+            ),
+        )
+        .expect("com_class attribute parsing failed");
+
+        assert!(cls.no_class_factory);
+        assert_eq!(cls.interfaces.len(), 1);
+        assert_eq!(cls.interfaces[0], parse_quote!(ITestInterface));
+    }
 }
@@ -0,0 +1,260 @@
+use super::macros::*;
+use super::*;
+use crate::prelude::*;
+
+use syn::spanned::Spanned;
+use syn::{Abi, Item, ItemMod, Lit, LitStr, Pat, ReturnType, Type};
+
+intercom_attribute!(
+    ComDllModuleAttr<ComDllModuleAttrParam, NoParams> {
+        dllname : LitStr,
+    }
+);
+
+/// Details of a `mod` marked with the `#[com_dll_module]` attribute.
+#[derive(Debug)]
+pub struct ComDllModule
+{
+    pub name: Ident,
+    pub dll_name: String,
+    pub functions: Vec<ComDllFunction>,
+    pub constants: Vec<ComDllConstant>,
+    pub item: ItemMod,
+}
+
+/// Entry point exported from the DLL.
+#[derive(Debug)]
+pub struct ComDllFunction
+{
+    pub name: Ident,
+    pub args: Vec<(Ident, Type)>,
+    pub return_type: Option<Type>,
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+/// Constant described in the module.
+#[derive(Debug)]
+pub struct ComDllConstant
+{
+    pub name: Ident,
+    pub ty: Type,
+
+    /// The value formatted as an IDL literal.
+    pub value: String,
+
+    /// IDL type of the constant if it isn't described by the Rust type.
+    pub idl_ty: Option<String>,
+    pub span: Span,
+}
+
+impl ComDllModule
+{
+    /// Parses a `#[com_dll_module]` attribute and the associated module.
+    pub fn parse(
+        crate_name: &str,
+        attr_params: TokenStream,
+        item: TokenStream,
+    ) -> ParseResult<ComDllModule>
+    {
+        let item: ItemMod = ::syn::parse2(item).map_err(|_| {
+            ParseError::ComDllModule("<Unknown>".into(), "Item syntax error".into())
+        })?;
+        let name = item.ident.clone();
+        let err = |msg: &str| ParseError::ComDllModule(name.to_string(), msg.into());
+
+        let attr: ComDllModuleAttr = ::syn::parse2(attr_params)
+            .map_err(|e| err(&format!("Attribute syntax error: {}", e)))?;
+        let dll_name = match attr.dllname().map_err(|msg| err(&msg))? {
+            Some(dll_name) => dll_name.value(),
+            None => format!("{}.dll", crate_name),
+        };
+
+        let content = match &item.content {
+            Some((_, content)) => content,
+            None => return Err(err("The module must be defined inline")),
+        };
+
+        let mut functions = vec![];
+        let mut constants = vec![];
+        for module_item in content {
+            match module_item {
+                Item::Fn(f) if f.sig.abi.is_some() => {
+                    if !is_system_abi(f.sig.abi.as_ref()) {
+                        return Err(err(&format!(
+                            "Entry point `{}` must use the \"system\" ABI",
+                            f.sig.ident
+                        )));
+                    }
+
+                    let args = f
+                        .sig
+                        .inputs
+                        .iter()
+                        .map(|arg| match arg {
+                            syn::FnArg::Typed(pat) => match &*pat.pat {
+                                Pat::Ident(ident) => Ok((ident.ident.clone(), (*pat.ty).clone())),
+                                _ => Err(err(&format!(
+                                    "Unsupported argument pattern in `{}`",
+                                    f.sig.ident
+                                ))),
+                            },
+                            syn::FnArg::Receiver(_) => Err(err(&format!(
+                                "Entry point `{}` has a receiver",
+                                f.sig.ident
+                            ))),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let return_type = match &f.sig.output {
+                        ReturnType::Default => None,
+                        ReturnType::Type(_, ty) => Some((**ty).clone()),
+                    };
+
+                    functions.push(ComDllFunction {
+                        name: f.sig.ident.clone(),
+                        args,
+                        return_type,
+                        doc: crate::utils::get_doc(&f.attrs),
+                        span: f.sig.span(),
+                    });
+                }
+                Item::Const(c) => {
+                    let lit = match &*c.expr {
+                        syn::Expr::Lit(lit) => &lit.lit,
+                        _ => {
+                            return Err(err(&format!(
+                                "Constant `{}` must be a literal value",
+                                c.ident
+                            )))
+                        }
+                    };
+                    let (value, idl_ty) = match lit {
+                        Lit::Int(i) => (i.base10_digits().to_string(), None),
+                        Lit::Float(f) => (f.base10_digits().to_string(), None),
+                        Lit::Str(s) => (
+                            format!(
+                                "\"{}\"",
+                                s.value().replace('\\', "\\\\").replace('"', "\\\"")
+                            ),
+                            Some("char*".to_string()),
+                        ),
+                        _ => {
+                            return Err(err(&format!(
+                                "Constant `{}` must be an integer, float or string literal",
+                                c.ident
+                            )))
+                        }
+                    };
+
+                    constants.push(ComDllConstant {
+                        name: c.ident.clone(),
+                        ty: (*c.ty).clone(),
+                        value,
+                        idl_ty,
+                        span: c.span(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(ComDllModule {
+            name,
+            dll_name,
+            functions,
+            constants,
+            item,
+        })
+    }
+}
+
+fn is_system_abi(abi: Option<&Abi>) -> bool
+{
+    match abi.and_then(|abi| abi.name.as_ref()) {
+        Some(name) => name.value() == "system" || name.value() == "stdcall",
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    #[test]
+    fn parse_com_dll_module()
+    {
+        let module = ComDllModule::parse(
+            "my_lib",
+            quote!(dllname = "exports.dll"),
+            quote!(
+                mod exports
+                {
+                    /// Adds two numbers.
+                    #[no_mangle]
+                    pub extern "system" fn add(a: i32, b: i32) -> i32
+                    {
+                        a + b
+                    }
+
+                    pub const MAX_COUNT: u32 = 10;
+                    pub const GREETING: &str = "Say \"hi\"";
+
+                    fn helper() {}
+                }
+            ),
+        )
+        .expect("com_dll_module parsing failed");
+
+        assert_eq!(module.name, "exports");
+        assert_eq!(module.dll_name, "exports.dll");
+
+        assert_eq!(module.functions.len(), 1);
+        let add = &module.functions[0];
+        assert_eq!(add.name, "add");
+        assert_eq!(add.args.len(), 2);
+        assert_eq!(add.return_type, Some(parse_quote!(i32)));
+        assert_eq!(add.doc.as_deref(), Some("Adds two numbers."));
+
+        assert_eq!(module.constants.len(), 2);
+        assert_eq!(module.constants[0].name, "MAX_COUNT");
+        assert_eq!(module.constants[0].value, "10");
+        assert_eq!(module.constants[0].idl_ty, None);
+        assert_eq!(module.constants[1].value, "\"Say \\\"hi\\\"\"");
+        assert_eq!(module.constants[1].idl_ty.as_deref(), Some("char*"));
+    }
+
+    #[test]
+    fn parse_com_dll_module_with_default_dllname()
+    {
+        let module = ComDllModule::parse(
+            "my_lib",
+            quote!(),
+            quote!(
+                mod exports
+                {}
+            ),
+        )
+        .unwrap();
+        assert_eq!(module.dll_name, "my_lib.dll");
+    }
+
+    #[test]
+    fn entry_point_requires_system_abi()
+    {
+        let result = ComDllModule::parse(
+            "my_lib",
+            quote!(),
+            quote!(
+                mod exports
+                {
+                    pub extern "C" fn add(a: i32, b: i32) -> i32
+                    {
+                        a + b
+                    }
+                }
+            ),
+        );
+        assert!(result.is_err());
+    }
+}
@@ -1,25 +1,29 @@
-use super::macros::*;
 use super::*;
 use crate::prelude::*;
 
 use crate::ast_converters::*;
 use crate::guid::GUID;
 use crate::idents::{self, SomeIdent};
-use crate::methodinfo::ComMethodInfo;
+use crate::methodinfo::{ComMethodInfo, MethodKind, ReturnKind};
 use crate::quote::ToTokens;
-use crate::tyhandlers::ModelTypeSystem;
+use crate::returnhandlers::{self, ErrorPolicy};
+use crate::tyhandlers::{Direction, ModelTypeSystem};
 use indexmap::IndexMap;
 use proc_macro2::Span;
+use std::collections::BTreeMap;
 use std::iter::FromIterator;
 use syn::{Ident, LitStr, Path, TypePath, Visibility};
 
 intercom_attribute!(
-    ComInterfaceAttr< ComInterfaceAttrParam, NoParams > {
+    ComInterfaceAttr< ComInterfaceAttrParam, Ident > {
         com_iid : LitStr,
         raw_iid : LitStr,
         base : Path,
         vtable_of: Path,
         implemented_by: Path,
+        iid_namespace: LitStr,
+        error_policy: LitStr,
+        pointer_width: LitStr,
     }
 );
 
@@ -48,6 +52,27 @@ pub struct ComInterface
     pub itf_ref: TokenStream,
     pub vtable_of: Option<Path>,
     pub implemented_by: Option<Path>,
+    pub doc: Option<String>,
+
+    /// The `usize` and `isize` types are allowed with their width following
+    /// the compilation target.
+    pub target_pointer_width: bool,
+
+    /// Some of the IIDs are derived from the interface name instead of being
+    /// specified explicitly.
+    pub generated_iid: bool,
+
+    /// The interface derives from `IDispatch` and serves the late bound
+    /// calls.
+    pub dual: bool,
+
+    /// The interface is described as a `dispinterface`, which the clients
+    /// call through `IDispatch` only. Implies `dual`.
+    pub dispinterface: bool,
+
+    /// The interface has fallible methods that store the `IErrorInfo` on
+    /// failure.
+    pub error_info: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -95,15 +120,86 @@ impl ComInterface
             )
         })?;
 
+        // The futures outlive the call so they can't borrow the object as
+        // the async fns do. Traits with futures wouldn't be object safe.
+        for sig in &fns {
+            let returns_impl = match &sig.output {
+                syn::ReturnType::Type(_, ty) => matches!(**ty, syn::Type::ImplTrait(..)),
+                syn::ReturnType::Default => false,
+            };
+            let msg = if sig.asyncness.is_some() {
+                format!(
+                    "Method {} is async, return impl Future + Send + 'static instead",
+                    sig.ident
+                )
+            } else if itf_type == crate::utils::InterfaceType::Trait && returns_impl {
+                format!(
+                    "Method {} returns a future, which is supported only on the impl interfaces",
+                    sig.ident
+                )
+            } else if itf_type == crate::utils::InterfaceType::Trait && is_typed_self(sig) {
+                format!(
+                    "Method {} has a pinned receiver, which is supported only on the impl interfaces",
+                    sig.ident
+                )
+            } else {
+                continue;
+            };
+            return Err(ParseError::ComInterface(ident.to_string(), msg));
+        }
+
+        // The flags are given without a value.
+        let mut dual = false;
+        let mut dispinterface = false;
+        for flag in attr.args() {
+            match flag.to_string().as_str() {
+                "dual" => dual = true,
+                "dispinterface" => dispinterface = true,
+                other => {
+                    return Err(ParseError::ComInterface(
+                        ident.to_string(),
+                        format!("Unexpected flag: `{}`", other),
+                    ))
+                }
+            }
+        }
+
+        if dual && dispinterface {
+            return Err(ParseError::ComInterface(
+                ident.to_string(),
+                "Interface cannot be both dual and a dispinterface".into(),
+            ));
+        }
+
+        // The dispinterfaces are served the same way as the dual interfaces,
+        // only the clients don't see their virtual table methods.
+        let dual = dual || dispinterface;
+
+        // The dual interfaces are called through their `ComItf`, which
+        // implements the methods of the trait interfaces only.
+        if dual && itf_type != crate::utils::InterfaceType::Trait {
+            return Err(ParseError::ComInterface(
+                ident.to_string(),
+                "Dual interfaces must be traits".into(),
+            ));
+        }
+
         // The second argument is the optional base class. If there's no base
-        // class defined, use IUnknown as the default. The value of NO_BASE will
-        // construct an interface that has no base class.
+        // class defined, use IUnknown as the default, or IDispatch for the
+        // dual interfaces. The value of NO_BASE will construct an interface
+        // that has no base class.
         //
         // In practice the NO_BASE should be used ONLY for the IUnknown itself.
         let base = attr
             .base()
             .map_err(|msg| ParseError::ComInterface(item.get_ident().unwrap().to_string(), msg))?;
         let base = match base {
+            Some(b) if dual && b.get_some_ident().map(|i| i != "IDispatch").unwrap_or(true) => {
+                return Err(ParseError::ComInterface(
+                    ident.to_string(),
+                    "Dual interfaces must derive from IDispatch".into(),
+                ))
+            }
             Some(b) => {
                 if b.get_ident().map(|i| i == "NO_BASE") == Some(true) {
                     None
@@ -111,6 +207,7 @@ impl ComInterface
                     Some(b.to_owned())
                 }
             }
+            None if dual => Some(syn::parse2(quote!(intercom::IDispatch)).unwrap()),
             None => Some(syn::parse2(quote!(intercom::IUnknown)).unwrap()),
         };
 
@@ -128,6 +225,84 @@ impl ComInterface
             parse_quote!(pub)
         };
 
+        // Automatic IIDs are derived from the namespace if one is given.
+        let iid_namespace = attr
+            .iid_namespace()
+            .map_err(|msg| ParseError::ComInterface(ident.to_string(), msg))?
+            .map(|ns| {
+                GUID::parse(&ns.value()).map_err(|_| {
+                    ParseError::ComInterface(ident.to_string(), "Bad IID namespace format".into())
+                })
+            })
+            .transpose()?;
+
+        // Error policy applies to all the `Result` methods of the interface.
+        let error_policy = attr
+            .error_policy()
+            .map_err(|msg| ParseError::ComInterface(ident.to_string(), msg))?
+            .map(|policy| {
+                policy
+                    .value()
+                    .parse::<ErrorPolicy>()
+                    .map_err(|msg| ParseError::ComInterface(ident.to_string(), msg))
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        // Pointer-width types are accepted only if the author opts into the
+        // width of the target.
+        let target_pointer_width = match attr
+            .pointer_width()
+            .map_err(|msg| ParseError::ComInterface(ident.to_string(), msg))?
+        {
+            None => false,
+            Some(width) if width.value() == "target" => true,
+            Some(width) => {
+                return Err(ParseError::ComInterface(
+                    ident.to_string(),
+                    format!(
+                        "Unknown pointer width \"{}\", use fixed width types instead",
+                        width.value()
+                    ),
+                ))
+            }
+        };
+
+        // The IIDs that aren't given explicitly are derived from the name and
+        // change if the interface is renamed.
+        let mut generated_iid = false;
+        for &ts in &[ModelTypeSystem::Automation, ModelTypeSystem::Raw] {
+            generated_iid |= attr
+                .iid(ts)
+                .map_err(|msg| ParseError::ComInterface(ident.to_string(), msg))?
+                .is_none();
+        }
+
+        // The doc comments become the help strings in the type library.
+        let doc = match &item {
+            syn::Item::Trait(t) => crate::utils::get_doc(&t.attrs),
+            syn::Item::Impl(i) => crate::utils::get_doc(&i.attrs),
+            _ => None,
+        };
+        let method_docs = crate::utils::get_method_docs(&item);
+        let method_cfgs = crate::utils::get_method_cfgs(&item);
+        let return_methods = crate::utils::get_com_return_methods(&item)
+            .map_err(|msg| ParseError::ComInterface(ident.to_string(), msg))?;
+        let returns = |method: &Ident, kind: ReturnKind| {
+            return_methods
+                .iter()
+                .any(|(name, k)| name == method && *k == kind)
+        };
+        let property_methods = crate::utils::get_property_methods(&item)
+            .map_err(|msg| ParseError::ComInterface(ident.to_string(), msg))?;
+        let method_options = crate::utils::get_com_method_options(&item)
+            .map_err(|msg| ParseError::ComInterface(ident.to_string(), msg))?;
+        let has_option = |method: &Ident, option: &str| {
+            method_options
+                .iter()
+                .any(|(name, o)| name == method && o == option)
+        };
+
         let variants = IndexMap::from_iter(
             [ModelTypeSystem::Automation, ModelTypeSystem::Raw]
                 .iter()
@@ -142,19 +317,277 @@ impl ComInterface
                                 "Bad IID format".into(),
                             )
                         })?,
-                        None => crate::utils::generate_iid(crate_name, &ident.to_string(), ts),
+                        None => match &iid_namespace {
+                            Some(ns) => {
+                                crate::utils::generate_namespaced_iid(ns, &ident.to_string(), ts)
+                            }
+                            None => crate::utils::generate_iid(crate_name, &ident.to_string(), ts),
+                        },
                     };
 
                     // Read the method details.
                     //
                     // TODO: Currently we ignore invalid methods. We should probably do
                     //       something smarter.
-                    let methods = fns
+                    let mut methods = fns
                         .iter()
-                        .map(|sig| ComMethodInfo::new(sig, ts))
+                        .map(|sig| ComMethodInfo::new(sig, ts, error_policy))
                         .filter_map(Result::ok)
+                        .map(|mut method| {
+                            method.doc = method_docs
+                                .iter()
+                                .find(|(name, _)| name == &method.name)
+                                .and_then(|(_, doc)| doc.clone());
+                            method.cfgs = method_cfgs
+                                .iter()
+                                .find(|(name, _)| name == &method.name)
+                                .map(|(_, cfgs)| cfgs.clone())
+                                .unwrap_or_default();
+                            if let Some((_, kind)) = property_methods
+                                .iter()
+                                .find(|(name, _)| name == &method.name)
+                            {
+                                method.kind = *kind;
+                            }
+                            method.is_operation = has_option(&method.name, "operation");
+                            method.is_hidden = has_option(&method.name, "hidden");
+                            method.is_restricted = has_option(&method.name, "restricted");
+                            method.is_newenum = has_option(&method.name, "newenum");
+
+                            // The Automation type system keeps the SAFEARRAY
+                            // as the buffers aren't Automation compatible.
+                            let elem_ty = method
+                                .retval_type
+                                .as_ref()
+                                .and_then(crate::utils::get_vec_ty);
+                            if let (ModelTypeSystem::Raw, Some(elem_ty), Some(return_ty)) =
+                                (ts, elem_ty, &method.return_type)
+                            {
+                                if returns(&method.name, ReturnKind::PtrAndLen) {
+                                    method.returnhandler = returnhandlers::get_ptr_and_len_handler(
+                                        elem_ty,
+                                        return_ty,
+                                        method.returnhandler.return_type_span(),
+                                        ts,
+                                        error_policy,
+                                    )
+                                    .into();
+                                }
+                            }
+                            method
+                        })
                         .collect::<Vec<_>>();
 
+                    // The dispids are assigned in the order of the methods so
+                    // leaving out a method would shift the rest of them.
+                    if let Some(method) = methods.iter().find(|m| dual && !m.cfgs.is_empty()) {
+                        return Err(ParseError::ComInterface(
+                            ident.to_string(),
+                            format!(
+                                "Method {} of a dual interface cannot be conditionally compiled",
+                                method.name
+                            ),
+                        ));
+                    }
+
+                    // Leaving out a method changes the virtual table so each
+                    // configuration must come with IIDs of its own. The
+                    // generated IIDs would be the same for all of them.
+                    if let Some(method) =
+                        methods.iter().find(|m| generated_iid && !m.cfgs.is_empty())
+                    {
+                        return Err(ParseError::ComInterface(
+                            ident.to_string(),
+                            format!(
+                                "Method {} is conditionally compiled, which changes the \
+                                 virtual table: specify com_iid and raw_iid explicitly for \
+                                 each configuration",
+                                method.name
+                            ),
+                        ));
+                    }
+
+                    // The property getters return the value and the setters
+                    // receive it as their only parameter.
+                    for method in &methods {
+                        let value = method.retval_type.as_ref().or(method.return_type.as_ref());
+                        let has_value = value.map(|ty| !crate::utils::is_unit(ty)).unwrap_or(false);
+                        let msg = match method.kind {
+                            MethodKind::PropertyGet if !method.args.is_empty() || !has_value => {
+                                format!(
+                                    "Method {} must take no parameters and return a value \
+                                     for #[com_property(get)]",
+                                    method.name
+                                )
+                            }
+                            MethodKind::PropertyPut
+                                if method.args.len() != 1
+                                    || method.args[0].dir != Direction::In =>
+                            {
+                                format!(
+                                    "Method {} must take exactly one input parameter \
+                                     for #[com_property(put)]",
+                                    method.name
+                                )
+                            }
+                            _ => continue,
+                        };
+                        return Err(ParseError::ComInterface(ident.to_string(), msg));
+                    }
+
+                    // Returning references is hazardous enough that the
+                    // methods must opt into the borrowed arrays explicitly.
+                    // The static slices outlive every client so they are
+                    // borrowed without the opt-in.
+                    for method in &methods {
+                        let return_ty = method.retval_type.as_ref().or(method.return_type.as_ref());
+                        let borrows = returns(&method.name, ReturnKind::BorrowArray);
+                        let msg = match (return_ty, borrows) {
+                            (Some(syn::Type::Reference(r)), true)
+                                if r.mutability.is_none()
+                                    && matches!(*r.elem, syn::Type::Slice(..)) =>
+                            {
+                                continue
+                            }
+                            (Some(syn::Type::Reference(r)), false)
+                                if r.mutability.is_none()
+                                    && matches!(*r.elem, syn::Type::Slice(..))
+                                    && matches!(&r.lifetime, Some(l) if l.ident == "static") =>
+                            {
+                                continue
+                            }
+                            (Some(syn::Type::Reference(..)), false) => format!(
+                                "Method {} returns a reference, borrowed arrays \
+                                 require #[com_return(borrow_array)]",
+                                method.name
+                            ),
+                            (_, true) => format!(
+                                "Method {} must return a slice for \
+                                 #[com_return(borrow_array)]",
+                                method.name
+                            ),
+                            _ => continue,
+                        };
+                        return Err(ParseError::ComInterface(ident.to_string(), msg));
+                    }
+
+                    // The buffers are returned from the `Vec` values only.
+                    for method in &methods {
+                        let returns_vec = method
+                            .retval_type
+                            .as_ref()
+                            .and_then(crate::utils::get_vec_ty)
+                            .is_some();
+                        if returns(&method.name, ReturnKind::PtrAndLen) && !returns_vec {
+                            return Err(ParseError::ComInterface(
+                                ident.to_string(),
+                                format!(
+                                    "Method {} must return ComResult<Vec<T>> for \
+                                     #[com_return(ptr_and_len)]",
+                                    method.name
+                                ),
+                            ));
+                        }
+                    }
+
+                    // The element counts describe the buffers passed as raw
+                    // pointers.
+                    for method in &methods {
+                        for arg in &method.args {
+                            let buffer = match &arg.length_of {
+                                Some(buffer) => buffer,
+                                None => continue,
+                            };
+                            let is_buffer = method
+                                .args
+                                .iter()
+                                .any(|a| a.name == buffer && matches!(a.ty, syn::Type::Ptr(..)));
+                            let msg = if !is_buffer {
+                                format!(
+                                    "Method {} has no buffer parameter {} for {}",
+                                    method.name, buffer, arg.name
+                                )
+                            } else if arg.handler.rust_ty() != syn::parse_quote!(u32) {
+                                format!(
+                                    "Method {} must use u32 for the length {} of {}",
+                                    method.name, arg.name, buffer
+                                )
+                            } else if arg.dir == Direction::Out && method.infallible {
+                                format!(
+                                    "Method {} must return ComResult<T> to report the \
+                                     length {} of {}",
+                                    method.name, arg.name, buffer
+                                )
+                            } else {
+                                continue;
+                            };
+                            return Err(ParseError::ComInterface(ident.to_string(), msg));
+                        }
+                    }
+
+                    // The operations run after the call has returned so they
+                    // can't borrow the parameters or mutate the object. The
+                    // caller receives the `IOperation` in place of the value.
+                    for method in methods.iter_mut().filter(|m| m.is_operation) {
+                        let borrows = method.args.iter().any(|arg| {
+                            matches!(arg.ty, syn::Type::Reference(..) | syn::Type::Ptr(..))
+                        });
+                        let msg = if itf_type != crate::utils::InterfaceType::Struct {
+                            format!(
+                                "Method {} is an operation, which is supported only on the \
+                                 impl interfaces",
+                                method.name
+                            )
+                        } else if !method.is_const || method.is_pinned || method.is_future {
+                            format!(
+                                "Method {} must take &self for #[com_method(operation)]",
+                                method.name
+                            )
+                        } else if method.retval_type.is_none() {
+                            format!(
+                                "Method {} must return Result<T, E> for \
+                                 #[com_method(operation)]",
+                                method.name
+                            )
+                        } else if borrows || method.args.iter().any(|a| a.dir != Direction::In) {
+                            format!(
+                                "Method {} must take its parameters by value for \
+                                 #[com_method(operation)]",
+                                method.name
+                            )
+                        } else if method.kind != MethodKind::Method
+                            || return_methods.iter().any(|(name, _)| name == &method.name)
+                        {
+                            format!(
+                                "Method {} cannot combine #[com_method(operation)] with \
+                                 #[com_property] or #[com_return]",
+                                method.name
+                            )
+                        } else {
+                            let span = method.returnhandler.return_type_span();
+                            method.retval_type = Some(
+                                syn::parse2(quote_spanned!(span =>
+                                    intercom::ComRc<dyn intercom::operation::IOperation>))
+                                .unwrap(),
+                            );
+                            method.return_type = Some(
+                                syn::parse2(quote_spanned!(span => intercom::ComError)).unwrap(),
+                            );
+                            method.returnhandler = returnhandlers::get_return_handler(
+                                &method.retval_type,
+                                &method.return_type,
+                                span,
+                                ts,
+                                error_policy,
+                            )
+                            .map_err(|msg| ParseError::ComInterface(ident.to_string(), msg.into()))?
+                            .into();
+                            method.infallible = false;
+                            continue;
+                        };
+                        return Err(ParseError::ComInterface(ident.to_string(), msg));
+                    }
+
                     Ok((
                         ts,
                         ComInterfaceVariant {
@@ -167,6 +600,13 @@ impl ComInterface
                 .collect::<Result<Vec<_>, _>>()?,
         );
 
+        // Only the fallible methods report their errors and the policy may
+        // opt out of the IErrorInfo altogether.
+        let error_info = error_policy == ErrorPolicy::ErrorInfo
+            && variants
+                .values()
+                .any(|variant| variant.methods.iter().any(|method| !method.infallible));
+
         let itf_ref = match itf_type {
             crate::utils::InterfaceType::Trait => quote_spanned!(ident.span() => dyn #path),
             crate::utils::InterfaceType::Struct => quote_spanned!(ident.span() => #path),
@@ -190,6 +630,12 @@ impl ComInterface
             visibility,
             variants,
             itf_ref,
+            doc,
+            target_pointer_width,
+            generated_iid,
+            dual,
+            dispinterface,
+            error_info,
         })
     }
 
@@ -209,6 +655,42 @@ impl ComInterface
     }
 }
 
+impl ComInterfaceVariant
+{
+    /// DISPIDs of the methods when called through `IDispatch`.
+    ///
+    /// The DISPIDs follow the method order starting from 1 as 0 is reserved
+    /// for the default member. The accessors of a property share the DISPID
+    /// of the first accessor.
+    pub fn dispids(&self) -> Vec<i32>
+    {
+        let mut properties = BTreeMap::new();
+        self.methods
+            .iter()
+            .enumerate()
+            .map(|(idx, method)| {
+                let dispid = idx as i32 + 1;
+                match method.property_name() {
+                    Some(property) => *properties.entry(property).or_insert(dispid),
+                    None => dispid,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Checks whether the method takes the self parameter with an explicit type.
+fn is_typed_self(sig: &syn::Signature) -> bool
+{
+    match sig.inputs.first() {
+        Some(syn::FnArg::Typed(pat)) => match &*pat.pat {
+            syn::Pat::Ident(ident) => ident.ident == "self",
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod test
 {
@@ -304,6 +786,37 @@ mod test
         assert_eq!(variant.methods[1].name, "two");
     }
 
+    #[test]
+    fn parse_com_interface_with_iid_namespace()
+    {
+        let parse = |crate_name| {
+            ComInterface::from_ast(
+                crate_name,
+                quote!(iid_namespace = "2a5d8bb1-6b43-4a7a-9b0e-0e4d0e2c2f51"),
+                quote!(
+                    pub trait INamespaced
+                    {
+                        fn one(&self);
+                    }
+                ),
+            )
+            .expect("com_interface attribute parsing failed")
+        };
+
+        // The crate name doesn't affect the namespaced IIDs.
+        let itf = parse("crate_a");
+        assert_eq!(itf.variants, parse("crate_b").variants);
+
+        assert_eq!(
+            itf.variants[&Automation].iid,
+            GUID::parse("225198ED-BAFB-54D3-B587-5968D0EE0B63").unwrap()
+        );
+        assert_eq!(
+            itf.variants[&Raw].iid,
+            GUID::parse("7E3003A9-D0E2-50CD-BB83-FE7B9F63A358").unwrap()
+        );
+    }
+
     #[test]
     fn parse_com_interface_with_base_interface()
     {
@@ -385,4 +898,567 @@ mod test
         assert_eq!(variant.methods[0].name, "one");
         assert_eq!(variant.methods[1].name, "two");
     }
+
+    #[test]
+    fn parse_com_interface_with_error_policy()
+    {
+        let error_code = |policy: TokenStream| {
+            let itf = ComInterface::from_ast(
+                "not used",
+                policy,
+                quote!(
+                    pub trait IFallible
+                    {
+                        fn fail(&self) -> ComResult<u32>;
+                    }
+                ),
+            )
+            .expect("com_interface attribute parsing failed");
+
+            let method = &itf.variants[&Automation].methods[0];
+            let result = Ident::new("__result", Span::call_site());
+            (
+                method.returnhandler.rust_to_com_return(&result).to_string(),
+                method.returnhandler.com_to_rust_return(&result).to_string(),
+            )
+        };
+
+        let (default_rust_to_com, default_com_to_rust) = error_code(quote!());
+        let (info_rust_to_com, info_com_to_rust) = error_code(quote!(error_policy = "error_info"));
+        let (hr_rust_to_com, hr_com_to_rust) = error_code(quote!(error_policy = "hresult_only"));
+
+        assert_eq!(default_rust_to_com, info_rust_to_com);
+        assert_eq!(default_com_to_rust, info_com_to_rust);
+
+        assert!(info_rust_to_com.contains("store_error"));
+        assert!(info_com_to_rust.contains("load_error"));
+        assert!(!hr_rust_to_com.contains("store_error"));
+        assert!(!hr_com_to_rust.contains("load_error"));
+        assert!(hr_com_to_rust.contains("ComError :: new_hr"));
+
+        let result = ComInterface::from_ast(
+            "not used",
+            quote!(error_policy = "unknown"),
+            quote!(
+                pub trait IFallible
+                {
+                    fn fail(&self) -> ComResult<u32>;
+                }
+            ),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_method_cfgs()
+    {
+        let item = quote!(
+            trait IExtensible
+            {
+                fn base(&self);
+
+                #[cfg(feature = "extended")]
+                fn extended(&self);
+            }
+        );
+        let itf = ComInterface::from_ast(
+            "not used",
+            quote!(
+                com_iid = "12345678-1234-1234-1234-567890ABCDEF",
+                raw_iid = "12345678-1234-1234-1234-567890FEDCBA"
+            ),
+            item.clone(),
+        )
+        .unwrap();
+
+        let methods = &itf.variants[&Automation].methods;
+        assert!(methods[0].cfgs.is_empty());
+        assert_eq!(
+            methods[1]
+                .cfgs
+                .iter()
+                .map(|cfg| cfg.to_token_stream().to_string())
+                .collect::<Vec<_>>(),
+            vec![quote!(#[cfg(feature = "extended")]).to_string()]
+        );
+
+        // The generated IIDs would not change with the virtual table.
+        let result = ComInterface::from_ast("not used", quote!(), item.clone());
+        assert!(result.is_err());
+        let result = ComInterface::from_ast(
+            "not used",
+            quote!(com_iid = "12345678-1234-1234-1234-567890ABCDEF"),
+            item,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn error_info_requires_fallible_methods()
+    {
+        let parse = |attr, item| ComInterface::from_ast("not used", attr, item).unwrap();
+
+        let fallible = quote!(
+            pub trait IFallible
+            {
+                fn fail(&self) -> ComResult<u32>;
+            }
+        );
+        assert!(parse(quote!(), fallible.clone()).error_info);
+        assert!(!parse(quote!(error_policy = "hresult_only"), fallible).error_info);
+
+        let infallible = parse(
+            quote!(),
+            quote!(
+                pub trait IInfallible
+                {
+                    fn value(&self) -> u32;
+                }
+            ),
+        );
+        assert!(!infallible.error_info);
+    }
+
+    #[test]
+    fn futures_require_impl_interface()
+    {
+        let parse = |item| ComInterface::from_ast("not used", quote!(), item);
+
+        let itf = parse(quote!(
+            impl Downloader
+            {
+                fn download(&self, size: u32)
+                    -> impl Future<Output = ComResult<u32>> + Send + 'static
+                {
+                }
+            }
+        ))
+        .unwrap();
+        let method = &itf.variants[&ModelTypeSystem::Automation].methods[0];
+        assert!(method.is_future);
+        let args = method.raw_com_args();
+        assert_eq!(args.len(), 2);
+        assert_eq!(args[1].name, "__completion");
+        assert_eq!(args[1].dir, crate::tyhandlers::Direction::In);
+
+        assert!(parse(quote!(
+            impl Downloader
+            {
+                async fn download(&self) -> ComResult<u32> {}
+            }
+        ))
+        .is_err());
+
+        assert!(parse(quote!(
+            trait IDownloader
+            {
+                fn download(&self) -> impl Future<Output = ComResult<u32>>;
+            }
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn operation_returns_handle()
+    {
+        let parse = |item| ComInterface::from_ast("not used", quote!(), item);
+
+        let itf = parse(quote!(
+            impl Calculator
+            {
+                #[com_method(operation)]
+                fn square(&self, value: u32) -> ComResult<u32> {}
+            }
+        ))
+        .unwrap();
+        let method = &itf.variants[&ModelTypeSystem::Raw].methods[0];
+        assert!(method.is_operation);
+        assert_eq!(
+            method.retval_type,
+            Some(parse_quote!(
+                intercom::ComRc<dyn intercom::operation::IOperation>
+            ))
+        );
+        assert_eq!(method.rust_return_ty, parse_quote!(ComResult<u32>));
+
+        for item in [
+            quote!(
+                trait ICalculator
+                {
+                    #[com_method(operation)]
+                    fn square(&self, value: u32) -> ComResult<u32>;
+                }
+            ),
+            quote!(
+                impl Calculator
+                {
+                    #[com_method(operation)]
+                    fn square(&mut self, value: u32) -> ComResult<u32> {}
+                }
+            ),
+            quote!(
+                impl Calculator
+                {
+                    #[com_method(operation)]
+                    fn square(&self, value: u32) -> u32 {}
+                }
+            ),
+            quote!(
+                impl Calculator
+                {
+                    #[com_method(operation)]
+                    fn length(&self, text: &str) -> ComResult<u32> {}
+                }
+            ),
+            quote!(
+                impl Calculator
+                {
+                    #[com_method(operation)]
+                    #[com_property(get)]
+                    fn value(&self) -> ComResult<u32> {}
+                }
+            ),
+            quote!(
+                impl Calculator
+                {
+                    #[com_method(blocking)]
+                    fn square(&self, value: u32) -> ComResult<u32> {}
+                }
+            ),
+        ] {
+            assert!(parse(item).is_err());
+        }
+    }
+
+    #[test]
+    fn method_options()
+    {
+        let itf = ComInterface::from_ast(
+            "not used",
+            quote!(),
+            quote!(
+                trait IPlaylist
+                {
+                    #[com_method(newenum)]
+                    fn tracks(&self) -> ComResult<ComRc<dyn IUnknown>>;
+                    #[com_method(hidden, restricted)]
+                    fn reset(&self);
+                }
+            ),
+        )
+        .unwrap();
+        let methods = &itf.variants[&ModelTypeSystem::Automation].methods;
+        assert!(methods[0].is_newenum && !methods[0].is_hidden && !methods[0].is_restricted);
+        assert!(!methods[1].is_newenum && methods[1].is_hidden && methods[1].is_restricted);
+    }
+
+    #[test]
+    fn pinned_receiver_requires_impl_interface()
+    {
+        let parse = |item| ComInterface::from_ast("not used", quote!(), item);
+
+        let itf = parse(quote!(
+            impl Anchor
+            {
+                fn register(self: Pin<&mut Self>) -> ComResult<()> {}
+            }
+        ))
+        .unwrap();
+        let method = &itf.variants[&ModelTypeSystem::Automation].methods[0];
+        assert!(method.is_pinned);
+        assert!(!method.is_const);
+
+        assert!(parse(quote!(
+            trait IAnchor
+            {
+                fn register(self: Pin<&mut Self>) -> ComResult<()>;
+            }
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn length_of_requires_buffer()
+    {
+        let parse = |item| ComInterface::from_ast("not used", quote!(), item);
+
+        let itf = parse(quote!(
+            trait IReader
+            {
+                unsafe fn read(
+                    &self,
+                    buffer: *mut u8,
+                    #[com_arg(length_of = "buffer")] capacity: u32,
+                    #[com_arg(length_of = "buffer")] out_count: &mut u32,
+                ) -> ComResult<()>;
+            }
+        ))
+        .unwrap();
+        let method = &itf.variants[&ModelTypeSystem::Raw].methods[0];
+        let args = method.raw_com_args();
+        assert_eq!(args[0].length_of, None);
+        assert_eq!(args[1].length_of.as_deref(), Some("buffer"));
+        assert_eq!(args[2].length_of.as_deref(), Some("buffer"));
+        assert_eq!(args[2].dir, crate::tyhandlers::Direction::Out);
+
+        assert!(parse(quote!(
+            trait IReader
+            {
+                fn read(&self, buffer: u32, #[com_arg(length_of = "buffer")] count: u32);
+            }
+        ))
+        .is_err());
+
+        assert!(parse(quote!(
+            trait IReader
+            {
+                unsafe fn read(&self, #[com_arg(length_of = "data")] count: u32);
+            }
+        ))
+        .is_err());
+
+        assert!(parse(quote!(
+            trait IReader
+            {
+                unsafe fn read(&self, buffer: *mut u8, #[com_arg(length_of = "buffer")] count: u8);
+            }
+        ))
+        .is_err());
+
+        assert!(parse(quote!(
+            trait IReader
+            {
+                unsafe fn read(
+                    &self,
+                    buffer: *mut u8,
+                    #[com_arg(length_of = "buffer")] out_count: &mut u32,
+                );
+            }
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn borrowed_array_requires_com_return()
+    {
+        let parse = |item| ComInterface::from_ast("not used", quote!(), item);
+
+        assert!(parse(quote!(
+            trait IMatrix
+            {
+                #[com_return(borrow_array)]
+                fn values(&self) -> ComResult<&[f64]>;
+            }
+        ))
+        .is_ok());
+
+        assert!(parse(quote!(
+            trait IMatrix
+            {
+                fn values(&self) -> ComResult<&[f64]>;
+            }
+        ))
+        .is_err());
+
+        assert!(parse(quote!(
+            trait IMatrix
+            {
+                fn identity(&self) -> ComResult<&'static [f64]>;
+            }
+        ))
+        .is_ok());
+
+        assert!(parse(quote!(
+            trait IMatrix
+            {
+                fn identity(&self) -> ComResult<&'static mut [f64]>;
+            }
+        ))
+        .is_err());
+
+        assert!(parse(quote!(
+            trait IMatrix
+            {
+                #[com_return(borrow_array)]
+                fn values(&self) -> ComResult<Vec<String>>;
+            }
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn ptr_and_len_returns_raw_buffer()
+    {
+        let parse = |item| ComInterface::from_ast("not used", quote!(), item);
+
+        let itf = parse(quote!(
+            trait IReader
+            {
+                #[com_return(ptr_and_len)]
+                fn read(&self) -> ComResult<Vec<u8>>;
+            }
+        ))
+        .unwrap();
+
+        // The Raw type system returns the buffer and the element count.
+        let method = &itf.variants[&ModelTypeSystem::Raw].methods[0];
+        let args = method.raw_com_args();
+        assert_eq!(args.len(), 2);
+        let raw_u8: syn::Type = syn::parse_quote!(
+            <u8 as intercom::type_system::ExternType<intercom::type_system::RawTypeSystem>>::ForeignType
+        );
+        assert_eq!(args[0].ty, syn::parse_quote!(*mut #raw_u8));
+        assert_eq!(args[0].dir, crate::tyhandlers::Direction::Out);
+        assert_eq!(args[1].ty, syn::parse_quote!(u32));
+        assert_eq!(args[1].length_of.as_deref(), Some("__out"));
+
+        // Automation keeps the SAFEARRAY.
+        let method = &itf.variants[&ModelTypeSystem::Automation].methods[0];
+        let args = method.raw_com_args();
+        assert_eq!(args.len(), 1);
+        assert_eq!(args[0].dir, crate::tyhandlers::Direction::Retval);
+
+        assert!(parse(quote!(
+            trait IReader
+            {
+                #[com_return(ptr_and_len)]
+                fn read(&self) -> ComResult<String>;
+            }
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn property_accessors_require_value()
+    {
+        let parse = |item| ComInterface::from_ast("not used", quote!(), item);
+
+        let itf = parse(quote!(
+            trait ILabel
+            {
+                #[com_property(get)]
+                fn text(&self) -> ComResult<String>;
+                #[com_property(put)]
+                fn set_text(&self, text: String) -> ComResult<()>;
+            }
+        ))
+        .unwrap();
+        let methods = &itf.variants[&ModelTypeSystem::Automation].methods;
+        assert_eq!(methods[0].kind, MethodKind::PropertyGet);
+        assert_eq!(methods[1].kind, MethodKind::PropertyPut);
+
+        assert!(parse(quote!(
+            trait ILabel
+            {
+                #[com_property(get)]
+                fn text(&self, index: u32) -> ComResult<String>;
+            }
+        ))
+        .is_err());
+
+        assert!(parse(quote!(
+            trait ILabel
+            {
+                #[com_property(get)]
+                fn refresh(&self) -> ComResult<()>;
+            }
+        ))
+        .is_err());
+
+        assert!(parse(quote!(
+            trait ILabel
+            {
+                #[com_property(put)]
+                fn set_text(&self, text: String, index: u32) -> ComResult<()>;
+            }
+        ))
+        .is_err());
+
+        assert!(parse(quote!(
+            trait ILabel
+            {
+                #[com_property(set)]
+                fn set_text(&self, text: String) -> ComResult<()>;
+            }
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn dual_interfaces_derive_from_idispatch()
+    {
+        let itf = ComInterface::from_ast(
+            "not used",
+            quote!(dual),
+            quote!(
+                trait IScore
+                {
+                    fn reset(&self);
+                    #[com_property(get)]
+                    fn get_points(&self) -> u32;
+                    #[com_property(put)]
+                    fn set_points(&self, points: u32);
+                    fn add(&self, points: u32);
+                }
+            ),
+        )
+        .unwrap();
+        assert!(itf.dual);
+        assert_eq!(
+            itf.base_interface.to_token_stream().to_string(),
+            quote!(intercom::IDispatch).to_string()
+        );
+
+        // The property accessors share the DISPID.
+        let variant = &itf.variants[&ModelTypeSystem::Automation];
+        assert_eq!(variant.dispids(), vec![1, 2, 2, 4]);
+
+        let err = |attr, item| {
+            let err = ComInterface::from_ast("not used", attr, item).unwrap_err();
+            format!("{}", err)
+        };
+        assert!(err(
+            quote!(dual, base = IUnknown),
+            quote!(
+                trait IScore {}
+            )
+        )
+        .contains("Dual interfaces must derive from IDispatch"));
+        assert!(err(quote!(dual), quote!(impl Score {})).contains("Dual interfaces must be traits"));
+        assert!(err(
+            quote!(duel),
+            quote!(
+                trait IScore {}
+            )
+        )
+        .contains("Unexpected flag: `duel`"));
+        assert!(err(
+            quote!(dual, dispinterface),
+            quote!(
+                trait IScore {}
+            )
+        )
+        .contains("Interface cannot be both dual and a dispinterface"));
+        assert!(err(
+            quote!(dual),
+            quote!(
+                trait IScore
+                {
+                    #[cfg(feature = "extended")]
+                    fn reset(&self);
+                }
+            )
+        )
+        .contains("Method reset of a dual interface cannot be conditionally compiled"));
+
+        let itf = ComInterface::from_ast(
+            "not used",
+            quote!(dispinterface),
+            quote!(
+                trait IScore {}
+            ),
+        )
+        .unwrap();
+        assert!(itf.dual && itf.dispinterface);
+    }
 }
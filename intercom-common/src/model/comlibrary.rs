@@ -10,6 +10,7 @@ pub enum LibraryItemType
     Module(Path),
     Class(Path),
     Interface(Path),
+    Import(Path),
 }
 
 impl syn::parse::Parse for LibraryItemType
@@ -21,8 +22,9 @@ impl syn::parse::Parse for LibraryItemType
             "module" => Ok(LibraryItemType::Module(input.parse()?)),
             "class" => Ok(LibraryItemType::Class(input.parse()?)),
             "interface" => Ok(LibraryItemType::Interface(input.parse()?)),
+            "import" => Ok(LibraryItemType::Import(input.parse()?)),
             _ => Err(input.error(&format!(
-                "Expected 'class', 'interface' or 'module', found {}",
+                "Expected 'class', 'interface', 'import' or 'module', found {}",
                 ident
             ))),
         }
@@ -33,6 +35,7 @@ intercom_attribute!(
     ComLibraryAttr< ComLibraryAttrParam, LibraryItemType > {
         libid : LitStr,
         on_load : Path,
+        uses : LitStr,
     }
 );
 
@@ -43,8 +46,12 @@ pub struct ComLibrary
     pub name: String,
     pub libid: GUID,
     pub on_load: Option<Path>,
+    pub uses: Option<String>,
     pub coclasses: Vec<Path>,
     pub interfaces: Vec<Path>,
+
+    /// Interfaces defined in the `uses` type library.
+    pub imports: Vec<Path>,
     pub submodules: Vec<Path>,
 }
 
@@ -63,23 +70,37 @@ impl ComLibrary
         };
 
         let on_load = attr.on_load().map_err(ParseError::ComLibrary)?.cloned();
+        let uses = attr
+            .uses()
+            .map_err(ParseError::ComLibrary)?
+            .map(|uses| uses.value());
 
         let mut coclasses = vec![];
         let mut interfaces = vec![];
+        let mut imports = vec![];
         let mut submodules = vec![];
         for arg in attr.args().into_iter().cloned() {
             match arg {
                 LibraryItemType::Class(cls) => coclasses.push(cls),
                 LibraryItemType::Interface(cls) => interfaces.push(cls),
+                LibraryItemType::Import(itf) => imports.push(itf),
                 LibraryItemType::Module(cls) => submodules.push(cls),
             }
         }
 
+        if !imports.is_empty() && uses.is_none() {
+            return Err(ParseError::ComLibrary(
+                "Imported interfaces require the `uses` type library".into(),
+            ));
+        }
+
         Ok(ComLibrary {
             name: crate_name.to_owned(),
             on_load,
+            uses,
             coclasses,
             interfaces,
+            imports,
             submodules,
             libid,
         })
@@ -149,4 +170,20 @@ mod test
             GUID::parse("22EC0095-CD17-3AFD-6C4F-531464178911").unwrap()
         );
     }
+
+    #[test]
+    fn parse_com_library_with_uses()
+    {
+        let lib = ComLibrary::parse(
+            "lib",
+            quote!(uses = "other.tlb", class Foo, import other::IOther),
+        )
+        .unwrap();
+        assert_eq!(lib.uses, Some("other.tlb".to_string()));
+        assert_eq!(lib.coclasses.len(), 1);
+        assert_eq!(lib.imports, vec![parse_quote!(other::IOther)]);
+
+        let err = ComLibrary::parse("lib", quote!(import other::IOther)).unwrap_err();
+        assert!(format!("{:?}", err).contains("require the `uses` type library"));
+    }
 }
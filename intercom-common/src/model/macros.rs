@@ -202,7 +202,7 @@ macro_rules! intercom_attribute {
             )*
 
             #[doc = "Gets the positional arguments"]
-            pub fn args<'a>( &'a self ) -> Vec<&'a $params>
+            pub fn args( &self ) -> Vec<&$params>
             {
                 // Return the positional arguments.
                 self.0.iter().filter_map( |p| match p {
@@ -20,6 +20,9 @@ pub enum ParseError
     #[fail(display = "Parsing [com_interface] item {} failed: {}", _0, _1)]
     ComInterface(String, String),
 
+    #[fail(display = "Parsing [com_dll_module] item {} failed: {}", _0, _1)]
+    ComDllModule(String, String),
+
     #[fail(display = "Processing crate failed: {}", _0)]
     ComCrate(String),
 
@@ -38,3 +41,5 @@ mod comclass;
 pub use self::comclass::*;
 mod cominterface;
 pub use self::cominterface::*;
+mod comdllmodule;
+pub use self::comdllmodule::*;
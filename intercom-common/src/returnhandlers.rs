@@ -49,6 +49,10 @@ pub trait ReturnHandler: ::std::fmt::Debug
 }
 
 /// Void return type.
+///
+/// The void methods have no `HRESULT` in the vtable so there is nothing to
+/// check on the client side. Methods that may fail are declared to return
+/// `ComResult<()>` instead, which is handled by `ErrorResultHandler`.
 #[derive(Debug)]
 struct VoidHandler(Span);
 impl ReturnHandler for VoidHandler
@@ -132,8 +136,39 @@ impl ReturnHandler for ReturnOnlyHandler
     }
 }
 
+/// Interface-wide policy for reporting the `Err` values of `Result` methods.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ErrorPolicy
+{
+    /// Errors are reported with the `HRESULT` only.
+    HResultOnly,
+
+    /// Errors are reported with the `HRESULT` and `IErrorInfo`.
+    #[default]
+    ErrorInfo,
+}
+
+impl std::str::FromStr for ErrorPolicy
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String>
+    {
+        Ok(match s {
+            "hresult_only" => ErrorPolicy::HResultOnly,
+            "error_info" => ErrorPolicy::ErrorInfo,
+            _ => return Err(format!("Unknown error policy \"{}\"", s)),
+        })
+    }
+}
+
 /// Result type that supports error info for the `Err` value. Converted to
 /// `[retval]` on success or `HRESULT` + `IErrorInfo` on error.
+///
+/// With the `HResultOnly` policy the `IErrorInfo` is neither stored nor
+/// loaded and the errors carry the `HRESULT` only.
+///
+/// `Success<T>` values are converted to the `[retval]` of `T` and report
+/// their own success `HRESULT` instead of `S_OK`.
 #[derive(Debug)]
 struct ErrorResultHandler
 {
@@ -141,6 +176,22 @@ struct ErrorResultHandler
     return_ty: Type,
     span: Span,
     type_system: ModelTypeSystem,
+    error_policy: ErrorPolicy,
+}
+
+impl ErrorResultHandler
+{
+    /// The type written into the out parameters.
+    fn value_ty(&self) -> &Type
+    {
+        utils::get_success_ty(&self.retval_ty).unwrap_or(&self.retval_ty)
+    }
+
+    /// Checks whether the `Ok` value carries its own success `HRESULT`.
+    fn is_success(&self) -> bool
+    {
+        utils::get_success_ty(&self.retval_ty).is_some()
+    }
 }
 
 impl ReturnHandler for ErrorResultHandler
@@ -179,6 +230,15 @@ impl ReturnHandler for ErrorResultHandler
         } else {
             quote!( #( #ok_values )* )
         };
+        let ok_values = match self.is_success() {
+            true => quote!(match #result {
+                intercom::raw::S_FALSE => intercom::Success::s_false(#ok_values),
+                _ => intercom::Success::ok(#ok_values),
+            }),
+            false => ok_values,
+        };
+
+        let error = error_from_hresult(self.error_policy, result);
 
         // Return statement checks for S_OK (should be is_success) HRESULT and
         // yields either Ok or Err Result based on that.
@@ -188,10 +248,7 @@ impl ReturnHandler for ErrorResultHandler
                 #( #temp_values; )*
                 Ok( #ok_values )
             } else {
-                return Err( intercom::load_error(
-                        self,
-                        &__intercom_iid,
-                        #result ) );
+                return Err( #error );
             }
         )
     }
@@ -210,17 +267,42 @@ impl ReturnHandler for ErrorResultHandler
         // Generate the pattern for the Ok(..).
         // Tuples get (v0, v1, v2, ..) pattern while everything else is
         // represented with just Ok( v0 ) as there's just one parameter.
-        let ok_pattern = {
+        let value_pattern = {
             // quote! takes ownership of tokens if we allow so let's give them
             // by reference here.
             let rok_idents = &ok_idents;
-            match self.retval_ty {
+            match self.value_ty() {
                 Type::Tuple(_) => quote!( ( #( #rok_idents ),* ) ),
 
                 // Non-tuples should have only one ident. Concatenate the vector.
                 _ => quote!( #( #rok_idents )* ),
             }
         };
+        let ok_pattern = match self.is_success() {
+            true => quote!(__success),
+            false => value_pattern.clone(),
+        };
+
+        let error_hresult = error_to_hresult(self.error_policy);
+
+        // Absent optional interfaces are reported with `S_FALSE` so the
+        // callers can tell them apart without checking the pointer. The
+        // status is resolved before the value is moved into the output.
+        // `Success` values carry their status alongside the value.
+        let (status, ok_hresult) = match utils::is_optional_interface(&self.retval_ty) {
+            _ if self.is_success() => (
+                quote!(let (__status, #value_pattern) = intercom::Success::into_parts(__success);),
+                quote!(__status),
+            ),
+            true => (
+                quote!(let __status = match &#ok_pattern {
+                    None => intercom::raw::S_FALSE,
+                    Some(_) => intercom::raw::S_OK,
+                };),
+                quote!(__status),
+            ),
+            false => (quote!(), quote!(intercom::raw::S_OK)),
+        };
 
         let (temp_writes, ok_writes, err_writes) = write_out_values(
             &ok_idents,
@@ -231,31 +313,414 @@ impl ReturnHandler for ErrorResultHandler
         );
         quote!(
             match #result.and_then(|#ok_pattern| {
+                #status
+
                 // These may fail, resulting in early exit from the lambda.
                 #( #temp_writes; )*
 
                 // Once we get here, everything should succeed.
                 #( #ok_writes; )*
-                Ok( intercom::raw::S_OK )
+                Ok( #ok_hresult )
             }) {
                 Ok( s ) => s,
                 Err( e ) => {
                     #( #err_writes );*;
-                    intercom::store_error( e ).hresult
+                    #error_hresult
+                },
+            }
+        )
+    }
+
+    fn com_out_args(&self) -> Vec<ComArg>
+    {
+        get_out_args_for_result(self.value_ty(), self.span, self.type_system)
+    }
+
+    fn is_infallible(&self) -> bool
+    {
+        false
+    }
+}
+
+/// Optional value converted to `[retval]` with `S_OK` when present. Absent
+/// values are reported with `S_FALSE` and a zeroed `[retval]`.
+///
+/// The client side receives `ComResult<Option<T>>` with the errors of the call
+/// in `Err`. The bare `Option<T>` has no room for the errors so only the
+/// interfaces without Rust clients, the impl interfaces, may return it.
+///
+/// The bare `Option<ComRc<T>>` is written as a null pointer when absent.
+/// `ComResult<Option<ComRc<T>>>` is handled with the other results instead.
+#[derive(Debug)]
+struct OptionHandler
+{
+    value_ty: Type,
+    option_ty: Type,
+    is_result: bool,
+    span: Span,
+    type_system: ModelTypeSystem,
+    error_policy: ErrorPolicy,
+}
+
+impl ReturnHandler for OptionHandler
+{
+    fn type_system(&self) -> ModelTypeSystem
+    {
+        self.type_system
+    }
+    fn rust_ty(&self) -> Type
+    {
+        syn::parse2(quote_spanned!(self.span => intercom::raw::HRESULT)).unwrap()
+    }
+    fn return_type_span(&self) -> Span
+    {
+        self.span
+    }
+
+    fn com_to_rust_return(&self, result: &Ident) -> TokenStream
+    {
+        let (temp_values, ok_values) = get_rust_ok_values(self.com_out_args(), false);
+        let error = error_from_hresult(self.error_policy, result);
+        quote!(
+            if #result == intercom::raw::S_OK {
+                #( #temp_values; )*
+                Ok( Some( #( #ok_values )* ) )
+            } else if #result == intercom::raw::S_FALSE {
+                Ok( None )
+            } else {
+                return Err( #error );
+            }
+        )
+    }
+
+    fn rust_to_com_return(&self, result: &Ident) -> TokenStream
+    {
+        let value = Ident::new("__value", self.span);
+        let out_arg = self.com_out_args().remove(0);
+        let out_name = &out_arg.name;
+        let default = out_arg.handler.default_value();
+        let ok_value = out_arg
+            .handler
+            .rust_to_com(&value, self.span, Direction::Out, false);
+        let (some_pattern, none_pattern, err_arm) = match self.is_result {
+            false if utils::is_interface_rc(&self.value_ty) => {
+                (quote!(#value @ Some(_)), quote!(None), quote!())
+            }
+            true => {
+                let error_hresult = error_to_hresult(self.error_policy);
+                (
+                    quote!(Ok(Some(#value))),
+                    quote!(Ok(None)),
+                    quote!(Err(e) => #error_hresult,),
+                )
+            }
+            false => (quote!(Some(#value)), quote!(None), quote!()),
+        };
+
+        // The retval is cleared first so that it is left zeroed even if the
+        // conversion fails.
+        quote!(
+            *#out_name = #default;
+            match #result {
+                #some_pattern => {
+                    *#out_name = #ok_value;
+                    intercom::raw::S_OK
                 },
+                #none_pattern => intercom::raw::S_FALSE,
+                #err_arm
             }
         )
     }
 
     fn com_out_args(&self) -> Vec<ComArg>
     {
-        get_out_args_for_result(&self.retval_ty, self.span, self.type_system)
+        // The interfaces are passed as `Option` to allow the null pointer.
+        let ty = match utils::is_interface_rc(&self.value_ty) {
+            true => self.option_ty.clone(),
+            false => self.value_ty.clone(),
+        };
+        vec![ComArg::new(
+            Ident::new("__out", self.span),
+            ty,
+            self.span,
+            Direction::Retval,
+            self.type_system,
+        )]
+    }
+
+    fn is_infallible(&self) -> bool
+    {
+        false
+    }
+}
+
+/// Tuple returned without a `Result`. Converted to `[out]` parameters for
+/// each of the fields with `HRESULT` reporting the conversion failures.
+///
+/// The tuple has no room for errors so only the interfaces without Rust
+/// clients, the impl interfaces, may return tuples.
+#[derive(Debug)]
+struct TupleHandler
+{
+    tuple_ty: Type,
+    span: Span,
+    type_system: ModelTypeSystem,
+}
+
+impl ReturnHandler for TupleHandler
+{
+    fn type_system(&self) -> ModelTypeSystem
+    {
+        self.type_system
+    }
+    fn rust_ty(&self) -> Type
+    {
+        syn::parse2(quote_spanned!(self.span => intercom::raw::HRESULT)).unwrap()
+    }
+    fn return_type_span(&self) -> Span
+    {
+        self.span
+    }
+
+    fn rust_to_com_return(&self, result: &Ident) -> TokenStream
+    {
+        let idents = self
+            .com_out_args()
+            .iter()
+            .enumerate()
+            .map(|(idx, _)| Ident::new(&format!("v{}", idx + 1), Span::call_site()))
+            .collect::<Vec<_>>();
+        let (temp_writes, ok_writes, default_writes) = write_out_values(
+            &idents,
+            self.com_out_args(),
+            false,
+            self.span,
+            self.type_system,
+        );
+
+        // The out parameters are cleared first so that they are left zeroed
+        // if any of the conversions fail.
+        quote!(
+            let ( #( #idents, )* ) = #result;
+            #( #default_writes; )*
+            #( #temp_writes; )*
+            #( #ok_writes; )*
+            intercom::raw::S_OK
+        )
+    }
+
+    fn com_out_args(&self) -> Vec<ComArg>
+    {
+        get_out_args_for_result(&self.tuple_ty, self.span, self.type_system)
+    }
+
+    fn is_infallible(&self) -> bool
+    {
+        false
+    }
+}
+
+/// Future completed through a completion handler.
+///
+/// The caller passes in the `ICompletionHandler` as an additional parameter.
+/// The call returns once the future has been started and the handler receives
+/// the output of the future when it completes.
+#[derive(Debug)]
+struct FutureHandler
+{
+    span: Span,
+    type_system: ModelTypeSystem,
+}
+
+impl ReturnHandler for FutureHandler
+{
+    fn type_system(&self) -> ModelTypeSystem
+    {
+        self.type_system
+    }
+
+    fn rust_ty(&self) -> Type
+    {
+        syn::parse2(quote_spanned!(self.span => intercom::raw::HRESULT)).unwrap()
+    }
+
+    fn return_type_span(&self) -> Span
+    {
+        self.span
+    }
+
+    fn is_infallible(&self) -> bool
+    {
+        false
+    }
+
+    fn com_ty(&self) -> Type
+    {
+        let ts = self.type_system.as_typesystem_type(self.span);
+        syn::parse2(quote_spanned!(self.span=>
+            < intercom::raw::HRESULT as
+                intercom::type_system::ExternType< #ts >>
+                    ::ForeignType ))
+        .unwrap()
+    }
+
+    fn rust_to_com_return(&self, result: &Ident) -> TokenStream
+    {
+        // Taking the handler as ComRc adds the reference that keeps the
+        // handler alive until the future has completed.
+        let ts = self.type_system.as_typesystem_type(self.span);
+        quote_spanned!(self.span =>
+            intercom::operation::complete(
+                <intercom::ComRc<dyn intercom::operation::ICompletionHandler>
+                    as intercom::type_system::ExternInput<#ts>>
+                        ::from_foreign_parameter(__completion)?,
+                #result)
+        )
+    }
+
+    fn com_out_args(&self) -> Vec<ComArg>
+    {
+        vec![ComArg::new(
+            Ident::new("__completion", self.span),
+            syn::parse2(quote_spanned!(self.span =>
+                intercom::ComRc<dyn intercom::operation::ICompletionHandler>))
+            .unwrap(),
+            self.span,
+            Direction::In,
+            self.type_system,
+        )]
+    }
+}
+
+/// Vector converted to a buffer allocated with `CoTaskMemAlloc` and its
+/// element count, `[out, size_is(, *__out_len)] T** __out` and
+/// `[out] u32* __out_len`, on `Ok` or `HRESULT` + `IErrorInfo` on error.
+///
+/// The elements are converted into their Raw type system representation,
+/// such as `char*` for the strings. The buffer is null and the count zero on
+/// error.
+#[derive(Debug)]
+struct PtrAndLenHandler
+{
+    elem_ty: Type,
+    return_ty: Type,
+    span: Span,
+    type_system: ModelTypeSystem,
+    error_policy: ErrorPolicy,
+}
+
+impl ReturnHandler for PtrAndLenHandler
+{
+    fn type_system(&self) -> ModelTypeSystem
+    {
+        self.type_system
+    }
+
+    fn rust_ty(&self) -> Type
+    {
+        self.return_ty.clone()
+    }
+
+    fn return_type_span(&self) -> Span
+    {
+        self.span
     }
 
     fn is_infallible(&self) -> bool
     {
         false
     }
+
+    fn com_ty(&self) -> Type
+    {
+        let ts = self.type_system.as_typesystem_type(self.span);
+        syn::parse2(quote_spanned!(self.span=>
+            < intercom::raw::HRESULT as
+                intercom::type_system::ExternType< #ts >>
+                    ::ForeignType ))
+        .unwrap()
+    }
+
+    fn com_to_rust_return(&self, result: &Ident) -> TokenStream
+    {
+        let error = error_from_hresult(self.error_policy, result);
+        quote!(
+            if #result == intercom::raw::S_OK || #result == intercom::raw::S_FALSE {
+                Ok(intercom::alloc::take_buffer(__out, __out_len)?)
+            } else {
+                return Err( #error );
+            }
+        )
+    }
+
+    fn rust_to_com_return(&self, result: &Ident) -> TokenStream
+    {
+        let error_hresult = error_to_hresult(self.error_policy);
+        quote!(
+            let __buffer = match #result {
+                Ok(values) => intercom::alloc::allocate_buffer(values).map_err(|e| e.hresult),
+                Err(e) => Err(#error_hresult),
+            };
+            match __buffer {
+                Ok((ptr, len)) => {
+                    *__out = ptr;
+                    *__out_len = len;
+                    intercom::raw::S_OK
+                }
+                Err(hr) => {
+                    *__out = std::ptr::null_mut();
+                    *__out_len = 0;
+                    hr
+                }
+            }
+        )
+    }
+
+    fn com_out_args(&self) -> Vec<ComArg>
+    {
+        let elem_ty = &self.elem_ty;
+        let buffer = ComArg::new(
+            Ident::new("__out", self.span),
+            syn::parse2(quote_spanned!(self.span =>
+                *mut <#elem_ty as intercom::type_system::ExternType<
+                    intercom::type_system::RawTypeSystem>>::ForeignType))
+            .unwrap(),
+            self.span,
+            Direction::Out,
+            self.type_system,
+        );
+        let mut len = ComArg::new(
+            Ident::new("__out_len", self.span),
+            syn::parse2(quote_spanned!(self.span => u32)).unwrap(),
+            self.span,
+            Direction::Out,
+            self.type_system,
+        );
+        len.length_of = Some("__out".to_string());
+        vec![buffer, len]
+    }
+}
+
+/// Gets the client side error for the failed `HRESULT`.
+fn error_from_hresult(error_policy: ErrorPolicy, result: &Ident) -> TokenStream
+{
+    match error_policy {
+        ErrorPolicy::HResultOnly => quote!(intercom::ComError::new_hr(#result)),
+        ErrorPolicy::ErrorInfo => quote!(intercom::load_error(self, &__intercom_iid, #result)),
+    }
+}
+
+/// Gets the `HRESULT` for the `Err` value `e` on the server side.
+fn error_to_hresult(error_policy: ErrorPolicy) -> TokenStream
+{
+    match error_policy {
+        ErrorPolicy::HResultOnly => quote!(intercom::ComError::from(e).hresult),
+        ErrorPolicy::ErrorInfo => quote!({
+            #[allow(unused_imports)]
+            use intercom::error::chain::{NoSourceChain, SourceChain};
+            intercom::store_error((&e).intercom_source_chain().into_com_error(e)).hresult
+        }),
+    }
 }
 
 fn get_out_args_for_result(
@@ -309,6 +774,8 @@ fn write_out_values(
         let ok_value = out_arg
             .handler
             .rust_to_com(ident, span, Direction::Out, infallible);
+        // The default value is null for interface pointers, which ensures the
+        // caller won't end up releasing a pointer it doesn't own.
         let err_value = out_arg.handler.default_value();
 
         temp_tokens.push(quote!( let #temp_name = intercom::type_system::OutputGuard::<#ts, #ty>::wrap( #ok_value ) ));
@@ -344,23 +811,82 @@ fn get_rust_ok_values(
     (temp_tokens, ok_tokens)
 }
 
+/// Gets the return handler for the methods returning futures.
+pub fn get_future_handler(span: Span, type_system: ModelTypeSystem) -> Box<dyn ReturnHandler>
+{
+    Box::new(FutureHandler { span, type_system })
+}
+
+/// Gets the return handler for the `ComResult<Vec<T>>` methods marked with
+/// `#[com_return(ptr_and_len)]`.
+pub fn get_ptr_and_len_handler(
+    elem_ty: &Type,
+    return_ty: &Type,
+    span: Span,
+    type_system: ModelTypeSystem,
+    error_policy: ErrorPolicy,
+) -> Box<dyn ReturnHandler>
+{
+    Box::new(PtrAndLenHandler {
+        elem_ty: elem_ty.clone(),
+        return_ty: return_ty.clone(),
+        span,
+        type_system,
+        error_policy,
+    })
+}
+
+/// Gets the `T` of the `ComResult<Option<T>>` values handled by
+/// `OptionHandler`.
+fn get_option_value_ty(ty: &Type) -> Option<&Type>
+{
+    utils::get_option_ty(ty).filter(|value_ty| !utils::is_interface_rc(value_ty))
+}
+
 /// Resolves the correct return handler to use.
 pub fn get_return_handler(
     retval_ty: &Option<Type>,
     return_ty: &Option<Type>,
     span: Span,
     type_system: ModelTypeSystem,
+    error_policy: ErrorPolicy,
 ) -> Result<Box<dyn ReturnHandler>, &'static str>
 {
     Ok(match (retval_ty, return_ty) {
-        (&None, &None) => Box::new(VoidHandler(span)),
-        (&None, &Some(ref ty)) => Box::new(ReturnOnlyHandler(ty.clone(), type_system, span)),
-        (&Some(ref rv), &Some(ref rt)) => Box::new(ErrorResultHandler {
-            retval_ty: rv.clone(),
-            return_ty: rt.clone(),
+        (None, None) => Box::new(VoidHandler(span)),
+        (None, Some(ty @ Type::Tuple(_))) => Box::new(TupleHandler {
+            tuple_ty: ty.clone(),
             span,
             type_system,
         }),
+        (None, Some(ty)) => match utils::get_option_ty(ty) {
+            Some(value_ty) => Box::new(OptionHandler {
+                value_ty: value_ty.clone(),
+                option_ty: ty.clone(),
+                is_result: false,
+                span,
+                type_system,
+                error_policy,
+            }),
+            None => Box::new(ReturnOnlyHandler(ty.clone(), type_system, span)),
+        },
+        (Some(rv), Some(rt)) => match get_option_value_ty(rv) {
+            Some(value_ty) => Box::new(OptionHandler {
+                value_ty: value_ty.clone(),
+                option_ty: rv.clone(),
+                is_result: true,
+                span,
+                type_system,
+                error_policy,
+            }),
+            None => Box::new(ErrorResultHandler {
+                retval_ty: rv.clone(),
+                return_ty: rt.clone(),
+                span,
+                type_system,
+                error_policy,
+            }),
+        },
 
         // Unsupported return scheme. Note we are using Result::Err instead of
         // Option::None here because having no return handler is unsupported
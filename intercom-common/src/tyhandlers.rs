@@ -9,6 +9,13 @@ pub enum Direction
     In,
     Out,
     Retval,
+
+    /// The value is passed in and written back by the callee.
+    ///
+    /// The value is passed with its ownership in both directions. The callee
+    /// takes the original value and hands back a new one, which allows the
+    /// callee to reallocate `BSTR` values.
+    InOut,
 }
 
 #[derive(PartialEq, Eq, Debug, Hash)]
@@ -88,13 +95,31 @@ impl quote::IdentFragment for ModelTypeSystem
 pub struct TypeContext
 {
     type_system: ModelTypeSystem,
+    idl_type: Option<String>,
+    is_parameter: bool,
 }
 
 impl TypeContext
 {
     pub fn new(type_system: ModelTypeSystem) -> TypeContext
     {
-        TypeContext { type_system }
+        TypeContext {
+            type_system,
+            idl_type: None,
+            is_parameter: false,
+        }
+    }
+
+    /// Constructs a context for a method parameter.
+    ///
+    /// The `idl_type` overrides the type name used in the IDL.
+    pub fn parameter(type_system: ModelTypeSystem, idl_type: Option<String>) -> TypeContext
+    {
+        TypeContext {
+            type_system,
+            idl_type,
+            is_parameter: true,
+        }
     }
 }
 
@@ -114,18 +139,62 @@ impl TypeHandler
         self.ty.clone()
     }
 
+    /// The type implementing the extern traits for the Rust type.
+    ///
+    /// The tuples are passed through the `#[repr(C)]` tuple structures.
+    pub fn extern_ty(&self) -> Type
+    {
+        match tuple_struct(&self.ty) {
+            Some((name, elems)) => parse_quote!(intercom::type_system::#name<#( #elems ),*>),
+            None => self.ty.clone(),
+        }
+    }
+
     /// The COM type.
     pub fn com_ty(&self, span: Span) -> Type
     {
         // Construct bits for the quote.
-        let ty = &self.ty;
+        let ty = &self.extern_ty();
         let ts = self.context.type_system.as_typesystem_type(span);
+        if self.is_counted() {
+            return syn::parse2(quote_spanned!(span =>
+                <#ty as intercom::type_system::ExternCountedInput<#ts>>::ForeignType))
+            .unwrap();
+        }
         syn::parse2(
             quote_spanned!(span => <#ty as intercom::type_system::ExternType<#ts>>::ForeignType),
         )
         .unwrap()
     }
 
+    /// The type name used in the IDL instead of the COM type name.
+    ///
+    /// The override affects only the IDL. The marshaling is defined by the
+    /// COM type.
+    pub fn idl_ty(&self) -> Option<&str>
+    {
+        self.context.idl_type.as_deref()
+    }
+
+    /// True if the value is passed as a pointer followed by an element count.
+    ///
+    /// The `&[ComItf<T>]` slices are passed this way in the raw type system.
+    /// The byte slices are passed this way in both type systems. Only the
+    /// parameters are counted, the returned slices are SAFEARRAYs.
+    pub fn is_counted(&self) -> bool
+    {
+        self.context.is_parameter
+            && ((self.context.type_system == ModelTypeSystem::Raw
+                && crate::utils::is_interface_slice(&self.ty))
+                || crate::utils::is_byte_slice(&self.ty))
+    }
+
+    /// True if the value is a buffer the callee writes into.
+    pub fn is_out_buffer(&self) -> bool
+    {
+        self.is_counted() && crate::utils::is_mut_byte_slice(&self.ty)
+    }
+
     /// Converts a COM parameter named by the ident into a Rust type.
     pub fn com_to_rust(
         &self,
@@ -140,7 +209,24 @@ impl TypeHandler
         let ts = self.context.type_system.as_typesystem_type(span);
         let (tr, unwrap) = resolve_type_handling(dir, infallible, span);
         let (maybe_ref, maybe_as_ref) = resolve_ref(ty);
+
+        // The tuple elements are moved out of the structure.
+        if let (Direction::In, Some((_, elems))) = (dir, tuple_struct(ty)) {
+            let ext = self.extern_ty();
+            let fields = (0..elems.len()).map(syn::Index::from);
+            return quote_spanned!(span=> {
+                let __tuple = <#ext as #tr<#ts>>::from_foreign_parameter(#ident)#unwrap;
+                ( #( __tuple.#fields, )* )
+            });
+        }
+
         match dir {
+            Direction::In if self.is_counted() => {
+                let count = crate::idents::element_count(ident);
+                quote_spanned!(span=>
+                    #maybe_ref <#ty as intercom::type_system::ExternCountedInput<#ts>>
+                        ::from_foreign_parameter(#ident, #count)?)
+            }
             Direction::In => quote_spanned!(span=>
                     #maybe_ref <#ty as #tr<#ts>>
                         ::from_foreign_parameter(#ident)#unwrap#maybe_as_ref),
@@ -150,6 +236,16 @@ impl TypeHandler
             // Rust's memory management.
             Direction::Out | Direction::Retval => quote_spanned!(span=>
                     <#ty as #tr<#ts>>::from_foreign_output(#ident)),
+
+            // The in-out parameters are pointers to the value. The value is
+            // taken out of the pointer to bring it under Rust's memory
+            // management, leaving the pointer with a default value.
+            Direction::InOut => {
+                let default = self.default_value();
+                quote_spanned!(span=>
+                    <#ty as #tr<#ts>>::from_foreign_output(
+                        std::mem::replace(&mut *#ident, #default))#unwrap)
+            }
         }
     }
 
@@ -166,16 +262,35 @@ impl TypeHandler
         let ty = &self.ty;
         let ts = self.context.type_system.as_typesystem_type(span);
         let (tr, unwrap) = resolve_type_handling(dir, infallible, span);
+        if let (Direction::In, Some((name, elems))) = (dir, tuple_struct(ty)) {
+            let ext = self.extern_ty();
+            let fields = (0..elems.len()).map(syn::Index::from);
+            return quote_spanned!(span=>
+                    <#ext as #tr<#ts>>::into_foreign_parameter(
+                        intercom::type_system::#name( #( #ident.#fields ),* ))#unwrap.0);
+        }
+
         match dir {
             Direction::In => quote_spanned!(span=>
                     <#ty as #tr<#ts>>
                         ::into_foreign_parameter(#ident)#unwrap.0),
-            Direction::Out | Direction::Retval => quote_spanned!(span=>
+            Direction::Out | Direction::Retval | Direction::InOut => quote_spanned!(span=>
                     <#ty as #tr<#ts>>
                         ::into_foreign_output(#ident)#unwrap),
         }
     }
 
+    /// Converts a counted Rust parameter named by the ident into a tuple of
+    /// the COM pointer, the element count and the lease keeping them valid.
+    pub fn rust_to_com_counted(&self, ident: &Ident, span: Span) -> TokenStream
+    {
+        let ty = &self.ty;
+        let ts = self.context.type_system.as_typesystem_type(span);
+        quote_spanned!(span=>
+            <#ty as intercom::type_system::ExternCountedInput<#ts>>
+                ::into_foreign_parameter(#ident)?)
+    }
+
     /// Gets the default value for the type.
     pub fn default_value(&self) -> TokenStream
     {
@@ -183,10 +298,26 @@ impl TypeHandler
     }
 }
 
+/// Resolves the name and the element types of the tuple structure used for
+/// passing the tuple type by value.
+fn tuple_struct(ty: &Type) -> Option<(Ident, Vec<&Type>)>
+{
+    match ty {
+        Type::Tuple(t) if (2..=4).contains(&t.elems.len()) => Some((
+            Ident::new(&format!("Tuple{}", t.elems.len()), Span::call_site()),
+            t.elems.iter().collect(),
+        )),
+        _ => None,
+    }
+}
+
 fn resolve_ref(ty: &Type) -> (TokenStream, TokenStream)
 {
-    if let syn::Type::Reference(..) = ty {
-        return (quote!(&), quote!());
+    if let syn::Type::Reference(r) = ty {
+        return match r.mutability {
+            Some(_) => (quote!(&mut), quote!()),
+            None => (quote!(&), quote!()),
+        };
     }
 
     if has_ref(ty) {
@@ -221,7 +352,7 @@ fn resolve_type_handling(dir: Direction, infallible: bool, span: Span)
 {
     let dir_part = match dir {
         Direction::In => "Input",
-        Direction::Out | Direction::Retval => "Output",
+        Direction::Out | Direction::Retval | Direction::InOut => "Output",
     };
     let (fallibility, unwrap) = match infallible {
         true => ("Infallible", quote!()),
@@ -1,3 +1,4 @@
+use crate::methodinfo::{MethodKind, ReturnKind};
 use crate::prelude::*;
 use crate::tyhandlers::ModelTypeSystem;
 use syn::*;
@@ -116,6 +117,39 @@ pub fn generate_iid(crate_name: &str, item_name: &str, type_system: ModelTypeSys
     )
 }
 
+/// Generates an IID from an explicit namespace.
+///
+/// The IIDs are name-based UUIDs (version 5) as specified in RFC-4122,
+/// section 4.3: the SHA-1 hash is calculated over the namespace GUID in
+/// network byte order followed by the name in UTF-8. The automation IID uses
+/// the interface name as is while the raw IID uses the name suffixed with
+/// `:raw`.
+///
+/// Unlike `generate_iid`, the result doesn't depend on the crate name.
+pub fn generate_namespaced_iid(
+    namespace: &guid::GUID,
+    item_name: &str,
+    type_system: ModelTypeSystem,
+) -> guid::GUID
+{
+    match type_system {
+        ModelTypeSystem::Automation => generate_guid_v5(namespace, item_name),
+        ModelTypeSystem::Raw => generate_guid_v5(namespace, &format!("{}:raw", item_name)),
+    }
+}
+
+/// Generates a CLSID from an explicit namespace.
+///
+/// The CLSIDs are name-based UUIDs (version 5) similar to the namespaced
+/// IIDs. The name is the class name suffixed with `:clsid` so that the class
+/// and its implicit interface get different GUIDs.
+///
+/// Unlike `generate_clsid`, the result doesn't depend on the crate name.
+pub fn generate_namespaced_clsid(namespace: &guid::GUID, item_name: &str) -> guid::GUID
+{
+    generate_guid_v5(namespace, &format!("{}:clsid", item_name))
+}
+
 pub fn generate_libid(crate_name: &str) -> guid::GUID
 {
     generate_guid(&["LIBID", crate_name].join(":"))
@@ -165,6 +199,31 @@ pub fn generate_guid(key: &str) -> guid::GUID
     }
 }
 
+/// Generates a name-based version 5 UUID as specified in RFC-4122.
+pub fn generate_guid_v5(namespace: &guid::GUID, name: &str) -> guid::GUID
+{
+    let mut hash = sha1::Sha1::new();
+    hash.update(&namespace.data1.to_be_bytes());
+    hash.update(&namespace.data2.to_be_bytes());
+    hash.update(&namespace.data3.to_be_bytes());
+    hash.update(&namespace.data4);
+    hash.update(name.as_bytes());
+
+    let digest = hash.digest();
+    let bytes = digest.bytes();
+
+    let mut data4 = [0; 8];
+    data4.copy_from_slice(&bytes[8..16]);
+    data4[0] = (data4[0] & 0b0011_1111) | 0b1000_0000;
+
+    guid::GUID {
+        data1: u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        data2: u16::from_be_bytes([bytes[4], bytes[5]]),
+        data3: (u16::from_be_bytes([bytes[6], bytes[7]]) & 0x0fff) | 0x5000,
+        data4,
+    }
+}
+
 pub fn ty_to_string(ty: &syn::Type) -> String
 {
     quote!( #ty )
@@ -182,6 +241,340 @@ pub fn is_unit(tk: &Type) -> bool
     }
 }
 
+/// Gets the documentation from the `///` doc comments.
+///
+/// The comment lines are joined with newlines. Returns `None` if the item
+/// has no documentation.
+pub fn get_doc(attrs: &[Attribute]) -> Option<String>
+{
+    let lines = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(MetaNameValue {
+                lit: Lit::Str(s), ..
+            })) => Some(s.value()),
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').map(str::to_string).unwrap_or(line))
+        .collect::<Vec<_>>();
+
+    let doc = lines.join("\n").trim().to_string();
+    match doc.is_empty() {
+        true => None,
+        false => Some(doc),
+    }
+}
+
+/// Gets the attributes of the methods in an interface trait or impl.
+fn get_method_attrs(item: &Item) -> Vec<(&Ident, &[Attribute])>
+{
+    match item {
+        Item::Impl(itm) => itm
+            .items
+            .iter()
+            .filter_map(|i| match i {
+                ImplItem::Method(m) => Some((&m.sig.ident, &m.attrs[..])),
+                _ => None,
+            })
+            .collect(),
+        Item::Trait(itm) => itm
+            .items
+            .iter()
+            .filter_map(|i| match i {
+                TraitItem::Method(m) => Some((&m.sig.ident, &m.attrs[..])),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Gets the documentation of the methods in an interface trait or impl.
+pub fn get_method_docs(item: &Item) -> Vec<(Ident, Option<String>)>
+{
+    get_method_attrs(item)
+        .into_iter()
+        .map(|(ident, attrs)| (ident.clone(), get_doc(attrs)))
+        .collect()
+}
+
+/// Gets the `#[cfg]` attributes of the methods in an interface trait or impl.
+pub fn get_method_cfgs(item: &Item) -> Vec<(Ident, Vec<Attribute>)>
+{
+    get_method_attrs(item)
+        .into_iter()
+        .map(|(ident, attrs)| {
+            let cfgs = attrs.iter().filter(|attr| attr.path.is_ident("cfg"));
+            (ident.clone(), cfgs.cloned().collect())
+        })
+        .collect()
+}
+
+/// Gets the methods marked with `#[com_return(borrow_array)]` or
+/// `#[com_return(ptr_and_len)]`.
+pub fn get_com_return_methods(item: &Item)
+    -> std::result::Result<Vec<(Ident, ReturnKind)>, String>
+{
+    let mut methods = vec![];
+    for (ident, attrs) in get_method_attrs(item) {
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("com_return")) {
+            match attr.parse_args::<Ident>() {
+                Ok(option) if option == "borrow_array" => {
+                    methods.push((ident.clone(), ReturnKind::BorrowArray))
+                }
+                Ok(option) if option == "ptr_and_len" => {
+                    methods.push((ident.clone(), ReturnKind::PtrAndLen))
+                }
+                _ => return Err(format!("Bad com_return attribute on method {}", ident)),
+            }
+        }
+    }
+    Ok(methods)
+}
+
+/// Gets the methods marked with `#[com_property(get)]` or
+/// `#[com_property(put)]`.
+pub fn get_property_methods(item: &Item) -> std::result::Result<Vec<(Ident, MethodKind)>, String>
+{
+    let mut methods = vec![];
+    for (ident, attrs) in get_method_attrs(item) {
+        for attr in attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("com_property"))
+        {
+            match attr.parse_args::<Ident>() {
+                Ok(option) if option == "get" => {
+                    methods.push((ident.clone(), MethodKind::PropertyGet))
+                }
+                Ok(option) if option == "put" => {
+                    methods.push((ident.clone(), MethodKind::PropertyPut))
+                }
+                _ => return Err(format!("Bad com_property attribute on method {}", ident)),
+            }
+        }
+    }
+    Ok(methods)
+}
+
+/// Gets the options of the methods marked with `#[com_method(..)]`.
+///
+/// The options are `operation`, `hidden`, `restricted` and `newenum`.
+pub fn get_com_method_options(item: &Item) -> std::result::Result<Vec<(Ident, Ident)>, String>
+{
+    let mut options = vec![];
+    for (ident, attrs) in get_method_attrs(item) {
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("com_method")) {
+            let parsed = attr
+                .parse_args_with(punctuated::Punctuated::<Ident, Token![,]>::parse_terminated)
+                .ok()
+                .filter(|parsed| !parsed.is_empty())
+                .ok_or_else(|| format!("Bad com_method attribute on method {}", ident))?;
+            for option in parsed {
+                match option.to_string().as_str() {
+                    "operation" | "hidden" | "restricted" | "newenum" => {
+                        options.push((ident.clone(), option))
+                    }
+                    _ => {
+                        return Err(format!(
+                            "Unknown com_method option {} on method {}",
+                            option, ident
+                        ))
+                    }
+                }
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// Checks whether the type is a `&[Variant]` slice.
+pub fn is_variant_slice(ty: &Type) -> bool
+{
+    is_slice_of(ty, "Variant")
+}
+
+/// Checks whether the type is a `&[ComItf<T>]` slice.
+pub fn is_interface_slice(ty: &Type) -> bool
+{
+    is_slice_of(ty, "ComItf")
+}
+
+/// Checks whether the type is a `&[u8]` or a `&mut [u8]`.
+pub fn is_byte_slice(ty: &Type) -> bool
+{
+    is_slice_of(ty, "u8")
+}
+
+/// Checks whether the type is a `&mut [u8]`.
+pub fn is_mut_byte_slice(ty: &Type) -> bool
+{
+    match ty {
+        Type::Reference(r) => r.mutability.is_some() && is_byte_slice(ty),
+        _ => false,
+    }
+}
+
+/// Checks whether the type is an `Option<ComRc<T>>`.
+pub fn is_optional_interface(ty: &Type) -> bool
+{
+    get_option_ty(ty).map(is_interface_rc).unwrap_or(false)
+}
+
+/// Checks whether the type is a `ComRc<T>`.
+pub fn is_interface_rc(ty: &Type) -> bool
+{
+    match ty {
+        Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident == "ComRc")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Gets the `T` of an `Option<T>` type.
+pub fn get_option_ty(ty: &Type) -> Option<&Type>
+{
+    get_generic_arg(ty, "Option")
+}
+
+/// Gets the `T` of a `Vec<T>` type.
+pub fn get_vec_ty(ty: &Type) -> Option<&Type>
+{
+    get_generic_arg(ty, "Vec")
+}
+
+/// Gets the `T` of a `Success<T>` type.
+pub fn get_success_ty(ty: &Type) -> Option<&Type>
+{
+    get_generic_arg(ty, "Success")
+}
+
+/// Gets the only type argument of the named generic type.
+fn get_generic_arg<'a>(ty: &'a Type, name: &str) -> Option<&'a Type>
+{
+    let segment = match ty {
+        Type::Path(p) => match p.path.segments.last() {
+            Some(segment) if segment.ident == name => segment,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Checks whether the type is a slice reference of the named element type.
+fn is_slice_of(ty: &Type, elem: &str) -> bool
+{
+    let slice = match ty {
+        Type::Reference(r) => match &*r.elem {
+            Type::Slice(slice) => slice,
+            _ => return false,
+        },
+        _ => return false,
+    };
+
+    match &*slice.elem {
+        Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident == elem)
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Names of the types converting to and from a `Variant`.
+const VARIANT_TYPES: &[&str] = &[
+    "bool",
+    "i8",
+    "u8",
+    "i16",
+    "u16",
+    "i32",
+    "u32",
+    "i64",
+    "u64",
+    "f32",
+    "f64",
+    "String",
+    "BString",
+    "CString",
+    "SystemTime",
+    "Variant",
+];
+
+/// Gets the type an `IDispatch` argument is read into from its `Variant`.
+///
+/// The borrowed strings are read into their owned counterparts.
+pub fn get_dispatch_arg_ty(ty: &Type) -> Option<Type>
+{
+    match ty {
+        Type::Path(p) => is_variant_type(&p.path, VARIANT_TYPES).then(|| ty.clone()),
+        Type::Reference(r) if r.mutability.is_none() => match &*r.elem {
+            Type::Path(p) if is_variant_type(&p.path, &["str", "BStr", "CStr"]) => {
+                let elem = &r.elem;
+                Some(parse_quote!(<#elem as ToOwned>::Owned))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Checks whether the `IDispatch` return value converts into a `Variant`.
+pub fn is_dispatch_return_ty(ty: &Type) -> bool
+{
+    match ty {
+        Type::Path(p) => is_variant_type(&p.path, VARIANT_TYPES) || is_interface_rc(ty),
+        _ => is_unit(ty),
+    }
+}
+
+fn is_variant_type(path: &syn::Path, names: &[&str]) -> bool
+{
+    path.segments
+        .last()
+        .map(|segment| segment.arguments.is_empty() && names.iter().any(|n| segment.ident == n))
+        .unwrap_or(false)
+}
+
+/// Finds a pointer-width `usize` or `isize` type within the type.
+pub fn find_pointer_width_ty(ty: &Type) -> Option<&Ident>
+{
+    match ty {
+        Type::Path(p) => p.path.segments.last().and_then(|segment| {
+            if segment.ident == "usize" || segment.ident == "isize" {
+                return Some(&segment.ident);
+            }
+            match &segment.arguments {
+                syn::PathArguments::AngleBracketed(generics) => {
+                    generics.args.iter().find_map(|arg| match arg {
+                        syn::GenericArgument::Type(t) => find_pointer_width_ty(t),
+                        _ => None,
+                    })
+                }
+                _ => None,
+            }
+        }),
+        Type::Reference(r) => find_pointer_width_ty(&r.elem),
+        Type::Slice(s) => find_pointer_width_ty(&s.elem),
+        Type::Tuple(t) => t.elems.iter().find_map(find_pointer_width_ty),
+        _ => None,
+    }
+}
+
 pub fn unit_ty(span: Span) -> Type
 {
     syn::parse2(quote_spanned!(span => ())).unwrap()
@@ -246,6 +639,33 @@ mod test
 {
     use super::*;
 
+    #[test]
+    fn doc_comments()
+    {
+        let item: ItemTrait = parse_quote!(
+            /// First line.
+            ///
+            /// Second paragraph.
+            trait IDocumented
+            {
+                /// Documented method.
+                fn documented(&self);
+                fn undocumented(&self);
+            }
+        );
+
+        assert_eq!(
+            get_doc(&item.attrs).as_deref(),
+            Some("First line.\n\nSecond paragraph.")
+        );
+
+        let docs = get_method_docs(&Item::Trait(item));
+        assert_eq!(docs[0].0, "documented");
+        assert_eq!(docs[0].1.as_deref(), Some("Documented method."));
+        assert_eq!(docs[1].0, "undocumented");
+        assert_eq!(docs[1].1, None);
+    }
+
     /// Tests the `ty_to_string` by converting parameter to Type and back to
     /// String to ensure they equal.
     fn test_ty(ty_str: &str)
@@ -270,4 +690,108 @@ mod test
     {
         test_ty("()")
     }
+
+    #[test]
+    fn variant_slice()
+    {
+        assert!(is_variant_slice(&parse_str("&[Variant]").unwrap()));
+        assert!(is_variant_slice(
+            &parse_str("&[intercom::Variant]").unwrap()
+        ));
+        assert!(!is_variant_slice(&parse_str("&[u8]").unwrap()));
+        assert!(!is_variant_slice(&parse_str("Vec<Variant>").unwrap()));
+    }
+
+    #[test]
+    fn interface_slice()
+    {
+        assert!(is_interface_slice(
+            &parse_str("&[ComItf<dyn IFoo>]").unwrap()
+        ));
+        assert!(is_interface_slice(
+            &parse_str("&[intercom::ComItf<dyn IFoo>]").unwrap()
+        ));
+        assert!(!is_interface_slice(
+            &parse_str("&ComItf<dyn IFoo>").unwrap()
+        ));
+        assert!(!is_interface_slice(&parse_str("&[Variant]").unwrap()));
+    }
+
+    #[test]
+    fn byte_slice()
+    {
+        assert!(is_byte_slice(&parse_str("&[u8]").unwrap()));
+        assert!(is_byte_slice(&parse_str("&'a mut [u8]").unwrap()));
+        assert!(!is_byte_slice(&parse_str("&[u16]").unwrap()));
+        assert!(!is_byte_slice(&parse_str("Vec<u8>").unwrap()));
+        assert!(is_mut_byte_slice(&parse_str("&mut [u8]").unwrap()));
+        assert!(!is_mut_byte_slice(&parse_str("&[u8]").unwrap()));
+    }
+
+    #[test]
+    fn optional_interface()
+    {
+        assert!(is_optional_interface(
+            &parse_str("Option<ComRc<dyn IFoo>>").unwrap()
+        ));
+        assert!(is_optional_interface(
+            &parse_str("std::option::Option<intercom::ComRc<dyn IFoo>>").unwrap()
+        ));
+        assert!(!is_optional_interface(
+            &parse_str("ComRc<dyn IFoo>").unwrap()
+        ));
+        assert!(!is_optional_interface(
+            &parse_str("Option<&ComItf<dyn IFoo>>").unwrap()
+        ));
+    }
+
+    #[test]
+    fn pointer_width_ty()
+    {
+        let find = |ty: &str| find_pointer_width_ty(&parse_str(ty).unwrap()).map(|i| i.to_string());
+        assert_eq!(find("usize").as_deref(), Some("usize"));
+        assert_eq!(find("ComResult<(String, isize)>").as_deref(), Some("isize"));
+        assert_eq!(find("&[usize]").as_deref(), Some("usize"));
+        assert_eq!(find("ComResult<Vec<u64>>"), None);
+        assert_eq!(find("u32"), None);
+    }
+
+    #[test]
+    fn guid_v5_matches_rfc()
+    {
+        // The DNS namespace example from the Python uuid module:
+        // uuid.uuid5(uuid.NAMESPACE_DNS, 'python.org')
+        let dns = guid::GUID::parse("6ba7b810-9dad-11d1-80b4-00c04fd430c8").unwrap();
+        assert_eq!(
+            generate_guid_v5(&dns, "python.org"),
+            guid::GUID::parse("886313e1-3b8a-5372-9b90-0c9aee199e5d").unwrap()
+        );
+    }
+
+    #[test]
+    fn namespaced_iid_is_stable()
+    {
+        let ns1 = guid::GUID::parse("2a5d8bb1-6b43-4a7a-9b0e-0e4d0e2c2f51").unwrap();
+        let ns2 = guid::GUID::parse("b1f0a0f3-3f0c-4f43-8f0b-7c3a1d1e9c02").unwrap();
+
+        // The IID must not change between builds.
+        let iid = generate_namespaced_iid(&ns1, "IFoo", ModelTypeSystem::Automation);
+        assert_eq!(
+            iid,
+            guid::GUID::parse("6dea3a0c-b80c-5841-b9d0-8baa65faa0e1").unwrap()
+        );
+
+        assert_ne!(
+            iid,
+            generate_namespaced_iid(&ns1, "IFoo", ModelTypeSystem::Raw)
+        );
+        assert_ne!(
+            iid,
+            generate_namespaced_iid(&ns2, "IFoo", ModelTypeSystem::Automation)
+        );
+        assert_ne!(
+            iid,
+            generate_namespaced_iid(&ns1, "IBar", ModelTypeSystem::Automation)
+        );
+    }
 }
@@ -1,5 +1,6 @@
 
 #include "src/callingconvention.hpp"
+#include "src/declspec.hpp"
 #include "src/comdef.hpp"
 #include "src/classfactory.hpp"
 #include "src/conversions.hpp"
@@ -0,0 +1,15 @@
+#ifndef INTERCOM_CPP_DECLSPEC_H
+#define INTERCOM_CPP_DECLSPEC_H
+
+// On Windows platform the interface IDs are attached to the interfaces for
+// __uuidof.
+#ifdef _MSC_VER
+
+#define INTERCOM_UUID( iid ) __declspec( uuid( iid ) )
+#else
+
+#define INTERCOM_UUID( iid )
+
+#endif
+
+#endif
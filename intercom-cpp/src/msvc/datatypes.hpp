@@ -75,6 +75,7 @@ namespace intercom
 
     typedef OLECHAR OLECHAR;
     typedef BSTR BSTR;
+    typedef LPWSTR LPWSTR;
 
     typedef HRESULT HRESULT;
     typedef SCODE SCODE;
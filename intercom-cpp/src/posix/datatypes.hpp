@@ -35,6 +35,7 @@ namespace intercom
 
     typedef char16_t OLECHAR;
     typedef OLECHAR* BSTR;
+    typedef OLECHAR* LPWSTR;
 
     typedef uint32_t HRESULT;
     typedef uint32_t SCODE;
@@ -77,6 +78,7 @@ using DWORD = intercom::DWORD;
 using WORD = intercom::WORD;
 
 using BSTR = intercom::BSTR;
+using LPWSTR = intercom::LPWSTR;
 using HRESULT = intercom::HRESULT;
 
 #endif
@@ -7,6 +7,83 @@
 #include "../error_codes.hpp"
 #include "../guiddef.hpp"
 
-class IDispatch;
+#include "iunknown.hpp"
+
+namespace intercom
+{
+    struct VARIANT;
+
+    typedef int32_t DISPID;
+    typedef uint32_t LCID;
+
+    static const DISPID DISPID_UNKNOWN = -1;
+    static const DISPID DISPID_PROPERTYPUT = -3;
+
+    static const WORD DISPATCH_METHOD = 0x1;
+    static const WORD DISPATCH_PROPERTYGET = 0x2;
+    static const WORD DISPATCH_PROPERTYPUT = 0x4;
+    static const WORD DISPATCH_PROPERTYPUTREF = 0x8;
+
+    struct DISPPARAMS
+    {
+        VARIANT* rgvarg;
+        DISPID* rgdispidNamedArgs;
+        UINT cArgs;
+        UINT cNamedArgs;
+    };
+
+    struct EXCEPINFO
+    {
+        WORD wCode;
+        WORD wReserved;
+        BSTR bstrSource;
+        BSTR bstrDescription;
+        BSTR bstrHelpFile;
+        DWORD dwHelpContext;
+        PVOID pvReserved;
+        PVOID pfnDeferredFillIn;
+        SCODE scode;
+    };
+}
+
+// The type infos are not available.
+struct ITypeInfo;
+
+// MIDL_INTERFACE("00020400-0000-0000-C000-000000000046")
+struct IDispatch : public IUnknown
+{
+public:
+
+    virtual intercom::HRESULT INTERCOM_CC GetTypeInfoCount(
+        intercom::UINT *pctinfo
+    ) = 0;
+
+    virtual intercom::HRESULT INTERCOM_CC GetTypeInfo(
+        intercom::UINT iTInfo,
+        intercom::LCID lcid,
+        ITypeInfo **ppTInfo
+    ) = 0;
+
+    virtual intercom::HRESULT INTERCOM_CC GetIDsOfNames(
+        intercom::REFIID riid,
+        intercom::OLECHAR **rgszNames,
+        intercom::UINT cNames,
+        intercom::LCID lcid,
+        intercom::DISPID *rgDispId
+    ) = 0;
+
+    virtual intercom::HRESULT INTERCOM_CC Invoke(
+        intercom::DISPID dispIdMember,
+        intercom::REFIID riid,
+        intercom::LCID lcid,
+        intercom::WORD wFlags,
+        intercom::DISPPARAMS *pDispParams,
+        intercom::VARIANT *pVarResult,
+        intercom::EXCEPINFO *pExcepInfo,
+        intercom::UINT *puArgErr
+    ) = 0;
+};
+
+static const intercom::IID IID_IDispatch = { 0x00020400, 0x0000, 0x0000, { 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46 } };
 
 #endif
@@ -0,0 +1,114 @@
+//! References to COM objects that may be shared between threads.
+//!
+//! The COM interface pointers are bound to the apartment they were acquired
+//! in, which is why `ComRc` is neither `Send` nor `Sync`. The objects
+//! implementing `IAgileObject` may be called from any thread so the
+//! references to them may be moved between threads in an `AgileRef`.
+
+use crate::attributes::ComInterface;
+use crate::interfaces::IAgileObject;
+use crate::type_system::AutomationTypeSystem;
+use crate::{ComItf, ComRc, ComResult};
+
+/// Reference to a COM object implementing `IAgileObject`.
+pub struct AgileRef<I: ComInterface + ?Sized>(ComRc<I>);
+
+// The object promises to be callable from any thread by implementing
+// IAgileObject. The COM classes implementing the interface in Rust must be
+// Send and Sync themselves.
+unsafe impl<I: ComInterface + ?Sized> Send for AgileRef<I> {}
+unsafe impl<I: ComInterface + ?Sized> Sync for AgileRef<I> {}
+
+impl<I: ComInterface + ?Sized> AgileRef<I>
+{
+    /// Creates an agile reference to the object.
+    ///
+    /// Fails with `E_NOINTERFACE` if the object does not implement
+    /// `IAgileObject`.
+    pub fn new(itf: &ComItf<I>) -> ComResult<AgileRef<I>>
+    {
+        // The marker is queried with the standard IID as the foreign objects
+        // know only that one.
+        itf.as_iunknown()
+            .query_interface_ts::<AutomationTypeSystem, dyn IAgileObject>()?;
+        Ok(AgileRef(ComItf::as_rc(itf)))
+    }
+
+    /// Gets a reference to the object for the current thread.
+    pub fn get(&self) -> ComRc<I>
+    {
+        self.0.clone()
+    }
+}
+
+impl<I: ComInterface + ?Sized> Clone for AgileRef<I>
+{
+    fn clone(&self) -> Self
+    {
+        AgileRef(self.0.clone())
+    }
+}
+
+impl<I: ComInterface + ?Sized> std::fmt::Debug for AgileRef<I>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        f.debug_tuple("AgileRef").field(&self.0).finish()
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::AgileRef;
+    use crate::interfaces::IAgileObject;
+    use crate::{ComBox, ComRc};
+
+    #[crate::com_interface]
+    trait ICounter
+    {
+        fn get(&self) -> crate::ComResult<u32>;
+    }
+
+    #[crate::com_class(clsid = None, ICounter, IAgileObject)]
+    struct AgileCounter;
+
+    impl ICounter for AgileCounter
+    {
+        fn get(&self) -> crate::ComResult<u32>
+        {
+            Ok(1)
+        }
+    }
+    impl IAgileObject for AgileCounter {}
+
+    #[crate::com_class(clsid = None, ICounter)]
+    struct Counter;
+
+    impl ICounter for Counter
+    {
+        fn get(&self) -> crate::ComResult<u32>
+        {
+            Ok(2)
+        }
+    }
+
+    #[test]
+    fn agile_object_moves_between_threads()
+    {
+        let rc = ComRc::<dyn ICounter>::from(ComBox::new(AgileCounter));
+        let agile = AgileRef::new(&rc).unwrap();
+        let value = std::thread::spawn(move || agile.get().get().unwrap())
+            .join()
+            .unwrap();
+        assert_eq!(value, 1);
+    }
+
+    #[test]
+    fn non_agile_object_is_rejected()
+    {
+        let rc = ComRc::<dyn ICounter>::from(ComBox::new(Counter));
+        let err = AgileRef::new(&rc).unwrap_err();
+        assert_eq!(err.hresult, crate::raw::E_NOINTERFACE);
+    }
+}
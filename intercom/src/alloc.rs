@@ -1,4 +1,5 @@
 use super::*;
+use crate::type_system::{ExternOutput, RawTypeSystem, TypeSystem};
 use intercom::raw::BSTR;
 use std::os::raw;
 
@@ -10,8 +11,10 @@ pub struct Allocator;
 
 #[crate::com_interface(
     com_iid = "18EE22B3-B0C6-44A5-A94A-7A417676FB66",
-    raw_iid = "7A6F6564-04B5-4455-A223-EA0512B8CC63"
+    raw_iid = "7A6F6564-04B5-4455-A223-EA0512B8CC63",
+    pointer_width = "target"
 )]
+/// Allocates memory shared with the intercom libraries.
 pub trait IAllocator: crate::IUnknown
 {
     /// Allocates a new BSTR based on an existing string value.
@@ -120,6 +123,177 @@ pub unsafe fn free(ptr: *mut raw::c_void)
     os::free(ptr)
 }
 
+/// Copies the values into memory allocated with `allocate`.
+fn allocate_copy<T: Copy>(values: &[T]) -> ComResult<*mut T>
+{
+    unsafe {
+        let ptr = allocate(std::mem::size_of_val(values)) as *mut T;
+        if ptr.is_null() {
+            return Err(ComError::E_OUTOFMEMORY);
+        }
+        std::ptr::copy_nonoverlapping(values.as_ptr(), ptr, values.len());
+        Ok(ptr)
+    }
+}
+
+/// Allocates a C string for an out-parameter.
+///
+/// The string is copied into memory allocated with `allocate` so the client
+/// can free it with the COM task allocator, such as `CoTaskMemFree` on
+/// Windows, instead of the Rust allocator.
+pub fn allocate_cstring(source: &std::ffi::CStr) -> ComResult<*mut raw::c_char>
+{
+    allocate_copy(source.to_bytes_with_nul()).map(|ptr| ptr as *mut raw::c_char)
+}
+
+/// Takes the ownership of a C string received as an out-parameter.
+///
+/// # Safety
+///
+/// The `ptr` must be a valid, null terminated string allocated with
+/// `allocate`. The memory is freed and the pointer must not be used after
+/// the call.
+pub unsafe fn take_cstring(ptr: *mut raw::c_char) -> std::ffi::CString
+{
+    let cstring = std::ffi::CStr::from_ptr(ptr).to_owned();
+    free(ptr as *mut raw::c_void);
+    cstring
+}
+
+/// Allocates a wide string for an out-parameter.
+///
+/// The characters are copied with a terminating zero into memory allocated
+/// with `allocate` so the client can free the `LPWSTR` with the COM task
+/// allocator.
+pub fn allocate_wstring(source: &WString) -> ComResult<*mut u16>
+{
+    let mut chars = Vec::with_capacity(source.as_slice().len() + 1);
+    chars.extend_from_slice(source.as_slice());
+    chars.push(0);
+    allocate_copy(&chars)
+}
+
+/// Takes the ownership of a wide string received as an out-parameter.
+///
+/// # Safety
+///
+/// The `ptr` must be null or a valid, null terminated string allocated with
+/// `allocate`. The memory is freed and the pointer must not be used after
+/// the call.
+pub unsafe fn take_wstring(ptr: *mut u16) -> WString
+{
+    let wstring = WString::from_ptr(ptr);
+    free(ptr as *mut raw::c_void);
+    wstring
+}
+
+/// Allocates a value for an out-parameter.
+///
+/// The value is converted into its foreign representation in memory
+/// allocated with `allocate` so the client can free the pointer with the COM
+/// task allocator. Used for returning the structures by pointer.
+pub fn allocate_value<TS: TypeSystem, T: ExternOutput<TS>>(
+    value: T,
+) -> ComResult<*mut T::ForeignType>
+{
+    unsafe {
+        let ptr = allocate(std::mem::size_of::<T::ForeignType>().max(1)) as *mut T::ForeignType;
+        if ptr.is_null() {
+            return Err(ComError::E_OUTOFMEMORY);
+        }
+        match value.into_foreign_output() {
+            Ok(foreign) => {
+                ptr.write(foreign);
+                Ok(ptr)
+            }
+            Err(e) => {
+                free(ptr as *mut raw::c_void);
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Takes the ownership of a value received as an out-parameter.
+///
+/// # Safety
+///
+/// The `ptr` must point to a value allocated with `allocate`. The memory is
+/// freed and the pointer must not be used after the call.
+pub unsafe fn take_value<TS: TypeSystem, T: ExternOutput<TS>>(
+    ptr: *mut T::ForeignType,
+) -> ComResult<T>
+{
+    let value = T::from_foreign_output(ptr.read());
+    free(ptr as *mut raw::c_void);
+    value
+}
+
+/// Allocates a buffer for an out-parameter.
+///
+/// The values are converted into their Raw type system representation in
+/// memory allocated with `allocate` so the client can free it with the COM
+/// task allocator. Returns the buffer and the element count. Empty values are
+/// returned as a null buffer.
+pub fn allocate_buffer<T: ExternOutput<RawTypeSystem>>(
+    values: Vec<T>,
+) -> ComResult<(*mut T::ForeignType, u32)>
+{
+    let len = std::convert::TryFrom::try_from(values.len())
+        .map_err(|_| ComError::E_INVALIDARG.with_message("Buffer is too large"))?;
+    if values.is_empty() {
+        return Ok((std::ptr::null_mut(), 0));
+    }
+    unsafe {
+        let ptr =
+            allocate(std::mem::size_of::<T::ForeignType>() * values.len()) as *mut T::ForeignType;
+        if ptr.is_null() {
+            return Err(ComError::E_OUTOFMEMORY);
+        }
+
+        // The elements converted before a failure own their data so they
+        // are released with the buffer.
+        for (idx, value) in values.into_iter().enumerate() {
+            match value.into_foreign_output() {
+                Ok(element) => ptr.add(idx).write(element),
+                Err(e) => {
+                    for done in 0..idx {
+                        T::drop_foreign_output(ptr.add(done).read());
+                    }
+                    free(ptr as *mut raw::c_void);
+                    return Err(e);
+                }
+            }
+        }
+        Ok((ptr, len))
+    }
+}
+
+/// Takes the ownership of a buffer received as an out-parameter.
+///
+/// The elements are converted back into Rust values. All of the elements are
+/// released even if one of the conversions fails.
+///
+/// # Safety
+///
+/// The `ptr` must be null or point to `len` elements allocated with
+/// `allocate`. The memory is freed and the pointer must not be used after
+/// the call.
+pub unsafe fn take_buffer<T: ExternOutput<RawTypeSystem>>(
+    ptr: *mut T::ForeignType,
+    len: u32,
+) -> ComResult<Vec<T>>
+{
+    if ptr.is_null() {
+        return Ok(vec![]);
+    }
+    let values = (0..len as usize)
+        .map(|idx| T::from_foreign_output(ptr.add(idx).read()))
+        .collect::<Vec<_>>();
+    free(ptr as *mut raw::c_void);
+    values.into_iter().collect()
+}
+
 #[cfg(windows)]
 mod os
 {
@@ -227,3 +401,162 @@ mod os
         libc::free(ptr as *mut _)
     }
 }
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    #[test]
+    fn cstring_out_param_uses_task_allocator()
+    {
+        let ptr = <String as ExternOutput<RawTypeSystem>>::into_foreign_output("Hello".to_string())
+            .unwrap();
+        unsafe {
+            assert_eq!(std::ffi::CStr::from_ptr(ptr).to_str().unwrap(), "Hello");
+
+            // The client frees the out-parameter with the task allocator.
+            free(ptr as *mut raw::c_void);
+        }
+
+        let ptr = allocate_cstring(&std::ffi::CString::new("World").unwrap()).unwrap();
+        let value = unsafe { <String as ExternOutput<RawTypeSystem>>::from_foreign_output(ptr) };
+        assert_eq!(value.unwrap(), "World");
+    }
+
+    #[test]
+    fn wstring_out_param_uses_task_allocator()
+    {
+        let ptr = <WString as ExternOutput<RawTypeSystem>>::into_foreign_output(WString::from(
+            "caf\u{e9}",
+        ))
+        .unwrap();
+        unsafe {
+            let chars = std::slice::from_raw_parts(ptr.0, 5);
+            assert_eq!(chars, &[0x63, 0x61, 0x66, 0xe9, 0]);
+
+            // The client frees the `LPWSTR` with the task allocator.
+            free(ptr.0 as *mut raw::c_void);
+        }
+
+        let ptr = allocate_wstring(&WString::from("World")).unwrap();
+        let value = unsafe { take_wstring(ptr) };
+        assert_eq!(value.to_string().unwrap(), "World");
+    }
+
+    #[test]
+    fn boxed_out_param_uses_task_allocator()
+    {
+        type Point = crate::type_system::Tuple2<i32, i32>;
+        let ptr = <Box<Point> as ExternOutput<RawTypeSystem>>::into_foreign_output(Box::new(
+            crate::type_system::Tuple2(3, -4),
+        ))
+        .unwrap();
+        unsafe {
+            assert_eq!(*ptr, crate::type_system::Tuple2(3, -4));
+
+            // The client frees the structure with the task allocator.
+            free(ptr as *mut raw::c_void);
+        }
+
+        let ptr = allocate_value::<RawTypeSystem, _>("text".to_string()).unwrap();
+        let value = unsafe { take_value::<RawTypeSystem, String>(ptr) };
+        assert_eq!(value.unwrap(), "text");
+    }
+
+    #[crate::com_interface]
+    trait IReader
+    {
+        #[com_return(ptr_and_len)]
+        fn read(&self, count: u32) -> ComResult<Vec<u8>>;
+
+        #[com_return(ptr_and_len)]
+        fn flags(&self, count: u32) -> ComResult<Vec<bool>>;
+
+        #[com_return(ptr_and_len)]
+        fn names(&self, count: u32) -> ComResult<Vec<String>>;
+    }
+
+    #[crate::com_class(clsid = None, IReader)]
+    struct Reader;
+
+    impl IReader for Reader
+    {
+        fn read(&self, count: u32) -> ComResult<Vec<u8>>
+        {
+            match count {
+                0 => Err(ComError::E_INVALIDARG),
+                _ => Ok((1..=count as u8).collect()),
+            }
+        }
+
+        fn flags(&self, count: u32) -> ComResult<Vec<bool>>
+        {
+            Ok((0..count).map(|i| i % 2 == 0).collect())
+        }
+
+        fn names(&self, count: u32) -> ComResult<Vec<String>>
+        {
+            Ok((0..count).map(|i| format!("name {}", i)).collect())
+        }
+    }
+
+    #[test]
+    fn vec_is_returned_as_ptr_and_len()
+    {
+        let reader = crate::ComRc::<dyn IReader>::from(crate::ComBox::new(Reader));
+
+        // The Raw type system returns the buffer and its length.
+        let itf = ComItf::ptr::<RawTypeSystem>(&reader).unwrap();
+        let vtbl = unsafe { &**(itf.ptr.as_ptr() as *const *const __IReaderRawVTable) };
+        unsafe {
+            let mut ptr = std::ptr::null_mut();
+            let mut len = 0;
+            let hr = (vtbl.read)(itf.ptr.as_ptr(), 3, &mut ptr, &mut len);
+            assert_eq!(hr, crate::raw::S_OK);
+            assert_eq!(len, 3);
+            assert_eq!(std::slice::from_raw_parts(ptr, len as usize), &[1, 2, 3]);
+            free(ptr as *mut raw::c_void);
+
+            let hr = (vtbl.read)(itf.ptr.as_ptr(), 0, &mut ptr, &mut len);
+            assert_eq!(hr, crate::raw::E_INVALIDARG);
+            assert!(ptr.is_null());
+            assert_eq!(len, 0);
+        }
+
+        // The clients receive the Vec through both type systems.
+        let raw = ComItf::raw(&reader).unwrap();
+        assert_eq!(raw.read(4).unwrap(), vec![1, 2, 3, 4]);
+        assert_eq!(raw.read(0).unwrap_err().hresult, crate::raw::E_INVALIDARG);
+        let automation = ComItf::automation(&reader).unwrap();
+        assert_eq!(automation.read(2).unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn buffer_elements_are_converted()
+    {
+        let reader = crate::ComRc::<dyn IReader>::from(crate::ComBox::new(Reader));
+
+        // The strings are stored as C strings owned by the buffer.
+        let itf = ComItf::ptr::<RawTypeSystem>(&reader).unwrap();
+        let vtbl = unsafe { &**(itf.ptr.as_ptr() as *const *const __IReaderRawVTable) };
+        unsafe {
+            let mut ptr = std::ptr::null_mut();
+            let mut len = 0;
+            let hr = (vtbl.names)(itf.ptr.as_ptr(), 2, &mut ptr, &mut len);
+            assert_eq!(hr, crate::raw::S_OK);
+            assert_eq!(len, 2);
+            let names = std::slice::from_raw_parts(ptr, len as usize)
+                .iter()
+                .map(|&name| take_cstring(name))
+                .collect::<Vec<_>>();
+            assert_eq!(names[1].to_str().unwrap(), "name 1");
+            free(ptr as *mut raw::c_void);
+        }
+
+        let raw = ComItf::raw(&reader).unwrap();
+        assert_eq!(raw.flags(3).unwrap(), vec![true, false, true]);
+        assert_eq!(raw.names(0).unwrap(), Vec::<String>::new());
+        assert_eq!(raw.names(2).unwrap(), vec!["name 0", "name 1"]);
+    }
+}
@@ -4,6 +4,61 @@ use crate::type_system;
 use crate::RawComResult;
 use crate::REFIID;
 use crate::{type_system::TypeSystem, IID};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Lock guarding the method calls into a COM class.
+///
+/// The classes with `#[com_class(locking = RwLock)]` are guarded by a
+/// `RwLock<()>`. The `&self` methods acquire a shared lock while the
+/// `&mut self` methods acquire an exclusive lock. The other classes use `()`,
+/// which takes no space in the `ComBoxData` and never blocks.
+///
+/// The locks are not re-entrant. A method that calls out to a client which
+/// calls back into the same object deadlocks if either of the calls requires
+/// the exclusive lock.
+pub trait ClassLock: Default
+{
+    /// Acquires a shared lock for a `&self` method call.
+    ///
+    /// Returns `None` if the lock doesn't synchronize the calls.
+    fn lock_shared(&self) -> Option<RwLockReadGuard<'_, ()>>;
+}
+
+/// Lock that allows serving `&mut self` method calls.
+#[diagnostic::on_unimplemented(
+    message = "COM methods with `&mut self` receiver require a locking policy",
+    label = "the class has no locking policy",
+    note = "specify one with `#[com_class(locking = RwLock, ...)]`"
+)]
+pub trait ExclusiveClassLock: ClassLock
+{
+    /// Acquires an exclusive lock for a `&mut self` method call.
+    fn lock_exclusive(&self) -> RwLockWriteGuard<'_, ()>;
+}
+
+impl ClassLock for ()
+{
+    fn lock_shared(&self) -> Option<RwLockReadGuard<'_, ()>>
+    {
+        None
+    }
+}
+
+impl ClassLock for RwLock<()>
+{
+    fn lock_shared(&self) -> Option<RwLockReadGuard<'_, ()>>
+    {
+        Some(self.read().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl ExclusiveClassLock for RwLock<()>
+{
+    fn lock_exclusive(&self) -> RwLockWriteGuard<'_, ()>
+    {
+        self.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
 
 /// Trait required by any COM coclass type.
 ///
@@ -12,14 +67,67 @@ pub trait ComClass
 {
     type VTableList: Copy;
     const VTABLE: Self::VTableList;
+
+    /// Lock guarding the method calls, `()` if the class has no locking
+    /// policy.
+    type Lock: ClassLock;
+
     fn query_interface(vtables: &Self::VTableList, riid: REFIID) -> RawComResult<RawComPtr>;
     fn interface_supports_error_info(riid: REFIID) -> bool;
+
+    /// Invoked once when the last reference to the object is released,
+    /// before the object is dropped.
+    fn on_release(&mut self) {}
+
+    /// Formats the object for the `Debug` output of its `ComBox`.
+    ///
+    /// The classes implementing `Debug` use their `Debug` implementation.
+    /// Other classes are represented by the class name.
+    fn fmt_debug(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        f.write_str(std::any::type_name::<Self>())
+    }
 }
 
-pub trait HasInterface<T: ComInterface + ?Sized>: ComClass
+/// Selects the `fmt_debug` implementation in the generated `ComClass` code.
+///
+/// The method call `(&DebugSelector(self)).fmt_debug(f)` resolves to
+/// `FmtWithDebug` when the class implements `Debug` and to `FmtWithName`
+/// otherwise. This way the `com_class` attribute doesn't need to see the
+/// `Debug` implementation of the class.
+#[doc(hidden)]
+pub struct DebugSelector<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait FmtWithDebug
 {
+    fn fmt_debug(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result;
 }
 
+impl<'a, T: std::fmt::Debug + ?Sized> FmtWithDebug for DebugSelector<'a, T>
+{
+    fn fmt_debug(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        std::fmt::Debug::fmt(self.0, f)
+    }
+}
+
+#[doc(hidden)]
+pub trait FmtWithName
+{
+    fn fmt_debug(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result;
+}
+
+impl<'a, 'b, T: ?Sized> FmtWithName for &'b DebugSelector<'a, T>
+{
+    fn fmt_debug(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        f.write_str(std::any::type_name::<T>())
+    }
+}
+
+pub trait HasInterface<T: ComInterface + ?Sized>: ComClass {}
+
 pub trait ComClassInterface<TInterface: ?Sized, TS: TypeSystem>: ComClass + Sized
 {
     fn offset() -> usize;
@@ -53,6 +161,19 @@ pub trait ComInterface
     /// is not a COM interface.
     type TSelf: ?Sized;
 
+    /// Primary IID of the COM interface.
+    ///
+    /// This is the IID of the Automation variant, or the IID of the first
+    /// type system variant for interfaces without an Automation variant.
+    const IID: IID;
+
+    /// True for the dual interfaces, which serve `IDispatch` as well.
+    const DUAL: bool = false;
+
+    /// True for the interfaces whose methods store the `IErrorInfo` on
+    /// failure. Includes the methods of the base interfaces.
+    const SUPPORTS_ERROR_INFO: bool = false;
+
     /// IID of the COM interface.
     fn iid(ts: type_system::TypeSystemName) -> Option<&'static IID>;
 
@@ -69,6 +190,31 @@ pub trait ComInterface
     fn deref(com_itf: &crate::ComItf<Self>) -> &Self;
 }
 
+/// Late bound calls into a dual interface.
+///
+/// Implemented by `#[com_interface(dual)]` for serving `IDispatch`. The
+/// DISPIDs follow the order of the interface methods, starting from 1. The
+/// property accessors share the DISPID of the first accessor.
+pub trait ComDispatch: ComInterface
+{
+    /// Gets the DISPID of a method or a property by its name.
+    ///
+    /// The names are matched ignoring the case and the underscores so both
+    /// `GetName` and `get_name` find the method `get_name`.
+    fn dispid_of_name(name: &str) -> Option<i32>;
+
+    /// Calls the member identified by the DISPID.
+    ///
+    /// - `flags` - The `DISPATCH_*` flags telling the kind of the call.
+    /// - `args` - The arguments in the order of the parameters.
+    fn invoke(
+        itf: &mut crate::ComItf<Self>,
+        dispid: i32,
+        flags: u16,
+        args: Vec<crate::Variant>,
+    ) -> crate::ComResult<crate::Variant>;
+}
+
 pub trait ComInterfaceVariant<TS: TypeSystem>
 {
     type VTable: Copy + 'static;
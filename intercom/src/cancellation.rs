@@ -0,0 +1,148 @@
+//! Cancellation of long running methods.
+//!
+//! A method that may run for a long time can take an `ICancellationToken`
+//! from the caller and poll it while working. Once the caller cancels the
+//! token, the method stops and fails with `E_ABORT`.
+//!
+//! ```rust,ignore
+//! #[com_interface]
+//! trait IIndexer
+//! {
+//!     fn index(&self, cancel: &ComItf<dyn ICancellationToken>) -> ComResult<u32>;
+//! }
+//!
+//! impl IIndexer for Indexer
+//! {
+//!     fn index(&self, cancel: &ComItf<dyn ICancellationToken>) -> ComResult<u32>
+//!     {
+//!         for file in &self.files {
+//!             cancellation::check(cancel)?;
+//!             self.index_file(file);
+//!         }
+//!         Ok(self.files.len() as u32)
+//!     }
+//! }
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use super::*;
+
+/// Tells the method whether the caller has cancelled the call.
+#[com_interface(
+    com_iid = "8f4c2a6e-1d3b-4e59-a7c0-5b9e3d1f6a24",
+    raw_iid = "2b7e9d13-6a4f-4c82-9e15-d3a8c6f04b71"
+)]
+pub trait ICancellationToken: crate::IUnknown
+{
+    /// Checks whether the call has been cancelled.
+    fn is_cancelled(&self) -> ComResult<bool>;
+}
+
+/// Token the Rust callers pass to the cancellable methods.
+///
+/// The token is cancelled with `cancel` or, for the tokens created with
+/// `with_timeout`, once the timeout elapses.
+#[com_class(clsid = None, ICancellationToken)]
+#[derive(Default)]
+pub struct CancellationToken
+{
+    cancelled: AtomicBool,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken
+{
+    /// Creates a token that is cancelled once the `timeout` elapses.
+    pub fn with_timeout(timeout: Duration) -> CancellationToken
+    {
+        CancellationToken {
+            cancelled: AtomicBool::new(false),
+            deadline: Some(Instant::now() + timeout),
+        }
+    }
+
+    /// Cancels the calls polling the token.
+    pub fn cancel(&self)
+    {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+impl ICancellationToken for CancellationToken
+{
+    fn is_cancelled(&self) -> ComResult<bool>
+    {
+        let expired = self
+            .deadline
+            .map(|deadline| Instant::now() >= deadline)
+            .unwrap_or(false);
+        Ok(expired || self.cancelled.load(Ordering::SeqCst))
+    }
+}
+
+/// Fails with `E_ABORT` if the call has been cancelled.
+///
+/// The errors of querying the token are returned as they are.
+pub fn check(token: &ComItf<dyn ICancellationToken>) -> ComResult<()>
+{
+    match token.is_cancelled()? {
+        true => Err(ComError::E_ABORT),
+        false => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    #[com_interface]
+    trait ICounter
+    {
+        fn count_to(&self, limit: u32, cancel: &ComItf<dyn ICancellationToken>) -> ComResult<u32>;
+    }
+
+    #[com_class(clsid = None, ICounter)]
+    struct Counter;
+
+    impl ICounter for Counter
+    {
+        fn count_to(&self, limit: u32, cancel: &ComItf<dyn ICancellationToken>) -> ComResult<u32>
+        {
+            let mut count = 0;
+            while count < limit {
+                check(cancel)?;
+                count += 1;
+            }
+            Ok(count)
+        }
+    }
+
+    #[test]
+    fn cancelled_token_aborts_method()
+    {
+        let counter = ComRc::<dyn ICounter>::from(ComBox::new(Counter));
+        let token = ComBox::new(CancellationToken::default());
+        let token_rc = ComRc::<dyn ICancellationToken>::from(&token);
+
+        assert_eq!(counter.count_to(3, &token_rc).unwrap(), 3);
+
+        token.cancel();
+        let err = counter.count_to(3, &token_rc).unwrap_err();
+        assert_eq!(err.hresult, raw::E_ABORT);
+    }
+
+    #[test]
+    fn token_is_cancelled_after_timeout()
+    {
+        let token = ComBox::new(CancellationToken::with_timeout(Duration::from_millis(0)));
+        let token = ComRc::<dyn ICancellationToken>::from(&token);
+        assert_eq!(check(&token).unwrap_err().hresult, raw::E_ABORT);
+
+        let token = ComBox::new(CancellationToken::with_timeout(Duration::from_secs(3600)));
+        let token = ComRc::<dyn ICancellationToken>::from(&token);
+        assert!(check(&token).is_ok());
+    }
+}
@@ -32,7 +32,7 @@ impl<T: Default + attributes::ComClass> IClassFactory for ClassFactory<T>
     fn create_instance(&self, _outer: RawComPtr, riid: REFIID) -> ComResult<RawComPtr>
     {
         unsafe {
-            let instance = ComBox::new(T::default());
+            let instance = ComBox::try_new(T::default())?;
             let mut out = std::ptr::null_mut();
             let hr = ComBoxData::query_interface(instance.as_ref(), riid, &mut out);
             if hr == raw::S_OK {
@@ -70,6 +70,84 @@ impl<T: Default + attributes::ComClass> ClassFactory<T>
             phantom: std::marker::PhantomData,
         };
 
-        intercom::ComBoxData::query_interface(intercom::ComBox::new(factory).as_mut(), riid, out)
+        intercom::ComBoxData::query_interface(intercom::ComBox::new(factory).as_ref(), riid, out)
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use crate::type_system::AutomationTypeSystem;
+
+    #[com_class(Self)]
+    #[derive(Default)]
+    pub struct Creatable;
+
+    #[com_interface]
+    impl Creatable
+    {
+        fn get_value(&self) -> u32
+        {
+            1
+        }
+    }
+
+    // Not `Default` as the class factory is not needed.
+    #[com_class(no_class_factory, Self)]
+    pub struct Internal
+    {
+        value: u32,
+    }
+
+    #[com_interface]
+    impl Internal
+    {
+        fn get_value(&self) -> u32
+        {
+            self.value
+        }
+    }
+
+    com_module!(class Creatable, class Internal,);
+
+    #[test]
+    fn no_class_factory_is_not_available()
+    {
+        let riid =
+            <dyn IClassFactory as attributes::ComInterfaceVariant<AutomationTypeSystem>>::iid()
+                as REFIID;
+        let mut out = std::ptr::null_mut();
+        unsafe {
+            assert_eq!(
+                __get_module_class_factory(&CLSID_Internal, riid, &mut out),
+                None
+            );
+            assert!(out.is_null());
+
+            assert_eq!(
+                __get_module_class_factory(&CLSID_Creatable, riid, &mut out),
+                Some(raw::S_OK)
+            );
+            let factory =
+                raw::InterfacePtr::<AutomationTypeSystem, dyn IClassFactory>::new(out).unwrap();
+            drop(ComRc::wrap(factory));
+        }
+
+        let classes = __gather_module_types()
+            .into_iter()
+            .filter_map(|ty| match ty {
+                typelib::TypeInfo::Class(cls) => Some(cls),
+                _ => None,
+            })
+            .map(|cls| (cls.name.to_string(), cls.options.no_class_factory))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            classes,
+            vec![
+                ("Creatable".to_string(), false),
+                ("Internal".to_string(), true)
+            ]
+        );
     }
 }
@@ -1,8 +1,9 @@
 use super::*;
-use crate::attributes::{ComClass, ComInterface, HasInterface};
+use crate::attributes::{ClassLock, ComClass, ComInterface, ExclusiveClassLock, HasInterface};
 use crate::raw::RawComPtr;
 use crate::type_system::TypeSystemName;
 use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{RwLockReadGuard, RwLockWriteGuard};
 
 /// Pointer to a COM-enabled Rust struct.
 ///
@@ -16,6 +17,13 @@ use std::sync::atomic::{AtomicU32, Ordering};
 /// Technically the memory layout is specified by the [`ComBoxData`](struct.ComBoxData.html)
 /// type, however that type shouldn't be needed by the user. For all intents
 /// the `ComBox` type is _the_ COM-compatible object handle.
+///
+/// The value is kept at a stable address until the last reference is
+/// released, which makes it safe for the objects to hand out pointers to
+/// themselves, such as when registering callbacks with external systems.
+/// Values that aren't `Unpin` are mutable only through `Pin<&mut T>` as a
+/// `&mut T` would allow moving them. The COM methods of such objects use the
+/// `self: Pin<&mut Self>` receiver instead of `&mut self`.
 pub struct ComBox<T: ComClass>
 {
     data: *mut ComBoxData<T>,
@@ -37,6 +45,38 @@ impl<T: ComClass> ComBox<T>
         ComBox { data: cb }
     }
 
+    /// Constructs a new `ComBox`, failing with `E_OUTOFMEMORY` if the memory
+    /// cannot be allocated.
+    ///
+    /// - `value` - The initial state to use for the COM object.
+    pub fn try_new(value: T) -> ComResult<ComBox<T>>
+    {
+        Self::try_new_with(value, std::alloc::alloc)
+    }
+
+    /// Constructs a new `ComBox` with the memory acquired from `alloc`.
+    fn try_new_with(
+        value: T,
+        alloc: unsafe fn(std::alloc::Layout) -> *mut u8,
+    ) -> ComResult<ComBox<T>>
+    {
+        let cb = ComBoxData::try_new_with(value, alloc)?;
+        unsafe { ComBoxData::add_ref(&*cb) };
+        Ok(ComBox { data: cb })
+    }
+
+    /// Gets a pinned mutable reference to the value.
+    pub fn pin_mut(this: &mut Self) -> std::pin::Pin<&mut T>
+    {
+        this.as_mut_data().pin_mut()
+    }
+
+    fn as_mut_data(&mut self) -> &mut ComBoxData<T>
+    {
+        // 'data' should always be valid pointer.
+        unsafe { self.data.as_mut().expect("ComBox had null reference") }
+    }
+
     /// Acquires a ComItf for this struct.
     ///
     /// # Safety
@@ -77,12 +117,15 @@ impl<T: ComClass> ComBox<T>
     }
 }
 
-impl<T: ComClass + std::fmt::Debug> std::fmt::Debug for ComBox<T>
+/// Debug builds include the reference count of the object.
+impl<T: ComClass> std::fmt::Debug for ComBox<T>
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
     {
         write!(f, "ComBox(")?;
-        self.as_ref().fmt(f)?;
+        ComClass::fmt_debug(&**self, f)?;
+        #[cfg(debug_assertions)]
+        write!(f, ", ref_count = {}", self.as_ref().get_ref_count())?;
         write!(f, ")")
     }
 }
@@ -97,12 +140,11 @@ impl<T: ComClass> Drop for ComBox<T>
     }
 }
 
-impl<T: ComClass> AsMut<ComBoxData<T>> for ComBox<T>
+impl<T: ComClass + Unpin> AsMut<ComBoxData<T>> for ComBox<T>
 {
     fn as_mut(&mut self) -> &mut ComBoxData<T>
     {
-        // 'data' should always be valid pointer.
-        unsafe { self.data.as_mut().expect("ComBox had null reference") }
+        self.as_mut_data()
     }
 }
 
@@ -156,11 +198,15 @@ impl<I: ComInterface + ?Sized, T: HasInterface<I>> From<&ComBox<T>> for ComRc<I>
 /// Furthermore the `new_ptr` constructor and the `IUnknown` methods `add_ref`
 /// and `release` must be used correctly together. Failure to do so will result
 /// either in memory leaks or access to dangling pointers.
+///
+/// The value is pinned for the lifetime of the `ComBoxData`. Values that
+/// aren't `Unpin` are mutable only through [`pin_mut`](#method.pin_mut).
 #[repr(C)]
 pub struct ComBoxData<T: ComClass>
 {
     vtable_list: T::VTableList,
     ref_count: AtomicU32,
+    lock: T::Lock,
     value: T,
 }
 
@@ -180,10 +226,38 @@ impl<T: ComClass> ComBoxData<T>
         Box::into_raw(Box::new(ComBoxData {
             vtable_list: T::VTABLE,
             ref_count: AtomicU32::new(0),
+            lock: Default::default(),
             value,
         }))
     }
 
+    /// Creates a new ComBoxData with the memory acquired from `alloc`.
+    ///
+    /// The memory is released with the global allocator so `alloc` must
+    /// allocate from it or fail by returning null. The failure is reported as
+    /// `E_OUTOFMEMORY` instead of aborting the process.
+    fn try_new_with(
+        value: T,
+        alloc: unsafe fn(std::alloc::Layout) -> *mut u8,
+    ) -> ComResult<*mut ComBoxData<T>>
+    {
+        let layout = std::alloc::Layout::new::<ComBoxData<T>>();
+        let ptr = unsafe { alloc(layout) } as *mut ComBoxData<T>;
+        if ptr.is_null() {
+            return Err(ComError::E_OUTOFMEMORY);
+        }
+
+        unsafe {
+            ptr.write(ComBoxData {
+                vtable_list: T::VTABLE,
+                ref_count: AtomicU32::new(0),
+                lock: Default::default(),
+                value,
+            })
+        };
+        Ok(ptr)
+    }
+
     /// Acquires a specific interface pointer.
     ///
     /// Increments the reference count to include the reference through the
@@ -225,12 +299,39 @@ impl<T: ComClass> ComBoxData<T>
         previous_value + 1
     }
 
+    /// Gets a pinned mutable reference to the value.
+    ///
+    /// The value is never moved out of the `ComBoxData` so it remains pinned
+    /// until the object is destroyed.
+    pub fn pin_mut(&mut self) -> std::pin::Pin<&mut T>
+    {
+        unsafe { std::pin::Pin::new_unchecked(&mut self.value) }
+    }
+
     /// Gets the reference count of the object.
     pub fn get_ref_count(&self) -> u32
     {
         self.ref_count.load(Ordering::Relaxed)
     }
 
+    /// Acquires a shared lock for a `&self` method call.
+    ///
+    /// Returns `None` if the class doesn't specify a locking policy.
+    pub fn lock_shared(&self) -> Option<RwLockReadGuard<'_, ()>>
+    {
+        self.lock.lock_shared()
+    }
+
+    /// Acquires an exclusive lock for a `&mut self` method call.
+    ///
+    /// Available only for the classes with a locking policy.
+    pub fn lock_exclusive(&self) -> RwLockWriteGuard<'_, ()>
+    where
+        T::Lock: ExclusiveClassLock,
+    {
+        self.lock.lock_exclusive()
+    }
+
     /// Decrements the reference count. Destroys the object if the count reaches
     /// zero.
     ///
@@ -269,6 +370,11 @@ impl<T: ComClass> ComBoxData<T>
         // it back to a box and then dropping the box. This should reverse the
         // allocation we did by boxing the value in the first place.
         if rc == 0 {
+            // Let the class clean up before it is dropped. The reference
+            // count is held at one while the hook runs so any temporary
+            // references the hook takes won't drop the box a second time.
+            (*this).ref_count.store(1, Ordering::Relaxed);
+            (*this).value.on_release();
             drop(Box::from_raw(this));
         }
         rc
@@ -385,7 +491,7 @@ where
 
 impl<T> std::ops::DerefMut for ComBoxData<T>
 where
-    T: ComClass,
+    T: ComClass + Unpin,
 {
     fn deref_mut(&mut self) -> &mut T
     {
@@ -406,7 +512,7 @@ where
 
 impl<T> std::ops::DerefMut for ComBox<T>
 where
-    T: ComClass,
+    T: ComClass + Unpin,
 {
     fn deref_mut(&mut self) -> &mut T
     {
@@ -421,3 +527,218 @@ impl<T: Default + ComClass> Default for ComBox<T>
         ComBox::new(T::default())
     }
 }
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    #[crate::com_class(clsid = None, on_release = "cleanup")]
+    struct Cleaned
+    {
+        releases: Arc<AtomicUsize>,
+    }
+
+    impl Cleaned
+    {
+        fn cleanup(&mut self)
+        {
+            self.releases.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    unsafe fn failing_alloc(_: std::alloc::Layout) -> *mut u8
+    {
+        std::ptr::null_mut()
+    }
+
+    #[test]
+    fn failed_allocation_is_out_of_memory()
+    {
+        let releases = Arc::new(AtomicUsize::new(0));
+        let result = ComBox::try_new_with(
+            Cleaned {
+                releases: releases.clone(),
+            },
+            failing_alloc,
+        );
+        assert_eq!(result.err().unwrap().hresult, raw::E_OUTOFMEMORY);
+        assert_eq!(Arc::strong_count(&releases), 1);
+
+        let combox = ComBox::try_new(Cleaned {
+            releases: releases.clone(),
+        })
+        .unwrap();
+        drop(combox);
+        assert_eq!(releases.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn on_release_runs_on_final_release()
+    {
+        let releases = Arc::new(AtomicUsize::new(0));
+        let combox = ComBox::new(Cleaned {
+            releases: releases.clone(),
+        });
+
+        let first = ComRc::<dyn IUnknown>::from(&combox);
+        let second = first.clone();
+        drop(combox);
+        drop(first);
+        assert_eq!(releases.load(Ordering::SeqCst), 0);
+
+        drop(second);
+        assert_eq!(releases.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn try_clone_detects_released_object()
+    {
+        let releases = Arc::new(AtomicUsize::new(0));
+        let combox = ComBox::new(Cleaned {
+            releases: releases.clone(),
+        });
+        let rc = ComRc::<dyn IUnknown>::from(&combox);
+
+        // Simulate a reference that outlived the object while keeping the
+        // memory around for the test.
+        combox.as_ref().ref_count.store(0, Ordering::Relaxed);
+        let result = ComRc::try_clone(&rc);
+        assert_eq!(result.unwrap_err().hresult, raw::E_POINTER);
+
+        // The failed clone doesn't release the reference it took. Restore the
+        // references of the `combox` and `rc` before dropping them.
+        combox.as_ref().ref_count.store(2, Ordering::Relaxed);
+        drop(rc);
+        drop(combox);
+        assert_eq!(releases.load(Ordering::SeqCst), 1);
+    }
+
+    #[crate::com_class(clsid = None)]
+    #[derive(Debug)]
+    struct Counter
+    {
+        count: u32,
+    }
+
+    #[test]
+    fn debug_output_names_the_class()
+    {
+        let releases = Arc::new(AtomicUsize::new(0));
+        let cleaned = ComBox::new(Cleaned { releases });
+        let output = format!("{:?}", cleaned);
+        assert!(output.starts_with("ComBox("));
+        assert!(output.contains("Cleaned"));
+
+        let counter = ComBox::new(Counter { count: 3 });
+        let _rc = ComRc::<dyn IUnknown>::from(&counter);
+        let output = format!("{:?}", counter);
+        assert!(output.contains(&format!("Counter {{ count: {} }}", counter.count)));
+
+        #[cfg(debug_assertions)]
+        assert!(output.ends_with(", ref_count = 2)"));
+    }
+
+    #[crate::com_class(clsid = None)]
+    struct Gauge
+    {
+        level: u32,
+    }
+
+    impl std::fmt::Debug for Gauge
+    {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+        {
+            write!(f, "Gauge at {}", self.level)
+        }
+    }
+
+    #[crate::com_class(clsid = None)]
+    #[derive(Debug)]
+    struct Wrapper<T: Default + 'static>
+    {
+        inner: T,
+    }
+
+    #[test]
+    fn debug_output_uses_the_debug_implementation()
+    {
+        let gauge = ComBox::new(Gauge { level: 7 });
+        assert!(format!("{:?}", gauge).starts_with("ComBox(Gauge at 7"));
+
+        // The `Debug` implementation requiring bounds the `ComClass`
+        // implementation doesn't have falls back on the class name.
+        let wrapper = ComBox::new(Wrapper { inner: 1u8 });
+        assert_eq!(wrapper.inner, 1);
+        assert!(format!("{:?}", wrapper).contains("Wrapper<u8>"));
+    }
+
+    #[crate::com_class(clsid = None, locking = RwLock, Self)]
+    struct Anchor
+    {
+        address: std::cell::Cell<usize>,
+        _pin: std::marker::PhantomPinned,
+    }
+
+    #[crate::com_interface]
+    impl Anchor
+    {
+        fn register(self: std::pin::Pin<&mut Self>) -> ComResult<()>
+        {
+            let address = &*self as *const Self as usize;
+            self.address.set(address);
+            Ok(())
+        }
+
+        fn is_registered_here(&self) -> bool
+        {
+            self.address.get() == self as *const Self as usize
+        }
+    }
+
+    #[test]
+    fn pinned_receiver_sees_stable_address()
+    {
+        use crate::attributes::ComInterfaceVariant;
+        use crate::type_system::RawTypeSystem;
+
+        let mut combox = ComBox::new(Anchor {
+            address: std::cell::Cell::new(0),
+            _pin: std::marker::PhantomPinned,
+        });
+        let rc = ComRc::<Anchor>::from(&combox);
+        let ptr = ComItf::ptr::<RawTypeSystem>(&rc).unwrap().ptr;
+        let vtbl =
+            ptr.as_ptr() as *const *const <Anchor as ComInterfaceVariant<RawTypeSystem>>::VTable;
+
+        unsafe {
+            assert_eq!(((**vtbl).register)(ptr.as_ptr()), raw::S_OK);
+            assert!(((**vtbl).is_registered_here)(ptr.as_ptr()));
+        }
+
+        ComBox::pin_mut(&mut combox).register().unwrap();
+        assert!(combox.is_registered_here());
+    }
+
+    #[test]
+    fn lock_is_stored_only_with_locking_policy()
+    {
+        // The classes without a locking policy have no lock in the box.
+        #[repr(C)]
+        struct Unlocked
+        {
+            vtable_list: <Cleaned as ComClass>::VTableList,
+            ref_count: AtomicU32,
+            value: Cleaned,
+        }
+        assert_eq!(
+            std::mem::size_of::<ComBoxData<Cleaned>>(),
+            std::mem::size_of::<Unlocked>()
+        );
+
+        assert!(std::mem::size_of::<<Anchor as ComClass>::Lock>() > 0);
+    }
+}
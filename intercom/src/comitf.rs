@@ -2,7 +2,8 @@ use super::*;
 use crate::attributes::ComInterface;
 use crate::interfaces::RawIUnknown;
 use crate::type_system::{
-    AutomationTypeSystem, ExternInput, ExternType, InfallibleExternInput, RawTypeSystem, TypeSystem,
+    AutomationTypeSystem, ExternCountedInput, ExternInput, ExternType, InfallibleExternInput,
+    RawTypeSystem, TypeSystem,
 };
 use std::marker::PhantomData;
 
@@ -99,7 +100,7 @@ impl ComItf<dyn IUnknown>
 {
     /// Tries to convert the ComRc into a different interface within a single
     /// type system. Used to implement the generic conversion method.
-    fn query_interface_ts<TS: TypeSystem, TTarget: ComInterface + ?Sized>(
+    pub(crate) fn query_interface_ts<TS: TypeSystem, TTarget: ComInterface + ?Sized>(
         &self,
     ) -> ComResult<ComRc<TTarget>>
     {
@@ -159,6 +160,90 @@ impl<T: ComInterface + ?Sized> ComItf<T>
         Err(ComError::E_NOINTERFACE)
     }
 
+    /// Gets the address identifying the COM object behind the interface.
+    ///
+    /// COM guarantees object identity only for the `IUnknown` interface so
+    /// the address is resolved by querying the object for `IUnknown`. All
+    /// interfaces to the same object result in the same address, which makes
+    /// the address suitable as a key for caching per-object state.
+    pub fn as_ptr_addr(this: &Self) -> ComResult<usize>
+    {
+        let iunk = this
+            .as_iunknown()
+            .query_interface_ts::<AutomationTypeSystem, dyn IUnknown>()?;
+        let ptr = ComItf::ptr::<AutomationTypeSystem>(&iunk).ok_or(ComError::E_POINTER)?;
+        Ok(ptr.ptr.as_ptr() as usize)
+    }
+
+    /// Gets the current reference count of the object for debugging.
+    ///
+    /// The count is observed through an `AddRef`/`Release` pair. Other
+    /// threads may add or release references at the same time so the value
+    /// is approximate and must not be used for anything but diagnostics.
+    #[cfg(debug_assertions)]
+    pub fn debug_refcount(this: &Self) -> u32
+    {
+        let iunk = this.as_raw_iunknown();
+        iunk.add_ref();
+        iunk.release()
+    }
+
+    /// Gets the address of the virtual table behind the interface for
+    /// profiling.
+    ///
+    /// All interface pointers of the same interface type and implementation
+    /// share the virtual table, which identifies the interface a call goes
+    /// through. The raw type system pointer is preferred if both are present.
+    ///
+    /// The layout of the virtual tables is an implementation detail so the
+    /// value is not portable and this is not part of the stable API.
+    #[cfg(debug_assertions)]
+    pub fn vtable_ptr(this: &Self) -> *const std::ffi::c_void
+    {
+        let ptr = match (&this.raw_ptr, &this.automation_ptr) {
+            (Some(raw), _) => raw.ptr,
+            (None, Some(automation)) => automation.ptr,
+            (None, None) => unreachable!("ComItf must have at least one interface pointer"),
+        };
+
+        // The interface pointer points to the virtual table pointer.
+        unsafe { *(ptr.as_ptr() as *const *const std::ffi::c_void) }
+    }
+
+    /// Gets the interface restricted to the raw type system.
+    ///
+    /// The calls made through the returned interface are dispatched through
+    /// the raw type system virtual table. If the `ComItf` does not hold a raw
+    /// pointer, the object is queried for one.
+    pub fn raw(this: &Self) -> ComResult<ComRc<T>>
+    {
+        Self::for_type_system::<RawTypeSystem>(this)
+    }
+
+    /// Gets the interface restricted to the automation type system.
+    ///
+    /// The calls made through the returned interface are dispatched through
+    /// the automation type system virtual table. If the `ComItf` does not
+    /// hold an automation pointer, the object is queried for one.
+    pub fn automation(this: &Self) -> ComResult<ComRc<T>>
+    {
+        Self::for_type_system::<AutomationTypeSystem>(this)
+    }
+
+    fn for_type_system<TS: TypeSystem>(this: &Self) -> ComResult<ComRc<T>>
+    {
+        match ComItf::ptr::<TS>(this) {
+            Some(ptr) => unsafe {
+                // The reference is added through the type system specific
+                // pointer in case the object counts references per interface.
+                let itf = ComItf::wrap(ptr);
+                itf.as_raw_iunknown().add_ref();
+                Ok(ComRc::attach(itf))
+            },
+            None => this.as_iunknown().query_interface_ts::<TS, T>(),
+        }
+    }
+
     pub fn as_rc(this: &Self) -> ComRc<T>
     {
         let iunk = this.as_raw_iunknown();
@@ -178,6 +263,49 @@ impl<T: ComInterface + ?Sized> ComItf<T>
     }
 }
 
+/// Conversions to and from the interface pointers of the `windows` crate.
+#[cfg(feature = "windows")]
+impl<T: ComInterface + ?Sized> ComItf<T>
+{
+    /// Queries the interface from a `windows` crate `IUnknown`.
+    ///
+    /// The returned `ComRc` holds its own reference to the object so the
+    /// `iunknown` remains owned by the caller.
+    pub fn from_windows(iunknown: &windows_core::IUnknown) -> ComResult<ComRc<T>>
+    {
+        use windows_core::Interface;
+
+        // Every COM interface starts with the IUnknown methods so the pointer
+        // can be used through either type system. The lone `ComItf` does not
+        // outlast the `iunknown` reference.
+        let iunk = unsafe {
+            let ptr =
+                raw::InterfacePtr::<AutomationTypeSystem, dyn IUnknown>::new(iunknown.as_raw())
+                    .ok_or(ComError::E_POINTER)?;
+            ComItf::wrap(ptr)
+        };
+        ComItf::query_interface::<T>(&iunk)
+    }
+
+    /// Converts the interface into a `windows` crate `IUnknown`.
+    ///
+    /// The returned `IUnknown` owns a new reference to the object, which is
+    /// released once the `IUnknown` is dropped.
+    pub fn to_windows(this: &Self) -> windows_core::IUnknown
+    {
+        use windows_core::Interface;
+
+        let ptr = match (this.automation_ptr, this.raw_ptr) {
+            (Some(ptr), _) => ptr.ptr.as_ptr(),
+            (None, Some(ptr)) => ptr.ptr.as_ptr(),
+            (None, None) => unreachable!("ComItf must have at least one interface pointer"),
+        };
+
+        this.as_raw_iunknown().add_ref();
+        unsafe { windows_core::IUnknown::from_raw(ptr) }
+    }
+}
+
 impl<T: ComInterface + ?Sized> ToOwned for ComItf<T>
 {
     type Owned = ComRc<T>;
@@ -281,6 +409,137 @@ where
     }
 }
 
+/// Interfaces received as an array parameter.
+///
+/// Holds a reference to each of the interfaces until dropped, which keeps
+/// them alive for the duration of the call.
+pub struct ComItfArray<T: ComInterface + ?Sized>
+{
+    items: Vec<ComItf<T>>,
+}
+
+impl<T: ComInterface + ?Sized> ComItfArray<T>
+{
+    /// Adds a reference to each of the interfaces.
+    pub fn new(items: Vec<ComItf<T>>) -> ComItfArray<T>
+    {
+        for itf in &items {
+            itf.as_raw_iunknown().add_ref();
+        }
+        ComItfArray { items }
+    }
+}
+
+impl<T: ComInterface + ?Sized> std::ops::Deref for ComItfArray<T>
+{
+    type Target = [ComItf<T>];
+
+    fn deref(&self) -> &[ComItf<T>]
+    {
+        &self.items
+    }
+}
+
+impl<T: ComInterface + ?Sized> Drop for ComItfArray<T>
+{
+    fn drop(&mut self)
+    {
+        for itf in &self.items {
+            itf.as_raw_iunknown().release();
+        }
+    }
+}
+
+impl<T: ComInterface + ?Sized> From<Vec<ComRc<T>>> for ComItfArray<T>
+{
+    /// Takes over the references of the interfaces.
+    fn from(items: Vec<ComRc<T>>) -> ComItfArray<T>
+    {
+        ComItfArray {
+            items: items.into_iter().map(ComRc::detach).collect(),
+        }
+    }
+}
+
+// Interface slices are passed as SAFEARRAY(IFoo*) in the automation type
+// system.
+unsafe impl<I: ComInterface + ?Sized> ExternType<AutomationTypeSystem> for &[ComItf<I>]
+where
+    I: ForeignType,
+{
+    type ForeignType = raw::ValueArray<AutomationTypeSystem, ComRc<I>>;
+}
+
+unsafe impl<I: ComInterface + ?Sized> ExternInput<AutomationTypeSystem> for &[ComItf<I>]
+where
+    I: ForeignType,
+{
+    type Lease = crate::variant::ValueArrayLease<AutomationTypeSystem, ComRc<I>>;
+    unsafe fn into_foreign_parameter(self) -> ComResult<(Self::ForeignType, Self::Lease)>
+    {
+        let array = raw::ValueArray::alloc(self.iter().map(ComItf::as_rc).collect())?;
+        Ok((array, crate::variant::ValueArrayLease(array)))
+    }
+
+    type Owned = ComItfArray<I>;
+    unsafe fn from_foreign_parameter(source: Self::ForeignType) -> ComResult<Self::Owned>
+    {
+        Ok(ComItfArray::from(source.to_vec()?))
+    }
+}
+
+// The raw type system passes the interface pointers and their count as
+// separate parameters.
+unsafe impl<I: ComInterface + ?Sized> ExternType<RawTypeSystem> for &[ComItf<I>]
+where
+    I: ForeignType,
+{
+    type ForeignType = *const Option<raw::InterfacePtr<RawTypeSystem, I>>;
+}
+
+unsafe impl<I: ComInterface + ?Sized> ExternCountedInput<RawTypeSystem> for &[ComItf<I>]
+where
+    I: ForeignType,
+{
+    type ForeignType = *const Option<raw::InterfacePtr<RawTypeSystem, I>>;
+
+    type Lease = Vec<Option<raw::InterfacePtr<RawTypeSystem, I>>>;
+    unsafe fn into_foreign_parameter(self) -> ComResult<(Self::ForeignType, u32, Self::Lease)>
+    {
+        let ptrs = self
+            .iter()
+            .map(|itf| match ComItf::ptr::<RawTypeSystem>(itf) {
+                Some(ptr) => Ok(Some(ptr)),
+                None => Err(ComError::E_POINTER),
+            })
+            .collect::<ComResult<Vec<_>>>()?;
+        Ok((ptrs.as_ptr(), ptrs.len() as u32, ptrs))
+    }
+
+    type Owned = ComItfArray<I>;
+    unsafe fn from_foreign_parameter(
+        source: Self::ForeignType,
+        count: u32,
+    ) -> ComResult<Self::Owned>
+    {
+        if count == 0 {
+            return Ok(ComItfArray::new(vec![]));
+        }
+        if source.is_null() {
+            return Err(ComError::E_POINTER);
+        }
+
+        let items = std::slice::from_raw_parts(source, count as usize)
+            .iter()
+            .map(|itf| match itf {
+                Some(ptr) => Ok(ComItf::wrap(*ptr)),
+                None => Err(ComError::E_POINTER),
+            })
+            .collect::<ComResult<Vec<_>>>()?;
+        Ok(ComItfArray::new(items))
+    }
+}
+
 #[cfg(windows)]
 #[link(name = "ole32")]
 extern "system" {
@@ -294,3 +553,261 @@ extern "system" {
         out: &mut raw::RawComPtr,
     ) -> crate::raw::HRESULT;
 }
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use crate::error::{ErrorStore, IErrorInfo, IErrorStore};
+    use crate::fixtures::{Greeter, IGreeter};
+    use crate::type_system::TypeSystemName;
+
+    fn interface_addr<I: ComInterface + ?Sized>(itf: &ComItf<I>) -> usize
+    {
+        match ComItf::ptr::<RawTypeSystem>(itf) {
+            Some(ptr) => ptr.ptr.as_ptr() as usize,
+            None => ComItf::ptr::<AutomationTypeSystem>(itf)
+                .unwrap()
+                .ptr
+                .as_ptr() as usize,
+        }
+    }
+
+    #[test]
+    fn as_ptr_addr_is_object_identity()
+    {
+        let object = ComBox::new(ErrorStore);
+        let store = ComRc::<dyn IErrorStore>::from(&object);
+        let support = ComItf::query_interface::<dyn ISupportErrorInfo>(&store).unwrap();
+
+        // The interface pointers themselves differ.
+        assert_ne!(interface_addr(&store), interface_addr(&support));
+
+        assert_eq!(
+            ComItf::as_ptr_addr(&store).unwrap(),
+            ComItf::as_ptr_addr(&support).unwrap()
+        );
+
+        let other = ComRc::<dyn IErrorStore>::from(ComBox::new(ErrorStore));
+        assert_ne!(
+            ComItf::as_ptr_addr(&store).unwrap(),
+            ComItf::as_ptr_addr(&other).unwrap()
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn debug_refcount_observes_references()
+    {
+        let first = ComRc::<dyn IErrorStore>::from(ComBox::new(ErrorStore));
+        let second = first.clone();
+        assert_eq!(ComItf::debug_refcount(&first), 2);
+
+        // Observing the count leaves it unchanged.
+        assert_eq!(ComItf::debug_refcount(&second), 2);
+
+        drop(second);
+        assert_eq!(ComItf::debug_refcount(&first), 1);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn vtable_ptr_is_shared_by_interface()
+    {
+        let first = ComRc::<dyn IErrorStore>::from(ComBox::new(ErrorStore));
+        let second = ComRc::<dyn IErrorStore>::from(ComBox::new(ErrorStore));
+        assert_ne!(interface_addr(&first), interface_addr(&second));
+        assert_eq!(ComItf::vtable_ptr(&first), ComItf::vtable_ptr(&second));
+
+        let support = ComItf::query_interface::<dyn ISupportErrorInfo>(&first).unwrap();
+        assert_ne!(ComItf::vtable_ptr(&first), ComItf::vtable_ptr(&support));
+    }
+
+    /// Generic client code that only knows the interface through its trait.
+    fn query_by_iid<I: ComInterface + ?Sized>(itf: &ComItf<dyn IUnknown>) -> ComResult<ComRc<I>>
+    {
+        let iunk: &dyn RawIUnknown = &**itf.as_raw_iunknown();
+        let ptr = iunk.query_interface(&I::IID)?;
+        unsafe {
+            raw::InterfacePtr::<AutomationTypeSystem, I>::new(ptr)
+                .map(|itf| ComRc::attach(ComItf::wrap(itf)))
+                .ok_or(ComError::E_POINTER)
+        }
+    }
+
+    #[test]
+    fn iid_const_is_automation_iid()
+    {
+        assert_eq!(
+            <dyn IErrorStore as ComInterface>::IID,
+            *<dyn IErrorStore as ComInterface>::iid(TypeSystemName::Automation).unwrap()
+        );
+
+        let store = ComRc::<dyn IErrorStore>::from(ComBox::new(ErrorStore));
+        let support = query_by_iid::<dyn ISupportErrorInfo>(store.as_iunknown()).unwrap();
+        assert_eq!(
+            ComItf::as_ptr_addr(&store).unwrap(),
+            ComItf::as_ptr_addr(&support).unwrap()
+        );
+
+        let err = query_by_iid::<dyn IErrorInfo>(store.as_iunknown()).unwrap_err();
+        assert_eq!(err.hresult, crate::raw::E_NOINTERFACE);
+    }
+
+    #[test]
+    fn call_through_type_system_variant()
+    {
+        let greeter = ComRc::<dyn IGreeter>::from(ComBox::new(Greeter::default()));
+
+        let raw = ComItf::raw(&greeter).unwrap();
+        assert!(ComItf::ptr::<RawTypeSystem>(&raw).is_some());
+        assert!(ComItf::ptr::<AutomationTypeSystem>(&raw).is_none());
+        assert_eq!(raw.greet("raw", 1).unwrap(), "Hello raw");
+
+        let automation = ComItf::automation(&greeter).unwrap();
+        assert!(ComItf::ptr::<RawTypeSystem>(&automation).is_none());
+        assert!(ComItf::ptr::<AutomationTypeSystem>(&automation).is_some());
+        assert_eq!(
+            automation.greet("automation", 1).unwrap(),
+            "Hello automation"
+        );
+
+        // A single type system interface can be used to get the other one.
+        let raw_again = ComItf::raw(&automation).unwrap();
+        assert_eq!(raw_again.greet("again", 1).unwrap(), "Hello again");
+    }
+
+    #[crate::com_interface]
+    trait IWriter
+    {
+        fn write(&self, value: u32, written: &mut u32) -> ComResult<()>;
+    }
+
+    #[crate::com_class(clsid = None, IWriter)]
+    struct Writer;
+
+    impl IWriter for Writer
+    {
+        fn write(&self, value: u32, written: &mut u32) -> ComResult<()>
+        {
+            if value == 0 {
+                return Err(ComError::E_INVALIDARG);
+            }
+
+            *written = value;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn explicit_out_param_with_unit_result()
+    {
+        let writer = ComRc::<dyn IWriter>::from(ComBox::new(Writer));
+
+        let mut written = 0;
+        writer.write(10, &mut written).unwrap();
+        assert_eq!(written, 10);
+
+        // The callee zeroes the parameter on failure but the caller leaves
+        // the Rust value untouched.
+        let mut written = 123;
+        let err = writer.write(0, &mut written).unwrap_err();
+        assert_eq!(err.hresult, crate::raw::E_INVALIDARG);
+        assert_eq!(written, 123);
+    }
+
+    fn ref_count<I: ComInterface + ?Sized>(itf: &ComItf<I>) -> u32
+    {
+        let iunk = itf.as_raw_iunknown();
+        iunk.add_ref();
+        iunk.release()
+    }
+
+    #[crate::com_interface]
+    trait IListeners
+    {
+        fn register(&self, listeners: &[ComItf<dyn IErrorStore>]) -> ComResult<String>;
+    }
+
+    #[crate::com_class(clsid = None, IListeners)]
+    struct Listeners;
+
+    impl IListeners for Listeners
+    {
+        fn register(&self, listeners: &[ComItf<dyn IErrorStore>]) -> ComResult<String>
+        {
+            Ok(listeners
+                .iter()
+                .map(|itf| ref_count(itf).to_string())
+                .collect::<Vec<_>>()
+                .join(","))
+        }
+    }
+
+    #[test]
+    fn interface_slice_parameter()
+    {
+        let listeners = ComRc::<dyn IListeners>::from(ComBox::new(Listeners));
+        let first = ComRc::<dyn IErrorStore>::from(ComBox::new(ErrorStore));
+        let second = ComRc::<dyn IErrorStore>::from(ComBox::new(ErrorStore));
+        let items = [ComItf { ..*first.as_ref() }, ComItf { ..*second.as_ref() }];
+
+        // The SAFEARRAY and the callee both hold a reference for the call.
+        let automation = ComItf::automation(&listeners).unwrap();
+        assert_eq!(automation.register(&items).unwrap(), "3,3");
+
+        // The raw pointer array is borrowed from the caller.
+        let raw = ComItf::raw(&listeners).unwrap();
+        assert_eq!(raw.register(&items).unwrap(), "2,2");
+        assert_eq!(raw.register(&[]).unwrap(), "");
+
+        assert_eq!(ref_count(&first), 1);
+        assert_eq!(ref_count(&second), 1);
+    }
+
+    #[cfg(feature = "windows")]
+    #[test]
+    fn windows_iunknown_round_trip()
+    {
+        use windows_core::Interface;
+
+        let ref_count = |itf: &ComItf<dyn IErrorStore>| {
+            let iunk = itf.as_raw_iunknown();
+            iunk.add_ref();
+            iunk.release()
+        };
+
+        let store = ComRc::<dyn IErrorStore>::from(ComBox::new(ErrorStore));
+        let unknown = ComItf::to_windows(&store);
+        assert_eq!(ref_count(&store), 2);
+
+        // Query for an intercom interface through the windows IUnknown.
+        let iid = <dyn ISupportErrorInfo as ComInterface>::IID;
+        let iid = windows_core::GUID {
+            data1: iid.data1,
+            data2: iid.data2,
+            data3: iid.data3,
+            data4: iid.data4,
+        };
+        let mut ptr = std::ptr::null_mut();
+        let hr = unsafe { unknown.query(&iid, &mut ptr) };
+        assert!(hr.is_ok());
+        let support = unsafe {
+            ComRc::attach(ComItf::wrap(
+                raw::InterfacePtr::<AutomationTypeSystem, dyn ISupportErrorInfo>::new(ptr).unwrap(),
+            ))
+        };
+        assert_eq!(
+            ComItf::as_ptr_addr(&store).unwrap(),
+            ComItf::as_ptr_addr(&support).unwrap()
+        );
+        drop(support);
+
+        let store_again = ComItf::<dyn IErrorStore>::from_windows(&unknown).unwrap();
+        assert_eq!(ref_count(&store), 3);
+
+        drop(unknown);
+        drop(store_again);
+        assert_eq!(ref_count(&store), 1);
+    }
+}
@@ -83,6 +83,45 @@ impl<T: ComInterface + ?Sized> ComRc<T>
     {
         ComRc::attach(ComItf::wrap(ptr))
     }
+
+    /// Clones the reference, failing if the object is detected to be in an
+    /// invalid state.
+    ///
+    /// Debug builds detect references to objects that have already been
+    /// released, in which case `E_POINTER` is returned. In release builds
+    /// the call behaves like the infallible `clone`.
+    pub fn try_clone(this: &Self) -> ComResult<ComRc<T>>
+    {
+        let count = this.as_raw_iunknown().add_ref();
+
+        // A live reference held by `this` means the count must be at least
+        // two after the increment. The object has already been destroyed
+        // otherwise so the new reference must not be released either.
+        if cfg!(debug_assertions) && count < 2 {
+            return Err(ComError::E_POINTER);
+        }
+
+        Ok(unsafe { ComRc::attach(ComItf { ..this.itf }) })
+    }
+
+    /// Detaches the interface pointer of a specific type system.
+    ///
+    /// The `ComRc` values acquired from foreign code hold only the pointer of
+    /// the type system they were received through. In that case the pointer
+    /// is queried from the object. Either way the returned pointer owns
+    /// exactly one reference and the original reference is released in case
+    /// of an error.
+    fn detach_ts<TS: TypeSystem>(rc: ComRc<T>) -> ComResult<raw::InterfacePtr<TS, T>>
+    {
+        let rc = match ComItf::ptr::<TS>(&rc) {
+            Some(_) => rc,
+            None => rc.as_iunknown().query_interface_ts::<TS, T>()?,
+        };
+
+        let ptr = ComItf::ptr::<TS>(&rc).ok_or(ComError::E_POINTER)?;
+        ComRc::detach(rc);
+        Ok(ptr)
+    }
 }
 
 #[cfg(windows)]
@@ -205,7 +244,7 @@ where
 {
     fn into_foreign_output(self) -> ComResult<Self::ForeignType>
     {
-        Ok(ComItf::ptr(&ComRc::detach(self)))
+        Ok(Some(ComRc::detach_ts(self)?))
     }
 
     unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
@@ -251,10 +290,7 @@ where
     {
         match self {
             None => Ok(None),
-            Some(rc) => match ComItf::ptr::<TS>(&ComRc::detach(rc)) {
-                None => Err(ComError::E_POINTER),
-                Some(ptr) => Ok(Some(ptr)),
-            },
+            Some(rc) => Ok(Some(ComRc::detach_ts(rc)?)),
         }
     }
 
@@ -266,3 +302,302 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use crate::error::{ErrorStore, IErrorStore};
+    use crate::type_system::{AutomationTypeSystem, RawTypeSystem};
+
+    fn ref_count<I: ComInterface + ?Sized>(itf: &ComItf<I>) -> u32
+    {
+        let iunk = itf.as_raw_iunknown();
+        iunk.add_ref();
+        iunk.release()
+    }
+
+    #[test]
+    fn output_transfers_single_reference()
+    {
+        let store = ComRc::<dyn IErrorStore>::from(ComBox::new(ErrorStore));
+        let keep = store.clone();
+        assert_eq!(ref_count(&keep), 2);
+
+        let ptr =
+            <ComRc<dyn IErrorStore> as ExternOutput<AutomationTypeSystem>>::into_foreign_output(
+                store,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(ref_count(&keep), 2);
+
+        drop(unsafe { ComRc::wrap(ptr) });
+        assert_eq!(ref_count(&keep), 1);
+    }
+
+    #[test]
+    fn output_queries_missing_type_system()
+    {
+        // Interfaces queried within a type system hold only the pointer of that
+        // type system, similar to the interfaces received from foreign code.
+        let keep = ComRc::<dyn IErrorStore>::from(ComBox::new(ErrorStore));
+        let store = keep
+            .as_iunknown()
+            .query_interface_ts::<AutomationTypeSystem, dyn IErrorStore>()
+            .unwrap();
+        assert!(ComItf::ptr::<RawTypeSystem>(&store).is_none());
+        assert_eq!(ref_count(&keep), 2);
+
+        let ptr =
+            <ComRc<dyn IErrorStore> as ExternOutput<RawTypeSystem>>::into_foreign_output(store)
+                .unwrap()
+                .unwrap();
+        assert_eq!(ref_count(&keep), 2);
+
+        drop(unsafe { ComRc::wrap(ptr) });
+        assert_eq!(ref_count(&keep), 1);
+    }
+
+    #[test]
+    fn try_clone_adds_reference()
+    {
+        let store = ComRc::<dyn IErrorStore>::from(ComBox::new(ErrorStore));
+        let clone = ComRc::try_clone(&store).unwrap();
+        assert_eq!(ref_count(&store), 2);
+
+        drop(clone);
+        assert_eq!(ref_count(&store), 1);
+    }
+
+    #[crate::com_interface]
+    trait IStoreSource
+    {
+        fn store(&self, kind: u32) -> ComResult<Option<ComRc<dyn IErrorStore>>>;
+    }
+
+    #[crate::com_class(clsid = None, IStoreSource)]
+    struct StoreSource(ComRc<dyn IErrorStore>);
+
+    impl IStoreSource for StoreSource
+    {
+        fn store(&self, kind: u32) -> ComResult<Option<ComRc<dyn IErrorStore>>>
+        {
+            match kind {
+                0 => Ok(None),
+                1 => Ok(Some(self.0.clone())),
+                _ => Err(ComError::E_INVALIDARG),
+            }
+        }
+    }
+
+    #[test]
+    fn optional_interface_output()
+    {
+        let store = ComRc::<dyn IErrorStore>::from(ComBox::new(ErrorStore));
+        let source = ComRc::<dyn IStoreSource>::from(ComBox::new(StoreSource(store.clone())));
+        assert_eq!(ref_count(&store), 2);
+
+        // Call through the vtable to see the HRESULT and the raw pointer. The
+        // output starts as a borrowed pointer to ensure the errors clear it.
+        let itf = ComItf::ptr::<AutomationTypeSystem>(&source).unwrap();
+        let call = |kind| unsafe {
+            let vtbl = &**(itf.ptr.as_ptr() as *const *const __IStoreSourceAutomationVTable);
+            let mut out = ComItf::ptr::<AutomationTypeSystem>(&store);
+            let hr = (vtbl.store)(itf.ptr.as_ptr(), kind, &mut out);
+            (hr, out)
+        };
+
+        let (hr, out) = call(1);
+        assert_eq!(hr, raw::S_OK);
+        assert_eq!(ref_count(&store), 3);
+        drop(unsafe { ComRc::wrap(out.unwrap()) });
+        assert_eq!(ref_count(&store), 2);
+
+        let (hr, out) = call(0);
+        assert_eq!(hr, raw::S_FALSE);
+        assert!(out.is_none());
+        assert_eq!(ref_count(&store), 2);
+
+        let (hr, out) = call(2);
+        assert_eq!(hr, raw::E_INVALIDARG);
+        assert!(out.is_none());
+        assert_eq!(ref_count(&store), 2);
+
+        // The client side turns the results back into the Rust values.
+        let found = source.store(1).unwrap().unwrap();
+        assert_eq!(ref_count(&store), 3);
+        drop(found);
+        assert!(source.store(0).unwrap().is_none());
+        assert_eq!(source.store(2).unwrap_err().hresult, raw::E_INVALIDARG);
+        assert_eq!(ref_count(&store), 2);
+    }
+
+    #[crate::com_interface]
+    trait IStoreLookup
+    {
+        fn code(&self, kind: u32) -> ComResult<Option<u32>>;
+    }
+
+    #[crate::com_class(clsid = None, IStoreLookup)]
+    struct StoreLookup;
+
+    impl IStoreLookup for StoreLookup
+    {
+        fn code(&self, kind: u32) -> ComResult<Option<u32>>
+        {
+            match kind {
+                0 => Ok(None),
+                u32::MAX => Err(ComError::E_INVALIDARG),
+                _ => Ok(Some(kind * 10)),
+            }
+        }
+    }
+
+    #[test]
+    fn option_output()
+    {
+        let lookup = ComRc::<dyn IStoreLookup>::from(ComBox::new(StoreLookup));
+
+        // The absent values are zeroed and reported with S_FALSE.
+        let itf = ComItf::ptr::<AutomationTypeSystem>(&lookup).unwrap();
+        let vtbl = unsafe { &**(itf.ptr.as_ptr() as *const *const __IStoreLookupAutomationVTable) };
+        let code = |kind| unsafe {
+            let mut out = 1;
+            let hr = (vtbl.code)(itf.ptr.as_ptr(), kind, &mut out);
+            (hr, out)
+        };
+        assert_eq!(code(2), (raw::S_OK, 20));
+        assert_eq!(code(0), (raw::S_FALSE, 0));
+        assert_eq!(code(u32::MAX), (raw::E_INVALIDARG, 0));
+
+        // The client side turns S_FALSE back into None and keeps the errors.
+        assert_eq!(lookup.code(3).unwrap(), Some(30));
+        assert_eq!(lookup.code(0).unwrap(), None);
+        assert_eq!(
+            lookup.code(u32::MAX).unwrap_err().hresult,
+            raw::E_INVALIDARG
+        );
+    }
+
+    #[test]
+    fn bare_option_output()
+    {
+        use crate::attributes::ComInterfaceVariant;
+
+        let store = ComRc::<dyn IErrorStore>::from(ComBox::new(ErrorStore));
+        let labels = ComRc::<StoreLabels>::from(ComBox::new(StoreLabels(store.clone())));
+        assert_eq!(ref_count(&store), 2);
+
+        // The impl interfaces have no Rust callers so they may return the
+        // bare Option.
+        let itf = ComItf::ptr::<RawTypeSystem>(&labels).unwrap();
+        let vtbl = unsafe {
+            &**(itf.ptr.as_ptr()
+                as *const *const <StoreLabels as ComInterfaceVariant<RawTypeSystem>>::VTable)
+        };
+        let code = |kind| unsafe {
+            let mut out = 1;
+            let hr = (vtbl.code)(itf.ptr.as_ptr(), kind, &mut out);
+            (hr, out)
+        };
+        assert_eq!(code(2), (raw::S_OK, 20));
+        assert_eq!(code(0), (raw::S_FALSE, 0));
+
+        // The absent interfaces are null pointers.
+        let find = |kind| unsafe {
+            let mut out = ComItf::ptr::<RawTypeSystem>(&store);
+            let hr = (vtbl.find)(itf.ptr.as_ptr(), kind, &mut out);
+            (hr, out)
+        };
+        let (hr, out) = find(1);
+        assert_eq!(hr, raw::S_OK);
+        assert_eq!(ref_count(&store), 3);
+        drop(unsafe { ComRc::wrap(out.unwrap()) });
+
+        let (hr, out) = find(0);
+        assert_eq!(hr, raw::S_FALSE);
+        assert!(out.is_none());
+        assert_eq!(ref_count(&store), 2);
+    }
+
+    #[crate::com_class(clsid = None, Self)]
+    struct StoreLabels(ComRc<dyn IErrorStore>);
+
+    #[crate::com_interface]
+    impl StoreLabels
+    {
+        fn code(&self, kind: u32) -> Option<u32>
+        {
+            match kind {
+                0 => None,
+                _ => Some(kind * 10),
+            }
+        }
+
+        fn find(&self, kind: u32) -> Option<ComRc<dyn IErrorStore>>
+        {
+            match kind {
+                0 => None,
+                _ => Some(self.0.clone()),
+            }
+        }
+
+        fn label(&self, kind: u32) -> (u32, String)
+        {
+            (kind * 10, format!("store {}", kind))
+        }
+
+        fn labeled(&self) -> (ComRc<dyn IErrorStore>, String)
+        {
+            (self.0.clone(), "default".to_string())
+        }
+    }
+
+    #[test]
+    fn tuple_output()
+    {
+        use crate::attributes::ComInterfaceVariant;
+
+        let store = ComRc::<dyn IErrorStore>::from(ComBox::new(ErrorStore));
+        let labels = ComRc::<StoreLabels>::from(ComBox::new(StoreLabels(store.clone())));
+        assert_eq!(ref_count(&store), 2);
+
+        // Each field is written to its own out parameter.
+        let itf = ComItf::ptr::<RawTypeSystem>(&labels).unwrap();
+        let vtbl = unsafe {
+            &**(itf.ptr.as_ptr()
+                as *const *const <StoreLabels as ComInterfaceVariant<RawTypeSystem>>::VTable)
+        };
+        let (hr, code, label) = unsafe {
+            let mut code = 0;
+            let mut label = std::ptr::null_mut();
+            let hr = (vtbl.label)(itf.ptr.as_ptr(), 2, &mut code, &mut label);
+            (
+                hr,
+                code,
+                <String as ExternOutput<RawTypeSystem>>::from_foreign_output(label),
+            )
+        };
+        assert_eq!(hr, raw::S_OK);
+        assert_eq!(code, 20);
+        assert_eq!(label.unwrap(), "store 2");
+
+        // The interface fields transfer their reference to the caller.
+        let (hr, found, label) = unsafe {
+            let mut found = None;
+            let mut label = std::ptr::null_mut();
+            let hr = (vtbl.labeled)(itf.ptr.as_ptr(), &mut found, &mut label);
+            (
+                hr,
+                found,
+                <String as ExternOutput<RawTypeSystem>>::from_foreign_output(label),
+            )
+        };
+        assert_eq!(hr, raw::S_OK);
+        assert_eq!(label.unwrap(), "default");
+        assert_eq!(ref_count(&store), 3);
+        drop(unsafe { ComRc::wrap(found.unwrap()) });
+        assert_eq!(ref_count(&store), 2);
+    }
+}
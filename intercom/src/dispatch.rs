@@ -0,0 +1,453 @@
+//! Serves the `IDispatch` calls of the dual interfaces.
+//!
+//! The scripting clients resolve the members by name with `GetIDsOfNames`
+//! and call them with `Invoke`, passing the arguments as `VARIANT`s. The
+//! calls are dispatched to the virtual table methods of the dual interface
+//! through the `ComDispatch` implementation the `#[com_interface(dual)]`
+//! generates.
+
+use crate::attributes::{ComClass, ComClassInterface, ComDispatch};
+use crate::raw::{RawComPtr, HRESULT};
+use crate::type_system::{AutomationTypeSystem, ExternOutput, TypeSystem};
+use crate::{ComItf, IntercomString, Variant, REFIID};
+
+pub mod raw
+{
+    use crate::type_system::AutomationTypeSystem;
+
+    /// DISPID returned by `GetIDsOfNames` for the unknown names.
+    pub const DISPID_UNKNOWN: i32 = -1;
+
+    /// DISPID of the named argument holding the value of a property put.
+    pub const DISPID_PROPERTYPUT: i32 = -3;
+
+    /// The member is called as a method.
+    pub const DISPATCH_METHOD: u16 = 0x1;
+
+    /// The member is called as a property getter.
+    pub const DISPATCH_PROPERTYGET: u16 = 0x2;
+
+    /// The member is called as a property setter.
+    pub const DISPATCH_PROPERTYPUT: u16 = 0x4;
+
+    /// The member is called as a property setter taking a reference.
+    pub const DISPATCH_PROPERTYPUTREF: u16 = 0x8;
+
+    /// Arguments of an `IDispatch::Invoke` call.
+    ///
+    /// The arguments are stored in the reverse order. The named arguments
+    /// come first.
+    #[repr(C)]
+    #[allow(non_snake_case)]
+    pub struct DISPPARAMS
+    {
+        pub rgvarg: *mut crate::raw::Variant<AutomationTypeSystem>,
+        pub rgdispidNamedArgs: *mut i32,
+        pub cArgs: u32,
+        pub cNamedArgs: u32,
+    }
+
+    impl crate::type_system::ForeignType for DISPPARAMS
+    {
+        fn type_name() -> &'static str
+        {
+            "DISPPARAMS"
+        }
+    }
+}
+
+/// Reports the type info count, which is zero as the members are resolved by
+/// name only.
+#[doc(hidden)]
+pub unsafe extern "system" fn get_type_info_count<I, S, TS>(
+    _self_vtable: RawComPtr,
+    count: *mut u32,
+) -> HRESULT
+where
+    I: ?Sized,
+    S: ComClassInterface<I, TS> + ComClass,
+    TS: TypeSystem,
+{
+    if count.is_null() {
+        return crate::raw::E_POINTER;
+    }
+    *count = 0;
+    crate::raw::S_OK
+}
+
+#[doc(hidden)]
+pub unsafe extern "system" fn get_type_info<I, S, TS>(
+    _self_vtable: RawComPtr,
+    _index: u32,
+    _lcid: u32,
+    type_info: *mut RawComPtr,
+) -> HRESULT
+where
+    I: ?Sized,
+    S: ComClassInterface<I, TS> + ComClass,
+    TS: TypeSystem,
+{
+    if type_info.is_null() {
+        return crate::raw::E_POINTER;
+    }
+    *type_info = std::ptr::null_mut();
+    crate::raw::DISP_E_BADINDEX
+}
+
+/// Resolves the DISPID of the member name.
+///
+/// The names following the member name name its parameters. The named
+/// parameters are not supported and resolve to `DISPID_UNKNOWN`.
+#[doc(hidden)]
+pub unsafe extern "system" fn get_ids_of_names<I, S, TS>(
+    _self_vtable: RawComPtr,
+    _riid: REFIID,
+    names: *mut *mut u16,
+    count: u32,
+    _lcid: u32,
+    dispids: *mut i32,
+) -> HRESULT
+where
+    I: ComDispatch + ?Sized,
+    S: ComClassInterface<I, TS> + ComClass,
+    TS: TypeSystem,
+{
+    if count > 0 && (names.is_null() || dispids.is_null()) {
+        return crate::raw::E_POINTER;
+    }
+
+    let mut hr = crate::raw::S_OK;
+    for idx in 0..count as usize {
+        let name = read_name(*names.add(idx));
+        let dispid = match idx {
+            0 => I::dispid_of_name(&name),
+            _ => None,
+        };
+        *dispids.add(idx) = dispid.unwrap_or(crate::raw::DISPID_UNKNOWN);
+        if dispid.is_none() {
+            hr = crate::raw::DISP_E_UNKNOWNNAME;
+        }
+    }
+    hr
+}
+
+/// Calls the member of the dual interface.
+///
+/// The errors of the member are described in the `EXCEPINFO`. The errors of
+/// the dispatch itself, such as a wrong number of arguments, are returned as
+/// the `DISP_E_*` codes.
+#[doc(hidden)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "system" fn invoke<I, S, TS>(
+    self_vtable: RawComPtr,
+    dispid: i32,
+    _riid: REFIID,
+    _lcid: u32,
+    flags: u16,
+    params: *mut crate::raw::DISPPARAMS,
+    result: *mut crate::raw::Variant<AutomationTypeSystem>,
+    excep_info: *mut crate::raw::EXCEPINFO,
+    arg_err: *mut u32,
+) -> HRESULT
+where
+    I: ComDispatch + ?Sized,
+    S: ComClassInterface<I, TS> + ComClass,
+    TS: TypeSystem,
+{
+    log::trace!(
+        "[{:p}] Serving {}::invoke({})",
+        self_vtable,
+        std::any::type_name::<S>(),
+        dispid
+    );
+    if params.is_null() {
+        return crate::raw::E_POINTER;
+    }
+    let params = &*params;
+
+    // Only the value of a property put may be passed as a named argument.
+    let named_args = match params.cNamedArgs {
+        0 => &[][..],
+        n => std::slice::from_raw_parts(params.rgdispidNamedArgs, n as usize),
+    };
+    if named_args
+        .iter()
+        .any(|&d| d != crate::raw::DISPID_PROPERTYPUT)
+    {
+        return crate::raw::DISP_E_NONAMEDARGS;
+    }
+
+    let mut args = Vec::with_capacity(params.cArgs as usize);
+    for idx in (0..params.cArgs).rev() {
+        match read_arg(*params.rgvarg.add(idx as usize)) {
+            Ok(arg) => args.push(arg),
+            Err(_) => {
+                if !arg_err.is_null() {
+                    *arg_err = idx;
+                }
+                return crate::raw::DISP_E_TYPEMISMATCH;
+            }
+        }
+    }
+
+    let mut itf = match crate::raw::InterfacePtr::<TS, I>::new(self_vtable) {
+        Some(ptr) => ComItf::wrap(ptr),
+        None => return crate::raw::E_POINTER,
+    };
+    let value = I::invoke(&mut itf, dispid, flags, args)
+        .and_then(<Variant as ExternOutput<AutomationTypeSystem>>::into_foreign_output);
+    match value {
+        Ok(value) => {
+            if !result.is_null() {
+                *result = value;
+            }
+            crate::raw::S_OK
+        }
+        Err(err) => match err.hresult {
+            crate::raw::DISP_E_MEMBERNOTFOUND
+            | crate::raw::DISP_E_BADPARAMCOUNT
+            | crate::raw::DISP_E_TYPEMISMATCH => err.hresult,
+            _ => {
+                if !excep_info.is_null() {
+                    *excep_info = err.to_excep_info();
+                }
+                crate::raw::DISP_E_EXCEPTION
+            }
+        },
+    }
+}
+
+/// Reads the null terminated name.
+unsafe fn read_name(name: *const u16) -> String
+{
+    if name.is_null() {
+        return String::new();
+    }
+    let len = (0..).take_while(|&i| *name.add(i) != 0).count();
+    String::from_utf16_lossy(std::slice::from_raw_parts(name, len))
+}
+
+/// Reads the argument without taking it over from the caller.
+///
+/// The strings and the interfaces are copied as the caller releases the
+/// originals after the call. The `VT_BYREF | VT_VARIANT` arguments are read
+/// from the variant they refer to.
+unsafe fn read_arg(arg: crate::raw::Variant<AutomationTypeSystem>) -> crate::ComResult<Variant>
+{
+    Ok(match Variant::from_raw(arg)? {
+        Variant::String(IntercomString::BString(bstr)) => {
+            let copy = bstr.clone();
+            std::mem::forget(bstr);
+            Variant::String(IntercomString::BString(copy))
+        }
+        Variant::IUnknown(rc) => {
+            let copy = rc.clone();
+            std::mem::forget(rc);
+            Variant::IUnknown(copy)
+        }
+        other => other,
+    })
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use crate::fixtures::{Greeter, IGreeter};
+    use crate::interfaces::RawIDispatch;
+    use crate::{ComBox, ComRc, IUnknown};
+    use std::convert::TryFrom;
+
+    fn dispid(dispatch: &ComRc<dyn RawIDispatch>, name: &str) -> (HRESULT, i32)
+    {
+        let mut name = name.encode_utf16().chain(Some(0)).collect::<Vec<_>>();
+        let mut names = [name.as_mut_ptr()];
+        let mut dispid = 0;
+        let hr = unsafe {
+            dispatch.get_ids_of_names(std::ptr::null(), names.as_mut_ptr(), 1, 0, &mut dispid)
+        };
+        (hr, dispid)
+    }
+
+    fn invoke(
+        dispatch: &ComRc<dyn RawIDispatch>,
+        dispid: i32,
+        flags: u16,
+        args: Vec<Variant>,
+    ) -> (HRESULT, Variant, crate::raw::EXCEPINFO)
+    {
+        // The arguments are passed in the reverse order.
+        let mut args = args
+            .into_iter()
+            .rev()
+            .map(|arg| crate::raw::Variant::<AutomationTypeSystem>::try_from(arg).unwrap())
+            .collect::<Vec<_>>();
+        unsafe {
+            let (hr, result, excep_info) = invoke_raw(dispatch, dispid, flags, &mut args);
+            for arg in args {
+                Variant::from_raw(arg).unwrap();
+            }
+            (hr, Variant::from_raw(result).unwrap(), excep_info)
+        }
+    }
+
+    unsafe fn invoke_raw(
+        dispatch: &ComRc<dyn RawIDispatch>,
+        dispid: i32,
+        flags: u16,
+        args: &mut [crate::raw::Variant<AutomationTypeSystem>],
+    ) -> (
+        HRESULT,
+        crate::raw::Variant<AutomationTypeSystem>,
+        crate::raw::EXCEPINFO,
+    )
+    {
+        let mut named = [crate::raw::DISPID_PROPERTYPUT];
+        let mut params = crate::raw::DISPPARAMS {
+            rgvarg: args.as_mut_ptr(),
+            rgdispidNamedArgs: named.as_mut_ptr(),
+            cArgs: args.len() as u32,
+            cNamedArgs: match flags {
+                crate::raw::DISPATCH_PROPERTYPUT => 1,
+                _ => 0,
+            },
+        };
+        let mut result = Default::default();
+        let mut excep_info: crate::raw::EXCEPINFO = std::mem::zeroed();
+        let mut arg_err = 0;
+        let hr = dispatch.invoke(
+            dispid,
+            std::ptr::null(),
+            0,
+            flags,
+            &mut params,
+            &mut result,
+            &mut excep_info,
+            &mut arg_err,
+        );
+        (hr, result, excep_info)
+    }
+
+    #[test]
+    fn dual_interface_serves_idispatch()
+    {
+        let greeter = ComRc::<dyn IGreeter>::from(ComBox::new(Greeter::default()));
+        let dispatch = ComItf::query_interface::<dyn RawIDispatch>(&greeter).unwrap();
+
+        let (hr, greet) = dispid(&dispatch, "Greet");
+        assert_eq!((hr, greet), (crate::raw::S_OK, 1));
+        let (hr, value, _) = invoke(
+            &dispatch,
+            greet,
+            crate::raw::DISPATCH_METHOD,
+            vec![Variant::from("World".to_string()), Variant::from(2u32)],
+        );
+        assert_eq!(hr, crate::raw::S_OK);
+        assert_eq!(String::try_from(value).unwrap(), "Hello World, Hello World");
+
+        // The accessors of the property share the DISPID.
+        let (hr, volume) = dispid(&dispatch, "volume");
+        assert_eq!((hr, volume), (crate::raw::S_OK, 2));
+        let put = invoke(
+            &dispatch,
+            volume,
+            crate::raw::DISPATCH_PROPERTYPUT,
+            vec![7u32.into()],
+        );
+        assert_eq!(put.0, crate::raw::S_OK);
+        let (hr, value, _) = invoke(&dispatch, volume, crate::raw::DISPATCH_PROPERTYGET, vec![]);
+        assert_eq!(hr, crate::raw::S_OK);
+        assert_eq!(u32::try_from(value).unwrap(), 7);
+        assert_eq!(greeter.get_volume(), 7);
+
+        let (hr, _, _) = invoke(&dispatch, greet, crate::raw::DISPATCH_METHOD, vec![]);
+        assert_eq!(hr, crate::raw::DISP_E_BADPARAMCOUNT);
+        let (hr, _, _) = invoke(&dispatch, 42, crate::raw::DISPATCH_METHOD, vec![]);
+        assert_eq!(hr, crate::raw::DISP_E_MEMBERNOTFOUND);
+        let (hr, dispid) = dispid(&dispatch, "Shout");
+        assert_eq!(
+            (hr, dispid),
+            (crate::raw::DISP_E_UNKNOWNNAME, crate::raw::DISPID_UNKNOWN)
+        );
+    }
+
+    #[test]
+    fn dispatch_errors_are_described()
+    {
+        let greeter = ComRc::<dyn IGreeter>::from(ComBox::new(Greeter::default()));
+        let dispatch = ComItf::query_interface::<dyn RawIDispatch>(&greeter).unwrap();
+
+        let (_, hush) = dispid(&dispatch, "hush");
+        let (hr, _, excep_info) = invoke(&dispatch, hush, crate::raw::DISPATCH_METHOD, vec![]);
+        assert_eq!(hr, crate::raw::DISP_E_EXCEPTION);
+        assert_eq!(excep_info.scode, crate::raw::E_ACCESSDENIED);
+        let description =
+            unsafe { crate::BString::from_ptr(excep_info.bstrDescription.0) }.to_string();
+        assert_eq!(description.unwrap(), "Too loud");
+    }
+
+    #[test]
+    fn variant_references_are_read_through()
+    {
+        use crate::raw::{var_type, VariantData, VariantType};
+
+        let greeter = ComRc::<dyn IGreeter>::from(ComBox::new(Greeter::default()));
+        let dispatch = ComItf::query_interface::<dyn RawIDispatch>(&greeter).unwrap();
+        let (_, greet) = dispid(&dispatch, "greet");
+
+        let mut values = [
+            crate::raw::Variant::try_from(Variant::from(2u32)).unwrap(),
+            crate::raw::Variant::try_from(Variant::from("VBScript".to_string())).unwrap(),
+        ];
+        let mut args = values
+            .iter_mut()
+            .map(|value| {
+                crate::raw::Variant::new(
+                    VariantType::new(var_type::BYREF | var_type::VARIANT),
+                    VariantData { pvarVal: value },
+                )
+            })
+            .collect::<Vec<_>>();
+        unsafe {
+            let (hr, result, _) =
+                invoke_raw(&dispatch, greet, crate::raw::DISPATCH_METHOD, &mut args);
+            assert_eq!(hr, crate::raw::S_OK);
+            let value = String::try_from(Variant::from_raw(result).unwrap()).unwrap();
+            assert_eq!(value, "Hello VBScript, Hello VBScript");
+
+            // The referenced string is still owned by the caller.
+            let name = String::try_from(Variant::from_raw(values[1]).unwrap()).unwrap();
+            assert_eq!(name, "VBScript");
+        }
+    }
+
+    #[test]
+    fn dispatch_arguments_are_read_as_interfaces()
+    {
+        use crate::raw::{var_type, VariantData, VariantType};
+
+        let greeter = ComRc::<dyn IGreeter>::from(ComBox::new(Greeter::default()));
+        let unknown = ComItf::query_interface::<dyn IUnknown>(&greeter).unwrap();
+        let mut ptr = ComItf::ptr::<AutomationTypeSystem>(&unknown);
+
+        let by_value = crate::raw::Variant::new(
+            VariantType::new(var_type::DISPATCH),
+            VariantData { punkVal: ptr },
+        );
+        let by_ref = crate::raw::Variant::new(
+            VariantType::new(var_type::BYREF | var_type::DISPATCH),
+            VariantData { ppunkVal: &mut ptr },
+        );
+        for arg in [by_value, by_ref] {
+            let rc = match unsafe { read_arg(arg) }.unwrap() {
+                Variant::IUnknown(rc) => rc,
+                other => panic!("Expected IUnknown, got {:?}", other),
+            };
+            let itf = ComItf::query_interface::<dyn IGreeter>(&rc).unwrap();
+            assert_eq!(itf.greet("JScript", 1).unwrap(), "Hello JScript");
+        }
+
+        // The references held by the caller are left intact.
+        assert_eq!(greeter.greet("COM", 1).unwrap(), "Hello COM");
+    }
+}
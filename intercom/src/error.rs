@@ -1,14 +1,19 @@
+use std::any::Any;
+use std::cell::RefCell;
 use std::convert::TryFrom;
 use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use super::*;
+use crate::agile::AgileRef;
 use crate::attributes::{ComInterface, ComInterfaceVariant};
+use crate::interfaces::IAgileObject;
 use crate::type_system::{
     AutomationTypeSystem, ExternOutput, ExternType, RawTypeSystem, TypeSystem,
 };
 
 /// Error structure containing the available information on a COM error.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ComError
 {
     /// `HRESULT` that triggered the error.
@@ -16,6 +21,14 @@ pub struct ComError
 
     /// Possible detailed error info.
     pub error_info: Option<ErrorInfo>,
+
+    /// Custom `IErrorInfo` object that should be used instead of constructing
+    /// one from the `error_info`.
+    ///
+    /// `ComError` must remain `Send` and `Sync` so that it can be used with
+    /// the standard error types. Only the agile objects are kept for this
+    /// reason. The object is boxed as it's rarely present.
+    error_info_object: Option<Box<AgileRef<dyn IErrorInfo>>>,
 }
 
 impl std::error::Error for ComError
@@ -38,9 +51,11 @@ impl std::fmt::Display for ComError
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
     {
+        // The HRESULT is included even with a message so the errors remain
+        // identifiable once they are converted into other error types.
         match self.description() {
-            Some(desc) => write!(f, "{}", desc),
-            None => write!(f, "COM error ({:#x})", self.hresult.hr),
+            Some(desc) if !desc.is_empty() => write!(f, "{}: {}", self.hresult, desc),
+            _ => write!(f, "{}", self.hresult),
         }
     }
 }
@@ -62,6 +77,7 @@ unsafe impl<TS: TypeSystem> ExternOutput<TS> for ComError
         Ok(ComError {
             hresult: source,
             error_info: None,
+            error_info_object: None,
         })
     }
 }
@@ -86,6 +102,66 @@ unsafe impl<TS: TypeSystem> ExternOutput<TS> for std::io::Error
     }
 }
 
+unsafe impl<TS: TypeSystem> ExternType<TS> for (raw::HRESULT, String)
+{
+    type ForeignType = raw::HRESULT;
+}
+
+unsafe impl<TS: TypeSystem> ExternOutput<TS> for (raw::HRESULT, String)
+{
+    fn into_foreign_output(self) -> ComResult<Self::ForeignType>
+    {
+        let com_error: ComError = ComError::from(self);
+        <ComError as ExternOutput<TS>>::into_foreign_output(com_error)
+    }
+
+    unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
+    {
+        let com_error: ComError = <ComError as ExternOutput<TS>>::from_foreign_output(source)?;
+        Ok(Self::from(com_error))
+    }
+}
+
+unsafe impl<TS: TypeSystem, I: ComInterface + ?Sized> ExternType<TS> for ErrorObject<I>
+{
+    type ForeignType = raw::HRESULT;
+}
+
+unsafe impl<TS: TypeSystem, I: ComInterface + ?Sized> ExternOutput<TS> for ErrorObject<I>
+{
+    fn into_foreign_output(self) -> ComResult<Self::ForeignType>
+    {
+        let com_error: ComError = ComError::from(self);
+        <ComError as ExternOutput<TS>>::into_foreign_output(com_error)
+    }
+
+    unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
+    {
+        let com_error: ComError = <ComError as ExternOutput<TS>>::from_foreign_output(source)?;
+        Ok(Self::from(com_error))
+    }
+}
+
+unsafe impl<TS: TypeSystem> ExternType<TS> for Vec<ComError>
+{
+    type ForeignType = raw::HRESULT;
+}
+
+unsafe impl<TS: TypeSystem> ExternOutput<TS> for Vec<ComError>
+{
+    fn into_foreign_output(self) -> ComResult<Self::ForeignType>
+    {
+        let com_error: ComError = ComError::from(self);
+        <ComError as ExternOutput<TS>>::into_foreign_output(com_error)
+    }
+
+    unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
+    {
+        let com_error: ComError = <ComError as ExternOutput<TS>>::from_foreign_output(source)?;
+        Ok(Self::from(com_error))
+    }
+}
+
 impl ComError
 {
     /// Constructs a new `ComError` from a `HRESULT` code.
@@ -94,6 +170,7 @@ impl ComError
         ComError {
             hresult,
             error_info: None,
+            error_info_object: None,
         }
     }
 
@@ -103,68 +180,224 @@ impl ComError
         ComError {
             hresult,
             error_info: Some(ErrorInfo::new(description)),
+            error_info_object: None,
         }
     }
 
+    /// Constructs a new `ComError` with a custom `IErrorInfo` object.
+    ///
+    /// The object is passed to `SetErrorInfo` as is when the error is
+    /// returned from a COM method instead of constructing the default error
+    /// info object. The object must implement `IAgileObject` for this as the
+    /// error may move between threads. Only the error info fields are kept
+    /// from the other objects.
+    pub fn with_error_info(hresult: raw::HRESULT, info: ComRc<dyn IErrorInfo>) -> ComError
+    {
+        ComError {
+            hresult,
+            error_info: read_error_info(&info),
+            error_info_object: AgileRef::new(&info).ok().map(Box::new),
+        }
+    }
+
+    /// Queries the error info object of the error for a typed error
+    /// interface.
+    ///
+    /// The object is available for the errors constructed with
+    /// `with_error_info` and for the errors returned from COM methods that
+    /// provided error info.
+    pub fn error_object<I: ComInterface + ?Sized>(&self) -> Option<ComRc<I>>
+    {
+        let info = self.error_info_object.as_ref()?.get();
+        ComItf::query_interface::<I>(&info).ok()
+    }
+
     pub fn with_message<S: Into<String>>(mut self, msg: S) -> Self
     {
         self.error_info = Some(ErrorInfo::new(msg.into()));
+        self.error_info_object = None;
+        self
+    }
+
+    /// Adds context to the error message while keeping the `HRESULT`.
+    ///
+    /// The context is prepended to the existing description, separated with
+    /// `": "`, so that the outermost layer reads first. Other error info
+    /// fields are preserved. A custom `IErrorInfo` object is discarded as
+    /// its description would no longer match.
+    pub fn context<S: Into<String>>(mut self, msg: S) -> Self
+    {
+        let msg = msg.into();
+        match &mut self.error_info {
+            Some(info) if !info.description.is_empty() => {
+                info.description = format!("{}: {}", msg, info.description);
+            }
+            Some(info) => info.description = msg,
+            None => self.error_info = Some(ErrorInfo::new(msg)),
+        }
+        self.error_info_object = None;
+        self
+    }
+
+    /// Marks the error as retryable.
+    ///
+    /// The flag tells the client that the call may succeed if attempted
+    /// again. It is transported through the `IErrorDetail` interface of the
+    /// error info so a custom `IErrorInfo` object is discarded.
+    pub fn retryable(mut self) -> Self
+    {
+        self.error_info
+            .get_or_insert_with(|| ErrorInfo::new(String::new()))
+            .retryable = true;
+        self.error_info_object = None;
         self
     }
 
+    /// Checks whether the server marked the error as retryable.
+    pub fn is_retryable(&self) -> bool
+    {
+        self.error_info
+            .as_ref()
+            .map(|e| e.retryable)
+            .unwrap_or(false)
+    }
+
     /// Gets the description if it's available.
     pub fn description(&self) -> Option<&str>
     {
         self.error_info.as_ref().map(|e| e.description.as_str())
     }
 
+    /// Describes the error as an `EXCEPINFO` for `IDispatch::Invoke`.
+    ///
+    /// Scripting clients read the error details from the `EXCEPINFO` when
+    /// `Invoke` returns `DISP_E_EXCEPTION`. The original `HRESULT` is stored
+    /// in the `scode`. The strings are allocated as `BSTR` values owned by
+    /// the caller.
+    pub fn to_excep_info(&self) -> raw::EXCEPINFO
+    {
+        let bstr = |s: &str| match s.is_empty() {
+            true => crate::raw::BSTR(std::ptr::null_mut()),
+            false => crate::raw::BSTR(BString::from(s).into_ptr()),
+        };
+
+        let (source, description, help_file, help_context) = match &self.error_info {
+            Some(info) => (
+                bstr(info.source()),
+                bstr(info.description()),
+                bstr(info.help_file()),
+                info.help_context(),
+            ),
+            None => (bstr(""), bstr(""), bstr(""), 0),
+        };
+
+        raw::EXCEPINFO {
+            wCode: 0,
+            wReserved: 0,
+            bstrSource: source,
+            bstrDescription: description,
+            bstrHelpFile: help_file,
+            dwHelpContext: help_context,
+            pvReserved: std::ptr::null_mut(),
+            pfnDeferredFillIn: std::ptr::null_mut(),
+            scode: self.hresult,
+        }
+    }
+
+    /// Constructs a `ComError` from an `EXCEPINFO` filled by `Invoke`.
+    ///
+    /// # Safety
+    ///
+    /// The strings must be valid `BSTR` values or null. Their ownership moves
+    /// to the `ComError` and the fields are nulled.
+    pub unsafe fn from_excep_info(info: &mut raw::EXCEPINFO) -> ComError
+    {
+        let take = |bstr: &mut crate::raw::BSTR| {
+            let ptr = std::mem::replace(&mut bstr.0, std::ptr::null_mut());
+            match ptr.is_null() {
+                true => String::new(),
+                false => BString::from_ptr(ptr).to_string().unwrap_or_default(),
+            }
+        };
+
+        ComError {
+            hresult: info.scode,
+            error_info: Some(ErrorInfo {
+                guid: GUID::zero_guid(),
+                source: take(&mut info.bstrSource),
+                description: take(&mut info.bstrDescription),
+                help_file: take(&mut info.bstrHelpFile),
+                help_context: info.dwHelpContext,
+                retryable: false,
+            }),
+            error_info_object: None,
+        }
+    }
+
     pub const E_NOTIMPL: ComError = ComError {
         hresult: raw::E_NOTIMPL,
         error_info: None,
+        error_info_object: None,
     };
     pub const E_NOINTERFACE: ComError = ComError {
         hresult: raw::E_NOINTERFACE,
         error_info: None,
+        error_info_object: None,
     };
     pub const E_POINTER: ComError = ComError {
         hresult: raw::E_POINTER,
         error_info: None,
+        error_info_object: None,
     };
     pub const E_ABORT: ComError = ComError {
         hresult: raw::E_ABORT,
         error_info: None,
+        error_info_object: None,
     };
     pub const E_FAIL: ComError = ComError {
         hresult: raw::E_FAIL,
         error_info: None,
+        error_info_object: None,
     };
     pub const E_INVALIDARG: ComError = ComError {
         hresult: raw::E_INVALIDARG,
         error_info: None,
+        error_info_object: None,
+    };
+    pub const E_OUTOFMEMORY: ComError = ComError {
+        hresult: raw::E_OUTOFMEMORY,
+        error_info: None,
+        error_info_object: None,
     };
     pub const E_ACCESSDENIED: ComError = ComError {
         hresult: raw::E_ACCESSDENIED,
         error_info: None,
+        error_info_object: None,
     };
     pub const STG_E_FILENOTFOUND: ComError = ComError {
         hresult: raw::STG_E_FILENOTFOUND,
         error_info: None,
+        error_info_object: None,
     };
     pub const RPC_E_DISCONNECTED: ComError = ComError {
         hresult: raw::RPC_E_DISCONNECTED,
         error_info: None,
+        error_info_object: None,
     };
     pub const RPC_E_CALL_REJECTED: ComError = ComError {
         hresult: raw::RPC_E_CALL_REJECTED,
         error_info: None,
+        error_info_object: None,
     };
     pub const RPC_E_CALL_CANCELED: ComError = ComError {
         hresult: raw::RPC_E_CALL_CANCELED,
         error_info: None,
+        error_info_object: None,
     };
     pub const RPC_E_TIMEOUT: ComError = ComError {
         hresult: raw::RPC_E_TIMEOUT,
         error_info: None,
+        error_info_object: None,
     };
 }
 
@@ -205,6 +438,16 @@ impl From<std::io::Error> for ComError
     }
 }
 
+/// Maps the I/O error of the result into a `ComError`.
+///
+/// Allows the servers to propagate the I/O errors with `?`, such as in
+/// `hr_from_io(std::fs::read(path))?`. The error is stored as the error info
+/// once the COM method returns it.
+pub fn hr_from_io<T>(result: std::io::Result<T>) -> ComResult<T>
+{
+    result.map_err(ComError::from)
+}
+
 impl From<raw::HRESULT> for ComResult<()>
 {
     fn from(hresult: raw::HRESULT) -> ComResult<()>
@@ -249,6 +492,177 @@ impl From<String> for crate::ComError
     }
 }
 
+/// Allows `Result<T, (HRESULT, String)>` as a lightweight error type that
+/// controls both the returned `HRESULT` and the error info description.
+impl From<(raw::HRESULT, String)> for crate::ComError
+{
+    fn from((hresult, description): (raw::HRESULT, String)) -> Self
+    {
+        Self::new_message(hresult, description)
+    }
+}
+
+/// Error types that know the `HRESULT` they should be reported with.
+///
+/// The types implementing the trait convert into `ComError`s that use the
+/// `hresult` instead of the generic `E_FAIL`. The `Display` output is used as
+/// the error info description. The client receives the `HRESULT` as is so
+/// the `From<ComError>` conversion of the error type can match on it.
+pub trait ErrorCode: std::fmt::Display
+{
+    /// Gets the `HRESULT` that represents the error.
+    fn hresult(&self) -> raw::HRESULT;
+}
+
+impl<T: ErrorCode> From<T> for crate::ComError
+{
+    fn from(error: T) -> Self
+    {
+        Self::new_message(error.hresult(), error.to_string())
+    }
+}
+
+/// Converts typed error objects into errors reported as `E_FAIL`.
+///
+/// The object is stored as the error info so it must implement `IErrorInfo`.
+/// The clients may then query the error info for the typed interface.
+impl<I: ComInterface + ?Sized> From<ComRc<I>> for crate::ComError
+{
+    fn from(error: ComRc<I>) -> Self
+    {
+        // SetErrorInfo takes the Automation interface.
+        let info = error
+            .as_iunknown()
+            .query_interface_ts::<AutomationTypeSystem, dyn IErrorInfo>();
+        match info {
+            Ok(info) => Self::with_error_info(raw::E_FAIL, info),
+            Err(_) => Self::new_message(
+                raw::E_FAIL,
+                "The error object does not implement IErrorInfo".to_string(),
+            ),
+        }
+    }
+}
+
+/// Typed error object of a failed call.
+///
+/// Allows `Result<T, ErrorObject<dyn IMyError>>` for transporting typed
+/// error objects. The object is stored as the error info on the server side
+/// so it must implement `IErrorInfo`, as well as `IAgileObject` for the error
+/// to carry it. The clients receive the object by querying the error info
+/// for the typed interface. The object is missing if the error info did not
+/// implement the interface, in which case only the `ComError` is available.
+pub struct ErrorObject<I: ComInterface + ?Sized>
+{
+    // Boxed to keep the `Result` values small.
+    error: Box<ComError>,
+    object: Option<ComRc<I>>,
+}
+
+impl<I: ComInterface + ?Sized> std::fmt::Debug for ErrorObject<I>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        f.debug_struct("ErrorObject")
+            .field("error", &self.error)
+            .field("object", &self.object)
+            .finish()
+    }
+}
+
+impl<I: ComInterface + ?Sized> ErrorObject<I>
+{
+    /// Constructs a new error reported as `E_FAIL`.
+    pub fn new(object: ComRc<I>) -> ErrorObject<I>
+    {
+        ErrorObject {
+            error: Box::new(ComError::from(object.clone())),
+            object: Some(object),
+        }
+    }
+
+    /// Gets the typed error object if one is available.
+    pub fn object(&self) -> Option<&ComRc<I>>
+    {
+        self.object.as_ref()
+    }
+
+    /// Gets the error itself.
+    pub fn error(&self) -> &ComError
+    {
+        &self.error
+    }
+}
+
+impl<I: ComInterface + ?Sized> From<ComRc<I>> for ErrorObject<I>
+{
+    fn from(object: ComRc<I>) -> Self
+    {
+        ErrorObject::new(object)
+    }
+}
+
+impl<I: ComInterface + ?Sized> From<ErrorObject<I>> for crate::ComError
+{
+    fn from(error: ErrorObject<I>) -> Self
+    {
+        *error.error
+    }
+}
+
+impl<I: ComInterface + ?Sized> From<crate::ComError> for ErrorObject<I>
+{
+    fn from(error: crate::ComError) -> Self
+    {
+        ErrorObject {
+            object: error.error_object(),
+            error: Box::new(error),
+        }
+    }
+}
+
+impl From<crate::ComError> for (raw::HRESULT, String)
+{
+    fn from(error: crate::ComError) -> Self
+    {
+        let description = error.description().unwrap_or("").to_owned();
+        (error.hresult, description)
+    }
+}
+
+/// Allows `Result<T, Vec<ComError>>` for reporting several errors at once.
+///
+/// A single error is passed through as is. Multiple errors are reported as
+/// `E_INVALIDARG` with the messages of the individual errors concatenated
+/// into one description.
+impl From<Vec<crate::ComError>> for crate::ComError
+{
+    fn from(mut errors: Vec<crate::ComError>) -> Self
+    {
+        if errors.len() == 1 {
+            return errors.remove(0);
+        }
+
+        let description = errors
+            .iter()
+            .map(|e| match e.description() {
+                Some(desc) => desc.to_string(),
+                None => e.hresult.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        Self::new_message(raw::E_INVALIDARG, description)
+    }
+}
+
+impl From<crate::ComError> for Vec<crate::ComError>
+{
+    fn from(error: crate::ComError) -> Self
+    {
+        vec![error]
+    }
+}
+
 #[cfg(windows)]
 #[allow(non_snake_case)]
 mod error_store
@@ -323,7 +737,7 @@ mod error_store
 }
 
 /// Error info COM object data.
-#[com_class( clsid = None, IErrorInfo )]
+#[com_class( clsid = None, IErrorInfo, IErrorDetail, IAgileObject )]
 #[derive(Debug, Clone)]
 pub struct ErrorInfo
 {
@@ -332,6 +746,7 @@ pub struct ErrorInfo
     description: String,
     help_file: String,
     help_context: u32,
+    retryable: bool,
 }
 
 impl ErrorInfo
@@ -344,6 +759,7 @@ impl ErrorInfo
             source: String::new(),
             help_file: String::new(),
             help_context: 0,
+            retryable: false,
         }
     }
 
@@ -367,6 +783,10 @@ impl ErrorInfo
     {
         self.help_context
     }
+    pub fn retryable(&self) -> bool
+    {
+        self.retryable
+    }
 }
 
 impl<'a> TryFrom<&'a dyn IErrorInfo> for ErrorInfo
@@ -381,6 +801,7 @@ impl<'a> TryFrom<&'a dyn IErrorInfo> for ErrorInfo
             description: source.get_description()?,
             help_file: source.get_help_file()?,
             help_context: source.get_help_context()?,
+            retryable: false,
         })
     }
 }
@@ -395,6 +816,41 @@ pub trait IErrorInfo: crate::IUnknown
     fn get_help_context(&self) -> ComResult<u32>;
 }
 
+/// Intercom specific details of an error.
+///
+/// Implemented by the error info objects alongside `IErrorInfo`. Error info
+/// objects from other sources don't implement the interface, in which case
+/// the details take their default values.
+#[com_interface(
+    com_iid = "4c7a2e91-3b5d-4f68-9a1c-7e2d8b4f6a03",
+    raw_iid = "9e3b6d52-8c1f-4a27-b5e4-0d6f2a7c3b18"
+)]
+pub trait IErrorDetail: crate::IUnknown
+{
+    /// Checks whether the call may succeed if attempted again.
+    fn get_retryable(&self) -> ComResult<bool>;
+}
+
+impl IAgileObject for ErrorInfo {}
+
+impl IErrorDetail for ErrorInfo
+{
+    fn get_retryable(&self) -> ComResult<bool>
+    {
+        Ok(self.retryable)
+    }
+}
+
+/// Reads the error info and the details of an `IErrorInfo` object.
+fn read_error_info(info: &ComItf<dyn IErrorInfo>) -> Option<ErrorInfo>
+{
+    let mut error_info = ErrorInfo::try_from(&**info as &dyn IErrorInfo).ok()?;
+    if let Ok(detail) = ComItf::query_interface::<dyn IErrorDetail>(info) {
+        error_info.retryable = detail.get_retryable().unwrap_or(false);
+    }
+    Some(error_info)
+}
+
 impl IErrorInfo for ErrorInfo
 {
     fn get_guid(&self) -> ComResult<GUID>
@@ -421,6 +877,9 @@ impl IErrorInfo for ErrorInfo
 
 /// Extracts the HRESULT from the error result and stores the extended error
 /// information in thread memory so it can be fetched by the COM client.
+///
+/// The COM methods describe the `Error::source` chain of the returned
+/// `std::error::Error` values in the error info before storing it.
 pub fn store_error<E>(error: E) -> ComError
 where
     E: Into<ComError>,
@@ -428,8 +887,16 @@ where
     // Convet the error.
     let com_error = error.into();
 
-    match com_error.error_info {
-        Some(ref error_info) => {
+    match (&com_error.error_info_object, &com_error.error_info) {
+        (Some(info), _) => {
+            // The error carries a custom IErrorInfo object. Store it as is.
+            let info = info.get();
+            let ptr = ComItf::ptr(&info).expect("Intercom did not implement correct type system");
+            unsafe {
+                error_store::SetErrorInfo(0, Some(ptr));
+            }
+        }
+        (None, Some(error_info)) => {
             // ComError contains ErrorInfo. We need to set this in the OS error
             // store.
 
@@ -444,7 +911,7 @@ where
                 error_store::SetErrorInfo(0, Some(ptr));
             }
         }
-        None => {
+        (None, None) => {
             // No error info in the ComError.
             unsafe {
                 error_store::SetErrorInfo(0, None);
@@ -456,6 +923,83 @@ where
     com_error
 }
 
+/// The maximum number of `Error::source` links included in the description.
+const MAX_SOURCE_CHAIN: usize = 16;
+
+/// Converts the errors returned from the COM methods into `ComError`s.
+///
+/// The generated code uses `(&e).intercom_source_chain().into_com_error(e)`.
+/// Method resolution picks `SourceChain` for the `std::error::Error` types,
+/// which appends the `Error::source` chain to the description, and falls
+/// back to `NoSourceChain` for the other error types.
+#[doc(hidden)]
+pub mod chain
+{
+    use super::{ComError, ErrorInfo, MAX_SOURCE_CHAIN};
+    use std::error::Error;
+
+    pub struct Chained;
+    pub struct Unchained;
+
+    pub trait SourceChain
+    {
+        fn intercom_source_chain(&self) -> Chained
+        {
+            Chained
+        }
+    }
+    impl<E: Error + Into<ComError>> SourceChain for E {}
+
+    pub trait NoSourceChain
+    {
+        fn intercom_source_chain(&self) -> Unchained
+        {
+            Unchained
+        }
+    }
+    impl<E: Into<ComError>> NoSourceChain for &E {}
+
+    impl Chained
+    {
+        pub fn into_com_error<E: Error + Into<ComError>>(self, error: E) -> ComError
+        {
+            let mut sources = vec![];
+            let mut source = error.source();
+            while let Some(s) = source {
+                if sources.len() == MAX_SOURCE_CHAIN {
+                    break;
+                }
+                sources.push(s.to_string());
+                source = s.source();
+            }
+
+            // The custom error info objects describe themselves.
+            let top = error.to_string();
+            let mut com_error = error.into();
+            if sources.is_empty() || com_error.error_info_object.is_some() {
+                return com_error;
+            }
+
+            let info = com_error
+                .error_info
+                .get_or_insert_with(|| ErrorInfo::new(String::new()));
+            if info.description.is_empty() {
+                info.description = top;
+            }
+            info.description = format!("{}: {}", info.description, sources.join(": "));
+            com_error
+        }
+    }
+
+    impl Unchained
+    {
+        pub fn into_com_error<E: Into<ComError>>(self, error: E) -> ComError
+        {
+            error.into()
+        }
+    }
+}
+
 pub fn load_error<I: ComInterface + ?Sized>(
     iunk: &ComItf<I>,
     iid: &GUID,
@@ -472,6 +1016,7 @@ pub fn load_error<I: ComInterface + ?Sized>(
         return ComError {
             hresult: err,
             error_info: None,
+            error_info_object: None,
         };
     }
 
@@ -481,12 +1026,19 @@ pub fn load_error<I: ComInterface + ?Sized>(
         _ => false,
     };
 
+    // The agile error info objects are kept with the error so that the typed
+    // error interfaces they implement remain available to the caller.
+    let info = match supports_errorinfo {
+        true => get_error_info(),
+        false => None,
+    };
     ComError {
         hresult: err,
-        error_info: match supports_errorinfo {
-            true => get_last_error(),
-            false => None,
-        },
+        error_info: info.as_ref().and_then(|info| read_error_info(info)),
+        error_info_object: info
+            .as_ref()
+            .and_then(|info| AgileRef::new(info).ok())
+            .map(Box::new),
     }
 }
 
@@ -498,39 +1050,144 @@ pub fn get_last_error() -> Option<ErrorInfo>
         None => return None,
     };
 
-    // FIXME ComRc Deref
-    let ierr: &dyn IErrorInfo = &*ierrorinfo;
-    ErrorInfo::try_from(ierr).ok()
+    read_error_info(&ierrorinfo)
 }
 
-/// Defines a way to handle errors based on the method return value type.
+/// Whether the panic messages are exposed in the error info.
+static PANIC_TO_ERROR_INFO: AtomicBool = AtomicBool::new(cfg!(debug_assertions));
+
+thread_local! {
+    /// Message of the last panic on the current thread recorded by the hook
+    /// installed with `install_panic_hook`.
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Controls whether the panic messages are exposed to the COM clients.
 ///
-/// The default implementation will terminate the process on the basis that
-/// errors must not silently leak. The specialization for `HRESULT` will return
-/// the `HRESULT`.
+/// Panics in the COM methods returning a `Result` are reported as `E_FAIL`.
+/// When exposed, the panic message is used as the `IErrorInfo` description.
+/// Otherwise the description is a generic message, which avoids leaking the
+/// implementation details to the clients.
 ///
-/// The user is free to implement this trait for their own types to handle
-/// custom status codes gracefully.
-pub trait ErrorValue
+/// The messages are exposed by default only in debug builds.
+pub fn set_panic_to_error_info(expose: bool)
 {
-    /// Attempts to convert a COM error into a custom status code. Must not panic.
-    fn from_error(_: ComError) -> Self;
+    PANIC_TO_ERROR_INFO.store(expose, Ordering::Relaxed);
 }
 
-impl<S, E: ErrorValue> ErrorValue for Result<S, E>
+/// Installs a panic hook that includes the panic location in the error info.
+///
+/// Without the hook only the panic message is available. The previously
+/// installed hook is still invoked for every panic.
+pub fn install_panic_hook()
 {
-    fn from_error(e: ComError) -> Self
-    {
-        Err(E::from_error(e))
-    }
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = panic_message(info.payload());
+        let message = match info.location() {
+            Some(location) => format!("{} at {}", message, location),
+            None => message,
+        };
+        LAST_PANIC.with(|last| *last.borrow_mut() = Some(message));
+        previous(info);
+    }));
 }
 
-impl<T: From<ComError>> ErrorValue for T
+/// Converts a panic caught in a COM method into an error.
+#[doc(hidden)]
+pub fn panic_error(payload: Box<dyn Any + Send>) -> ComError
 {
-    fn from_error(err: ComError) -> Self
-    {
-        err.into()
-    }
+    // Take the recorded message regardless of the policy so it doesn't
+    // remain around for a later panic.
+    let recorded = LAST_PANIC.with(|last| last.borrow_mut().take());
+    let message = match PANIC_TO_ERROR_INFO.load(Ordering::Relaxed) {
+        true => format!(
+            "Panicked: {}",
+            recorded.unwrap_or_else(|| panic_message(&*payload))
+        ),
+        false => "Internal error".to_string(),
+    };
+    ComError::new_message(raw::E_FAIL, message)
+}
+
+/// Formats the `&str` and `String` panic payloads.
+fn panic_message(payload: &(dyn Any + Send)) -> String
+{
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Unknown panic payload".to_string()
+    }
+}
+
+/// Retries a call failing with a transient RPC error.
+///
+/// Busy out-of-process servers reject incoming calls with
+/// `RPC_E_CALL_REJECTED` or `RPC_E_SERVERCALL_RETRYLATER` and expect the
+/// client to try again later, similar to what `IMessageFilter` does. The
+/// `call` is attempted at most `attempts` times with the delay starting at
+/// `backoff` and doubling after each rejection. The errors the server marked
+/// as retryable with `ComError::retryable` are retried the same way. Other
+/// errors are returned immediately.
+///
+/// ```rust,ignore
+/// let sheet = intercom::with_retry(5, Duration::from_millis(100), || excel.active_sheet())?;
+/// ```
+pub fn with_retry<T, F>(attempts: u32, backoff: std::time::Duration, mut call: F) -> ComResult<T>
+where
+    F: FnMut() -> ComResult<T>,
+{
+    let mut delay = backoff;
+    let mut remaining = attempts;
+    loop {
+        remaining = remaining.saturating_sub(1);
+        match call() {
+            Err(e) if remaining > 0 && (is_transient(e.hresult) || e.is_retryable()) => {
+                std::thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Checks whether the `HRESULT` signals the server was too busy to handle
+/// the call.
+fn is_transient(hr: raw::HRESULT) -> bool
+{
+    hr == raw::RPC_E_CALL_REJECTED || hr == raw::RPC_E_SERVERCALL_RETRYLATER
+}
+
+/// Defines a way to handle errors based on the method return value type.
+///
+/// The default implementation will terminate the process on the basis that
+/// errors must not silently leak. The specialization for `HRESULT` will return
+/// the `HRESULT`.
+///
+/// The user is free to implement this trait for their own types to handle
+/// custom status codes gracefully.
+pub trait ErrorValue
+{
+    /// Attempts to convert a COM error into a custom status code. Must not panic.
+    fn from_error(_: ComError) -> Self;
+}
+
+impl<S, E: ErrorValue> ErrorValue for Result<S, E>
+{
+    fn from_error(e: ComError) -> Self
+    {
+        Err(E::from_error(e))
+    }
+}
+
+impl<T: From<ComError>> ErrorValue for T
+{
+    fn from_error(err: ComError) -> Self
+    {
+        err.into()
+    }
 }
 
 #[com_class(IErrorStore)]
@@ -541,10 +1198,19 @@ pub struct ErrorStore;
     com_iid = "d7f996c5-0b51-4053-82f8-19a7261793a9",
     raw_iid = "7586c49a-abbd-4a06-b588-e3d02b431f01"
 )]
+/// Stores the error information of the current thread.
+///
+/// Gives the clients access to the error information on platforms without
+/// the `GetErrorInfo` and `SetErrorInfo` functions.
 pub trait IErrorStore: crate::IUnknown
 {
+    /// Gets the error information of the current thread.
     fn get_error_info(&self) -> ComResult<ComRc<dyn IErrorInfo>>;
+
+    /// Sets the error information of the current thread.
     fn set_error_info(&self, info: &ComItf<dyn IErrorInfo>) -> ComResult<()>;
+
+    /// Sets a plain error message as the error information.
     fn set_error_message(&self, msg: &str) -> ComResult<()>;
 }
 
@@ -597,7 +1263,10 @@ pub mod raw
 {
 
     /// COM method status code.
-    #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+    ///
+    /// The `Display` and `Debug` implementations show the name of the
+    /// well-known codes and the hexadecimal value of the others.
+    #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
     #[repr(C)]
     pub struct HRESULT
     {
@@ -625,6 +1294,58 @@ pub mod raw
         {
             !self.is_success()
         }
+
+        /// Gets the name of a well-known `HRESULT`, such as `"E_INVALIDARG"`.
+        pub fn name(&self) -> Option<&'static str>
+        {
+            Some(match *self {
+                S_OK => "S_OK",
+                S_FALSE => "S_FALSE",
+                E_NOTIMPL => "E_NOTIMPL",
+                E_NOINTERFACE => "E_NOINTERFACE",
+                E_POINTER => "E_POINTER",
+                E_ABORT => "E_ABORT",
+                E_FAIL => "E_FAIL",
+                E_PENDING => "E_PENDING",
+                E_INVALIDARG => "E_INVALIDARG",
+                E_OUTOFMEMORY => "E_OUTOFMEMORY",
+                E_CLASSNOTAVAILABLE => "E_CLASSNOTAVAILABLE",
+                E_ACCESSDENIED => "E_ACCESSDENIED",
+                STG_E_FILENOTFOUND => "STG_E_FILENOTFOUND",
+                RPC_E_DISCONNECTED => "RPC_E_DISCONNECTED",
+                RPC_E_CALL_REJECTED => "RPC_E_CALL_REJECTED",
+                RPC_E_SERVERCALL_RETRYLATER => "RPC_E_SERVERCALL_RETRYLATER",
+                RPC_E_CALL_CANCELED => "RPC_E_CALL_CANCELED",
+                RPC_E_TIMEOUT => "RPC_E_TIMEOUT",
+                DISP_E_EXCEPTION => "DISP_E_EXCEPTION",
+                DISP_E_MEMBERNOTFOUND => "DISP_E_MEMBERNOTFOUND",
+                DISP_E_TYPEMISMATCH => "DISP_E_TYPEMISMATCH",
+                DISP_E_UNKNOWNNAME => "DISP_E_UNKNOWNNAME",
+                DISP_E_NONAMEDARGS => "DISP_E_NONAMEDARGS",
+                DISP_E_BADINDEX => "DISP_E_BADINDEX",
+                DISP_E_BADPARAMCOUNT => "DISP_E_BADPARAMCOUNT",
+                _ => return None,
+            })
+        }
+    }
+
+    impl std::fmt::Display for HRESULT
+    {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+        {
+            match self.name() {
+                Some(name) => write!(f, "{} (0x{:08X})", name, self.hr),
+                None => write!(f, "0x{:08X}", self.hr),
+            }
+        }
+    }
+
+    impl std::fmt::Debug for HRESULT
+    {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+        {
+            write!(f, "HRESULT({})", self)
+        }
     }
 
     macro_rules! make_hr {
@@ -671,11 +1392,22 @@ pub mod raw
         E_FAIL = 0x8000_4005
     );
 
+    make_hr!(
+        /// `HRESULT` indicating the data required by the call isn't available
+        /// yet.
+        E_PENDING = 0x8000_000A
+    );
+
     make_hr!(
         /// `HRESULT` for invalid argument.
         E_INVALIDARG = 0x8007_0057
     );
 
+    make_hr!(
+        /// `HRESULT` for failed memory allocation.
+        E_OUTOFMEMORY = 0x8007_000E
+    );
+
     make_hr!(
         /// `HRESULT` for unavailable CLSID.
         E_CLASSNOTAVAILABLE = 0x8004_0111
@@ -687,6 +1419,849 @@ pub mod raw
     make_hr!(STG_E_FILENOTFOUND = 0x8003_0002);
     make_hr!(RPC_E_DISCONNECTED = 0x8001_0108);
     make_hr!(RPC_E_CALL_REJECTED = 0x8001_0001);
+    make_hr!(RPC_E_SERVERCALL_RETRYLATER = 0x8001_010A);
     make_hr!(RPC_E_CALL_CANCELED = 0x8001_0002);
     make_hr!(RPC_E_TIMEOUT = 0x8001_011F);
+
+    make_hr!(
+        /// `HRESULT` returned by `IDispatch::Invoke` when the `EXCEPINFO`
+        /// describes the error.
+        DISP_E_EXCEPTION = 0x8002_0009
+    );
+
+    make_hr!(
+        /// `HRESULT` returned by `IDispatch::Invoke` for an unknown DISPID.
+        DISP_E_MEMBERNOTFOUND = 0x8002_0003
+    );
+
+    make_hr!(
+        /// `HRESULT` returned by `IDispatch::Invoke` when an argument could
+        /// not be converted to the parameter type.
+        DISP_E_TYPEMISMATCH = 0x8002_0005
+    );
+
+    make_hr!(
+        /// `HRESULT` returned by `IDispatch::GetIDsOfNames` for an unknown
+        /// name.
+        DISP_E_UNKNOWNNAME = 0x8002_0006
+    );
+
+    make_hr!(
+        /// `HRESULT` returned by `IDispatch::Invoke` for named arguments
+        /// other than the value of a property put.
+        DISP_E_NONAMEDARGS = 0x8002_0007
+    );
+
+    make_hr!(
+        /// `HRESULT` returned by `IDispatch::GetTypeInfo` for an unknown type
+        /// info index.
+        DISP_E_BADINDEX = 0x8002_000B
+    );
+
+    make_hr!(
+        /// `HRESULT` returned by `IDispatch::Invoke` for a wrong number of
+        /// arguments.
+        DISP_E_BADPARAMCOUNT = 0x8002_000E
+    );
+
+    /// Exception information returned by `IDispatch::Invoke`.
+    #[repr(C)]
+    #[allow(non_snake_case)]
+    pub struct EXCEPINFO
+    {
+        pub wCode: u16,
+        pub wReserved: u16,
+        pub bstrSource: crate::raw::BSTR,
+        pub bstrDescription: crate::raw::BSTR,
+        pub bstrHelpFile: crate::raw::BSTR,
+        pub dwHelpContext: u32,
+        pub pvReserved: *mut std::os::raw::c_void,
+        pub pfnDeferredFillIn: *mut std::os::raw::c_void,
+        pub scode: HRESULT,
+    }
+
+    impl crate::type_system::ForeignType for EXCEPINFO
+    {
+        fn type_name() -> &'static str
+        {
+            "EXCEPINFO"
+        }
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::{hr_from_io, raw};
+    use crate::ComError;
+
+    #[test]
+    fn known_hresult_is_named()
+    {
+        assert_eq!(
+            format!("{}", raw::E_INVALIDARG),
+            "E_INVALIDARG (0x80070057)"
+        );
+        assert_eq!(format!("{}", raw::S_OK), "S_OK (0x00000000)");
+        assert_eq!(
+            format!("{:?}", raw::E_NOINTERFACE),
+            "HRESULT(E_NOINTERFACE (0x80004002))"
+        );
+    }
+
+    #[test]
+    fn unknown_hresult_is_hex()
+    {
+        let hr = raw::HRESULT::new(0x8004_1234u32 as i32);
+        assert_eq!(hr.name(), None);
+        assert_eq!(format!("{}", hr), "0x80041234");
+        assert_eq!(format!("{:?}", hr), "HRESULT(0x80041234)");
+    }
+
+    #[test]
+    fn error_displays_hresult_and_message()
+    {
+        let err = ComError::E_INVALIDARG.with_message("x must be positive");
+        assert_eq!(
+            err.to_string(),
+            "E_INVALIDARG (0x80070057): x must be positive"
+        );
+
+        let err = ComError::new_hr(raw::HRESULT::new(0x8004_1234u32 as i32));
+        assert_eq!(err.to_string(), "0x80041234");
+    }
+
+    #[test]
+    fn error_converts_to_boxed_error()
+    {
+        fn fails() -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+        {
+            Err(ComError::E_NOTIMPL.with_message("Not there yet"))?;
+            Ok(())
+        }
+
+        let err = fails().unwrap_err();
+        assert!(err.to_string().contains("E_NOTIMPL"));
+        assert!(err.to_string().contains("Not there yet"));
+    }
+
+    #[test]
+    fn io_result_maps_to_com_result()
+    {
+        let path = std::env::temp_dir().join("intercom_hr_from_io.txt");
+        std::fs::write(&path, b"contents").unwrap();
+        assert_eq!(hr_from_io(std::fs::read(&path)).unwrap(), b"contents");
+        std::fs::remove_file(&path).unwrap();
+
+        let err = hr_from_io(std::fs::read(&path)).unwrap_err();
+        assert_eq!(err.hresult, raw::STG_E_FILENOTFOUND);
+        assert!(err.description().is_some());
+    }
+
+    #[crate::com_interface]
+    trait ILoader
+    {
+        fn load(&self) -> crate::ComResult<()>;
+    }
+
+    #[crate::com_class(clsid = None, ILoader)]
+    struct Loader;
+
+    impl ILoader for Loader
+    {
+        fn load(&self) -> crate::ComResult<()>
+        {
+            let read = || Err(ComError::STG_E_FILENOTFOUND.with_message("config.toml"));
+            let parse = || read().map_err(|e: ComError| e.context("while reading config"));
+            parse().map_err(|e| e.context("while loading config"))
+        }
+    }
+
+    #[test]
+    fn context_is_chained()
+    {
+        let rc = crate::ComRc::<dyn ILoader>::from(crate::ComBox::new(Loader));
+        let err = rc.load().unwrap_err();
+        assert_eq!(err.hresult, raw::STG_E_FILENOTFOUND);
+        assert_eq!(
+            err.description(),
+            Some("while loading config: while reading config: config.toml")
+        );
+
+        let err = ComError::E_FAIL.context("while saving");
+        assert_eq!(err.hresult, raw::E_FAIL);
+        assert_eq!(err.description(), Some("while saving"));
+    }
+
+    #[crate::com_interface]
+    trait IValidator
+    {
+        fn validate(&self, values: &str) -> Result<(), Vec<ComError>>;
+    }
+
+    #[crate::com_class(clsid = None, IValidator)]
+    struct Validator;
+
+    impl IValidator for Validator
+    {
+        fn validate(&self, values: &str) -> Result<(), Vec<ComError>>
+        {
+            let errors = values
+                .split(',')
+                .filter(|v| v.parse::<u32>().is_err())
+                .map(|v| ComError::E_INVALIDARG.with_message(format!("Bad value: {}", v)))
+                .collect::<Vec<_>>();
+            match errors.is_empty() {
+                true => Ok(()),
+                false => Err(errors),
+            }
+        }
+    }
+
+    #[test]
+    fn aggregate_errors_are_concatenated()
+    {
+        let rc = crate::ComRc::<dyn IValidator>::from(crate::ComBox::new(Validator));
+        rc.validate("1,2").unwrap();
+
+        let errors = rc.validate("1,x,2,y").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].hresult, raw::E_INVALIDARG);
+        assert_eq!(errors[0].description(), Some("Bad value: x; Bad value: y"));
+    }
+
+    #[crate::com_interface(error_policy = "hresult_only")]
+    trait IQuiet
+    {
+        fn fail(&self) -> crate::ComResult<u32>;
+    }
+
+    #[crate::com_class(clsid = None, IQuiet)]
+    struct Quiet;
+
+    impl IQuiet for Quiet
+    {
+        fn fail(&self) -> crate::ComResult<u32>
+        {
+            Err(ComError::E_INVALIDARG.with_message("Not reported"))
+        }
+    }
+
+    #[test]
+    fn hresult_only_policy_drops_error_info()
+    {
+        let rc = crate::ComRc::<dyn IQuiet>::from(crate::ComBox::new(Quiet));
+        let err = rc.fail().unwrap_err();
+        assert_eq!(err.hresult, raw::E_INVALIDARG);
+        assert_eq!(err.description(), None);
+    }
+
+    #[crate::com_interface]
+    trait IFinder
+    {
+        fn find(&self, name: &str) -> crate::ComResult<crate::Success<u32>>;
+    }
+
+    #[crate::com_class(clsid = None, IFinder)]
+    struct Finder;
+
+    impl IFinder for Finder
+    {
+        fn find(&self, name: &str) -> crate::ComResult<crate::Success<u32>>
+        {
+            match name {
+                "" => Err(ComError::E_INVALIDARG),
+                "answer" => Ok(crate::Success::ok(42)),
+                _ => Ok(crate::Success::s_false(0)),
+            }
+        }
+    }
+
+    #[test]
+    fn success_hresult_is_returned()
+    {
+        let rc = crate::ComRc::<dyn IFinder>::from(crate::ComBox::new(Finder));
+        assert_eq!(rc.find("answer").unwrap(), crate::Success::ok(42));
+        assert_eq!(rc.find("question").unwrap(), crate::Success::s_false(0));
+        assert_eq!(rc.find("question").unwrap().hresult(), raw::S_FALSE);
+        assert_eq!(rc.find("").unwrap_err().hresult, raw::E_INVALIDARG);
+    }
+
+    #[crate::com_interface]
+    trait IPlain
+    {
+        fn value(&self) -> u32;
+    }
+
+    #[crate::com_interface]
+    trait IRaising
+    {
+        fn fail(&self) -> crate::ComResult<u32>;
+    }
+
+    #[crate::com_class(clsid = None, IRaising, IQuiet, IPlain)]
+    struct Mixed;
+
+    impl IRaising for Mixed
+    {
+        fn fail(&self) -> crate::ComResult<u32>
+        {
+            Err(ComError::E_INVALIDARG.with_message("Reported"))
+        }
+    }
+
+    impl IQuiet for Mixed
+    {
+        fn fail(&self) -> crate::ComResult<u32>
+        {
+            Err(ComError::E_INVALIDARG)
+        }
+    }
+
+    impl IPlain for Mixed
+    {
+        fn value(&self) -> u32
+        {
+            1
+        }
+    }
+
+    #[test]
+    fn error_info_is_supported_for_raising_interfaces()
+    {
+        use crate::attributes::ComInterfaceVariant;
+        use crate::type_system::{AutomationTypeSystem, RawTypeSystem};
+        use crate::{ComItf, ISupportErrorInfo};
+
+        let rc = crate::ComRc::<dyn IPlain>::from(crate::ComBox::new(Mixed));
+        let support = ComItf::query_interface::<dyn ISupportErrorInfo>(&rc).unwrap();
+        let supports = |iid: &crate::IID| support.interface_supports_error_info(iid);
+
+        assert_eq!(
+            supports(<dyn IRaising as ComInterfaceVariant<
+                AutomationTypeSystem,
+            >>::iid()),
+            raw::S_OK
+        );
+        assert_eq!(
+            supports(<dyn IRaising as ComInterfaceVariant<RawTypeSystem>>::iid()),
+            raw::S_OK
+        );
+        assert_eq!(
+            supports(<dyn IQuiet as ComInterfaceVariant<AutomationTypeSystem>>::iid()),
+            raw::S_FALSE
+        );
+        assert_eq!(
+            supports(<dyn IPlain as ComInterfaceVariant<AutomationTypeSystem>>::iid()),
+            raw::S_FALSE
+        );
+    }
+
+    #[crate::com_interface]
+    trait IPanicky
+    {
+        fn explode(&self, message: &str) -> crate::ComResult<u32>;
+    }
+
+    #[crate::com_class(clsid = None, IPanicky)]
+    struct Panicky;
+
+    impl IPanicky for Panicky
+    {
+        fn explode(&self, message: &str) -> crate::ComResult<u32>
+        {
+            panic!("{}", message);
+        }
+    }
+
+    #[test]
+    fn panic_is_reported_as_error_info()
+    {
+        let rc = crate::ComRc::<dyn IPanicky>::from(crate::ComBox::new(Panicky));
+
+        // Without the hook only the message is known.
+        super::set_panic_to_error_info(true);
+        let err = rc.explode("kaboom").unwrap_err();
+        assert_eq!(err.hresult, raw::E_FAIL);
+        assert_eq!(err.description(), Some("Panicked: kaboom"));
+
+        super::install_panic_hook();
+        let err = rc.explode("boom").unwrap_err();
+        assert_eq!(err.hresult, raw::E_FAIL);
+        let description = err.description().unwrap();
+        assert!(description.starts_with("Panicked: boom at "));
+        assert!(description.contains("error.rs"));
+
+        super::set_panic_to_error_info(false);
+        let err = rc.explode("secret").unwrap_err();
+        assert_eq!(err.hresult, raw::E_FAIL);
+        assert_eq!(err.description(), Some("Internal error"));
+
+        super::set_panic_to_error_info(cfg!(debug_assertions));
+    }
+
+    #[test]
+    fn transient_error_is_retried()
+    {
+        let delay = std::time::Duration::from_millis(1);
+
+        let mut calls = 0;
+        let result = super::with_retry(3, delay, || {
+            calls += 1;
+            match calls {
+                1 => Err(ComError::RPC_E_CALL_REJECTED),
+                2 => Err(ComError::new_hr(raw::RPC_E_SERVERCALL_RETRYLATER)),
+                _ => Ok(calls),
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        let mut calls = 0;
+        let result = super::with_retry(3, delay, || -> crate::ComResult<()> {
+            calls += 1;
+            Err(ComError::E_INVALIDARG)
+        });
+        assert_eq!(result.unwrap_err().hresult, raw::E_INVALIDARG);
+        assert_eq!(calls, 1);
+
+        let mut calls = 0;
+        let result = super::with_retry(2, delay, || -> crate::ComResult<()> {
+            calls += 1;
+            Err(ComError::RPC_E_CALL_REJECTED)
+        });
+        assert_eq!(result.unwrap_err().hresult, raw::RPC_E_CALL_REJECTED);
+        assert_eq!(calls, 2);
+    }
+
+    #[crate::com_interface]
+    trait IJob
+    {
+        fn run(&self) -> crate::ComResult<u32>;
+    }
+
+    #[crate::com_class(clsid = None, IJob)]
+    #[derive(Default)]
+    struct Job
+    {
+        attempts: std::cell::Cell<u32>,
+    }
+
+    impl IJob for Job
+    {
+        fn run(&self) -> crate::ComResult<u32>
+        {
+            let attempts = self.attempts.get() + 1;
+            self.attempts.set(attempts);
+            match attempts {
+                1 => Err(ComError::E_FAIL.with_message("Database locked").retryable()),
+                2 => Err(ComError::E_FAIL.retryable()),
+                3 => Err(ComError::E_FAIL.with_message("Database missing")),
+                _ => Ok(attempts),
+            }
+        }
+    }
+
+    #[test]
+    fn retryable_flag_is_transported()
+    {
+        let rc = crate::ComRc::<dyn IJob>::from(crate::ComBox::new(Job::default()));
+
+        let err = rc.run().unwrap_err();
+        assert_eq!(err.hresult, raw::E_FAIL);
+        assert!(err.is_retryable());
+        assert_eq!(err.description(), Some("Database locked"));
+
+        let err = rc.run().unwrap_err();
+        assert!(err.is_retryable());
+        assert_eq!(err.to_string(), "E_FAIL (0x80004005)");
+
+        let err = rc.run().unwrap_err();
+        assert!(!err.is_retryable());
+        assert_eq!(rc.run().unwrap(), 4);
+    }
+
+    #[test]
+    fn retryable_error_is_retried()
+    {
+        let delay = std::time::Duration::from_millis(1);
+        let rc = crate::ComRc::<dyn IJob>::from(crate::ComBox::new(Job::default()));
+
+        // The retryable errors are retried until the permanent one.
+        let err = super::with_retry(5, delay, || rc.run()).unwrap_err();
+        assert_eq!(err.description(), Some("Database missing"));
+        assert_eq!(super::with_retry(5, delay, || rc.run()).unwrap(), 4);
+    }
+
+    #[test]
+    fn error_converts_to_excep_info()
+    {
+        let err = ComError::new_message(raw::E_INVALIDARG, "Bad value".to_string());
+        let mut info = err.to_excep_info();
+        assert_eq!(info.scode, raw::E_INVALIDARG);
+        assert_eq!(info.wCode, 0);
+        assert!(info.bstrSource.0.is_null());
+        assert!(info.bstrHelpFile.0.is_null());
+
+        let err = unsafe { ComError::from_excep_info(&mut info) };
+        assert!(info.bstrDescription.0.is_null());
+        assert_eq!(err.hresult, raw::E_INVALIDARG);
+        assert_eq!(err.description(), Some("Bad value"));
+
+        let mut info = ComError::E_FAIL.to_excep_info();
+        assert!(info.bstrDescription.0.is_null());
+        let err = unsafe { ComError::from_excep_info(&mut info) };
+        assert_eq!(err.hresult, raw::E_FAIL);
+        assert_eq!(err.description(), Some(""));
+    }
+
+    #[crate::com_interface]
+    trait IParseError
+    {
+        fn get_line(&self) -> crate::ComResult<u32>;
+    }
+
+    #[crate::com_class(
+        clsid = None,
+        super::IErrorInfo,
+        IParseError,
+        crate::interfaces::IAgileObject
+    )]
+    struct ParseError
+    {
+        line: u32,
+    }
+
+    impl super::IErrorInfo for ParseError
+    {
+        fn get_guid(&self) -> crate::ComResult<crate::GUID>
+        {
+            Ok(crate::GUID::zero_guid())
+        }
+        fn get_source(&self) -> crate::ComResult<String>
+        {
+            Ok("parser".to_string())
+        }
+        fn get_description(&self) -> crate::ComResult<String>
+        {
+            Ok(format!("Syntax error on line {}", self.line))
+        }
+        fn get_help_file(&self) -> crate::ComResult<String>
+        {
+            Ok(String::new())
+        }
+        fn get_help_context(&self) -> crate::ComResult<u32>
+        {
+            Ok(0)
+        }
+    }
+
+    impl crate::interfaces::IAgileObject for ParseError {}
+
+    impl IParseError for ParseError
+    {
+        fn get_line(&self) -> crate::ComResult<u32>
+        {
+            Ok(self.line)
+        }
+    }
+
+    #[crate::com_interface]
+    trait IParser
+    {
+        fn parse(&self, line: u32) -> Result<u32, super::ErrorObject<dyn IParseError>>;
+        fn check(&self, line: u32) -> crate::ComResult<()>;
+    }
+
+    #[crate::com_class(clsid = None, IParser)]
+    struct Parser;
+
+    impl IParser for Parser
+    {
+        fn parse(&self, line: u32) -> Result<u32, super::ErrorObject<dyn IParseError>>
+        {
+            match line {
+                0 => Ok(10),
+                u32::MAX => Err(ComError::E_INVALIDARG.into()),
+                _ => {
+                    let error: crate::ComRc<dyn IParseError> =
+                        crate::ComBox::new(ParseError { line }).into();
+                    Err(error.into())
+                }
+            }
+        }
+
+        fn check(&self, line: u32) -> crate::ComResult<()>
+        {
+            let error: crate::ComRc<dyn IParseError> =
+                crate::ComBox::new(ParseError { line }).into();
+            Err(error.into())
+        }
+    }
+
+    #[test]
+    fn typed_error_object_is_transported()
+    {
+        let rc = crate::ComRc::<dyn IParser>::from(crate::ComBox::new(Parser));
+        assert_eq!(rc.parse(0).unwrap(), 10);
+
+        // The typed client receives the error object through the error info.
+        let err = rc.parse(7).unwrap_err();
+        assert_eq!(err.error().hresult, raw::E_FAIL);
+        assert_eq!(err.object().unwrap().get_line().unwrap(), 7);
+
+        // Errors without the object are still reported.
+        let err = rc.parse(u32::MAX).unwrap_err();
+        assert_eq!(err.error().hresult, raw::E_INVALIDARG);
+        assert!(err.object().is_none());
+
+        // The untyped clients may query the error info for the object.
+        let err = rc.check(3).unwrap_err();
+        assert_eq!(err.hresult, raw::E_FAIL);
+        assert_eq!(err.description(), Some("Syntax error on line 3"));
+        let typed = err.error_object::<dyn IParseError>().unwrap();
+        assert_eq!(typed.get_line().unwrap(), 3);
+        assert!(err.error_object::<dyn IJob>().is_none());
+    }
+
+    #[crate::com_class(clsid = None, super::IErrorInfo)]
+    struct ThreadBoundErrorInfo;
+
+    impl super::IErrorInfo for ThreadBoundErrorInfo
+    {
+        fn get_guid(&self) -> crate::ComResult<crate::GUID>
+        {
+            Ok(crate::GUID::zero_guid())
+        }
+        fn get_source(&self) -> crate::ComResult<String>
+        {
+            Ok("worker".to_string())
+        }
+        fn get_description(&self) -> crate::ComResult<String>
+        {
+            Ok("Not agile".to_string())
+        }
+        fn get_help_file(&self) -> crate::ComResult<String>
+        {
+            Ok(String::new())
+        }
+        fn get_help_context(&self) -> crate::ComResult<u32>
+        {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn non_agile_error_info_keeps_fields()
+    {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let info =
+            crate::ComRc::<dyn super::IErrorInfo>::from(crate::ComBox::new(ThreadBoundErrorInfo));
+        let err = ComError::with_error_info(raw::E_ABORT, info);
+        assert_send_sync(&err);
+        assert!(err.error_object::<dyn super::IErrorInfo>().is_none());
+        assert_eq!(err.description(), Some("Not agile"));
+        assert_eq!(err.error_info.as_ref().unwrap().source(), "worker");
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum VaultError
+    {
+        Locked,
+        Missing(String),
+        Other(raw::HRESULT),
+    }
+
+    const E_VAULT_MISSING: raw::HRESULT = raw::HRESULT {
+        hr: 0x8004_0201u32 as i32,
+    };
+
+    impl std::fmt::Display for VaultError
+    {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+        {
+            match self {
+                VaultError::Locked => write!(f, "The vault is locked"),
+                VaultError::Missing(key) => write!(f, "No entry for {}", key),
+                VaultError::Other(hr) => write!(f, "Vault error {}", hr),
+            }
+        }
+    }
+
+    unsafe impl<TS: super::TypeSystem> super::ExternType<TS> for VaultError
+    {
+        type ForeignType = raw::HRESULT;
+    }
+
+    unsafe impl<TS: super::TypeSystem> super::ExternOutput<TS> for VaultError
+    {
+        fn into_foreign_output(self) -> crate::ComResult<raw::HRESULT>
+        {
+            Ok(super::ErrorCode::hresult(&self))
+        }
+
+        unsafe fn from_foreign_output(source: raw::HRESULT) -> crate::ComResult<Self>
+        {
+            Ok(ComError::new_hr(source).into())
+        }
+    }
+
+    impl super::ErrorCode for VaultError
+    {
+        fn hresult(&self) -> raw::HRESULT
+        {
+            match self {
+                VaultError::Locked => raw::E_ACCESSDENIED,
+                VaultError::Missing(..) => E_VAULT_MISSING,
+                VaultError::Other(hr) => *hr,
+            }
+        }
+    }
+
+    impl From<ComError> for VaultError
+    {
+        fn from(error: ComError) -> Self
+        {
+            match error.hresult {
+                raw::E_ACCESSDENIED => VaultError::Locked,
+                E_VAULT_MISSING => {
+                    VaultError::Missing(error.description().unwrap_or_default().to_string())
+                }
+                hr => VaultError::Other(hr),
+            }
+        }
+    }
+
+    #[crate::com_interface]
+    trait IVault
+    {
+        fn open(&self, key: u32) -> Result<u32, VaultError>;
+        fn read(&self, key: u32) -> crate::ComResult<u32>;
+    }
+
+    #[crate::com_class(clsid = None, IVault)]
+    struct Vault;
+
+    impl IVault for Vault
+    {
+        fn open(&self, key: u32) -> Result<u32, VaultError>
+        {
+            match key {
+                0 => Ok(1),
+                1 => Err(VaultError::Locked),
+                _ => Err(VaultError::Missing(key.to_string())),
+            }
+        }
+
+        fn read(&self, key: u32) -> crate::ComResult<u32>
+        {
+            Ok(self.open(key)?)
+        }
+    }
+
+    #[test]
+    fn error_code_is_preserved()
+    {
+        let rc = crate::ComRc::<dyn IVault>::from(crate::ComBox::new(Vault));
+        assert_eq!(rc.open(0).unwrap(), 1);
+        assert_eq!(rc.open(1).unwrap_err(), VaultError::Locked);
+        assert_eq!(
+            rc.open(5).unwrap_err(),
+            VaultError::Missing("No entry for 5".to_string())
+        );
+
+        let err = rc.read(1).unwrap_err();
+        assert_eq!(err.hresult, raw::E_ACCESSDENIED);
+        assert_eq!(err.description(), Some("The vault is locked"));
+        let err = rc.read(2).unwrap_err();
+        assert_eq!(err.hresult, E_VAULT_MISSING);
+        assert_eq!(err.description(), Some("No entry for 2"));
+    }
+
+    #[derive(Debug)]
+    struct Layer(&'static str, Option<Box<Layer>>);
+
+    impl std::fmt::Display for Layer
+    {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+        {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl std::error::Error for Layer
+    {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)>
+        {
+            self.1
+                .as_ref()
+                .map(|l| l as &(dyn std::error::Error + 'static))
+        }
+    }
+
+    #[derive(Debug)]
+    struct Endless;
+
+    impl std::fmt::Display for Endless
+    {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+        {
+            write!(f, "again")
+        }
+    }
+
+    impl std::error::Error for Endless
+    {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)>
+        {
+            Some(&Endless)
+        }
+    }
+
+    #[crate::com_interface]
+    trait IConfigReader
+    {
+        fn read_config(&self, depth: u32) -> std::io::Result<()>;
+    }
+
+    #[crate::com_class(clsid = None, IConfigReader)]
+    struct ConfigReader;
+
+    impl IConfigReader for ConfigReader
+    {
+        fn read_config(&self, depth: u32) -> std::io::Result<()>
+        {
+            let other = |e: Box<dyn std::error::Error + Send + Sync>| Err(std::io::Error::other(e));
+            match depth {
+                0 => Err(std::io::ErrorKind::NotFound.into()),
+                1 => other(Box::new(Layer("outer", None))),
+                2 => other(Box::new(Layer(
+                    "outer",
+                    Some(Box::new(Layer(
+                        "middle",
+                        Some(Box::new(Layer("inner", None))),
+                    ))),
+                ))),
+                _ => other(Box::new(Endless)),
+            }
+        }
+    }
+
+    #[test]
+    fn source_chain_is_described()
+    {
+        let rc = crate::ComRc::<dyn IConfigReader>::from(crate::ComBox::new(ConfigReader));
+        let description = |depth| {
+            let err = rc.read_config(depth).unwrap_err();
+            let err = err.into_inner().unwrap().downcast::<ComError>().unwrap();
+            err.description().map(str::to_string)
+        };
+
+        assert_eq!(description(0), Some("entity not found".to_string()));
+        assert_eq!(description(1), Some("outer".to_string()));
+        assert_eq!(description(2), Some("outer: middle: inner".to_string()));
+
+        let endless = description(3).unwrap();
+        assert_eq!(endless.split(": ").count(), 1 + super::MAX_SOURCE_CHAIN);
+    }
 }
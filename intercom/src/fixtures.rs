@@ -0,0 +1,48 @@
+//! COM classes shared by the unit tests.
+//!
+//! The behavior visible to the COM clients is tested through the testlib
+//! and the cpp-raw tests. The fixtures here cover the Rust side only.
+
+use crate::{ComError, ComResult};
+use std::cell::Cell;
+
+#[crate::com_interface(dual)]
+pub trait IGreeter
+{
+    fn greet(&self, name: &str, times: u32) -> ComResult<String>;
+    #[com_property(get)]
+    fn get_volume(&self) -> u32;
+    #[com_property(put)]
+    fn set_volume(&self, volume: u32);
+    fn hush(&self) -> ComResult<()>;
+}
+
+#[crate::com_class(clsid = None, IGreeter)]
+#[derive(Default)]
+pub struct Greeter
+{
+    volume: Cell<u32>,
+}
+
+impl IGreeter for Greeter
+{
+    fn greet(&self, name: &str, times: u32) -> ComResult<String>
+    {
+        Ok(vec![format!("Hello {}", name); times as usize].join(", "))
+    }
+
+    fn get_volume(&self) -> u32
+    {
+        self.volume.get()
+    }
+
+    fn set_volume(&self, volume: u32)
+    {
+        self.volume.set(volume)
+    }
+
+    fn hush(&self) -> ComResult<()>
+    {
+        Err(ComError::E_ACCESSDENIED.with_message("Too loud"))
+    }
+}
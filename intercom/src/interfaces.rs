@@ -14,9 +14,7 @@ use crate::type_system::{AutomationTypeSystem, RawTypeSystem};
     raw_iid = "11111111-0000-0000-C000-000000000046",
     base = NO_BASE,
     vtable_of = RawIUnknown )]
-pub trait IUnknown
-{
-}
+pub trait IUnknown {}
 
 #[com_interface(
     com_iid = "00000000-0000-0000-C000-000000000046",
@@ -130,6 +128,115 @@ where
     intercom::ComBoxData::<S>::release(combox)
 }
 
+/// The `IDispatch` COM interface.
+///
+/// The `IDispatch` allows the scripting clients, such as VBScript, to call
+/// the methods by name without knowing the virtual table layout. The dual
+/// interfaces derive from `IDispatch` and serve the calls with their own
+/// methods.
+///
+/// For Rust code, Intercom implements the interface automatically for the
+/// interfaces declared with `#[com_interface(dual)]`.
+#[com_interface(
+    com_iid = "00020400-0000-0000-C000-000000000046",
+    raw_iid = "11120400-0000-0000-C000-000000000046",
+    vtable_of = RawIDispatch )]
+pub trait IDispatch: IUnknown {}
+
+#[com_interface(
+    com_iid = "00020400-0000-0000-C000-000000000046",
+    raw_iid = "11120400-0000-0000-C000-000000000046"
+)]
+pub trait RawIDispatch: IUnknown
+{
+    /// Gets the number of type infos describing the object, either 0 or 1.
+    ///
+    /// # Safety
+    ///
+    /// The `count` must be a valid pointer for writing a `u32`.
+    unsafe fn get_type_info_count(&self, count: *mut u32) -> crate::raw::HRESULT;
+
+    /// Gets the type info describing the object.
+    ///
+    /// # Safety
+    ///
+    /// The `type_info` must be a valid pointer for writing an interface
+    /// pointer. The caller owns the returned reference.
+    unsafe fn get_type_info(
+        &self,
+        index: u32,
+        lcid: u32,
+        type_info: *mut crate::raw::RawComPtr,
+    ) -> crate::raw::HRESULT;
+
+    /// Resolves the DISPIDs of the member name and its parameter names.
+    ///
+    /// # Safety
+    ///
+    /// The `names` must point to `count` valid null-terminated wide strings
+    /// and the `dispids` must be valid for writing `count` DISPIDs.
+    unsafe fn get_ids_of_names(
+        &self,
+        riid: crate::REFIID,
+        names: *mut *mut u16,
+        count: u32,
+        lcid: u32,
+        dispids: *mut i32,
+    ) -> crate::raw::HRESULT;
+
+    /// Calls the member identified by the DISPID.
+    ///
+    /// # Safety
+    ///
+    /// The `params` must point to valid `DISPPARAMS`. The `result`,
+    /// `excep_info` and `arg_err` must be either null or valid for writing.
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn invoke(
+        &self,
+        dispid: i32,
+        riid: crate::REFIID,
+        lcid: u32,
+        flags: u16,
+        params: *mut crate::raw::DISPPARAMS,
+        result: *mut crate::raw::Variant<AutomationTypeSystem>,
+        excep_info: *mut crate::raw::EXCEPINFO,
+        arg_err: *mut u32,
+    ) -> crate::raw::HRESULT;
+}
+
+impl<I, S> crate::attributes::ComInterfaceVTableFor<I, S, RawTypeSystem> for dyn IDispatch
+where
+    I: crate::attributes::ComDispatch + ?Sized,
+    S: intercom::attributes::ComClassInterface<I, RawTypeSystem> + intercom::attributes::ComClass,
+{
+    const VTABLE: Self::VTable = Self::VTable {
+        __base:
+            <dyn IUnknown as crate::attributes::ComInterfaceVTableFor<I, S, RawTypeSystem>>::VTABLE,
+        get_type_info_count: crate::dispatch::get_type_info_count::<I, S, RawTypeSystem>,
+        get_type_info: crate::dispatch::get_type_info::<I, S, RawTypeSystem>,
+        get_ids_of_names: crate::dispatch::get_ids_of_names::<I, S, RawTypeSystem>,
+        invoke: crate::dispatch::invoke::<I, S, RawTypeSystem>,
+    };
+}
+impl<I, S> crate::attributes::ComInterfaceVTableFor<I, S, AutomationTypeSystem> for dyn IDispatch
+where
+    I: crate::attributes::ComDispatch + ?Sized,
+    S: intercom::attributes::ComClassInterface<I, AutomationTypeSystem>
+        + intercom::attributes::ComClass,
+{
+    const VTABLE: Self::VTable = Self::VTable {
+        __base: <dyn IUnknown as crate::attributes::ComInterfaceVTableFor<
+            I,
+            S,
+            AutomationTypeSystem,
+        >>::VTABLE,
+        get_type_info_count: crate::dispatch::get_type_info_count::<I, S, AutomationTypeSystem>,
+        get_type_info: crate::dispatch::get_type_info::<I, S, AutomationTypeSystem>,
+        get_ids_of_names: crate::dispatch::get_ids_of_names::<I, S, AutomationTypeSystem>,
+        invoke: crate::dispatch::invoke::<I, S, AutomationTypeSystem>,
+    };
+}
+
 /// The `ISupportErrorInfo` COM interface.
 ///
 /// The `ISupportErrorInfo` is part of COM error handling concept. As the
@@ -173,8 +280,9 @@ pub trait ISupportErrorInfo: IUnknown
     /// `IErrorInfo` on failure.
     ///
     /// Intercom will implement the support for `IErrorInfo` automatically
-    /// for all custom interfaces the user defines. This includes returning
-    /// `S_OK` from this method.
+    /// for all custom interfaces the user defines. This method returns `S_OK`
+    /// for the interfaces with fallible methods under the `error_info` policy
+    /// and `S_FALSE` for the rest.
     ///
     fn interface_supports_error_info(&self, riid: crate::REFIID) -> crate::raw::HRESULT;
 }
@@ -195,3 +303,43 @@ pub mod isupporterrorinfo
         }
     }
 }
+
+/// The `IAgileObject` COM interface.
+///
+/// Marks the objects that may be called from any thread without marshaling.
+/// The interface has no methods of its own. Such objects may be held in an
+/// `AgileRef`, which moves them between threads.
+///
+/// Intercom requires the COM classes implementing the interface to be `Send`
+/// and `Sync`.
+#[com_interface(
+    com_iid = "94EA2B94-E9CC-49E0-C0FF-EE64CA8F5B90",
+    raw_iid = "11EA2B94-E9CC-49E0-C0FF-EE64CA8F5B90",
+    vtable_of = RawIUnknown )]
+pub trait IAgileObject: IUnknown {}
+
+impl<I, S> crate::attributes::ComInterfaceVTableFor<I, S, RawTypeSystem> for dyn IAgileObject
+where
+    I: ?Sized,
+    S: intercom::attributes::ComClassInterface<I, RawTypeSystem>
+        + intercom::attributes::ComClass
+        + Send
+        + Sync,
+{
+    const VTABLE: Self::VTable =
+        <dyn IUnknown as crate::attributes::ComInterfaceVTableFor<I, S, RawTypeSystem>>::VTABLE;
+}
+impl<I, S> crate::attributes::ComInterfaceVTableFor<I, S, AutomationTypeSystem> for dyn IAgileObject
+where
+    I: ?Sized,
+    S: intercom::attributes::ComClassInterface<I, AutomationTypeSystem>
+        + intercom::attributes::ComClass
+        + Send
+        + Sync,
+{
+    const VTABLE: Self::VTable = <dyn IUnknown as crate::attributes::ComInterfaceVTableFor<
+        I,
+        S,
+        AutomationTypeSystem,
+    >>::VTABLE;
+}
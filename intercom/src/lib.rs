@@ -86,16 +86,27 @@ pub use crate::strings::*;
 mod guid;
 pub use crate::guid::GUID;
 pub mod error;
-pub use crate::error::{load_error, store_error, ComError, ErrorValue};
+pub use crate::error::{
+    hr_from_io, install_panic_hook, load_error, set_panic_to_error_info, store_error, with_retry,
+    ComError, ErrorCode, ErrorObject, ErrorValue,
+};
+pub mod agile;
 pub mod alloc;
+pub mod cancellation;
+pub mod dispatch;
 pub mod interfaces;
+pub mod operation;
 pub mod runtime;
 mod variant;
 pub use crate::variant::{Variant, VariantError};
+mod systemtime;
+mod tuples;
 pub mod type_system;
 pub mod typelib;
 pub use type_system::ForeignType;
 pub mod attributes;
+#[cfg(test)]
+mod fixtures;
 pub mod logging;
 
 #[cfg(windows)]
@@ -123,9 +134,13 @@ pub mod registry
     }
 }
 
+// The cancellation and the operation interfaces are described in every
+// library so that the methods may refer to them.
 com_module!(
     class intercom::alloc::Allocator,
     class intercom::error::ErrorStore,
+    interface intercom::cancellation::ICancellationToken,
+    interface intercom::operation::IOperation,
 );
 
 /// Raw COM pointer type.
@@ -147,7 +162,9 @@ pub mod raw
     use std::os::raw::c_void;
     use std::ptr::NonNull;
 
+    pub use crate::dispatch::raw::*;
     pub use crate::error::raw::*;
+    pub use crate::systemtime::raw::*;
     pub use crate::type_system::{ForeignType, TypeSystem};
     pub use crate::variant::raw::*;
 
@@ -164,6 +181,18 @@ pub mod raw
     #[repr(transparent)]
     pub struct BSTR(pub *mut u16);
 
+    /// A null terminated UTF-16 string allocated with the COM task allocator.
+    #[derive(
+        Clone,
+        Copy,
+        intercom_attributes::ExternType,
+        intercom_attributes::ExternInput,
+        intercom_attributes::ExternOutput,
+        intercom_attributes::ForeignType,
+    )]
+    #[repr(transparent)]
+    pub struct LPWSTR(pub *mut u16);
+
     #[repr(transparent)]
     #[derive(PartialEq, Eq)]
     pub struct InterfacePtr<TS: TypeSystem, I: ?Sized>
@@ -261,6 +290,8 @@ pub const IID_IErrorInfo: GUID = GUID {
 pub use crate::interfaces::IUnknown;
 // pub use crate::interfaces::__IUnknown_AutomationVtbl as IUnknownVtbl;
 
+pub use crate::interfaces::IDispatch;
+
 pub use crate::interfaces::ISupportErrorInfo;
 // pub use crate::interfaces::__ISupportErrorInfo_AutomationVtbl as ISupportErrorInfoVtbl;
 
@@ -270,6 +301,59 @@ pub use crate::interfaces::ISupportErrorInfo;
 /// `[out, retval]` parameter and `HRESULT` return value.
 pub type ComResult<A> = Result<A, ComError>;
 
+/// Successful value with an explicit success `HRESULT`.
+///
+/// Methods returning `ComResult<Success<T>>` report the `hresult` to the COM
+/// callers instead of `S_OK`, such as `S_FALSE` for a search that found
+/// nothing. The `Success` is constructed with either `ok` or `s_false` so the
+/// `hresult` is always `S_OK` or `S_FALSE`. The Rust callers receive the
+/// `HRESULT` the server returned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Success<T>
+{
+    hresult: raw::HRESULT,
+    value: T,
+}
+
+impl<T> Success<T>
+{
+    /// Succeeds with `S_OK`.
+    pub fn ok(value: T) -> Success<T>
+    {
+        Success {
+            hresult: raw::S_OK,
+            value,
+        }
+    }
+
+    /// Succeeds with `S_FALSE`.
+    pub fn s_false(value: T) -> Success<T>
+    {
+        Success {
+            hresult: raw::S_FALSE,
+            value,
+        }
+    }
+
+    /// Gets the `HRESULT`, either `S_OK` or `S_FALSE`.
+    pub fn hresult(&self) -> raw::HRESULT
+    {
+        self.hresult
+    }
+
+    /// Gets the value written into the `[retval]` parameter.
+    pub fn into_value(self) -> T
+    {
+        self.value
+    }
+
+    /// Splits the `Success` into the `HRESULT` and the value.
+    pub fn into_parts(self) -> (raw::HRESULT, T)
+    {
+        (self.hresult, self.value)
+    }
+}
+
 /// Basic COM result type.
 ///
 /// The `ComResult` maps the Rust concept of `Ok` and `Err` values to COM
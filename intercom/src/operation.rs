@@ -0,0 +1,667 @@
+//! Completion handles for long running operations.
+//!
+//! A method that would block the caller for a long time can start the work
+//! with [`spawn`] and return the `IOperation` handle immediately. The work
+//! runs on a thread pool managed by intercom, which keeps intercom free of
+//! any specific async runtime while still allowing every COM client to wait
+//! for the operation or to poll it for completion.
+//!
+//! [`spawn`]: fn.spawn.html
+//!
+//! The methods of the impl interfaces marked with `#[com_method(operation)]`
+//! are run this way by intercom. The method takes `&self` and its parameters
+//! by value and returns `Result<T, E>`. The COM callers receive the
+//! `IOperation` in place of the `T`, which `get_result` returns once the
+//! method has completed.
+//!
+//! The methods of the impl interfaces may also return
+//! `impl Future<Output = Result<T, E>> + Send + 'static`. Such methods take
+//! an `ICompletionHandler` from the caller in place of the return value. The
+//! future is driven on the same thread pool and the handler is notified once
+//! the future completes. The handler is notified from the worker threads so
+//! it must implement `IAgileObject`.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::time::Duration;
+
+use super::*;
+use crate::agile::AgileRef;
+use crate::attributes::ComClass;
+
+/// Completion handle of an operation running in the background.
+#[com_interface(
+    com_iid = "6d0b9c3e-5f2a-4e71-8b46-2c9a1e7f3d58",
+    raw_iid = "b85e1f47-09a3-4c6d-9e2b-7f4a3d6c1e90"
+)]
+pub trait IOperation: crate::IUnknown
+{
+    /// Checks whether the operation has completed.
+    fn is_complete(&self) -> ComResult<bool>;
+
+    /// Waits for the operation to complete.
+    ///
+    /// Returns `true` if the operation completed within `timeout_ms`
+    /// milliseconds.
+    fn wait(&self, timeout_ms: u32) -> ComResult<bool>;
+
+    /// Gets the result of the operation.
+    ///
+    /// Fails with `E_PENDING` if the operation hasn't completed yet. Otherwise
+    /// the result of the work is returned, including any error it failed
+    /// with.
+    fn get_result(&self) -> ComResult<Variant>;
+}
+
+/// Result of the work stored for the clients.
+///
+/// `Variant` may hold COM interfaces, which can't be sent between threads, so
+/// the value is stored in a form that the variant can be created from on
+/// every `get_result` call.
+type Outcome = Result<Box<dyn Fn() -> Variant + Send>, ComError>;
+
+/// State shared between the operation object and the thread running the
+/// work.
+#[derive(Default)]
+struct OperationState
+{
+    outcome: Mutex<Option<Outcome>>,
+    completed: Condvar,
+}
+
+/// Operation object returned by `spawn`.
+#[com_class(clsid = None, IOperation)]
+pub struct Operation
+{
+    state: Arc<OperationState>,
+}
+
+impl IOperation for Operation
+{
+    fn is_complete(&self) -> ComResult<bool>
+    {
+        Ok(self.state.outcome.lock().unwrap().is_some())
+    }
+
+    fn wait(&self, timeout_ms: u32) -> ComResult<bool>
+    {
+        let outcome = self.state.outcome.lock().unwrap();
+        let (outcome, _) = self
+            .state
+            .completed
+            .wait_timeout_while(outcome, Duration::from_millis(timeout_ms.into()), |o| {
+                o.is_none()
+            })
+            .unwrap();
+        Ok(outcome.is_some())
+    }
+
+    fn get_result(&self) -> ComResult<Variant>
+    {
+        match &*self.state.outcome.lock().unwrap() {
+            Some(Ok(value)) => Ok(value()),
+            Some(Err(e)) => Err(e.clone()),
+            None => Err(ComError::new_hr(raw::E_PENDING)),
+        }
+    }
+}
+
+/// Starts the `work` on the intercom thread pool.
+///
+/// Returns the handle to the operation immediately. Panics within the work
+/// are reported as errors through the handle.
+pub fn spawn<T, F>(work: F) -> ComRc<dyn IOperation>
+where
+    F: FnOnce() -> ComResult<T> + Send + 'static,
+    T: Into<Variant> + Clone + Send + 'static,
+{
+    let state = Arc::new(OperationState::default());
+    let worker_state = state.clone();
+    submit(Box::new(move || {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(work))
+            .unwrap_or_else(|payload| Err(crate::error::panic_error(payload)));
+        let outcome: Outcome = match result {
+            Ok(value) => Ok(Box::new(move || value.clone().into())),
+            Err(e) => Err(e),
+        };
+        *worker_state.outcome.lock().unwrap() = Some(outcome);
+        worker_state.completed.notify_all();
+    }));
+
+    ComRc::from(ComBox::new(Operation { state }))
+}
+
+/// Runs the `work` of a `#[com_method(operation)]` method on the intercom
+/// thread pool.
+///
+/// The object is kept alive until the work has completed and the shared lock
+/// of the class is held while the work runs.
+///
+/// # Safety
+///
+/// The `object` must be contained in a `ComBoxData`.
+#[doc(hidden)]
+pub unsafe fn spawn_method<S, F, T, E>(object: &S, work: F) -> ComRc<dyn IOperation>
+where
+    S: ComClass + Send + Sync + 'static,
+    F: FnOnce(&S) -> Result<T, E> + Send + 'static,
+    T: Into<Variant> + Clone + Send + 'static,
+    E: Into<ComError>,
+{
+    let object = ObjectRef::new(ComBoxData::of(object));
+    spawn(move || {
+        let data = object.get();
+        let _lock = data.lock_shared();
+        work(data).map_err(Into::into)
+    })
+}
+
+/// Reference keeping the object of an operation alive on the worker thread.
+struct ObjectRef<S: ComClass>(*mut ComBoxData<S>);
+
+// The worker calls the object and may release the last reference to it.
+unsafe impl<S: ComClass + Send + Sync> Send for ObjectRef<S> {}
+
+impl<S: ComClass> ObjectRef<S>
+{
+    unsafe fn new(data: &ComBoxData<S>) -> ObjectRef<S>
+    {
+        ComBoxData::add_ref(data);
+        ObjectRef(data as *const _ as *mut _)
+    }
+
+    fn get(&self) -> &ComBoxData<S>
+    {
+        unsafe { &*self.0 }
+    }
+}
+
+impl<S: ComClass> Drop for ObjectRef<S>
+{
+    fn drop(&mut self)
+    {
+        unsafe { ComBoxData::release(self.0) };
+    }
+}
+
+/// Receives the result of a method returning a future.
+#[com_interface(
+    com_iid = "3a9d5e71-c04b-4f2e-a6d8-51b7e2c9f043",
+    raw_iid = "e4c61b28-7d95-4a3f-8e0c-96f2b5d13a7e"
+)]
+pub trait ICompletionHandler: crate::IUnknown
+{
+    /// Called with the output of the future once it has completed.
+    fn on_success(&self, result: Variant) -> ComResult<()>;
+
+    /// Called with the error if the future failed.
+    fn on_error(&self, error: raw::HRESULT, description: String) -> ComResult<()>;
+}
+
+/// Drives the `future` of a COM method on the intercom thread pool and
+/// notifies the `handler` once it has completed.
+///
+/// The handler is notified from the worker threads so it must implement
+/// `IAgileObject`. Other handlers are rejected with `E_NOINTERFACE`. The
+/// handler reference is held until the handler has been notified.
+#[doc(hidden)]
+pub fn complete<F, T, E>(handler: ComRc<dyn ICompletionHandler>, future: F) -> raw::HRESULT
+where
+    F: Future<Output = Result<T, E>> + Send + 'static,
+    T: Into<Variant>,
+    E: Into<ComError>,
+{
+    let handler = match AgileRef::new(&handler) {
+        Ok(handler) => handler,
+        Err(e) => {
+            let e = e.with_message("The completion handler must implement IAgileObject");
+            return crate::store_error(e).hresult;
+        }
+    };
+
+    Task::spawn(async move {
+        let result = CatchUnwind(Box::pin(future)).await;
+        let handler = handler.get();
+        let notified = match result {
+            Ok(Ok(value)) => handler.on_success(value.into()),
+            Ok(Err(e)) => {
+                let (hr, description) = e.into().into();
+                handler.on_error(hr, description)
+            }
+            Err(payload) => {
+                let (hr, description) = crate::error::panic_error(payload).into();
+                handler.on_error(hr, description)
+            }
+        };
+
+        // There's no one to report the failures of the handler itself to.
+        drop(notified);
+    });
+
+    raw::S_OK
+}
+
+/// Future that reports the panics of the inner future as its output.
+struct CatchUnwind<F>(Pin<Box<F>>);
+
+impl<F: Future> Future for CatchUnwind<F>
+{
+    type Output = std::thread::Result<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output>
+    {
+        let future = self.0.as_mut();
+        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Pending) => Poll::Pending,
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Err(payload) => Poll::Ready(Err(payload)),
+        }
+    }
+}
+
+/// Future driven on the intercom thread pool.
+///
+/// The task is polled on a worker whenever it is woken. Pending tasks don't
+/// occupy the workers so any number of them may wait for each other.
+struct Task
+{
+    future: Mutex<Option<Pin<Box<dyn Future<Output = ()> + Send>>>>,
+}
+
+impl Task
+{
+    fn spawn<F: Future<Output = ()> + Send + 'static>(future: F)
+    {
+        let task = Arc::new(Task {
+            future: Mutex::new(Some(Box::pin(future))),
+        });
+        task.wake();
+    }
+
+    fn poll(self: Arc<Self>)
+    {
+        // Wakes during the poll submit the task again. The worker picking it
+        // up waits for the lock and polls the future once this poll is done.
+        let mut slot = self.future.lock().unwrap();
+        let future = match slot.as_mut() {
+            Some(future) => future,
+            None => return,
+        };
+
+        let waker = Waker::from(self.clone());
+        let mut context = Context::from_waker(&waker);
+        if future.as_mut().poll(&mut context).is_ready() {
+            *slot = None;
+        }
+    }
+}
+
+impl Wake for Task
+{
+    fn wake(self: Arc<Self>)
+    {
+        submit(Box::new(move || self.poll()));
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Sender for the jobs of the thread pool, started on the first use.
+static POOL: Mutex<Option<mpsc::Sender<Job>>> = Mutex::new(None);
+
+fn submit(job: Job)
+{
+    let mut pool = POOL.lock().unwrap();
+    let sender = pool.get_or_insert_with(|| {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        for i in 0..workers {
+            let receiver = receiver.clone();
+            std::thread::Builder::new()
+                .name(format!("intercom-worker-{}", i))
+                .spawn(move || loop {
+                    // The lock is released before running the job so the
+                    // other workers can pick up jobs in the meantime.
+                    let job = receiver.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                })
+                .expect("Failed to start the intercom worker thread");
+        }
+        sender
+    });
+    sender
+        .send(job)
+        .expect("The intercom worker threads have stopped");
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use crate::interfaces::IAgileObject;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn operation_is_polled_to_completion()
+    {
+        let (start, started) = mpsc::channel::<()>();
+        let op = spawn(move || -> ComResult<u32> {
+            started.recv().unwrap();
+            Ok(42)
+        });
+
+        assert!(!op.is_complete().unwrap());
+        assert!(!op.wait(1).unwrap());
+        assert_eq!(op.get_result().unwrap_err().hresult, raw::E_PENDING);
+
+        start.send(()).unwrap();
+        while !op.is_complete().unwrap() {
+            std::thread::yield_now();
+        }
+        assert!(op.wait(0).unwrap());
+        assert_eq!(u32::try_from(op.get_result().unwrap()).unwrap(), 42);
+        assert_eq!(u32::try_from(op.get_result().unwrap()).unwrap(), 42);
+    }
+
+    #[test]
+    fn operation_reports_errors()
+    {
+        let failed =
+            spawn(|| -> ComResult<u32> { Err(ComError::E_ACCESSDENIED.with_message("Locked")) });
+        assert!(failed.wait(10_000).unwrap());
+        let err = failed.get_result().unwrap_err();
+        assert_eq!(err.hresult, raw::E_ACCESSDENIED);
+        assert_eq!(err.description(), Some("Locked"));
+
+        let panicked = spawn(|| -> ComResult<u32> { panic!("Out of work") });
+        assert!(panicked.wait(10_000).unwrap());
+        assert_eq!(panicked.get_result().unwrap_err().hresult, raw::E_FAIL);
+    }
+
+    #[crate::com_class(clsid = None, Self)]
+    struct Calculator
+    {
+        gate: Mutex<mpsc::Receiver<()>>,
+    }
+
+    #[crate::com_interface]
+    impl Calculator
+    {
+        #[com_method(operation)]
+        fn square(&self, value: u32) -> ComResult<u32>
+        {
+            self.gate.lock().unwrap().recv().unwrap();
+            match value {
+                0 => Err(ComError::E_INVALIDARG.with_message("Nothing to square")),
+                _ => Ok(value * value),
+            }
+        }
+    }
+
+    #[test]
+    fn operation_method_returns_handle()
+    {
+        use crate::attributes::ComInterfaceVariant;
+        use crate::type_system::{ExternOutput, RawTypeSystem};
+
+        let (open, gate) = mpsc::channel();
+        let calculator = ComRc::<Calculator>::from(ComBox::new(Calculator {
+            gate: Mutex::new(gate),
+        }));
+        let ptr = ComItf::ptr::<RawTypeSystem>(&calculator).unwrap().ptr;
+        let square = |value: u32| unsafe {
+            let vtbl = ptr.as_ptr()
+                as *const *const <Calculator as ComInterfaceVariant<RawTypeSystem>>::VTable;
+            let mut op = None;
+            let hr = ((**vtbl).square)(ptr.as_ptr(), value, &mut op);
+            assert_eq!(hr, raw::S_OK);
+            <ComRc<dyn IOperation> as ExternOutput<RawTypeSystem>>::from_foreign_output(op).unwrap()
+        };
+
+        // The calls return before the method has run.
+        let op = square(7);
+        let failed = square(0);
+        assert!(!op.is_complete().unwrap());
+
+        // The operations hold their own reference to the object.
+        drop(calculator);
+        open.send(()).unwrap();
+        open.send(()).unwrap();
+        while !op.is_complete().unwrap() {
+            std::thread::yield_now();
+        }
+        assert_eq!(u32::try_from(op.get_result().unwrap()).unwrap(), 49);
+
+        assert!(failed.wait(10_000).unwrap());
+        let err = failed.get_result().unwrap_err();
+        assert_eq!(err.hresult, raw::E_INVALIDARG);
+        assert_eq!(err.description(), Some("Nothing to square"));
+    }
+
+    /// Completion seen by the `Recorder`.
+    #[derive(Debug, PartialEq)]
+    enum Event
+    {
+        Success(u32),
+        Error(raw::HRESULT, String),
+        Dropped,
+    }
+
+    #[crate::com_class(clsid = None, ICompletionHandler, IAgileObject)]
+    struct Recorder
+    {
+        events: Mutex<mpsc::Sender<Event>>,
+    }
+
+    impl ICompletionHandler for Recorder
+    {
+        fn on_success(&self, result: Variant) -> ComResult<()>
+        {
+            let value = u32::try_from(result)?;
+            self.events
+                .lock()
+                .unwrap()
+                .send(Event::Success(value))
+                .unwrap();
+            Ok(())
+        }
+
+        fn on_error(&self, error: raw::HRESULT, description: String) -> ComResult<()>
+        {
+            let event = Event::Error(error, description);
+            self.events.lock().unwrap().send(event).unwrap();
+            Ok(())
+        }
+    }
+
+    impl IAgileObject for Recorder {}
+
+    impl Drop for Recorder
+    {
+        fn drop(&mut self)
+        {
+            self.events.lock().unwrap().send(Event::Dropped).unwrap();
+        }
+    }
+
+    /// Future that is pending on the first poll.
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce
+    {
+        type Output = ();
+        fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut Context) -> Poll<()>
+        {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    #[crate::com_class(clsid = None, Self)]
+    struct Downloader;
+
+    #[crate::com_interface]
+    impl Downloader
+    {
+        fn download(&self, size: u32) -> impl Future<Output = ComResult<u32>> + Send + 'static
+        {
+            async move {
+                YieldOnce(false).await;
+                match size {
+                    0 => Err(ComError::E_INVALIDARG.with_message("Nothing to download")),
+                    _ => Ok(size * 2),
+                }
+            }
+        }
+    }
+
+    fn download(size: u32) -> Vec<Event>
+    {
+        use crate::attributes::ComInterfaceVariant;
+        use crate::type_system::RawTypeSystem;
+
+        let (sender, events) = mpsc::channel();
+        let handler = ComRc::<dyn ICompletionHandler>::from(ComBox::new(Recorder {
+            events: Mutex::new(sender),
+        }));
+
+        let downloader = ComRc::<Downloader>::from(ComBox::new(Downloader));
+        let ptr = ComItf::ptr::<RawTypeSystem>(&downloader).unwrap().ptr;
+        let hr = unsafe {
+            let vtbl = ptr.as_ptr()
+                as *const *const <Downloader as ComInterfaceVariant<RawTypeSystem>>::VTable;
+            ((**vtbl).download)(ptr.as_ptr(), size, ComItf::ptr(&handler))
+        };
+        assert_eq!(hr, raw::S_OK);
+
+        // The call holds its own reference to the handler until the handler
+        // has been notified.
+        drop(handler);
+        vec![events.recv().unwrap(), events.recv().unwrap()]
+    }
+
+    #[test]
+    fn future_completes_through_handler()
+    {
+        assert_eq!(download(21), vec![Event::Success(42), Event::Dropped]);
+        assert_eq!(
+            download(0),
+            vec![
+                Event::Error(raw::E_INVALIDARG, "Nothing to download".to_string()),
+                Event::Dropped
+            ]
+        );
+    }
+
+    #[crate::com_class(clsid = None, ICompletionHandler)]
+    struct ThreadBoundHandler;
+
+    impl ICompletionHandler for ThreadBoundHandler
+    {
+        fn on_success(&self, _result: Variant) -> ComResult<()>
+        {
+            Ok(())
+        }
+
+        fn on_error(&self, _error: raw::HRESULT, _description: String) -> ComResult<()>
+        {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn non_agile_handler_is_rejected()
+    {
+        let handler = ComRc::<dyn ICompletionHandler>::from(ComBox::new(ThreadBoundHandler));
+        let hr = complete(handler, async { Ok::<u32, ComError>(1) });
+        assert_eq!(hr, raw::E_NOINTERFACE);
+    }
+
+    /// Gate the futures wait on.
+    #[derive(Default)]
+    struct Gate
+    {
+        state: Mutex<(bool, Vec<Waker>)>,
+    }
+
+    impl Gate
+    {
+        fn open(&self)
+        {
+            let mut state = self.state.lock().unwrap();
+            state.0 = true;
+            state.1.drain(..).for_each(Waker::wake);
+        }
+    }
+
+    /// Future that is pending until the gate is opened.
+    struct WaitGate(Arc<Gate>);
+
+    impl Future for WaitGate
+    {
+        type Output = ();
+        fn poll(self: std::pin::Pin<&mut Self>, cx: &mut Context) -> Poll<()>
+        {
+            let mut state = self.0.state.lock().unwrap();
+            if state.0 {
+                return Poll::Ready(());
+            }
+            state.1.push(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn pending_futures_do_not_occupy_workers()
+    {
+        let (sender, events) = mpsc::channel();
+        let handler = ComRc::<dyn ICompletionHandler>::from(ComBox::new(Recorder {
+            events: Mutex::new(sender),
+        }));
+
+        // More futures wait for the gate than there are workers. The future
+        // opening the gate is started last.
+        let gate = Arc::new(Gate::default());
+        let waiting = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            + 1;
+        for _ in 0..waiting {
+            let gate = gate.clone();
+            let hr = complete(handler.clone(), async move {
+                WaitGate(gate).await;
+                Ok::<u32, ComError>(1)
+            });
+            assert_eq!(hr, raw::S_OK);
+        }
+        let hr = complete(handler.clone(), async move {
+            gate.open();
+            Ok::<u32, ComError>(0)
+        });
+        assert_eq!(hr, raw::S_OK);
+        drop(handler);
+
+        let timeout = Duration::from_secs(10);
+        let mut completed = 0;
+        for _ in 0..=waiting {
+            match events.recv_timeout(timeout).unwrap() {
+                Event::Success(value) => completed += value,
+                event => panic!("Unexpected event {:?}", event),
+            }
+        }
+        assert_eq!(completed as usize, waiting);
+        assert_eq!(events.recv_timeout(timeout).unwrap(), Event::Dropped);
+    }
+}
@@ -188,8 +188,10 @@ fn register_or_unregister(dll: HANDLE, lib: TypeLib, do_register: bool) -> Resul
 
     register_typelib(&path, &lib, &lib_name, do_register)?;
 
+    // The classes without a class factory cannot be created through COM so
+    // there's no point in registering them.
     for cls in lib.types.iter().filter_map(|t| match t {
-        TypeInfo::Class(cls) => Some(cls),
+        TypeInfo::Class(cls) if !cls.options.no_class_factory => Some(cls),
         _ => None,
     }) {
         register_class(&path, &lib, &lib_name, &lib_version, &cls, do_register)?;
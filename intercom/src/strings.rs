@@ -13,7 +13,7 @@ use std::{
 };
 
 use crate::intercom::{ComError, ComResult};
-use crate::raw::BSTR;
+use crate::raw::{BSTR, LPWSTR};
 use crate::type_system::{
     AutomationTypeSystem, ExternInput, ExternOutput, ExternType, RawTypeSystem,
 };
@@ -352,6 +352,116 @@ impl Drop for BString
     }
 }
 
+/// An owned UTF-16 string Rust type.
+///
+/// Used for passing the strings as `LPWSTR` values in the raw type system.
+/// Unlike `BSTR` the `LPWSTR` is only zero terminated. The strings returned
+/// to the COM clients are allocated with the COM task allocator, so the
+/// clients free them with `CoTaskMemFree` on Windows. The automation type
+/// system passes the strings as `BSTR` values.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct WString(
+    // The characters without the terminating zero.
+    Vec<u16>,
+);
+
+impl std::fmt::Debug for WString
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        write!(f, "WString(\"{}\")", String::from_utf16_lossy(&self.0))
+    }
+}
+
+impl WString
+{
+    /// Creates a `WString` from 16-bit wide characters.
+    ///
+    /// The string ends at the first zero character.
+    pub fn from_wide(chars: &[u16]) -> WString
+    {
+        let len = chars.iter().position(|&c| c == 0).unwrap_or(chars.len());
+        WString(chars[..len].to_vec())
+    }
+
+    /// Copies a zero terminated wide character string into a `WString`.
+    ///
+    /// Null pointers result in an empty string.
+    ///
+    /// # Safety
+    ///
+    /// The parameter must be null or a valid pointer to a zero terminated
+    /// string.
+    pub unsafe fn from_ptr(ptr: *const u16) -> WString
+    {
+        if ptr.is_null() {
+            return WString::default();
+        }
+
+        let mut len = 0;
+        while *ptr.add(len) != 0 {
+            len += 1;
+        }
+        WString(std::slice::from_raw_parts(ptr, len).to_vec())
+    }
+
+    /// Gets the string as a slice of 16-bit characters.
+    ///
+    /// Does not include the terminating zero.
+    pub fn as_slice(&self) -> &[u16]
+    {
+        &self.0
+    }
+
+    pub fn to_string(&self) -> Result<String, FormatError>
+    {
+        String::from_utf16(&self.0).map_err(|_| FormatError)
+    }
+
+    /// Copies the string into a `BString`.
+    fn to_bstring(&self) -> BString
+    {
+        if self.0.is_empty() {
+            return BString::default();
+        }
+
+        unsafe {
+            let bstr = os::SysAllocStringLen(self.0.as_ptr(), self.0.len() as u32);
+            if bstr.0.is_null() {
+                panic!("Allocating BStr failed.");
+            }
+            BString::from_ptr(bstr.0)
+        }
+    }
+
+    /// Copies a `BStr` into a `WString`.
+    fn from_bstr(bstr: &BStr) -> WString
+    {
+        match bstr.as_ptr().is_null() {
+            true => WString::default(),
+            false => WString::from_wide(unsafe {
+                std::slice::from_raw_parts(bstr.as_ptr(), bstr.len_bytes() as usize / 2)
+            }),
+        }
+    }
+}
+
+impl From<&str> for WString
+{
+    fn from(source: &str) -> WString
+    {
+        WString::from_wide(&source.encode_utf16().collect::<Vec<_>>())
+    }
+}
+
+impl From<String> for WString
+{
+    fn from(source: String) -> WString
+    {
+        WString::from(source.as_str())
+    }
+}
+
 pub type CStr = std::ffi::CStr;
 pub type CString = std::ffi::CString;
 
@@ -599,13 +709,13 @@ unsafe impl ExternOutput<RawTypeSystem> for String
     {
         log::trace!("String::into_foreign_output<Raw>");
         let cstring = CString::new(self).map_err(|_| ComError::E_INVALIDARG)?;
-        Ok(cstring.into_raw())
+        crate::alloc::allocate_cstring(&cstring)
     }
 
     unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
     {
         log::trace!("String::from_foreign_output<Raw>");
-        let cstring = CString::from_raw(source);
+        let cstring = crate::alloc::take_cstring(source);
         cstring.into_string().map_err(|_| ComError::E_INVALIDARG)
     }
 }
@@ -715,6 +825,8 @@ unsafe impl ExternOutput<AutomationTypeSystem> for BString
     fn into_foreign_output(self) -> ComResult<Self::ForeignType>
     {
         log::trace!("BString::into_foreign_output<Automation>");
+
+        // BString is already a BSTR. Hand over the allocation as is.
         Ok(BSTR(self.into_ptr() as *mut _))
     }
 
@@ -733,13 +845,13 @@ unsafe impl ExternOutput<RawTypeSystem> for BString
         self.to_string()
             .map_err(|_| ComError::E_INVALIDARG)
             .and_then(|string| CString::new(string).map_err(|_| ComError::E_INVALIDARG))
-            .map(|cstring| cstring.into_raw())
+            .and_then(|cstring| crate::alloc::allocate_cstring(&cstring))
     }
 
     unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
     {
         log::trace!("BString::from_foreign_output<Raw>");
-        CString::from_raw(source)
+        crate::alloc::take_cstring(source)
             .into_string()
             .map(BString::from)
             .map_err(|_| ComError::E_INVALIDARG)
@@ -824,13 +936,13 @@ unsafe impl ExternOutput<RawTypeSystem> for CString
     fn into_foreign_output(self) -> ComResult<Self::ForeignType>
     {
         log::trace!("CString::into_foreign_output<Raw>");
-        Ok(self.into_raw())
+        crate::alloc::allocate_cstring(&self)
     }
 
     unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
     {
         log::trace!("CString::from_foreign_output<Raw>");
-        Ok(CString::from_raw(source))
+        Ok(crate::alloc::take_cstring(source))
     }
 }
 
@@ -934,6 +1046,84 @@ unsafe impl<'a> ExternInput<RawTypeSystem> for &'a BStr
     }
 }
 
+// WString
+unsafe impl ExternType<AutomationTypeSystem> for WString
+{
+    type ForeignType = BSTR;
+}
+
+unsafe impl ExternType<RawTypeSystem> for WString
+{
+    type ForeignType = LPWSTR;
+}
+
+unsafe impl ExternInput<AutomationTypeSystem> for WString
+{
+    type Lease = BString;
+    unsafe fn into_foreign_parameter(self) -> ComResult<(Self::ForeignType, Self::Lease)>
+    {
+        log::trace!("WString::into_foreign_parameter<Automation>");
+        let bstring = self.to_bstring();
+        Ok((BSTR(bstring.as_ptr() as *mut _), bstring))
+    }
+
+    type Owned = Self;
+    unsafe fn from_foreign_parameter(source: Self::ForeignType) -> ComResult<Self::Owned>
+    {
+        log::trace!("WString::from_foreign_parameter<Automation>");
+        Ok(WString::from_bstr(BStr::from_ptr(source.0)))
+    }
+}
+
+unsafe impl ExternInput<RawTypeSystem> for WString
+{
+    type Lease = Vec<u16>;
+    unsafe fn into_foreign_parameter(self) -> ComResult<(Self::ForeignType, Self::Lease)>
+    {
+        log::trace!("WString::into_foreign_parameter<Raw>");
+        let mut chars = self.0;
+        chars.push(0);
+        Ok((LPWSTR(chars.as_mut_ptr()), chars))
+    }
+
+    type Owned = Self;
+    unsafe fn from_foreign_parameter(source: Self::ForeignType) -> ComResult<Self::Owned>
+    {
+        log::trace!("WString::from_foreign_parameter<Raw>");
+        Ok(WString::from_ptr(source.0))
+    }
+}
+
+unsafe impl ExternOutput<AutomationTypeSystem> for WString
+{
+    fn into_foreign_output(self) -> ComResult<Self::ForeignType>
+    {
+        log::trace!("WString::into_foreign_output<Automation>");
+        Ok(BSTR(self.to_bstring().into_ptr()))
+    }
+
+    unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
+    {
+        log::trace!("WString::from_foreign_output<Automation>");
+        Ok(WString::from_bstr(&BString::from_ptr(source.0)))
+    }
+}
+
+unsafe impl ExternOutput<RawTypeSystem> for WString
+{
+    fn into_foreign_output(self) -> ComResult<Self::ForeignType>
+    {
+        log::trace!("WString::into_foreign_output<Raw>");
+        crate::alloc::allocate_wstring(&self).map(LPWSTR)
+    }
+
+    unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
+    {
+        log::trace!("WString::from_foreign_output<Raw>");
+        Ok(crate::alloc::take_wstring(source.0))
+    }
+}
+
 #[cfg(test)]
 mod test
 {
@@ -959,6 +1149,116 @@ mod test
         }
     }
 
+    #[test]
+    fn bstring_output_transfers_allocation()
+    {
+        let bstring: BString = "foo".into();
+        let ptr = bstring.as_ptr();
+
+        let foreign =
+            <BString as ExternOutput<AutomationTypeSystem>>::into_foreign_output(bstring).unwrap();
+        assert_eq!(foreign.0 as *const u16, ptr);
+
+        let bstring = unsafe {
+            <BString as ExternOutput<AutomationTypeSystem>>::from_foreign_output(foreign).unwrap()
+        };
+        assert_eq!(bstring.as_ptr(), ptr);
+        assert_eq!(bstring.to_string().unwrap(), "foo");
+    }
+
+    #[test]
+    fn string_round_trips_through_bstr()
+    {
+        let original = "caf\u{e9} \u{1f980}".to_string();
+
+        // The crab is outside the BMP and needs a surrogate pair.
+        let (foreign, lease) = unsafe {
+            <String as ExternInput<AutomationTypeSystem>>::into_foreign_parameter(original.clone())
+                .unwrap()
+        };
+        assert_eq!(lease.len(), 7);
+        let units = unsafe { std::slice::from_raw_parts(foreign.0 as *const u16, 7) };
+        assert_eq!(
+            units,
+            original.encode_utf16().collect::<Vec<_>>().as_slice()
+        );
+
+        let param = unsafe {
+            <String as ExternInput<AutomationTypeSystem>>::from_foreign_parameter(foreign).unwrap()
+        };
+        assert_eq!(param, original);
+
+        let foreign =
+            <String as ExternOutput<AutomationTypeSystem>>::into_foreign_output(original.clone())
+                .unwrap();
+        let output = unsafe {
+            <String as ExternOutput<AutomationTypeSystem>>::from_foreign_output(foreign).unwrap()
+        };
+        assert_eq!(output, original);
+    }
+
+    #[test]
+    fn string_round_trips_through_raw()
+    {
+        let original = "caf\u{e9} \u{1f980}".to_string();
+
+        let (foreign, _lease) = unsafe {
+            <String as ExternInput<RawTypeSystem>>::into_foreign_parameter(original.clone())
+                .unwrap()
+        };
+        let param = unsafe {
+            <String as ExternInput<RawTypeSystem>>::from_foreign_parameter(foreign).unwrap()
+        };
+        assert_eq!(param, original);
+
+        let foreign =
+            <String as ExternOutput<RawTypeSystem>>::into_foreign_output(original.clone()).unwrap();
+        let output = unsafe {
+            <String as ExternOutput<RawTypeSystem>>::from_foreign_output(foreign).unwrap()
+        };
+        assert_eq!(output, original);
+    }
+
+    #[crate::com_interface]
+    trait IEditor
+    {
+        fn append(&self, #[com_arg(in_out)] text: &mut String) -> ComResult<()>;
+        fn append_and_fail(&self, #[com_arg(in_out)] text: &mut String) -> ComResult<()>;
+    }
+
+    #[crate::com_class(clsid = None, IEditor)]
+    struct Editor;
+
+    impl IEditor for Editor
+    {
+        fn append(&self, text: &mut String) -> ComResult<()>
+        {
+            text.push_str(" \u{1f980}");
+            Ok(())
+        }
+
+        fn append_and_fail(&self, text: &mut String) -> ComResult<()>
+        {
+            text.push_str(" \u{1f980}");
+            Err(ComError::E_FAIL)
+        }
+    }
+
+    #[test]
+    fn in_out_string_is_reallocated_by_callee()
+    {
+        let editor = crate::ComRc::<dyn IEditor>::from(crate::ComBox::new(Editor));
+
+        let mut text = "caf\u{e9}".to_string();
+        editor.append(&mut text).unwrap();
+        assert_eq!(text, "caf\u{e9} \u{1f980}");
+
+        // The failed calls leave the Rust value as it was.
+        let mut text = "caf\u{e9}".to_string();
+        assert!(editor.append_and_fail(&mut text).is_err());
+        assert_eq!(text, "caf\u{e9}");
+    }
+
     #[test]
     fn can_construct_bstr()
     {
@@ -0,0 +1,197 @@
+//! Marshaling of the calendar times as the `SYSTEMTIME` structure.
+//!
+//! The `chrono` feature maps `chrono::DateTime<Utc>` to `SYSTEMTIME` for the
+//! interfaces that prefer the calendar fields over the floating point `DATE`.
+
+pub mod raw
+{
+    /// Calendar time with a millisecond precision.
+    ///
+    /// Declared in `wtypes.idl`, which the IDL imports through `oaidl.idl`.
+    #[repr(C)]
+    #[derive(Copy, Clone, Debug, Default, PartialEq)]
+    #[allow(non_snake_case)]
+    pub struct SYSTEMTIME
+    {
+        pub wYear: u16,
+        pub wMonth: u16,
+        pub wDayOfWeek: u16,
+        pub wDay: u16,
+        pub wHour: u16,
+        pub wMinute: u16,
+        pub wSecond: u16,
+        pub wMilliseconds: u16,
+    }
+
+    impl crate::type_system::ForeignType for SYSTEMTIME
+    {
+        fn type_name() -> &'static str
+        {
+            "SYSTEMTIME"
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls
+{
+    use super::raw::SYSTEMTIME;
+    use crate::prelude::*;
+    use crate::type_system::{ExternInput, ExternOutput, ExternType, TypeSystem};
+    use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Timelike, Utc};
+
+    /// The years `SYSTEMTIME` is able to represent.
+    const YEARS: std::ops::RangeInclusive<i32> = 1601..=30827;
+
+    fn to_systemtime(time: &DateTime<Utc>) -> ComResult<SYSTEMTIME>
+    {
+        if !YEARS.contains(&time.year()) {
+            return Err(ComError::E_INVALIDARG);
+        }
+
+        // Leap seconds are reported as nanoseconds past the second.
+        let milliseconds = (time.nanosecond() / 1_000_000).min(999);
+        Ok(SYSTEMTIME {
+            wYear: time.year() as u16,
+            wMonth: time.month() as u16,
+            wDayOfWeek: time.weekday().num_days_from_sunday() as u16,
+            wDay: time.day() as u16,
+            wHour: time.hour() as u16,
+            wMinute: time.minute() as u16,
+            wSecond: time.second() as u16,
+            wMilliseconds: milliseconds as u16,
+        })
+    }
+
+    /// Converts the fields into a time, failing with `E_INVALIDARG` if any of
+    /// them is out of range. The day of the week is ignored.
+    fn from_systemtime(time: &SYSTEMTIME) -> ComResult<DateTime<Utc>>
+    {
+        if !YEARS.contains(&i32::from(time.wYear)) {
+            return Err(ComError::E_INVALIDARG);
+        }
+
+        NaiveDate::from_ymd_opt(
+            i32::from(time.wYear),
+            u32::from(time.wMonth),
+            u32::from(time.wDay),
+        )
+        .and_then(|date| {
+            date.and_hms_milli_opt(
+                u32::from(time.wHour),
+                u32::from(time.wMinute),
+                u32::from(time.wSecond),
+                u32::from(time.wMilliseconds),
+            )
+        })
+        // The milliseconds past 999 would denote a leap second.
+        .filter(|_| time.wMilliseconds < 1000)
+        .map(|naive| Utc.from_utc_datetime(&naive))
+        .ok_or(ComError::E_INVALIDARG)
+    }
+
+    unsafe impl<TS: TypeSystem> ExternType<TS> for DateTime<Utc>
+    {
+        type ForeignType = SYSTEMTIME;
+    }
+
+    unsafe impl<TS: TypeSystem> ExternInput<TS> for DateTime<Utc>
+    {
+        type Lease = ();
+        unsafe fn into_foreign_parameter(self) -> ComResult<(Self::ForeignType, ())>
+        {
+            to_systemtime(&self).map(|time| (time, ()))
+        }
+
+        type Owned = Self;
+        unsafe fn from_foreign_parameter(source: Self::ForeignType) -> ComResult<Self::Owned>
+        {
+            from_systemtime(&source)
+        }
+    }
+
+    unsafe impl<TS: TypeSystem> ExternOutput<TS> for DateTime<Utc>
+    {
+        fn into_foreign_output(self) -> ComResult<Self::ForeignType>
+        {
+            to_systemtime(&self)
+        }
+
+        unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
+        {
+            from_systemtime(&source)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "chrono"))]
+mod test
+{
+    use super::raw::SYSTEMTIME;
+    use crate::prelude::*;
+    use crate::type_system::{AutomationTypeSystem, ExternInput};
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    #[crate::com_interface]
+    trait IClock
+    {
+        fn later(&self, time: DateTime<Utc>, seconds: i64) -> ComResult<DateTime<Utc>>;
+    }
+
+    #[crate::com_class(clsid = None, IClock)]
+    struct Clock;
+
+    impl IClock for Clock
+    {
+        fn later(&self, time: DateTime<Utc>, seconds: i64) -> ComResult<DateTime<Utc>>
+        {
+            Ok(time + Duration::seconds(seconds))
+        }
+    }
+
+    #[test]
+    fn systemtime_round_trip()
+    {
+        let time =
+            Utc.with_ymd_and_hms(2020, 2, 29, 23, 59, 30).unwrap() + Duration::milliseconds(250);
+        let rc = crate::ComRc::<dyn IClock>::from(crate::ComBox::new(Clock));
+        assert_eq!(rc.later(time, 0).unwrap(), time);
+        assert_eq!(
+            rc.later(time, 60).unwrap(),
+            Utc.with_ymd_and_hms(2020, 3, 1, 0, 0, 30).unwrap() + Duration::milliseconds(250)
+        );
+
+        let (raw, _) = unsafe {
+            <DateTime<Utc> as ExternInput<AutomationTypeSystem>>::into_foreign_parameter(time)
+        }
+        .unwrap();
+        assert_eq!(
+            raw,
+            SYSTEMTIME {
+                wYear: 2020,
+                wMonth: 2,
+                wDayOfWeek: 6,
+                wDay: 29,
+                wHour: 23,
+                wMinute: 59,
+                wSecond: 30,
+                wMilliseconds: 250,
+            }
+        );
+    }
+
+    #[test]
+    fn out_of_range_month_is_rejected()
+    {
+        let time = SYSTEMTIME {
+            wYear: 2020,
+            wMonth: 13,
+            wDay: 1,
+            ..Default::default()
+        };
+        let result = unsafe {
+            <DateTime<Utc> as ExternInput<AutomationTypeSystem>>::from_foreign_parameter(time)
+        };
+        assert_eq!(result.unwrap_err().hresult, crate::raw::E_INVALIDARG);
+    }
+}
@@ -0,0 +1,193 @@
+//! Structures for passing tuples by value.
+//!
+//! Rust tuples have no defined layout so the tuple parameters are passed as
+//! `#[repr(C)]` structures with a field for each element. The structures are
+//! named after the foreign types of the elements, such as `Tuple_i32_i32`,
+//! and described in the type library.
+//!
+//! The structures can be returned by pointer in a `Box`, such as
+//! `ComResult<Box<Tuple2<i32, i32>>>`.
+
+use crate::prelude::*;
+use crate::type_system::{
+    ExternInput, ExternOutput, ExternType, ForeignType, InfallibleExternInput, OutputGuard,
+    TypeSystem,
+};
+use crate::typelib::{Struct, StructField, TypeInfo};
+use std::borrow::Cow;
+
+/// Builds the type library name of a tuple structure from the element types.
+fn struct_name(elements: &[(Cow<'static, str>, u32)]) -> String
+{
+    let mut name = "Tuple".to_string();
+    for (ty, indirection_level) in elements {
+        name.push('_');
+        name.extend(ty.chars().map(|c| match c.is_ascii_alphanumeric() {
+            true => c,
+            false => '_',
+        }));
+        for _ in 0..*indirection_level {
+            name.push_str("Ptr");
+        }
+    }
+    name
+}
+
+macro_rules! tuple_struct {
+    ( $name:ident : $( $t:ident $idx:tt $field:literal ),* ) => {
+        /// Tuple with a `#[repr(C)]` layout.
+        #[repr(C)]
+        #[derive(Copy, Clone, Debug, Default, PartialEq)]
+        pub struct $name<$( $t ),*>( $( pub $t ),* );
+
+        impl<$( $t: ForeignType ),*> ForeignType for $name<$( $t ),*>
+        {
+            fn type_name() -> &'static str
+            {
+                stringify!($name)
+            }
+
+            fn type_library_name() -> Cow<'static, str>
+            {
+                struct_name(&[ $( ($t::type_library_name(), $t::indirection_level()) ),* ]).into()
+            }
+        }
+
+        unsafe impl<TS: TypeSystem, $( $t: ExternType<TS> ),*> ExternType<TS> for $name<$( $t ),*>
+        {
+            type ForeignType = $name<$( $t::ForeignType ),*>;
+
+            fn type_info() -> Option<TypeInfo>
+            {
+                Some(TypeInfo::Struct(ComBox::new(Struct {
+                    name: Self::ForeignType::type_library_name(),
+                    fields: vec![ $( StructField {
+                        name: $field.into(),
+                        ty: $t::ForeignType::type_library_name(),
+                        indirection_level: $t::ForeignType::indirection_level(),
+                    } ),* ],
+                })))
+            }
+        }
+
+        unsafe impl<TS: TypeSystem, $( $t: ExternInput<TS> ),*> ExternInput<TS> for $name<$( $t ),*>
+        {
+            type Lease = ( $( $t::Lease, )* );
+            unsafe fn into_foreign_parameter(self) -> ComResult<(Self::ForeignType, Self::Lease)>
+            {
+                let elements = ( $( <$t as ExternInput<TS>>::into_foreign_parameter(self.$idx)?, )* );
+                Ok(($name( $( elements.$idx.0 ),* ), ( $( elements.$idx.1, )* )))
+            }
+
+            type Owned = $name<$( $t::Owned ),*>;
+            unsafe fn from_foreign_parameter(source: Self::ForeignType) -> ComResult<Self::Owned>
+            {
+                Ok($name( $( <$t as ExternInput<TS>>::from_foreign_parameter(source.$idx)? ),* ))
+            }
+        }
+
+        unsafe impl<TS: TypeSystem, $( $t: ExternOutput<TS> ),*> ExternOutput<TS> for $name<$( $t ),*>
+        {
+            fn into_foreign_output(self) -> ComResult<Self::ForeignType>
+            {
+                // The guards release the converted elements if a later one fails.
+                let elements = ( $( OutputGuard::<TS, $t>::wrap(
+                        <$t as ExternOutput<TS>>::into_foreign_output(self.$idx)?), )* );
+                Ok($name( $( elements.$idx.consume() ),* ))
+            }
+
+            unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
+            {
+                // All of the elements are converted to release them even if one fails.
+                let elements = ( $( <$t as ExternOutput<TS>>::from_foreign_output(source.$idx), )* );
+                Ok($name( $( elements.$idx? ),* ))
+            }
+        }
+
+        unsafe impl<TS: TypeSystem, $( $t: InfallibleExternInput<TS> ),*> InfallibleExternInput<TS>
+            for $name<$( $t ),*>
+        {
+            type Lease = ( $( $t::Lease, )* );
+            unsafe fn into_foreign_parameter(self) -> (Self::ForeignType, Self::Lease)
+            {
+                let elements = ( $( <$t as InfallibleExternInput<TS>>::into_foreign_parameter(self.$idx), )* );
+                ($name( $( elements.$idx.0 ),* ), ( $( elements.$idx.1, )* ))
+            }
+
+            type Owned = $name<$( $t::Owned ),*>;
+            unsafe fn from_foreign_parameter(source: Self::ForeignType) -> Self::Owned
+            {
+                $name( $( <$t as InfallibleExternInput<TS>>::from_foreign_parameter(source.$idx) ),* )
+            }
+        }
+    };
+}
+
+tuple_struct!(Tuple2: A 0 "item1", B 1 "item2");
+tuple_struct!(Tuple3: A 0 "item1", B 1 "item2", C 2 "item3");
+tuple_struct!(Tuple4: A 0 "item1", B 1 "item2", C 2 "item3", D 3 "item4");
+
+#[cfg(test)]
+mod test
+{
+    use crate::prelude::*;
+    use crate::type_system::{AutomationTypeSystem, ExternType, ForeignType, RawTypeSystem};
+    use crate::typelib::TypeInfo;
+
+    #[crate::com_interface]
+    trait IPoints
+    {
+        fn length(&self, point: (i32, i32)) -> i32;
+        fn describe(&self, point: (i32, i32), label: (String, u32)) -> ComResult<String>;
+    }
+
+    #[crate::com_class(clsid = None, IPoints)]
+    struct Points;
+
+    impl IPoints for Points
+    {
+        fn length(&self, point: (i32, i32)) -> i32
+        {
+            point.0.abs() + point.1.abs()
+        }
+
+        fn describe(&self, point: (i32, i32), label: (String, u32)) -> ComResult<String>
+        {
+            Ok(format!(
+                "{} {}: ({}, {})",
+                label.0, label.1, point.0, point.1
+            ))
+        }
+    }
+
+    #[test]
+    fn tuple_input()
+    {
+        let rc = ComRc::<dyn IPoints>::from(ComBox::new(Points));
+        assert_eq!(rc.length((3, -4)), 7);
+        assert_eq!(
+            rc.describe((1, 2), ("point".to_string(), 5)).unwrap(),
+            "point 5: (1, 2)"
+        );
+    }
+
+    #[test]
+    fn tuple_is_described_as_struct()
+    {
+        type Point = super::Tuple2<i32, i32>;
+        let name = <Point as ExternType<RawTypeSystem>>::ForeignType::type_library_name();
+        assert_eq!(name, "Tuple_i32_i32");
+
+        let info = match <Point as ExternType<AutomationTypeSystem>>::type_info() {
+            Some(TypeInfo::Struct(s)) => s,
+            other => panic!("Unexpected type info: {:?}", other),
+        };
+        assert_eq!(info.name, "Tuple_i32_i32");
+        let fields = info
+            .fields
+            .iter()
+            .map(|f| (f.name.as_ref(), f.ty.as_ref()))
+            .collect::<Vec<_>>();
+        assert_eq!(fields, vec![("item1", "i32"), ("item2", "i32")]);
+    }
+}
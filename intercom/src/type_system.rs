@@ -1,6 +1,8 @@
 use crate::prelude::*;
 use crate::ComItf;
 
+pub use crate::tuples::{Tuple2, Tuple3, Tuple4};
+
 #[derive(Debug, Clone, Copy, Hash, PartialOrd, PartialEq)]
 #[repr(C)]
 pub enum TypeSystemName
@@ -103,11 +105,39 @@ pub trait ForeignType
     {
         0
     }
+
+    /// The name of the type in the type library.
+    ///
+    /// Defaults to the `type_name`. Types named after their element type,
+    /// such as the interface arrays, build the name when requested.
+    fn type_library_name() -> std::borrow::Cow<'static, str>
+    {
+        Self::type_name().into()
+    }
 }
 
 pub unsafe trait ExternType<TS: TypeSystem>
 {
     type ForeignType: ForeignType;
+
+    /// Type library information for types that are described in the type
+    /// library themselves, such as enums.
+    ///
+    /// The types marshaled as some other foreign type are named by the type
+    /// information in the IDL.
+    fn type_info() -> Option<crate::typelib::TypeInfo>
+    {
+        None
+    }
+
+    /// Name of the type in the IDL if it differs from the foreign type.
+    ///
+    /// The IDL declares the names of the types not described in the type
+    /// library as typedefs of the foreign types.
+    fn idl_type_name() -> Option<String>
+    {
+        Self::type_info().map(|ti| ti.name().to_string())
+    }
 }
 
 /// Defines a type that may be used as a parameter type in Intercom interfaces.
@@ -185,6 +215,11 @@ pub unsafe trait ExternOutput<TS: TypeSystem>: ExternType<TS> + Sized
 /// This trait will be used within the code generated in the procedural macros.
 /// It is important to ensure this trait is implemented in such a way that its
 /// use in the macros is sound.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be received as a parameter of an infallible method",
+    note = "the conversion may fail, such as when an enum receives an unknown discriminant",
+    note = "return `ComResult` from the method to report the conversion errors"
+)]
 pub unsafe trait InfallibleExternInput<TS: TypeSystem>: ExternType<TS> + Sized
 {
     type Lease;
@@ -213,6 +248,11 @@ pub unsafe trait InfallibleExternInput<TS: TypeSystem>: ExternType<TS> + Sized
 /// This trait will be used within the code generated in the procedural macros.
 /// It is important to ensure this trait is implemented in such a way that its
 /// use in the macros is sound.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be returned from an infallible method",
+    note = "the conversion may fail, such as when an enum receives an unknown discriminant",
+    note = "return `ComResult` from the method to report the conversion errors"
+)]
 pub unsafe trait InfallibleExternOutput<TS: TypeSystem>: ExternType<TS> + Sized
 {
     fn into_foreign_output(self) -> Self::ForeignType;
@@ -226,6 +266,41 @@ pub unsafe trait InfallibleExternOutput<TS: TypeSystem>: ExternType<TS> + Sized
     unsafe fn from_foreign_output(source: Self::ForeignType) -> Self;
 }
 
+/// Defines an input type passed as a pointer followed by an element count.
+///
+/// The count is passed as a separate `u32` parameter after the pointer.
+///
+/// # Safety
+///
+/// Implementing this trait allows Intercom to use the type as an input type.
+/// This trait will be used within the code generated in the procedural macros.
+/// It is important to ensure this trait is implemented in such a way that its
+/// use in the macros is sound.
+pub unsafe trait ExternCountedInput<TS: TypeSystem>: Sized
+{
+    /// The pointer to the elements.
+    type ForeignType: ForeignType;
+
+    type Lease;
+
+    /// # Safety
+    ///
+    /// The returned `ForeignType` value is valid only as long as the `Lease`
+    /// is held.
+    unsafe fn into_foreign_parameter(self) -> ComResult<(Self::ForeignType, u32, Self::Lease)>;
+
+    type Owned;
+
+    /// # Safety
+    ///
+    /// The `source` must point to at least `count` elements. The returned
+    /// `Owned` value shouldn't be used past the lifetime of the `source`.
+    unsafe fn from_foreign_parameter(
+        source: Self::ForeignType,
+        count: u32,
+    ) -> ComResult<Self::Owned>;
+}
+
 /// Holds a conversion result foreign value and cleans it up unless consumed
 pub struct OutputGuard<TS, TType>
 where
@@ -366,7 +441,89 @@ self_extern!(u64);
 self_extern!(usize);
 self_extern!(f32);
 self_extern!(f64);
-self_extern!(bool);
+
+// The Automation clients expect the booleans as 16-bit `VARIANT_BOOL` values.
+// The Raw type system passes the Rust `bool` as is.
+impl ForeignType for bool
+{
+    fn type_name() -> &'static str
+    {
+        "bool"
+    }
+}
+
+unsafe impl ExternType<AutomationTypeSystem> for bool
+{
+    type ForeignType = crate::raw::VariantBool;
+}
+
+unsafe impl ExternType<RawTypeSystem> for bool
+{
+    type ForeignType = bool;
+}
+
+macro_rules! bool_extern {
+    ( $ts:ty ) => {
+        unsafe impl ExternInput<$ts> for bool
+        {
+            type Lease = ();
+            unsafe fn into_foreign_parameter(self) -> ComResult<(Self::ForeignType, ())>
+            {
+                Ok((self.into(), ()))
+            }
+
+            type Owned = Self;
+            unsafe fn from_foreign_parameter(source: Self::ForeignType) -> ComResult<Self::Owned>
+            {
+                Ok(source.into())
+            }
+        }
+
+        unsafe impl ExternOutput<$ts> for bool
+        {
+            fn into_foreign_output(self) -> ComResult<Self::ForeignType>
+            {
+                Ok(self.into())
+            }
+
+            unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
+            {
+                Ok(source.into())
+            }
+        }
+
+        unsafe impl InfallibleExternInput<$ts> for bool
+        {
+            type Lease = ();
+            unsafe fn into_foreign_parameter(self) -> (Self::ForeignType, ())
+            {
+                (self.into(), ())
+            }
+
+            type Owned = Self;
+            unsafe fn from_foreign_parameter(source: Self::ForeignType) -> Self::Owned
+            {
+                source.into()
+            }
+        }
+
+        unsafe impl InfallibleExternOutput<$ts> for bool
+        {
+            fn into_foreign_output(self) -> Self::ForeignType
+            {
+                self.into()
+            }
+
+            unsafe fn from_foreign_output(source: Self::ForeignType) -> Self
+            {
+                source.into()
+            }
+        }
+    };
+}
+
+bool_extern!(AutomationTypeSystem);
+bool_extern!(RawTypeSystem);
 
 use crate::raw::HRESULT;
 self_extern!(HRESULT);
@@ -452,6 +609,11 @@ macro_rules! extern_ptr {
             {
                 <TPtr as ForeignType>::indirection_level() + 1
             }
+
+            fn type_library_name() -> std::borrow::Cow<'static, str>
+            {
+                <TPtr as ForeignType>::type_library_name()
+            }
         }
     }
 }
@@ -459,6 +621,148 @@ macro_rules! extern_ptr {
 extern_ptr!(mut);
 extern_ptr!(const);
 
+/// Fixed-size arrays of types that represent themselves.
+///
+/// The array is passed as a pointer to its first element so the arrays are
+/// supported only as output values, such as the `Ok` value of a `ComResult`.
+impl<T: ForeignType, const N: usize> ForeignType for [T; N]
+{
+    fn type_name() -> &'static str
+    {
+        T::type_name()
+    }
+
+    fn indirection_level() -> u32
+    {
+        T::indirection_level()
+    }
+}
+
+unsafe impl<TS: TypeSystem, T, const N: usize> ExternType<TS> for [T; N]
+where
+    T: ExternType<TS, ForeignType = T> + ForeignType + Copy,
+{
+    type ForeignType = [T; N];
+}
+
+unsafe impl<TS: TypeSystem, T, const N: usize> ExternOutput<TS> for [T; N]
+where
+    T: ExternType<TS, ForeignType = T> + ForeignType + Copy,
+{
+    fn into_foreign_output(self) -> ComResult<Self::ForeignType>
+    {
+        Ok(self)
+    }
+
+    unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
+    {
+        Ok(source)
+    }
+}
+
+/// Boxed values are returned as a pointer to the value.
+///
+/// The value is allocated with the COM task allocator so the clients free the
+/// returned structures with `CoTaskMemFree`. The boxes are supported only as
+/// output values.
+unsafe impl<TS: TypeSystem, T: ExternOutput<TS>> ExternType<TS> for Box<T>
+{
+    type ForeignType = *mut T::ForeignType;
+
+    fn type_info() -> Option<crate::typelib::TypeInfo>
+    {
+        T::type_info()
+    }
+
+    fn idl_type_name() -> Option<String>
+    {
+        T::idl_type_name()
+    }
+}
+
+unsafe impl<TS: TypeSystem, T: ExternOutput<TS>> ExternOutput<TS> for Box<T>
+{
+    fn into_foreign_output(self) -> ComResult<Self::ForeignType>
+    {
+        crate::alloc::allocate_value(*self)
+    }
+
+    unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
+    {
+        if source.is_null() {
+            return Err(ComError::E_POINTER);
+        }
+        crate::alloc::take_value(source).map(Box::new)
+    }
+}
+
+// The byte slices are passed as a pointer followed by the length instead of a
+// SAFEARRAY. The empty slices are passed as null pointers.
+unsafe impl<'a, TS: TypeSystem> ExternCountedInput<TS> for &'a [u8]
+{
+    type ForeignType = *const u8;
+
+    type Lease = ();
+    unsafe fn into_foreign_parameter(self) -> ComResult<(Self::ForeignType, u32, ())>
+    {
+        let len = byte_count(self.len())?;
+        let ptr = match self.is_empty() {
+            true => std::ptr::null(),
+            false => self.as_ptr(),
+        };
+        Ok((ptr, len, ()))
+    }
+
+    type Owned = &'a [u8];
+    unsafe fn from_foreign_parameter(
+        source: Self::ForeignType,
+        count: u32,
+    ) -> ComResult<Self::Owned>
+    {
+        match (count, source.is_null()) {
+            (0, _) => Ok(&[]),
+            (_, true) => Err(ComError::E_POINTER),
+            (_, false) => Ok(std::slice::from_raw_parts(source, count as usize)),
+        }
+    }
+}
+
+// The mutable byte slices are buffers the callee writes into.
+unsafe impl<'a, TS: TypeSystem> ExternCountedInput<TS> for &'a mut [u8]
+{
+    type ForeignType = *mut u8;
+
+    type Lease = ();
+    unsafe fn into_foreign_parameter(self) -> ComResult<(Self::ForeignType, u32, ())>
+    {
+        let len = byte_count(self.len())?;
+        let ptr = match self.is_empty() {
+            true => std::ptr::null_mut(),
+            false => self.as_mut_ptr(),
+        };
+        Ok((ptr, len, ()))
+    }
+
+    type Owned = &'a mut [u8];
+    unsafe fn from_foreign_parameter(
+        source: Self::ForeignType,
+        count: u32,
+    ) -> ComResult<Self::Owned>
+    {
+        match (count, source.is_null()) {
+            (0, _) => Ok(&mut []),
+            (_, true) => Err(ComError::E_POINTER),
+            (_, false) => Ok(std::slice::from_raw_parts_mut(source, count as usize)),
+        }
+    }
+}
+
+fn byte_count(len: usize) -> ComResult<u32>
+{
+    std::convert::TryFrom::try_from(len)
+        .map_err(|_| ComError::E_INVALIDARG.with_message("Buffer is too large"))
+}
+
 /// Defines the uninitialized values for out parameters when calling into
 /// Intercom interfaces.
 pub trait ExternDefault
@@ -477,3 +781,389 @@ impl<T> ExternDefault for T
         std::mem::zeroed()
     }
 }
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use crate::{BString, WString};
+    use std::fmt::Debug;
+
+    // The type handlers generate the Rust/COM conversions as calls to these
+    // traits. Passing a value through both directions of the conversion must
+    // result in the original value on every type system.
+    //
+    // The parameter conversions are checked while the lease is still alive
+    // as the owned values may borrow from it.
+
+    fn assert_parameter_round_trip<TS, T, E>(value: T, expected: E)
+    where
+        TS: TypeSystem,
+        T: ExternInput<TS>,
+        T::Owned: PartialEq<E> + Debug,
+        E: Debug,
+    {
+        unsafe {
+            let (foreign, _lease) = value.into_foreign_parameter().unwrap();
+            assert_eq!(T::from_foreign_parameter(foreign).unwrap(), expected);
+        }
+    }
+
+    fn assert_infallible_parameter_round_trip<TS, T, E>(value: T, expected: E)
+    where
+        TS: TypeSystem,
+        T: InfallibleExternInput<TS>,
+        T::Owned: PartialEq<E> + Debug,
+        E: Debug,
+    {
+        unsafe {
+            let (foreign, _lease) = value.into_foreign_parameter();
+            assert_eq!(T::from_foreign_parameter(foreign), expected);
+        }
+    }
+
+    fn output_round_trip<TS: TypeSystem, T: ExternOutput<TS>>(value: T) -> T
+    {
+        unsafe {
+            let foreign = value.into_foreign_output().unwrap();
+            T::from_foreign_output(foreign).unwrap()
+        }
+    }
+
+    fn infallible_output_round_trip<TS: TypeSystem, T: InfallibleExternOutput<TS>>(value: T) -> T
+    {
+        unsafe { T::from_foreign_output(value.into_foreign_output()) }
+    }
+
+    macro_rules! assert_round_trip {
+        ( $ts:ty : $( $value:expr ),* ) => { $(
+            assert_parameter_round_trip::<$ts, _, _>($value, $value);
+            assert_eq!(output_round_trip::<$ts, _>($value), $value);
+        )* };
+    }
+
+    macro_rules! assert_infallible_round_trip {
+        ( $ts:ty : $( $value:expr ),* ) => { $(
+            assert_round_trip!($ts: $value);
+            assert_infallible_parameter_round_trip::<$ts, _, _>($value, $value);
+            assert_eq!(infallible_output_round_trip::<$ts, _>($value), $value);
+        )* };
+    }
+
+    #[test]
+    fn primitives_round_trip()
+    {
+        assert_infallible_round_trip!(AutomationTypeSystem:
+            -1i8, 1u8, -1000i16, 1000u16, -100_000i32, 100_000u32,
+            i64::MIN, u64::MAX, isize::MIN, usize::MAX,
+            1.5f32, -1.5e100f64);
+        assert_infallible_round_trip!(RawTypeSystem:
+            -1i8, 1u8, -1000i16, 1000u16, -100_000i32, 100_000u32,
+            i64::MIN, u64::MAX, isize::MIN, usize::MAX,
+            1.5f32, -1.5e100f64);
+    }
+
+    #[test]
+    fn i32_handler()
+    {
+        crate::__test_handler!(i32, [0, 1, -1, i32::MIN, i32::MAX]);
+    }
+
+    #[test]
+    fn existing_handlers()
+    {
+        crate::__test_handler!(u8, [0, u8::MAX]);
+        crate::__test_handler!(u64, [0, u64::MAX]);
+        crate::__test_handler!(f64, [0.0, -1.5e100]);
+        crate::__test_handler!(bool, [true, false]);
+        crate::__test_handler!(String, [String::new(), "\u{1F980}".to_string()]);
+        crate::__test_handler!(BString, [BString::from("foo")]);
+        crate::__test_handler!(WString, [WString::default(), WString::from("\u{1F980}")]);
+        crate::__test_handler!(GappedEnum, [GappedEnum::Red, GappedEnum::Blue]);
+    }
+
+    #[test]
+    fn bool_round_trip()
+    {
+        assert_infallible_round_trip!(AutomationTypeSystem: true, false);
+        assert_infallible_round_trip!(RawTypeSystem: true, false);
+
+        // Automation represents true as all bits set.
+        let variant_true =
+            <bool as InfallibleExternOutput<AutomationTypeSystem>>::into_foreign_output(true);
+        assert_eq!(variant_true.0, 0xffff);
+        assert_eq!(
+            <bool as ExternType<AutomationTypeSystem>>::ForeignType::type_name(),
+            "VARIANT_BOOL"
+        );
+        assert_eq!(
+            <bool as ExternType<RawTypeSystem>>::ForeignType::type_name(),
+            "bool"
+        );
+    }
+
+    #[test]
+    fn strings_round_trip()
+    {
+        assert_round_trip!(AutomationTypeSystem:
+            String::new(), "foo".to_string(), "\u{1F980}".to_string(),
+            BString::from("foo"), BString::from("\u{1F980}"));
+        assert_round_trip!(RawTypeSystem:
+            String::new(), "foo".to_string(), "\u{1F980}".to_string(),
+            BString::from("foo"), BString::from("\u{1F980}"));
+
+        assert_parameter_round_trip::<AutomationTypeSystem, _, _>("foo", "foo");
+        assert_parameter_round_trip::<RawTypeSystem, _, _>("foo", "foo");
+
+        assert_round_trip!(AutomationTypeSystem:
+            WString::default(), WString::from("foo"), WString::from("\u{1F980}"));
+        assert_round_trip!(RawTypeSystem:
+            WString::default(), WString::from("foo"), WString::from("\u{1F980}"));
+    }
+
+    #[test]
+    fn boxes_round_trip()
+    {
+        let point = crate::type_system::Tuple2(1, "foo".to_string());
+        assert_eq!(
+            *output_round_trip::<AutomationTypeSystem, _>(Box::new(point.clone())),
+            point
+        );
+        assert_eq!(
+            *output_round_trip::<RawTypeSystem, _>(Box::new(point.clone())),
+            point
+        );
+        assert_eq!(
+            *output_round_trip::<RawTypeSystem, _>(Box::new(GappedEnum::Blue)),
+            GappedEnum::Blue
+        );
+
+        // The box points to the foreign value.
+        assert_eq!(
+            <Box<GappedEnum> as ExternType<RawTypeSystem>>::ForeignType::indirection_level(),
+            1
+        );
+        unsafe {
+            assert!(
+                <Box<GappedEnum> as ExternOutput<RawTypeSystem>>::from_foreign_output(
+                    std::ptr::null_mut()
+                )
+                .is_err()
+            );
+        }
+    }
+
+    #[derive(
+        Debug, Clone, Copy, PartialEq, crate::ExternType, crate::ExternInput, crate::ExternOutput,
+    )]
+    #[repr(C)]
+    enum GappedEnum
+    {
+        Red = 1,
+        Blue = 5,
+    }
+
+    #[test]
+    fn gapped_enum_validates_discriminants()
+    {
+        assert_round_trip!(AutomationTypeSystem: GappedEnum::Red, GappedEnum::Blue);
+        assert_round_trip!(RawTypeSystem: GappedEnum::Red, GappedEnum::Blue);
+
+        for &valid in &[1, 5] {
+            unsafe {
+                <GappedEnum as ExternInput<AutomationTypeSystem>>::from_foreign_parameter(valid)
+                    .unwrap();
+                <GappedEnum as ExternOutput<AutomationTypeSystem>>::from_foreign_output(valid)
+                    .unwrap();
+            }
+        }
+
+        for &invalid in &[0, 2, 3, 4, 6] {
+            unsafe {
+                let err =
+                    <GappedEnum as ExternInput<AutomationTypeSystem>>::from_foreign_parameter(
+                        invalid,
+                    )
+                    .unwrap_err();
+                assert_eq!(err.hresult, crate::raw::E_INVALIDARG);
+                let err = <GappedEnum as ExternOutput<AutomationTypeSystem>>::from_foreign_output(
+                    invalid,
+                )
+                .unwrap_err();
+                assert_eq!(err.hresult, crate::raw::E_INVALIDARG);
+            }
+        }
+    }
+
+    #[crate::com_interface]
+    trait IEnumSource
+    {
+        fn get_enum(&self, red: bool) -> crate::ComResult<GappedEnum>;
+    }
+
+    #[crate::com_class(clsid = None, IEnumSource)]
+    struct EnumSource;
+
+    impl IEnumSource for EnumSource
+    {
+        fn get_enum(&self, red: bool) -> crate::ComResult<GappedEnum>
+        {
+            Ok(if red {
+                GappedEnum::Red
+            } else {
+                GappedEnum::Blue
+            })
+        }
+    }
+
+    #[test]
+    fn enum_retval_is_discriminant()
+    {
+        let value = <GappedEnum as ExternOutput<AutomationTypeSystem>>::into_foreign_output(
+            GappedEnum::Blue,
+        )
+        .unwrap();
+        assert_eq!(value, 5);
+
+        let rc = crate::ComRc::<dyn IEnumSource>::from(crate::ComBox::new(EnumSource));
+        assert_eq!(rc.get_enum(true).unwrap(), GappedEnum::Red);
+        assert_eq!(rc.get_enum(false).unwrap(), GappedEnum::Blue);
+
+        match <GappedEnum as ExternType<AutomationTypeSystem>>::type_info() {
+            Some(crate::typelib::TypeInfo::Enum(e)) => {
+                assert_eq!(e.name, "GappedEnum");
+                let values = e
+                    .values
+                    .iter()
+                    .map(|v| (v.name.as_ref(), v.value))
+                    .collect::<Vec<_>>();
+                assert_eq!(values, vec![("Red", 1), ("Blue", 5)]);
+            }
+            other => panic!("Bad type info: {:?}", other),
+        }
+    }
+
+    #[crate::com_interface]
+    trait IHasher
+    {
+        fn hash(&self, data: &str) -> crate::ComResult<[u8; 32]>;
+    }
+
+    #[crate::com_class(clsid = None, IHasher)]
+    struct Hasher;
+
+    impl IHasher for Hasher
+    {
+        fn hash(&self, data: &str) -> crate::ComResult<[u8; 32]>
+        {
+            if data.is_empty() {
+                return Err(crate::ComError::E_INVALIDARG.with_message("Nothing to hash"));
+            }
+
+            let mut hash = [0u8; 32];
+            for (idx, b) in data.bytes().enumerate() {
+                hash[idx % 32] ^= b;
+            }
+            Ok(hash)
+        }
+    }
+
+    #[test]
+    fn array_result_round_trip()
+    {
+        let rc = crate::ComRc::<dyn IHasher>::from(crate::ComBox::new(Hasher));
+
+        let mut expected = [0u8; 32];
+        expected[..3].copy_from_slice(b"abc");
+        assert_eq!(rc.hash("abc").unwrap(), expected);
+
+        let err = rc.hash("").unwrap_err();
+        assert_eq!(err.hresult, crate::raw::E_INVALIDARG);
+        assert_eq!(err.description(), Some("Nothing to hash"));
+
+        assert_eq!(<[u8; 32] as ForeignType>::type_name(), "u8");
+    }
+
+    #[test]
+    fn wide_primitives_keep_type_names()
+    {
+        // The values themselves are covered by the primitive_tests in cpp-raw.
+        assert_eq!(
+            <f64 as ExternType<AutomationTypeSystem>>::ForeignType::type_name(),
+            "f64"
+        );
+        assert_eq!(
+            <f32 as ExternType<RawTypeSystem>>::ForeignType::type_name(),
+            "f32"
+        );
+        assert_eq!(
+            <u64 as ExternType<AutomationTypeSystem>>::ForeignType::type_name(),
+            "u64"
+        );
+        assert_eq!(
+            <i64 as ExternType<RawTypeSystem>>::ForeignType::type_name(),
+            "i64"
+        );
+    }
+
+    #[crate::com_interface]
+    trait ICodec
+    {
+        fn checksum(&self, data: &[u8]) -> crate::ComResult<u32>;
+        fn fill(&self, value: u8, buffer: &mut [u8]) -> crate::ComResult<()>;
+    }
+
+    #[crate::com_class(clsid = None, ICodec)]
+    struct Codec;
+
+    impl ICodec for Codec
+    {
+        fn checksum(&self, data: &[u8]) -> crate::ComResult<u32>
+        {
+            Ok(data.iter().map(|&b| u32::from(b)).sum())
+        }
+
+        fn fill(&self, value: u8, buffer: &mut [u8]) -> crate::ComResult<()>
+        {
+            for b in buffer {
+                *b = value;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn byte_slice_is_passed_as_ptr_and_len()
+    {
+        let rc = crate::ComRc::<dyn ICodec>::from(crate::ComBox::new(Codec));
+
+        for codec in &[
+            crate::ComItf::automation(&rc).unwrap(),
+            crate::ComItf::raw(&rc).unwrap(),
+        ] {
+            assert_eq!(codec.checksum(&[1, 2, 3]).unwrap(), 6);
+            assert_eq!(codec.checksum(&[]).unwrap(), 0);
+
+            let mut buffer = [0u8; 4];
+            codec.fill(7, &mut buffer).unwrap();
+            assert_eq!(buffer, [7; 4]);
+            codec.fill(7, &mut []).unwrap();
+        }
+
+        // The empty slices may be passed as null pointers.
+        let itf = crate::ComItf::ptr::<RawTypeSystem>(&rc).unwrap();
+        let vtbl = unsafe { &**(itf.ptr.as_ptr() as *const *const __ICodecRawVTable) };
+        unsafe {
+            let mut sum = 1;
+            let hr = (vtbl.checksum)(itf.ptr.as_ptr(), std::ptr::null(), 0, &mut sum);
+            assert_eq!(hr, crate::raw::S_OK);
+            assert_eq!(sum, 0);
+
+            let hr = (vtbl.fill)(itf.ptr.as_ptr(), 7, std::ptr::null_mut(), 0);
+            assert_eq!(hr, crate::raw::S_OK);
+
+            let hr = (vtbl.checksum)(itf.ptr.as_ptr(), std::ptr::null(), 3, &mut sum);
+            assert_eq!(hr, crate::raw::E_POINTER);
+        }
+    }
+}
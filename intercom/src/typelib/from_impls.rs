@@ -17,14 +17,32 @@ impl TypeLib
                     let itf = Interface::from_comrc(&ComItf::query_interface(&ty)?)?;
                     TypeInfo::Interface(ComBox::new(itf))
                 }
+                TypeInfoKind::Enum => {
+                    let e = Enum::from_comrc(&ComItf::query_interface(&ty)?)?;
+                    TypeInfo::Enum(ComBox::new(e))
+                }
+                TypeInfoKind::Module => {
+                    let m = Module::from_comrc(&ComItf::query_interface(&ty)?)?;
+                    TypeInfo::Module(ComBox::new(m))
+                }
+                TypeInfoKind::Struct => {
+                    let s = Struct::from_comrc(&ComItf::query_interface(&ty)?)?;
+                    TypeInfo::Struct(ComBox::new(s))
+                }
             });
         }
 
+        let mut imports = vec![];
+        for i in 0..lib.get_import_count()? {
+            imports.push(lib.get_import(i)?.into());
+        }
+
         let (name, libid, version) = lib.get_info()?;
         Ok(TypeLib {
             name: name.into(),
             libid,
             version: version.into(),
+            imports,
             types,
         })
     }
@@ -50,6 +68,78 @@ impl CoClass
             name: ti.get_name()?.into(),
             clsid: ti.get_clsid()?,
             interfaces,
+            options: ti.get_options()?,
+            doc: non_empty(ti.get_doc()?),
+        })
+    }
+}
+
+impl Enum
+{
+    pub fn from_comrc(ti: &ComRc<dyn IIntercomEnum>) -> Result<Enum, TypeLibError>
+    {
+        let mut values = vec![];
+        for v in 0..ti.get_value_count()? {
+            let (name, value) = ti.get_value(v)?;
+            values.push(EnumValue {
+                name: name.into(),
+                value,
+            });
+        }
+
+        Ok(Enum {
+            name: ti.get_name()?.into(),
+            values,
+            v1_enum: ti.is_v1_enum()?,
+        })
+    }
+}
+
+impl Struct
+{
+    pub fn from_comrc(ti: &ComRc<dyn IIntercomStruct>) -> Result<Struct, TypeLibError>
+    {
+        let mut fields = vec![];
+        for f in 0..ti.get_field_count()? {
+            let (name, ty, indirection_level) = ti.get_field(f)?;
+            fields.push(StructField {
+                name: name.into(),
+                ty: ty.into(),
+                indirection_level,
+            });
+        }
+
+        Ok(Struct {
+            name: ti.get_name()?.into(),
+            fields,
+        })
+    }
+}
+
+impl Module
+{
+    pub fn from_comrc(ti: &ComRc<dyn IIntercomModule>) -> Result<Module, TypeLibError>
+    {
+        let mut functions = vec![];
+        for f in 0..ti.get_function_count()? {
+            functions.push(ComBox::new(Method::from_comrc(&ti.get_function(f)?)?));
+        }
+
+        let mut constants = vec![];
+        for c in 0..ti.get_constant_count()? {
+            let (name, ty, value) = ti.get_constant(c)?;
+            constants.push(Constant {
+                name: name.into(),
+                ty: ty.into(),
+                value: value.into(),
+            });
+        }
+
+        Ok(Module {
+            name: ti.get_name()?.into(),
+            dll_name: ti.get_dll_name()?.into(),
+            functions,
+            constants,
         })
     }
 }
@@ -69,6 +159,7 @@ impl Interface
             name: ti.get_name()?.into(),
             options: ti.get_options()?,
             variants,
+            doc: non_empty(ti.get_doc()?),
         })
     }
 }
@@ -99,11 +190,15 @@ impl Method
         let mut parameters = vec![];
         for p in 0..ti.get_parameter_count()? {
             let (name, ty, indirection_level, direction) = ti.get_parameter(p)?;
+            let idl_ty = ti.get_parameter_idl_type(p)?;
+            let length_of = ti.get_parameter_length_of(p)?;
             parameters.push(Arg {
                 name: name.into(),
                 ty: ty.into(),
                 indirection_level,
                 direction,
+                idl_ty: non_empty(idl_ty),
+                length_of: non_empty(length_of),
             });
         }
 
@@ -115,8 +210,22 @@ impl Method
                 ty: return_ty.into(),
                 indirection_level: return_indirection_level,
                 direction: Direction::Return,
+                idl_ty: non_empty(ti.get_return_idl_type()?),
+                length_of: None,
             },
             parameters,
+            doc: non_empty(ti.get_doc()?),
+            kind: ti.get_kind()?,
+            options: ti.get_options()?,
         })
     }
 }
+
+/// Converts the empty strings used for missing values back into `None`.
+fn non_empty(value: String) -> Option<Cow<'static, str>>
+{
+    match value.is_empty() {
+        true => None,
+        false => Some(value.into()),
+    }
+}
@@ -42,6 +42,7 @@ pub struct TypeLib
     pub name: Cow<'static, str>,
     pub libid: GUID,
     pub version: Cow<'static, str>,
+    pub imports: Vec<Cow<'static, str>>,
     pub types: Vec<TypeInfo>,
 }
 
@@ -51,6 +52,10 @@ pub trait IIntercomTypeLib
     fn get_info(&self) -> ComResult<(String, GUID, String)>;
     fn get_type_count(&self) -> ComResult<u32>;
     fn get_type(&self, idx: u32) -> ComResult<ComRc<dyn IIntercomTypeInfo>>;
+
+    /// Type libraries referenced by the types in this library.
+    fn get_import_count(&self) -> ComResult<u32>;
+    fn get_import(&self, idx: u32) -> ComResult<String>;
 }
 
 // TypeInfo
@@ -60,6 +65,42 @@ pub enum TypeInfo
 {
     Class(ComBox<CoClass>),
     Interface(ComBox<Interface>),
+    Enum(ComBox<Enum>),
+    Module(ComBox<Module>),
+    Struct(ComBox<Struct>),
+}
+
+impl TypeInfo
+{
+    /// Gets the name of the type.
+    pub fn name(&self) -> &str
+    {
+        match self {
+            TypeInfo::Class(cls) => &cls.name,
+            TypeInfo::Interface(itf) => &itf.name,
+            TypeInfo::Enum(e) => &e.name,
+            TypeInfo::Module(m) => &m.name,
+            TypeInfo::Struct(s) => &s.name,
+        }
+    }
+
+    /// Marks the interfaces as defined in one of the imported type libraries.
+    ///
+    /// The other types are left out as the imported library defines them.
+    #[doc(hidden)]
+    pub fn __imported(types: Vec<TypeInfo>) -> Vec<TypeInfo>
+    {
+        types
+            .into_iter()
+            .filter_map(|t| match t {
+                TypeInfo::Interface(mut itf) => {
+                    itf.options.imported = true;
+                    Some(TypeInfo::Interface(itf))
+                }
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 #[derive(ExternType, ExternOutput, ForeignType, Debug)]
@@ -68,6 +109,9 @@ pub enum TypeInfoKind
 {
     CoClass,
     Interface,
+    Enum,
+    Module,
+    Struct,
 }
 
 #[com_interface]
@@ -85,7 +129,28 @@ pub struct CoClass
 {
     pub name: Cow<'static, str>,
     pub clsid: GUID,
+
+    /// Interfaces of the class, the default interface first.
     pub interfaces: Vec<InterfaceRef>,
+    pub options: ClassOptions,
+
+    /// Documentation of the class, if any.
+    pub doc: Option<Cow<'static, str>>,
+}
+
+#[derive(Debug, Clone, Default, ExternType, ExternOutput, ForeignType)]
+#[repr(C)]
+pub struct ClassOptions
+{
+    /// The class is created only internally and has no class factory.
+    pub no_class_factory: bool,
+
+    /// The CLSID is derived from the class name.
+    pub generated_clsid: bool,
+
+    /// The class is the application object of the library.
+    pub appobject: bool,
+    pub __non_exhaustive: (),
 }
 
 #[com_interface]
@@ -93,10 +158,15 @@ pub trait IIntercomCoClass
 {
     // FIXME: Support interface inheritance
     fn get_name(&self) -> ComResult<String>;
+    fn get_options(&self) -> ComResult<ClassOptions>;
 
     fn get_clsid(&self) -> ComResult<GUID>;
     fn get_interface_count(&self) -> ComResult<u32>;
     fn get_interface_ref(&self, idx: u32, ts: TypeSystemName) -> ComResult<(String, GUID)>;
+
+    /// Gets the documentation of the class or an empty string if the class
+    /// isn't documented.
+    fn get_doc(&self) -> ComResult<String>;
 }
 
 // TypeInfo::Interface
@@ -108,6 +178,9 @@ pub struct Interface
     pub name: Cow<'static, str>,
     pub variants: Vec<ComBox<InterfaceVariant>>,
     pub options: InterfaceOptions,
+
+    /// Documentation of the interface, if any.
+    pub doc: Option<Cow<'static, str>>,
 }
 
 #[derive(Debug, Clone, Default, ExternType, ExternOutput, ForeignType)]
@@ -115,6 +188,19 @@ pub struct Interface
 pub struct InterfaceOptions
 {
     pub class_impl_interface: bool,
+
+    /// Some of the IIDs are derived from the interface name.
+    pub generated_iid: bool,
+
+    /// The interface derives from `IDispatch` and serves the late bound
+    /// calls.
+    pub dual: bool,
+
+    /// The interface is described as a `dispinterface`.
+    pub dispinterface: bool,
+
+    /// The interface is defined in one of the imported type libraries.
+    pub imported: bool,
     pub __non_exhaustive: (),
 }
 
@@ -136,6 +222,10 @@ pub trait IIntercomInterface
 
     fn get_variant_count(&self) -> ComResult<u32>;
     fn get_variant(&self, idx: u32) -> ComResult<ComRc<dyn IIntercomInterfaceVariant>>;
+
+    /// Gets the documentation of the interface or an empty string if the
+    /// interface isn't documented.
+    fn get_doc(&self) -> ComResult<String>;
 }
 
 #[com_interface]
@@ -147,6 +237,96 @@ pub trait IIntercomInterfaceVariant
     fn get_method(&self, idx: u32) -> ComResult<ComRc<dyn IIntercomMethod>>;
 }
 
+// TypeInfo::Enum
+
+#[com_class(IIntercomTypeInfo, IIntercomEnum)]
+#[derive(Debug)]
+pub struct Enum
+{
+    pub name: Cow<'static, str>,
+    pub values: Vec<EnumValue>,
+
+    /// Whether the enum is 32 bits on the wire instead of the MIDL default of
+    /// 16 bits.
+    pub v1_enum: bool,
+}
+
+#[derive(Debug)]
+pub struct EnumValue
+{
+    pub name: Cow<'static, str>,
+    pub value: i64,
+}
+
+#[com_interface]
+pub trait IIntercomEnum
+{
+    fn get_name(&self) -> ComResult<String>;
+    fn get_value_count(&self) -> ComResult<u32>;
+    fn get_value(&self, idx: u32) -> ComResult<(String, i64)>;
+    fn is_v1_enum(&self) -> ComResult<bool>;
+}
+
+// TypeInfo::Module
+
+#[com_class(IIntercomTypeInfo, IIntercomModule)]
+#[derive(Debug)]
+pub struct Module
+{
+    pub name: Cow<'static, str>,
+    pub dll_name: Cow<'static, str>,
+    pub functions: Vec<ComBox<Method>>,
+    pub constants: Vec<Constant>,
+}
+
+#[derive(Debug)]
+pub struct Constant
+{
+    pub name: Cow<'static, str>,
+    pub ty: Cow<'static, str>,
+
+    /// The value formatted as an IDL literal.
+    pub value: Cow<'static, str>,
+}
+
+#[com_interface]
+pub trait IIntercomModule
+{
+    fn get_name(&self) -> ComResult<String>;
+    fn get_dll_name(&self) -> ComResult<String>;
+    fn get_function_count(&self) -> ComResult<u32>;
+    fn get_function(&self, idx: u32) -> ComResult<ComRc<dyn IIntercomMethod>>;
+    fn get_constant_count(&self) -> ComResult<u32>;
+    fn get_constant(&self, idx: u32) -> ComResult<(String, String, String)>;
+}
+
+// TypeInfo::Struct
+
+/// Structure passed by value, such as the tuple parameters.
+#[com_class(IIntercomTypeInfo, IIntercomStruct)]
+#[derive(Debug)]
+pub struct Struct
+{
+    pub name: Cow<'static, str>,
+    pub fields: Vec<StructField>,
+}
+
+#[derive(Debug)]
+pub struct StructField
+{
+    pub name: Cow<'static, str>,
+    pub ty: Cow<'static, str>,
+    pub indirection_level: u32,
+}
+
+#[com_interface]
+pub trait IIntercomStruct
+{
+    fn get_name(&self) -> ComResult<String>;
+    fn get_field_count(&self) -> ComResult<u32>;
+    fn get_field(&self, idx: u32) -> ComResult<(String, String, u32)>;
+}
+
 // Method
 
 #[com_class(IIntercomMethod)]
@@ -156,6 +336,28 @@ pub struct Method
     pub name: Cow<'static, str>,
     pub return_type: Arg,
     pub parameters: Vec<Arg>,
+
+    /// Documentation of the method, if any.
+    pub doc: Option<Cow<'static, str>>,
+
+    /// Property accessor the method is explicitly marked as.
+    pub kind: MethodKind,
+    pub options: MethodOptions,
+}
+
+#[derive(Debug, Clone, Default, ExternType, ExternOutput, ForeignType)]
+#[repr(C)]
+pub struct MethodOptions
+{
+    /// The method is hidden from the object browsers.
+    pub hidden: bool,
+
+    /// The method is not available to the scripting clients.
+    pub restricted: bool,
+
+    /// The method returns the enumerator of the collection.
+    pub newenum: bool,
+    pub __non_exhaustive: (),
 }
 
 #[derive(Debug)]
@@ -165,6 +367,12 @@ pub struct Arg
     pub ty: Cow<'static, str>,
     pub indirection_level: u32,
     pub direction: Direction,
+
+    /// Type name used in the IDL in place of `ty`, if overridden.
+    pub idl_ty: Option<Cow<'static, str>>,
+
+    /// Name of the buffer parameter whose element count the argument holds.
+    pub length_of: Option<Cow<'static, str>>,
 }
 
 #[derive(Debug, Clone, Copy, ExternType, ExternOutput, ForeignType, PartialEq, Eq)]
@@ -175,6 +383,16 @@ pub enum Direction
     Out,
     Retval,
     Return,
+    InOut,
+}
+
+#[derive(Debug, Clone, Copy, ExternType, ExternOutput, ForeignType, PartialEq, Eq)]
+#[repr(C)]
+pub enum MethodKind
+{
+    Method,
+    PropertyGet,
+    PropertyPut,
 }
 
 #[com_interface]
@@ -184,6 +402,26 @@ pub trait IIntercomMethod
     fn get_return_type(&self) -> ComResult<(String, u32)>;
     fn get_parameter_count(&self) -> ComResult<u32>;
     fn get_parameter(&self, idx: u32) -> ComResult<(String, String, u32, Direction)>;
+
+    /// Gets the IDL type override of the parameter or an empty string if the
+    /// type isn't overridden.
+    fn get_parameter_idl_type(&self, idx: u32) -> ComResult<String>;
+
+    /// Gets the name of the buffer parameter the parameter counts or an empty
+    /// string if the parameter isn't an element count.
+    fn get_parameter_length_of(&self, idx: u32) -> ComResult<String>;
+
+    /// Gets the documentation of the method or an empty string if the method
+    /// isn't documented.
+    fn get_doc(&self) -> ComResult<String>;
+
+    fn get_kind(&self) -> ComResult<MethodKind>;
+
+    /// Gets the IDL type override of the return value or an empty string if
+    /// the type isn't overridden.
+    fn get_return_idl_type(&self) -> ComResult<String>;
+
+    fn get_options(&self) -> ComResult<MethodOptions>;
 }
 
 // Impls
@@ -209,8 +447,21 @@ impl IIntercomTypeLib for TypeLib
         Ok(match &self.types[idx as usize] {
             TypeInfo::Class(cls) => ComRc::from(cls),
             TypeInfo::Interface(itf) => ComRc::from(itf),
+            TypeInfo::Enum(e) => ComRc::from(e),
+            TypeInfo::Module(m) => ComRc::from(m),
+            TypeInfo::Struct(s) => ComRc::from(s),
         })
     }
+
+    fn get_import_count(&self) -> ComResult<u32>
+    {
+        Ok(self.imports.len() as u32)
+    }
+
+    fn get_import(&self, idx: u32) -> ComResult<String>
+    {
+        Ok(self.imports[idx as usize].to_string())
+    }
 }
 
 impl IIntercomTypeInfo for CoClass
@@ -233,6 +484,11 @@ impl IIntercomCoClass for CoClass
         Ok(self.name.to_string())
     }
 
+    fn get_options(&self) -> ComResult<ClassOptions>
+    {
+        Ok(self.options.clone())
+    }
+
     fn get_clsid(&self) -> ComResult<GUID>
     {
         Ok(self.clsid.clone())
@@ -253,6 +509,15 @@ impl IIntercomCoClass for CoClass
             },
         ))
     }
+
+    fn get_doc(&self) -> ComResult<String>
+    {
+        Ok(self
+            .doc
+            .as_ref()
+            .map(|doc| doc.to_string())
+            .unwrap_or_default())
+    }
 }
 
 impl IIntercomTypeInfo for Interface
@@ -268,6 +533,130 @@ impl IIntercomTypeInfo for Interface
     }
 }
 
+impl IIntercomTypeInfo for Enum
+{
+    fn get_name(&self) -> ComResult<String>
+    {
+        Ok(self.name.to_string())
+    }
+
+    fn get_kind(&self) -> ComResult<TypeInfoKind>
+    {
+        Ok(TypeInfoKind::Enum)
+    }
+}
+
+impl IIntercomEnum for Enum
+{
+    fn get_name(&self) -> ComResult<String>
+    {
+        Ok(self.name.to_string())
+    }
+
+    fn get_value_count(&self) -> ComResult<u32>
+    {
+        Ok(self.values.len() as u32)
+    }
+
+    fn get_value(&self, idx: u32) -> ComResult<(String, i64)>
+    {
+        let value = &self.values[idx as usize];
+        Ok((value.name.to_string(), value.value))
+    }
+
+    fn is_v1_enum(&self) -> ComResult<bool>
+    {
+        Ok(self.v1_enum)
+    }
+}
+
+impl IIntercomTypeInfo for Module
+{
+    fn get_name(&self) -> ComResult<String>
+    {
+        Ok(self.name.to_string())
+    }
+
+    fn get_kind(&self) -> ComResult<TypeInfoKind>
+    {
+        Ok(TypeInfoKind::Module)
+    }
+}
+
+impl IIntercomModule for Module
+{
+    fn get_name(&self) -> ComResult<String>
+    {
+        Ok(self.name.to_string())
+    }
+
+    fn get_dll_name(&self) -> ComResult<String>
+    {
+        Ok(self.dll_name.to_string())
+    }
+
+    fn get_function_count(&self) -> ComResult<u32>
+    {
+        Ok(self.functions.len() as u32)
+    }
+
+    fn get_function(&self, idx: u32) -> ComResult<ComRc<dyn IIntercomMethod>>
+    {
+        Ok(ComRc::from(&self.functions[idx as usize]))
+    }
+
+    fn get_constant_count(&self) -> ComResult<u32>
+    {
+        Ok(self.constants.len() as u32)
+    }
+
+    fn get_constant(&self, idx: u32) -> ComResult<(String, String, String)>
+    {
+        let constant = &self.constants[idx as usize];
+        Ok((
+            constant.name.to_string(),
+            constant.ty.to_string(),
+            constant.value.to_string(),
+        ))
+    }
+}
+
+impl IIntercomTypeInfo for Struct
+{
+    fn get_name(&self) -> ComResult<String>
+    {
+        Ok(self.name.to_string())
+    }
+
+    fn get_kind(&self) -> ComResult<TypeInfoKind>
+    {
+        Ok(TypeInfoKind::Struct)
+    }
+}
+
+impl IIntercomStruct for Struct
+{
+    fn get_name(&self) -> ComResult<String>
+    {
+        Ok(self.name.to_string())
+    }
+
+    fn get_field_count(&self) -> ComResult<u32>
+    {
+        Ok(self.fields.len() as u32)
+    }
+
+    fn get_field(&self, idx: u32) -> ComResult<(String, String, u32)>
+    {
+        let field = &self.fields[idx as usize];
+        Ok((
+            field.name.to_string(),
+            field.ty.to_string(),
+            field.indirection_level,
+        ))
+    }
+}
+
 impl IIntercomInterface for Interface
 {
     fn get_name(&self) -> ComResult<String>
@@ -289,6 +678,15 @@ impl IIntercomInterface for Interface
     {
         Ok(ComRc::from(&self.variants[idx as usize]))
     }
+
+    fn get_doc(&self) -> ComResult<String>
+    {
+        Ok(self
+            .doc
+            .as_ref()
+            .map(|doc| doc.to_string())
+            .unwrap_or_default())
+    }
 }
 
 impl IIntercomInterfaceVariant for InterfaceVariant
@@ -343,6 +741,55 @@ impl IIntercomMethod for Method
             arg.direction,
         ))
     }
+
+    fn get_parameter_idl_type(&self, idx: u32) -> ComResult<String>
+    {
+        let arg = &self.parameters[idx as usize];
+        Ok(arg
+            .idl_ty
+            .as_ref()
+            .map(|ty| ty.to_string())
+            .unwrap_or_default())
+    }
+
+    fn get_parameter_length_of(&self, idx: u32) -> ComResult<String>
+    {
+        let arg = &self.parameters[idx as usize];
+        Ok(arg
+            .length_of
+            .as_ref()
+            .map(|buffer| buffer.to_string())
+            .unwrap_or_default())
+    }
+
+    fn get_doc(&self) -> ComResult<String>
+    {
+        Ok(self
+            .doc
+            .as_ref()
+            .map(|doc| doc.to_string())
+            .unwrap_or_default())
+    }
+
+    fn get_kind(&self) -> ComResult<MethodKind>
+    {
+        Ok(self.kind)
+    }
+
+    fn get_return_idl_type(&self) -> ComResult<String>
+    {
+        Ok(self
+            .return_type
+            .idl_ty
+            .as_ref()
+            .map(|ty| ty.to_string())
+            .unwrap_or_default())
+    }
+
+    fn get_options(&self) -> ComResult<MethodOptions>
+    {
+        Ok(self.options.clone())
+    }
 }
 
 impl CoClass
@@ -353,6 +800,8 @@ impl CoClass
             name,
             clsid,
             interfaces,
+            options: ClassOptions::default(),
+            doc: None,
         }
     }
 }
@@ -363,21 +812,49 @@ impl TypeLib
         name: Cow<'static, str>,
         libid: GUID,
         version: Cow<'static, str>,
+        imports: Vec<Cow<'static, str>>,
         mut types: Vec<TypeInfo>,
     ) -> TypeLib
     {
+        // The imported interfaces may also be gathered through the classes
+        // implementing them.
+        let imported = types
+            .iter()
+            .filter_map(|item| match item {
+                TypeInfo::Interface(itf) if itf.options.imported => Some(itf.name.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
         types.sort_by_key(|item| match item {
             TypeInfo::Class(cls) => ("class", cls.as_ref().name.to_string()),
             TypeInfo::Interface(itf) => ("itf", itf.as_ref().name.to_string()),
+            TypeInfo::Enum(e) => ("enum", e.as_ref().name.to_string()),
+            TypeInfo::Module(m) => ("module", m.as_ref().name.to_string()),
+            TypeInfo::Struct(s) => ("struct", s.as_ref().name.to_string()),
         });
         types.dedup_by_key(|item| match item {
             TypeInfo::Class(cls) => ("class", cls.as_ref().name.to_string()),
             TypeInfo::Interface(itf) => ("itf", itf.as_ref().name.to_string()),
+            TypeInfo::Enum(e) => ("enum", e.as_ref().name.to_string()),
+            TypeInfo::Module(m) => ("module", m.as_ref().name.to_string()),
+            TypeInfo::Struct(s) => ("struct", s.as_ref().name.to_string()),
         });
+
+        for item in &mut types {
+            match item {
+                TypeInfo::Interface(itf) if imported.contains(&itf.name) => {
+                    itf.options.imported = true
+                }
+                _ => {}
+            }
+        }
+
         TypeLib {
             name,
             libid,
             version,
+            imports,
             types,
         }
     }
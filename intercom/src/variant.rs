@@ -77,7 +77,8 @@ impl Variant
                 )),
                 raw::var_type::CY => Variant::Currency(Currency(src.data.cyVal)),
                 raw::var_type::DATE => Variant::SystemTime(src.data.date.into()),
-                raw::var_type::UNKNOWN => match src.data.punkVal {
+                // The IDispatch pointers are IUnknown pointers as well.
+                raw::var_type::UNKNOWN | raw::var_type::DISPATCH => match src.data.punkVal {
                     Some(ptr) => Variant::IUnknown(ComRc::wrap(ptr)),
                     None => Variant::None,
                 },
@@ -102,10 +103,14 @@ impl Variant
                 )),
                 raw::var_type::DATE => Variant::SystemTime((*src.data.pdate).into()),
                 raw::var_type::CY => Variant::Currency(Currency(*src.data.pcyVal)),
-                raw::var_type::UNKNOWN => match *src.data.ppunkVal {
+                raw::var_type::UNKNOWN | raw::var_type::DISPATCH => match *src.data.ppunkVal {
                     Some(ptr) => Variant::IUnknown(ComRc::wrap(ptr)),
                     None => Variant::None,
                 },
+                raw::var_type::VARIANT => match src.data.pvarVal.as_ref() {
+                    Some(inner) => Variant::from_raw(*inner)?,
+                    None => return Err(ComError::E_POINTER),
+                },
                 _ => return Err(ComError::E_NOTIMPL),
             }
         })
@@ -153,6 +158,106 @@ unsafe impl<TS: TypeSystem> ExternOutput<TS> for Variant
     }
 }
 
+// Variant slices are passed as SAFEARRAY(VARIANT), which matches the
+// `ParamArray` style vararg methods of the automation clients.
+unsafe impl<TS: TypeSystem> ExternType<TS> for &[Variant]
+{
+    type ForeignType = raw::ValueArray<TS, Variant>;
+}
+
+unsafe impl<TS: TypeSystem> ExternInput<TS> for &[Variant]
+{
+    type Lease = ValueArrayLease<TS, Variant>;
+    unsafe fn into_foreign_parameter(self) -> ComResult<(Self::ForeignType, Self::Lease)>
+    {
+        let array = raw::ValueArray::alloc(self.to_vec())?;
+        Ok((array, ValueArrayLease(array)))
+    }
+
+    type Owned = Vec<Variant>;
+    unsafe fn from_foreign_parameter(source: Self::ForeignType) -> ComResult<Self::Owned>
+    {
+        source.to_vec()
+    }
+}
+
+// Vectors are passed as SAFEARRAY values holding copies of the elements, such
+// as SAFEARRAY(BSTR) for the string lists. The empty vectors are passed as
+// arrays with no elements instead of null.
+unsafe impl<TS: TypeSystem, T: raw::SafeArrayItem> ExternType<TS> for Vec<T>
+{
+    type ForeignType = raw::ValueArray<TS, T>;
+}
+
+unsafe impl<TS: TypeSystem, T: raw::SafeArrayItem> ExternInput<TS> for Vec<T>
+{
+    type Lease = ValueArrayLease<TS, T>;
+    unsafe fn into_foreign_parameter(self) -> ComResult<(Self::ForeignType, Self::Lease)>
+    {
+        let array = raw::ValueArray::alloc(self)?;
+        Ok((array, ValueArrayLease(array)))
+    }
+
+    type Owned = Self;
+    unsafe fn from_foreign_parameter(source: Self::ForeignType) -> ComResult<Self::Owned>
+    {
+        source.to_vec()
+    }
+}
+
+unsafe impl<TS: TypeSystem, T: raw::SafeArrayItem> ExternOutput<TS> for Vec<T>
+{
+    fn into_foreign_output(self) -> ComResult<Self::ForeignType>
+    {
+        raw::ValueArray::alloc(self)
+    }
+
+    unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
+    {
+        source.into_vec()
+    }
+}
+
+// Borrowed numeric slices are returned as SAFEARRAY descriptors over the data
+// of the object. The methods must opt in with `#[com_return(borrow_array)]`
+// unless the slice is `'static`, in which case every call gets a fresh
+// descriptor over the static data without copying it.
+unsafe impl<TS: TypeSystem, T: raw::SafeArrayElement> ExternType<TS> for &[T]
+{
+    type ForeignType = raw::ValueArray<TS, T>;
+}
+
+unsafe impl<TS: TypeSystem, T: raw::SafeArrayElement> ExternOutput<TS> for &[T]
+{
+    fn into_foreign_output(self) -> ComResult<Self::ForeignType>
+    {
+        raw::ValueArray::describe(self)
+    }
+
+    unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
+    {
+        // The data remains owned by the object so only the descriptor is
+        // released.
+        let result = source.as_slice();
+        source.free();
+        result
+    }
+}
+
+/// Keeps the `SAFEARRAY` allocated for an array parameter alive for the
+/// duration of the call.
+pub struct ValueArrayLease<TS: TypeSystem, T: raw::SafeArrayItem>(
+    pub(crate) raw::ValueArray<TS, T>,
+);
+
+impl<TS: TypeSystem, T: raw::SafeArrayItem> Drop for ValueArrayLease<TS, T>
+{
+    fn drop(&mut self)
+    {
+        unsafe { self.0.free() }
+    }
+}
+
 impl<TS: TypeSystem> TryFrom<Variant> for raw::Variant<TS>
 {
     type Error = ComError;
@@ -629,11 +734,22 @@ pub mod raw
 
     use super::intercom_attributes::ForeignType;
     use crate::type_system::TypeSystem;
+    use std::convert::TryFrom;
+    use std::marker::PhantomData;
     use std::time::{Duration, SystemTime};
 
+    /// 16-bit Automation boolean with all bits set for `true`.
     #[repr(C)]
-    #[derive(Copy, Clone)]
-    pub struct VariantBool(u16);
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    pub struct VariantBool(pub u16);
+
+    impl crate::type_system::ForeignType for VariantBool
+    {
+        fn type_name() -> &'static str
+        {
+            "VARIANT_BOOL"
+        }
+    }
 
     impl From<VariantBool> for bool
     {
@@ -900,6 +1016,642 @@ pub mod raw
         pub const TYPEMASK: u16 = 0xfff;
     }
 
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    #[allow(non_snake_case)]
+    pub struct SafeArrayBound
+    {
+        pub cElements: u32,
+        pub lLbound: i32,
+    }
+
+    #[repr(C)]
+    #[allow(non_snake_case)]
+    pub struct SafeArray
+    {
+        pub cDims: u16,
+        pub fFeatures: u16,
+        pub cbElements: u32,
+        pub cLocks: u32,
+        pub pvData: *mut std::os::raw::c_void,
+        pub rgsabound: [SafeArrayBound; 1],
+    }
+
+    /// Feature flag for arrays whose data is not owned by the array.
+    const FADF_STATIC: u16 = 0x0002;
+
+    /// Feature flag for arrays that must not be resized.
+    const FADF_FIXEDSIZE: u16 = 0x0010;
+
+    /// Element types of the `SAFEARRAY` values that may describe Rust memory
+    /// without copying it.
+    pub trait SafeArrayElement: Copy
+    {
+        /// The `VARTYPE` of the elements.
+        const VAR_TYPE: u16;
+
+        /// The name of the array type in the type library.
+        fn array_type_name() -> &'static str;
+    }
+
+    macro_rules! impl_safe_array_element {
+        ( $( $t:ty => $vt:ident ),* ) => { $(
+            impl SafeArrayElement for $t
+            {
+                const VAR_TYPE: u16 = var_type::$vt;
+
+                fn array_type_name() -> &'static str
+                {
+                    concat!("SAFEARRAY(", stringify!($t), ")")
+                }
+            }
+        )* }
+    }
+
+    impl_safe_array_element!(
+        i8 => I1, u8 => UI1, i16 => I2, u16 => UI2, i32 => I4, u32 => UI4,
+        i64 => I8, u64 => UI8, f32 => R4, f64 => R8
+    );
+
+    /// Element types of the `SAFEARRAY` values.
+    ///
+    /// # Safety
+    ///
+    /// The `Element` must match the `VAR_TYPE` of the array. A zeroed
+    /// `Element` must be valid and own no data.
+    pub unsafe trait SafeArrayItem: Sized
+    {
+        /// The representation of the values in the array of the type system.
+        type Element<TS: TypeSystem>: Copy;
+
+        /// The `VARTYPE` of the elements.
+        const VAR_TYPE: u16;
+
+        /// The name of the array type.
+        fn array_type_name() -> &'static str;
+
+        /// The name of the array type in the type library.
+        fn array_type_library_name() -> std::borrow::Cow<'static, str>
+        {
+            Self::array_type_name().into()
+        }
+
+        /// Converts the value into an element owning its data.
+        fn into_element<TS: TypeSystem>(self) -> crate::ComResult<Self::Element<TS>>;
+
+        /// Converts the element into a value, taking the ownership of its
+        /// data.
+        ///
+        /// # Safety
+        ///
+        /// The element must not be used after this.
+        unsafe fn from_element<TS: TypeSystem>(
+            element: Self::Element<TS>,
+        ) -> crate::ComResult<Self>;
+
+        /// Copies the element into a value. The element keeps its data.
+        ///
+        /// # Safety
+        ///
+        /// The element must be valid.
+        unsafe fn copy_element<TS: TypeSystem>(
+            element: &Self::Element<TS>,
+        ) -> crate::ComResult<Self>;
+
+        /// Releases the data owned by the element.
+        ///
+        /// # Safety
+        ///
+        /// The element must not be used after this.
+        unsafe fn drop_element<TS: TypeSystem>(_element: Self::Element<TS>) {}
+    }
+
+    unsafe impl<T: SafeArrayElement> SafeArrayItem for T
+    {
+        type Element<TS: TypeSystem> = T;
+        const VAR_TYPE: u16 = <T as SafeArrayElement>::VAR_TYPE;
+
+        fn array_type_name() -> &'static str
+        {
+            <T as SafeArrayElement>::array_type_name()
+        }
+
+        fn into_element<TS: TypeSystem>(self) -> crate::ComResult<T>
+        {
+            Ok(self)
+        }
+
+        unsafe fn from_element<TS: TypeSystem>(element: T) -> crate::ComResult<T>
+        {
+            Ok(element)
+        }
+
+        unsafe fn copy_element<TS: TypeSystem>(element: &T) -> crate::ComResult<T>
+        {
+            Ok(*element)
+        }
+    }
+
+    unsafe impl SafeArrayItem for bool
+    {
+        type Element<TS: TypeSystem> = VariantBool;
+        const VAR_TYPE: u16 = var_type::BOOL;
+
+        fn array_type_name() -> &'static str
+        {
+            "SAFEARRAY(VARIANT_BOOL)"
+        }
+
+        fn into_element<TS: TypeSystem>(self) -> crate::ComResult<VariantBool>
+        {
+            Ok(self.into())
+        }
+
+        unsafe fn from_element<TS: TypeSystem>(element: VariantBool) -> crate::ComResult<bool>
+        {
+            Ok(element.into())
+        }
+
+        unsafe fn copy_element<TS: TypeSystem>(element: &VariantBool) -> crate::ComResult<bool>
+        {
+            Ok((*element).into())
+        }
+    }
+
+    unsafe impl SafeArrayItem for String
+    {
+        type Element<TS: TypeSystem> = *mut u16;
+        const VAR_TYPE: u16 = var_type::BSTR;
+
+        fn array_type_name() -> &'static str
+        {
+            "SAFEARRAY(BSTR)"
+        }
+
+        fn into_element<TS: TypeSystem>(self) -> crate::ComResult<*mut u16>
+        {
+            let bstr = crate::BString::from(self.as_str()).into_ptr();
+            if bstr.is_null() {
+                return Err(crate::ComError::E_OUTOFMEMORY);
+            }
+            Ok(bstr)
+        }
+
+        unsafe fn from_element<TS: TypeSystem>(element: *mut u16) -> crate::ComResult<String>
+        {
+            let result = Self::copy_element::<TS>(&element);
+            Self::drop_element::<TS>(element);
+            result
+        }
+
+        unsafe fn copy_element<TS: TypeSystem>(element: &*mut u16) -> crate::ComResult<String>
+        {
+            crate::BStr::from_ptr(*element)
+                .to_string()
+                .map_err(|_| crate::ComError::E_INVALIDARG)
+        }
+
+        unsafe fn drop_element<TS: TypeSystem>(element: *mut u16)
+        {
+            drop(crate::BString::from_ptr(element));
+        }
+    }
+
+    // The zeroed VARIANT is VT_EMPTY.
+    unsafe impl SafeArrayItem for super::Variant
+    {
+        type Element<TS: TypeSystem> = Variant<TS>;
+        const VAR_TYPE: u16 = var_type::VARIANT;
+
+        fn array_type_name() -> &'static str
+        {
+            "SAFEARRAY(VARIANT)"
+        }
+
+        fn into_element<TS: TypeSystem>(self) -> crate::ComResult<Variant<TS>>
+        {
+            Variant::try_from(self)
+        }
+
+        unsafe fn from_element<TS: TypeSystem>(
+            element: Variant<TS>,
+        ) -> crate::ComResult<super::Variant>
+        {
+            super::Variant::from_raw(element)
+        }
+
+        unsafe fn copy_element<TS: TypeSystem>(
+            element: &Variant<TS>,
+        ) -> crate::ComResult<super::Variant>
+        {
+            // The Variant conversion takes the ownership of the data so
+            // clone the value and forget the original.
+            let borrowed = std::mem::ManuallyDrop::new(super::Variant::from_raw(*element)?);
+            Ok((*borrowed).clone())
+        }
+
+        unsafe fn drop_element<TS: TypeSystem>(element: Variant<TS>)
+        {
+            drop(super::Variant::from_raw(element));
+        }
+    }
+
+    // The array holds a reference to each of the interfaces. The zeroed
+    // element is a null pointer.
+    unsafe impl<I> SafeArrayItem for crate::ComRc<I>
+    where
+        I: crate::attributes::ComInterface + crate::type_system::ForeignType + ?Sized,
+    {
+        type Element<TS: TypeSystem> = Option<crate::raw::InterfacePtr<TS, I>>;
+        const VAR_TYPE: u16 = var_type::UNKNOWN;
+
+        fn array_type_name() -> &'static str
+        {
+            "SAFEARRAY(IUnknown*)"
+        }
+
+        fn array_type_library_name() -> std::borrow::Cow<'static, str>
+        {
+            format!("SAFEARRAY({}*)", I::type_name()).into()
+        }
+
+        /// Fails with `E_POINTER` if the interface has no pointer for the
+        /// type system.
+        fn into_element<TS: TypeSystem>(self) -> crate::ComResult<Self::Element<TS>>
+        {
+            let ptr = crate::ComItf::ptr::<TS>(&self).ok_or(crate::ComError::E_POINTER)?;
+            std::mem::forget(self);
+            Ok(Some(ptr))
+        }
+
+        unsafe fn from_element<TS: TypeSystem>(element: Self::Element<TS>)
+            -> crate::ComResult<Self>
+        {
+            match element {
+                Some(ptr) => Ok(crate::ComRc::wrap(ptr)),
+                None => Err(crate::ComError::E_POINTER),
+            }
+        }
+
+        unsafe fn copy_element<TS: TypeSystem>(
+            element: &Self::Element<TS>,
+        ) -> crate::ComResult<Self>
+        {
+            match element {
+                Some(ptr) => Ok(crate::ComRc::from(*ptr)),
+                None => Err(crate::ComError::E_POINTER),
+            }
+        }
+
+        unsafe fn drop_element<TS: TypeSystem>(element: Self::Element<TS>)
+        {
+            if let Some(ptr) = element {
+                drop(crate::ComRc::wrap(ptr));
+            }
+        }
+    }
+
+    /// Moves the element out of the array, leaving a zeroed one behind.
+    unsafe fn take_element<E>(element: &mut E) -> E
+    {
+        let value = std::ptr::read(element);
+        (element as *mut E as *mut std::mem::MaybeUninit<E>).write(std::mem::MaybeUninit::zeroed());
+        value
+    }
+
+    /// One-dimensional `SAFEARRAY`.
+    ///
+    /// The arrays allocated with `alloc` own copies of the values. The arrays
+    /// created with `describe` point at memory they do not own and are
+    /// marked `FADF_STATIC` and `FADF_FIXEDSIZE` so the clients may neither
+    /// free nor resize the data. Freeing such an array releases the
+    /// descriptor only.
+    #[repr(transparent)]
+    pub struct ValueArray<TS: TypeSystem, T>(pub *mut SafeArray, PhantomData<(TS, T)>);
+
+    impl<TS: TypeSystem, T> Clone for ValueArray<TS, T>
+    {
+        fn clone(&self) -> Self
+        {
+            *self
+        }
+    }
+
+    impl<TS: TypeSystem, T> Copy for ValueArray<TS, T> {}
+
+    impl<TS: TypeSystem, T: SafeArrayItem> crate::type_system::ForeignType for ValueArray<TS, T>
+    {
+        fn type_name() -> &'static str
+        {
+            T::array_type_name()
+        }
+
+        fn type_library_name() -> std::borrow::Cow<'static, str>
+        {
+            T::array_type_library_name()
+        }
+    }
+
+    impl<TS: TypeSystem, T: SafeArrayItem> ValueArray<TS, T>
+    {
+        /// Allocates a new array holding the values.
+        ///
+        /// The array has no elements for an empty `values` but it is never
+        /// null.
+        pub fn alloc(values: Vec<T>) -> crate::ComResult<ValueArray<TS, T>>
+        {
+            unsafe {
+                let ptr = os::SafeArrayCreateVector(T::VAR_TYPE, 0, values.len() as u32);
+                if ptr.is_null() {
+                    return Err(crate::ComError::E_OUTOFMEMORY);
+                }
+
+                // The elements are zeroed so the array can be freed even if
+                // the conversion fails halfway.
+                let array = ValueArray(ptr, PhantomData);
+                let data = (*ptr).pvData as *mut T::Element<TS>;
+                for (idx, value) in values.into_iter().enumerate() {
+                    match value.into_element::<TS>() {
+                        Ok(element) => *data.add(idx) = element,
+                        Err(e) => {
+                            array.free();
+                            return Err(e);
+                        }
+                    }
+                }
+
+                Ok(array)
+            }
+        }
+
+        /// Copies the array elements into Rust values.
+        ///
+        /// The array remains owned by the caller. A null array is treated as
+        /// an empty one.
+        ///
+        /// # Safety
+        ///
+        /// The array must be a valid `SAFEARRAY` or null.
+        pub unsafe fn to_vec(self) -> crate::ComResult<Vec<T>>
+        {
+            match self.elements()? {
+                Some(elements) => elements.iter().map(|e| T::copy_element::<TS>(e)).collect(),
+                None => Ok(vec![]),
+            }
+        }
+
+        /// Moves the array elements into Rust values and releases the array.
+        ///
+        /// # Safety
+        ///
+        /// The array must be a valid `SAFEARRAY` or null and must not be used
+        /// after this.
+        pub unsafe fn into_vec(self) -> crate::ComResult<Vec<T>>
+        {
+            // The elements that have been taken are zeroed so the ones left
+            // behind by a failed conversion are released with the array.
+            let result = match self.elements() {
+                Ok(Some(elements)) => elements
+                    .iter_mut()
+                    .map(|e| T::from_element::<TS>(take_element(e)))
+                    .collect(),
+                Ok(None) => Ok(vec![]),
+                Err(e) => Err(e),
+            };
+            self.free();
+            result
+        }
+
+        /// Releases the elements and the array allocated with `alloc` or the
+        /// descriptor allocated with `describe`.
+        ///
+        /// # Safety
+        ///
+        /// The array must not be used after this.
+        pub unsafe fn free(self)
+        {
+            if self.0.is_null() {
+                return;
+            }
+
+            // The described data belongs to someone else.
+            if (*self.0).fFeatures & FADF_STATIC != 0 {
+                os::SafeArrayDestroyDescriptor(self.0);
+                return;
+            }
+
+            if let Ok(Some(elements)) = self.elements() {
+                for e in elements.iter_mut() {
+                    T::drop_element::<TS>(take_element(e));
+                }
+            }
+            os::SafeArrayDestroy(self.0);
+        }
+
+        unsafe fn elements<'a>(self) -> crate::ComResult<Option<&'a mut [T::Element<TS>]>>
+        {
+            if self.0.is_null() {
+                return Ok(None);
+            }
+
+            let array = &*self.0;
+            if array.cDims != 1
+                || array.cbElements as usize != std::mem::size_of::<T::Element<TS>>()
+            {
+                return Err(crate::ComError::E_INVALIDARG);
+            }
+
+            let len = array.rgsabound[0].cElements as usize;
+            if len == 0 || array.pvData.is_null() {
+                return Ok(None);
+            }
+
+            Ok(Some(std::slice::from_raw_parts_mut(
+                array.pvData as *mut T::Element<TS>,
+                len,
+            )))
+        }
+    }
+
+    impl<TS: TypeSystem, T: SafeArrayElement> ValueArray<TS, T>
+    {
+        /// Allocates an array descriptor pointing at the `data`.
+        ///
+        /// The elements are not copied. The `data` must outlive the array and
+        /// must not be modified while the array is in use.
+        pub fn describe(data: &[T]) -> crate::ComResult<ValueArray<TS, T>>
+        {
+            unsafe {
+                let mut ptr = std::ptr::null_mut();
+                let hr = os::SafeArrayAllocDescriptorEx(T::VAR_TYPE, 1, &mut ptr);
+                if hr != crate::raw::S_OK {
+                    return Err(crate::ComError::from(hr));
+                }
+                if ptr.is_null() {
+                    return Err(crate::ComError::E_OUTOFMEMORY);
+                }
+
+                let array = &mut *ptr;
+                array.fFeatures |= FADF_STATIC | FADF_FIXEDSIZE;
+                array.cbElements = std::mem::size_of::<T>() as u32;
+                array.pvData = data.as_ptr() as *mut std::os::raw::c_void;
+                array.rgsabound[0] = SafeArrayBound {
+                    cElements: data.len() as u32,
+                    lLbound: 0,
+                };
+
+                Ok(ValueArray(ptr, PhantomData))
+            }
+        }
+
+        /// Gets the elements of the array without copying them.
+        ///
+        /// # Safety
+        ///
+        /// The array must be a valid `SAFEARRAY` and the memory it points at
+        /// must remain valid for the lifetime `'a`.
+        pub unsafe fn as_slice<'a>(self) -> crate::ComResult<&'a [T]>
+        {
+            if self.0.is_null() {
+                return Err(crate::ComError::E_POINTER);
+            }
+
+            match self.elements()? {
+                Some(elements) => Ok(elements),
+                None => Ok(&[]),
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    #[allow(non_snake_case)]
+    mod os
+    {
+        use super::SafeArray;
+
+        #[link(name = "oleaut32")]
+        extern "system" {
+            pub fn SafeArrayCreateVector(
+                vt: u16,
+                lower_bound: i32,
+                elements: u32,
+            ) -> *mut SafeArray;
+
+            pub fn SafeArrayDestroy(psa: *mut SafeArray) -> crate::raw::HRESULT;
+
+            pub fn SafeArrayAllocDescriptorEx(
+                vt: u16,
+                dims: u32,
+                psa: *mut *mut SafeArray,
+            ) -> crate::raw::HRESULT;
+
+            pub fn SafeArrayDestroyDescriptor(psa: *mut SafeArray) -> crate::raw::HRESULT;
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[allow(non_snake_case)]
+    mod os
+    {
+        use super::{var_type, SafeArray, SafeArrayBound, Variant};
+        use crate::type_system::AutomationTypeSystem;
+
+        /// Feature flag for arrays of BSTR values.
+        const FADF_BSTR: u16 = 0x0100;
+
+        /// Feature flag for arrays of IUnknown pointers.
+        const FADF_UNKNOWN: u16 = 0x0200;
+
+        /// Feature flag for arrays of VARIANT values.
+        const FADF_VARIANT: u16 = 0x0800;
+
+        /// Creates a one-dimensional array of zeroed elements.
+        ///
+        /// Only the VT_VARIANT, VT_BSTR, VT_UNKNOWN, VT_BOOL and the numeric
+        /// arrays are needed by Intercom.
+        pub unsafe fn SafeArrayCreateVector(
+            vt: u16,
+            lower_bound: i32,
+            elements: u32,
+        ) -> *mut SafeArray
+        {
+            let (features, element_size) = match vt {
+                var_type::VARIANT => (
+                    FADF_VARIANT,
+                    std::mem::size_of::<Variant<AutomationTypeSystem>>(),
+                ),
+                var_type::BSTR => (FADF_BSTR, std::mem::size_of::<*mut u16>()),
+                var_type::UNKNOWN => (FADF_UNKNOWN, std::mem::size_of::<crate::raw::RawComPtr>()),
+                var_type::I1 | var_type::UI1 => (0, 1),
+                var_type::I2 | var_type::UI2 | var_type::BOOL => (0, 2),
+                var_type::I4 | var_type::UI4 | var_type::R4 => (0, 4),
+                var_type::I8 | var_type::UI8 | var_type::R8 => (0, 8),
+                _ => return std::ptr::null_mut(),
+            };
+
+            let data = libc::calloc(elements as usize + 1, element_size);
+            if data.is_null() {
+                return std::ptr::null_mut();
+            }
+
+            let array = libc::malloc(std::mem::size_of::<SafeArray>()) as *mut SafeArray;
+            if array.is_null() {
+                libc::free(data);
+                return std::ptr::null_mut();
+            }
+
+            array.write(SafeArray {
+                cDims: 1,
+                fFeatures: features,
+                cbElements: element_size as u32,
+                cLocks: 0,
+                pvData: data,
+                rgsabound: [SafeArrayBound {
+                    cElements: elements,
+                    lLbound: lower_bound,
+                }],
+            });
+            array
+        }
+
+        pub unsafe fn SafeArrayDestroy(psa: *mut SafeArray) -> crate::raw::HRESULT
+        {
+            if !psa.is_null() {
+                libc::free((*psa).pvData);
+                libc::free(psa as *mut libc::c_void);
+            }
+            crate::raw::S_OK
+        }
+
+        /// Allocates a one-dimensional array descriptor without data.
+        pub unsafe fn SafeArrayAllocDescriptorEx(
+            _vt: u16,
+            dims: u32,
+            psa: *mut *mut SafeArray,
+        ) -> crate::raw::HRESULT
+        {
+            if dims != 1 {
+                return crate::raw::E_INVALIDARG;
+            }
+
+            let array = libc::calloc(1, std::mem::size_of::<SafeArray>()) as *mut SafeArray;
+            if array.is_null() {
+                return crate::raw::E_OUTOFMEMORY;
+            }
+
+            (*array).cDims = 1;
+            *psa = array;
+            crate::raw::S_OK
+        }
+
+        /// Releases the descriptor without touching the data.
+        pub unsafe fn SafeArrayDestroyDescriptor(psa: *mut SafeArray) -> crate::raw::HRESULT
+        {
+            libc::free(psa as *mut libc::c_void);
+            crate::raw::S_OK
+        }
+    }
+
     pub struct VariantError(VariantType);
 
     impl From<VariantError> for crate::ComError
@@ -924,6 +1676,7 @@ mod test
 {
 
     use super::*;
+    use crate::type_system::AutomationTypeSystem;
 
     #[test]
     fn i8_to_variant()
@@ -1209,4 +1962,266 @@ mod test
             .unwrap()
         );
     }
+
+    #[crate::com_interface]
+    trait IVarargs
+    {
+        fn describe(&self, prefix: &str, args: &[Variant]) -> ComResult<String>;
+    }
+
+    #[crate::com_class(clsid = None, IVarargs)]
+    struct Varargs;
+
+    impl IVarargs for Varargs
+    {
+        fn describe(&self, prefix: &str, args: &[Variant]) -> ComResult<String>
+        {
+            let values = args
+                .iter()
+                .map(|v| match v {
+                    Variant::I32(i) => i.to_string(),
+                    Variant::String(s) => String::try_from(s.clone()).unwrap_or_default(),
+                    other => format!("{:?}", other),
+                })
+                .collect::<Vec<_>>();
+            Ok(format!("{}[{}]", prefix, values.join(",")))
+        }
+    }
+
+    #[test]
+    fn trailing_variant_arguments()
+    {
+        let rc = crate::ComRc::<dyn IVarargs>::from(crate::ComBox::new(Varargs));
+
+        assert_eq!(rc.describe("none", &[]).unwrap(), "none[]");
+        assert_eq!(
+            rc.describe("one", &[Variant::from(1i32)]).unwrap(),
+            "one[1]"
+        );
+        assert_eq!(
+            rc.describe(
+                "several",
+                &[
+                    Variant::from(1i32),
+                    Variant::from("two".to_string()),
+                    Variant::from(3i32)
+                ]
+            )
+            .unwrap(),
+            "several[1,two,3]"
+        );
+    }
+
+    #[test]
+    fn variant_array_round_trip()
+    {
+        let values = vec![Variant::from(7i32), Variant::from("seven".to_string())];
+        let array = raw::ValueArray::<AutomationTypeSystem, _>::alloc(values).unwrap();
+        unsafe {
+            let copied = array.to_vec().unwrap();
+            assert_eq!(copied.len(), 2);
+            assert_eq!(i32::try_from(copied[0].clone()).unwrap(), 7);
+            assert_eq!(String::try_from(copied[1].clone()).unwrap(), "seven");
+
+            // The values are moved out of the array on the way back.
+            let moved = array.into_vec().unwrap();
+            assert_eq!(String::try_from(moved[1].clone()).unwrap(), "seven");
+        }
+        assert_eq!(
+            <raw::ValueArray<AutomationTypeSystem, Variant> as crate::type_system::ForeignType>::type_name(),
+            "SAFEARRAY(VARIANT)"
+        );
+    }
+
+    #[crate::com_interface]
+    trait INames
+    {
+        fn names(&self, count: u32) -> ComResult<Vec<String>>;
+    }
+
+    #[crate::com_class(clsid = None, INames)]
+    struct Names;
+
+    impl INames for Names
+    {
+        fn names(&self, count: u32) -> ComResult<Vec<String>>
+        {
+            if count > 3 {
+                return Err(ComError::E_INVALIDARG);
+            }
+            Ok(["one", "two", "three"][..count as usize]
+                .iter()
+                .map(|s| s.to_string())
+                .collect())
+        }
+    }
+
+    #[test]
+    fn string_vec_round_trip()
+    {
+        let rc = crate::ComRc::<dyn INames>::from(crate::ComBox::new(Names));
+
+        assert_eq!(rc.names(0).unwrap(), Vec::<String>::new());
+        assert_eq!(rc.names(1).unwrap(), vec!["one"]);
+        assert_eq!(rc.names(3).unwrap(), vec!["one", "two", "three"]);
+        assert_eq!(rc.names(4).unwrap_err().hresult, crate::raw::E_INVALIDARG);
+
+        assert_eq!(
+            <raw::ValueArray<AutomationTypeSystem, String> as crate::type_system::ForeignType>::type_name(),
+            "SAFEARRAY(BSTR)"
+        );
+    }
+
+    #[crate::com_interface]
+    trait IVectors
+    {
+        fn squares(&self, count: u32) -> ComResult<Vec<i32>>;
+        fn negate(&self, values: Vec<bool>) -> ComResult<Vec<bool>>;
+        fn join(&self, values: Vec<String>) -> ComResult<String>;
+    }
+
+    #[crate::com_class(clsid = None, IVectors)]
+    struct Vectors;
+
+    impl IVectors for Vectors
+    {
+        fn squares(&self, count: u32) -> ComResult<Vec<i32>>
+        {
+            Ok((0..count as i32).map(|i| i * i).collect())
+        }
+
+        fn negate(&self, values: Vec<bool>) -> ComResult<Vec<bool>>
+        {
+            Ok(values.into_iter().map(|b| !b).collect())
+        }
+
+        fn join(&self, values: Vec<String>) -> ComResult<String>
+        {
+            Ok(values.join(","))
+        }
+    }
+
+    #[test]
+    fn vec_round_trip()
+    {
+        let rc = crate::ComRc::<dyn IVectors>::from(crate::ComBox::new(Vectors));
+
+        assert_eq!(rc.squares(0).unwrap(), Vec::<i32>::new());
+        assert_eq!(rc.squares(4).unwrap(), vec![0, 1, 4, 9]);
+        assert_eq!(rc.negate(vec![true, false]).unwrap(), vec![false, true]);
+        assert_eq!(rc.join(vec![]).unwrap(), "");
+        assert_eq!(
+            rc.join(vec!["a".to_string(), "b".to_string()]).unwrap(),
+            "a,b"
+        );
+
+        // The empty vectors are arrays without elements rather than null.
+        let array = raw::ValueArray::<AutomationTypeSystem, _>::alloc(Vec::<i32>::new()).unwrap();
+        unsafe {
+            assert!(!array.0.is_null());
+            assert_eq!((*array.0).rgsabound[0].cElements, 0);
+            array.free();
+        }
+
+        // The booleans are stored as VARIANT_BOOL.
+        let array = raw::ValueArray::<AutomationTypeSystem, _>::alloc(vec![true]).unwrap();
+        unsafe {
+            assert_eq!((*array.0).cbElements, 2);
+            assert_eq!(*((*array.0).pvData as *const u16), 0xffff);
+            array.free();
+        }
+
+        assert_eq!(
+            <raw::ValueArray<AutomationTypeSystem, i32> as crate::type_system::ForeignType>::type_name(),
+            "SAFEARRAY(i32)"
+        );
+        assert_eq!(
+            <raw::ValueArray<AutomationTypeSystem, bool> as crate::type_system::ForeignType>::type_name(),
+            "SAFEARRAY(VARIANT_BOOL)"
+        );
+    }
+
+    #[crate::com_interface]
+    trait IMatrix
+    {
+        #[com_return(borrow_array)]
+        fn values(&self) -> ComResult<&[f64]>;
+    }
+
+    #[crate::com_class(clsid = None, IMatrix)]
+    struct Matrix
+    {
+        values: Vec<f64>,
+    }
+
+    impl IMatrix for Matrix
+    {
+        fn values(&self) -> ComResult<&[f64]>
+        {
+            Ok(&self.values)
+        }
+    }
+
+    #[test]
+    fn borrowed_array_is_not_copied()
+    {
+        let values = (0..1_000_000).map(f64::from).collect::<Vec<_>>();
+        let data = values.as_ptr();
+        let rc = crate::ComRc::<dyn IMatrix>::from(crate::ComBox::new(Matrix { values }));
+
+        let returned = rc.values().unwrap();
+        assert_eq!(returned.as_ptr(), data);
+        assert_eq!(returned.len(), 1_000_000);
+        assert_eq!(returned[999_999], 999_999.0);
+
+        let array = raw::ValueArray::<AutomationTypeSystem, _>::describe(returned).unwrap();
+        unsafe {
+            assert_eq!((*array.0).fFeatures & 0x0012, 0x0012);
+            assert_eq!((*array.0).pvData as *const f64, data);
+            array.free();
+        }
+
+        assert_eq!(
+            <raw::ValueArray<AutomationTypeSystem, f64> as crate::type_system::ForeignType>::type_name(),
+            "SAFEARRAY(f64)"
+        );
+    }
+
+    const BLOB: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[crate::com_interface]
+    trait IBlob
+    {
+        fn blob(&self) -> ComResult<&'static [u8]>;
+    }
+
+    #[crate::com_class(clsid = None, IBlob)]
+    struct Blob;
+
+    impl IBlob for Blob
+    {
+        fn blob(&self) -> ComResult<&'static [u8]>
+        {
+            Ok(BLOB)
+        }
+    }
+
+    #[test]
+    fn static_array_is_not_copied()
+    {
+        let rc = crate::ComRc::<dyn IBlob>::from(crate::ComBox::new(Blob));
+
+        for _ in 0..2 {
+            let returned = rc.blob().unwrap();
+            assert_eq!(returned, BLOB);
+            assert_eq!(returned.as_ptr(), BLOB.as_ptr());
+        }
+
+        let array = raw::ValueArray::<AutomationTypeSystem, _>::describe(BLOB).unwrap();
+        unsafe {
+            assert_eq!((*array.0).fFeatures & 0x0012, 0x0012);
+            assert_eq!(array.to_vec().unwrap(), BLOB);
+            array.free();
+        }
+    }
 }
@@ -0,0 +1,72 @@
+#include "../cpp-utility/os.hpp"
+#include "../dependencies/catch.hpp"
+
+#include "testlib.hpp"
+
+TEST_CASE( "arrays" )
+{
+    // Initialize COM.
+    InitializeRuntime();
+
+    IArrayTests_Raw* pArrays = nullptr;
+    intercom::HRESULT hr = CreateInstance(
+            CLSID_ArrayTests,
+            IID_IArrayTests_Raw,
+            &pArrays );
+
+    REQUIRE( hr == intercom::SC_OK );
+    REQUIRE( pArrays != nullptr );
+
+    SECTION( "Byte slices are passed as a pointer and a count" )
+    {
+        uint8_t data[] = { 1, 2, 3, 250 };
+        uint32_t sum = 0;
+        REQUIRE( pArrays->Checksum( data, 4, OUT &sum ) == intercom::SC_OK );
+        REQUIRE( sum == 256 );
+
+        // The count limits the elements read.
+        REQUIRE( pArrays->Checksum( data, 2, OUT &sum ) == intercom::SC_OK );
+        REQUIRE( sum == 3 );
+
+        // The empty slices may be passed as null.
+        REQUIRE( pArrays->Checksum( nullptr, 0, OUT &sum ) == intercom::SC_OK );
+        REQUIRE( sum == 0 );
+        REQUIRE( pArrays->Checksum( nullptr, 1, OUT &sum ) == intercom::EC_POINTER );
+    }
+
+    SECTION( "Mutable byte slices are written by the callee" )
+    {
+        uint8_t buffer[] = { 0, 0, 0, 0 };
+        REQUIRE( pArrays->Fill( buffer, 3, 7 ) == intercom::SC_OK );
+        REQUIRE( buffer[ 0 ] == 7 );
+        REQUIRE( buffer[ 2 ] == 7 );
+        REQUIRE( buffer[ 3 ] == 0 );
+    }
+
+    SECTION( "Buffer element counts are checked against the capacity" )
+    {
+        uint8_t buffer[] = { 0, 0, 0, 0 };
+        uint32_t count = 123;
+        REQUIRE( pArrays->Read( 3, buffer, 4, OUT &count ) == intercom::SC_OK );
+        REQUIRE( count == 3 );
+        REQUIRE( buffer[ 0 ] == 1 );
+        REQUIRE( buffer[ 2 ] == 3 );
+        REQUIRE( buffer[ 3 ] == 0 );
+
+        // The count beyond the capacity is reported as an error.
+        count = 123;
+        REQUIRE( pArrays->Read( 5, buffer, 4, OUT &count ) == intercom::EC_FAIL );
+        REQUIRE( count == 0 );
+    }
+
+    SECTION( "Borrowed byte arrays are returned as SAFEARRAY" )
+    {
+        intercom::SAFEARRAY* array = nullptr;
+        REQUIRE( pArrays->Data( OUT &array ) == intercom::SC_OK );
+        REQUIRE( array != nullptr );
+    }
+
+    REQUIRE( pArrays->Release() == 0 );
+
+    UninitializeRuntime();
+}
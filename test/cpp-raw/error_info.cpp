@@ -92,6 +92,47 @@ namespace
             return hr;
 		}
 
+		virtual intercom::HRESULT INTERCOM_CC ReturnTupleerror(
+            intercom::HRESULT hr,
+            intercom::BSTR bstr
+		)
+		{
+            // Get the error store for storing the message.
+            IErrorStore_Automation* pErrorStore = nullptr;
+            intercom::HRESULT hr2 = CreateInstance(
+                CLSID_ErrorStore,
+                IID_IErrorStore_Automation,
+                &pErrorStore );
+            if( hr2 != intercom::SC_OK )
+                return hr2;
+
+            // Set the error message.
+            hr2 = pErrorStore->SetErrorMessage( bstr );
+            if( hr2 != intercom::SC_OK )
+                return hr2;
+
+            pErrorStore->Release();
+
+            // Return the hresult.
+            return hr;
+		}
+
+		virtual intercom::HRESULT INTERCOM_CC ReturnCustomErrorinfo(
+            intercom::HRESULT hr,
+            intercom::BSTR bstr
+		)
+		{
+            return intercom::EC_NOTIMPL;
+		}
+
+		virtual intercom::HRESULT INTERCOM_CC ReturnCodederror(
+            intercom::HRESULT hr,
+            intercom::BSTR bstr
+		)
+		{
+            return intercom::EC_NOTIMPL;
+		}
+
 		virtual intercom::HRESULT INTERCOM_CC QueryInterface(
 			const intercom::IID& riid,
 			void** out
@@ -358,6 +399,76 @@ TEST_CASE( "error_info" )
             pErrorInfo->Release();
         }
 
+        SECTION( "Returning (HRESULT, String) tuple" )
+        {
+            intercom::BSTR bstrError = AllocBstr( pAllocator, u"x too large" );
+            hr = pErrorSource->ReturnTupleerror( intercom::EC_INVALIDARG, bstrError );
+
+            REQUIRE( hr == intercom::EC_INVALIDARG );
+
+            IErrorInfo* pErrorInfo = nullptr;
+            hr = pErrorStore->GetErrorInfo( &pErrorInfo );
+            REQUIRE( hr == intercom::SC_OK );
+
+            intercom::BSTR bstrOut = nullptr;
+            hr = pErrorInfo->GetDescription( &bstrOut );
+            check_equal( u"x too large", bstrOut );
+            pAllocator->FreeBstr( bstrOut );
+            pAllocator->FreeBstr( bstrError );
+            pErrorInfo->Release();
+        }
+
+        SECTION( "Returning error with an error code" )
+        {
+            intercom::BSTR bstrError = AllocBstr( pAllocator, u"Error message" );
+            hr = pErrorSource->ReturnCodederror( 0x81234567, bstrError );
+
+            REQUIRE( hr == 0x81234567 );
+
+            IErrorInfo* pErrorInfo = nullptr;
+            hr = pErrorStore->GetErrorInfo( &pErrorInfo );
+            REQUIRE( hr == intercom::SC_OK );
+
+            intercom::BSTR bstrOut = nullptr;
+            hr = pErrorInfo->GetDescription( &bstrOut );
+            check_equal( u"Error message", bstrOut );
+            pAllocator->FreeBstr( bstrOut );
+            pAllocator->FreeBstr( bstrError );
+            pErrorInfo->Release();
+        }
+
+        SECTION( "Returning custom IErrorInfo object" )
+        {
+            intercom::BSTR bstrError = AllocBstr( pAllocator, u"Error message" );
+            hr = pErrorSource->ReturnCustomErrorinfo( 0x81234567, bstrError );
+
+            REQUIRE( hr == 0x81234567 );
+
+            IErrorInfo* pErrorInfo = nullptr;
+            hr = pErrorStore->GetErrorInfo( &pErrorInfo );
+            REQUIRE( hr == intercom::SC_OK );
+
+            intercom::BSTR bstrOut = nullptr;
+            hr = pErrorInfo->GetDescription( &bstrOut );
+            check_equal( u"Error message", bstrOut );
+            pAllocator->FreeBstr( bstrOut );
+
+            hr = pErrorInfo->GetSource( &bstrOut );
+            check_equal( u"ErrorTests", bstrOut );
+            pAllocator->FreeBstr( bstrOut );
+
+            hr = pErrorInfo->GetHelpFile( &bstrOut );
+            check_equal( u"errors.chm", bstrOut );
+            pAllocator->FreeBstr( bstrOut );
+
+            uint32_t helpContext = 0;
+            hr = pErrorInfo->GetHelpContext( &helpContext );
+            REQUIRE( helpContext == 42 );
+
+            pAllocator->FreeBstr( bstrError );
+            pErrorInfo->Release();
+        }
+
         SECTION( "Returning ComError from COM callback" )
         {
             ErrorSource source;
@@ -384,6 +495,20 @@ TEST_CASE( "error_info" )
             REQUIRE( source.qi == 1 );
             REQUIRE( source.release == 1 );
         }
+
+        SECTION( "Returning (HRESULT, String) tuple from COM callback" )
+        {
+            ErrorSource source;
+            REQUIRE( intercom::SC_OK == pErrorTests->TestTupleerror( &source ) );
+            REQUIRE( source.addRef == 0 );
+            REQUIRE( source.qi == 1 );
+            REQUIRE( source.release == 1 );
+        }
+
+        SECTION( "Reading custom IErrorInfo object from COM callback" )
+        {
+            REQUIRE( intercom::SC_OK == pErrorTests->TestCustomErrorinfo( pErrorSource ) );
+        }
     }
 
     REQUIRE( pErrorTests->Release() == 1 );
@@ -153,7 +153,7 @@ TEST_CASE( "nullable_parameters" )
             {
                 ICallback_Automation* pCallback;
                 hr = pTests->NullableOutput(0, &pCallback);
-                REQUIRE( hr == intercom::SC_OK );
+                REQUIRE( hr == intercom::SC_FALSE );
                 REQUIRE( pCallback == nullptr );
             }
         }
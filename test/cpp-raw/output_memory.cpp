@@ -36,6 +36,15 @@ struct OutputTests : public IOutputMemoryTests_Automation
         return intercom::SC_OK;
     }
 
+    virtual intercom::HRESULT INTERCOM_CC Error(
+        IUnknown* input,
+        OUT IUnknown** o1,
+        OUT IUnknown** o2
+    )
+    {
+        return intercom::EC_NOTIMPL;
+    }
+
     virtual intercom::HRESULT INTERCOM_CC CallSucceed(
         IOutputMemoryTests_Automation* itf,
         IUnknown* input
@@ -163,6 +172,25 @@ TEST_CASE( "output_memory" )
         }
     }
 
+    SECTION( "Error" )
+    {
+        OutputTests inputObject;
+        REQUIRE( inputObject.references == 0 );
+
+        // Intentionally assigned garbage values. The callee must null these
+        // as the caller must not release them on error.
+        IUnknown* o1 = (IUnknown*)-1;
+        IUnknown* o2 = (IUnknown*)1;
+        REQUIRE( intercom::EC_INVALIDARG == pTests->Error(&inputObject, OUT &o1, OUT &o2) );
+
+        REQUIRE( inputObject.addRefs == 1 );
+        REQUIRE( inputObject.releases == 1 );
+        REQUIRE( inputObject.references == 0 );
+
+        REQUIRE( o1 == nullptr );
+        REQUIRE( o2 == nullptr );
+    }
+
     REQUIRE( pTests->Release() == 0 );
 
     UninitializeRuntime();
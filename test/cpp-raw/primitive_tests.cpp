@@ -132,6 +132,63 @@ TEST_CASE( "primitive_tests" )
         REQUIRE( pOps->F64( 0.1234 ) == ( 1.0 / 0.1234 ) );
         REQUIRE( pOps->F64( 3.0 ) == ( 1.0 / 3.0 ) );
     }
+    SECTION( "raw type system" )
+    {
+        // The 64-bit and the floating point values are passed by value
+        // through the raw interface as well.
+        IPrimitiveOperations_Raw* pRawOps = nullptr;
+        hr = pOps->QueryInterface(
+                IID_IPrimitiveOperations_Raw,
+                OUT reinterpret_cast< void** >( &pRawOps ) );
+        REQUIRE( hr == intercom::SC_OK );
+        REQUIRE( pRawOps != nullptr );
+
+        REQUIRE( pRawOps->I64( std::numeric_limits<int64_t>::min() ) ==
+                std::numeric_limits<int64_t>::max() - 1 );
+        REQUIRE( pRawOps->I64( -( int64_t( 1 ) << 40 ) ) == ( int64_t( 1 ) << 40 ) - 2 );
+        REQUIRE( pRawOps->U64( std::numeric_limits<uint64_t>::max() - 1 ) == 0 );
+        REQUIRE( pRawOps->U64( 0 ) == std::numeric_limits<uint64_t>::max() - 1 );
+        REQUIRE( pRawOps->F32( 2.0f ) == 0.5f );
+        REQUIRE( pRawOps->F64( -4e100 ) == ( 1.0 / -4e100 ) );
+
+        REQUIRE( pRawOps->Release() == 1 );
+    }
+    SECTION( "enum" )
+    {
+        // The enums are passed as their discriminants.
+        int32_t color = -1;
+        REQUIRE( pOps->DefaultColor( OUT &color ) == intercom::SC_OK );
+        REQUIRE( color == 2 );
+
+        int32_t next = -1;
+        REQUIRE( pOps->NextColor( 3, OUT &next ) == intercom::SC_OK );
+        REQUIRE( next == 1 );
+
+        // The unknown discriminants are rejected.
+        next = -1;
+        REQUIRE( pOps->NextColor( 4, OUT &next ) == intercom::EC_INVALIDARG );
+        REQUIRE( next == 0 );
+    }
+
+    SECTION( "struct pointer" )
+    {
+        IAllocator_Automation* pAllocator = nullptr;
+        hr = CreateInstance(
+                CLSID_Allocator,
+                IID_IAllocator_Automation,
+                &pAllocator );
+        REQUIRE( hr == intercom::SC_OK );
+
+        test_lib::raw::Tuple_i32_i32* bounds = nullptr;
+        REQUIRE( pOps->Bounds( 5, -3, OUT &bounds ) == intercom::SC_OK );
+        REQUIRE( bounds != nullptr );
+        REQUIRE( bounds->item1 == -3 );
+        REQUIRE( bounds->item2 == 5 );
+
+        // The returned structures are allocated with the task allocator.
+        pAllocator->Free( bounds );
+        REQUIRE( pAllocator->Release() == 0 );
+    }
 
     REQUIRE( pOps->Release() == 0 );
 
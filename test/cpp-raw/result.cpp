@@ -56,6 +56,41 @@ TEST_CASE( "result" )
         }
     }
 
+    SECTION( "Explicit OUT parameters are written with unit results" )
+    {
+        uint32_t written = 123;
+
+        SECTION( "Success yields intercom::SC_OK and the OUT parameter" )
+        {
+            REQUIRE( pOps->Write( 10, OUT &written ) == intercom::SC_OK );
+            REQUIRE( written == 10 );
+        }
+
+        SECTION( "Failure yields error value and resets the OUT parameter" )
+        {
+            REQUIRE( pOps->Write( 0, OUT &written ) == intercom::EC_INVALIDARG );
+            REQUIRE( written == 0 );
+        }
+    }
+
+    SECTION( "IN/OUT parameters pass the value both ways" )
+    {
+        uint32_t value = 21;
+
+        SECTION( "Success yields intercom::SC_OK and the updated value" )
+        {
+            REQUIRE( pOps->Double( &value ) == intercom::SC_OK );
+            REQUIRE( value == 42 );
+        }
+
+        SECTION( "Failure yields error value and keeps the value" )
+        {
+            value = 0x80000000;
+            REQUIRE( pOps->Double( &value ) == intercom::EC_INVALIDARG );
+            REQUIRE( value == 0x80000000 );
+        }
+    }
+
     REQUIRE( pOps->Release() == 0 );
 
     UninitializeRuntime();
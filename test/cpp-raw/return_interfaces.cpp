@@ -82,6 +82,34 @@ TEST_CASE( "return_interfaces" )
             pChild->Release();
         }
 
+        SECTION( "Failed creation sets the out pointer to null" )
+        {
+            // Use an existing interface as a non-null sentinel value.
+            ICreatedClass_Automation* pCreated = pParent;
+            hr = pOps->TryCreateRoot( -1, OUT &pCreated );
+
+            REQUIRE( hr == intercom::EC_INVALIDARG );
+            REQUIRE( pCreated == nullptr );
+        }
+
+        SECTION( "Successful creation returns a single reference" )
+        {
+            ICreatedClass_Automation* pCreated = nullptr;
+            hr = pOps->TryCreateRoot( 1, OUT &pCreated );
+
+            REQUIRE( hr == intercom::SC_OK );
+            REQUIRE( pCreated != nullptr );
+
+            IRefCount_Automation* pRefCount = nullptr;
+            hr = pCreated->QueryInterface( IID_IRefCount_Automation, reinterpret_cast< void** >( &pRefCount ) );
+
+            // Two references: pCreated and pRefCount.
+            REQUIRE( pRefCount->GetRefCount() == 2 );
+
+            pRefCount->Release();
+            REQUIRE( pCreated->Release() == 0 );
+        }
+
         SECTION( "Returned interface corresponds with the type system" )
         {
             SECTION( "Automation interface returns automation interface" )
@@ -174,6 +202,25 @@ TEST_CASE( "return_interfaces" )
         REQUIRE( pParent->Release() == 0 );
     }
 
+    SECTION( "Classes without a class factory cannot be created" )
+    {
+        IInternalClass_Automation* pInternal = nullptr;
+        hr = CreateInstance(
+                CLSID_InternalClass,
+                IID_IInternalClass_Automation,
+                &pInternal );
+
+        REQUIRE( hr == intercom::EC_CLASSNOTREG );
+        REQUIRE( pInternal == nullptr );
+
+        hr = pOps->CreateInternal( 30, OUT &pInternal );
+        REQUIRE( hr == intercom::SC_OK );
+        REQUIRE( pInternal != nullptr );
+        REQUIRE( pInternal->GetId() == 30 );
+
+        REQUIRE( pInternal->Release() == 0 );
+    }
+
     REQUIRE( pOps->Release() == 0 );
 
     UninitializeRuntime();
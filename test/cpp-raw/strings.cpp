@@ -288,7 +288,9 @@ TEST_CASE( "strings" )
 
             REQUIRE( test_cstr_output != nullptr );
             REQUIRE( strcmp( test_cstr_output, u8"\U0001F600" ) == 0 );
-            REQUIRE( reinterpret_cast< uintptr_t >( test_cstr_output ) == test_ptr );
+
+            // CString into char* gets copied into the task allocator memory.
+            REQUIRE( reinterpret_cast< uintptr_t >( test_cstr_output ) != test_ptr );
         }
 
         SECTION( "BString into char* return value" ) {
@@ -320,7 +322,85 @@ TEST_CASE( "strings" )
         pAllocator->FreeBstr( test_text );
     }
 
+    IStringOutputs_Automation* pStringOutputs = nullptr;
+    hr = pStringTestsAutomation->QueryInterface(
+            IID_IStringOutputs_Automation,
+            OUT reinterpret_cast< void** >( &pStringOutputs ) );
+    REQUIRE( hr == intercom::SC_OK );
+
+    SECTION( "Failed String return leaves BSTR null" )
+    {
+        intercom::BSTR stale = reinterpret_cast< intercom::BSTR >(
+                static_cast< uintptr_t >( 0x1234 ) );
+
+        intercom::BSTR name = AllocBstr( pAllocator, u"caf\u00e9" );
+        intercom::BSTR test_text = stale;
+        intercom::HRESULT hr = pStringOutputs->Greet( name, OUT &test_text );
+        REQUIRE( hr == intercom::SC_OK );
+        check_equal( u"Hello, caf\u00e9!", test_text );
+        pAllocator->FreeBstr( test_text );
+        pAllocator->FreeBstr( name );
+
+        // Failure reported by the method.
+        test_text = stale;
+        hr = pStringOutputs->Greet( nullptr, OUT &test_text );
+        REQUIRE( hr == intercom::EC_INVALIDARG );
+        REQUIRE( test_text == nullptr );
+
+        // Failure converting the parameter before the method is called.
+        uint16_t data[] = { 0xd800 };
+        intercom::BSTR invalid = pAllocator->AllocBstr( data, 1 );
+        test_text = stale;
+        hr = pStringOutputs->Greet( invalid, OUT &test_text );
+        REQUIRE( hr == intercom::EC_INVALIDARG );
+        REQUIRE( test_text == nullptr );
+        pAllocator->FreeBstr( invalid );
+    }
+
+    SECTION( "In-out BSTR is reallocated by callee" )
+    {
+        intercom::BSTR test_text = AllocBstr( pAllocator, u"caf\u00e9" );
+        intercom::HRESULT hr = pStringOutputs->AppendCrab( &test_text );
+        REQUIRE( hr == intercom::SC_OK );
+        check_equal( u"caf\u00e9 \U0001F980", test_text );
+        pAllocator->FreeBstr( test_text );
+
+        // The callee hands back the value it holds even if the call fails.
+        test_text = AllocBstr( pAllocator, u"caf\u00e9" );
+        hr = pStringOutputs->AppendCrabAndFail( &test_text );
+        REQUIRE( hr == intercom::EC_FAIL );
+        check_equal( u"caf\u00e9 \U0001F980", test_text );
+        pAllocator->FreeBstr( test_text );
+    }
+
+    SECTION( "LPWSTR return value is allocated with the task allocator" )
+    {
+        IStringOutputs_Raw* pRawOutputs = nullptr;
+        hr = pStringOutputs->QueryInterface(
+                IID_IStringOutputs_Raw,
+                OUT reinterpret_cast< void** >( &pRawOutputs ) );
+        REQUIRE( hr == intercom::SC_OK );
+
+        char name[] = u8"caf\u00e9";
+        intercom::LPWSTR test_text = nullptr;
+        hr = pRawOutputs->GreetWide( name, OUT &test_text );
+        REQUIRE( hr == intercom::SC_OK );
+
+        const char16_t* expected = u"Hello, caf\u00e9!";
+        const char16_t* actual = reinterpret_cast< const char16_t* >( test_text );
+        REQUIRE( char_traits< char16_t >::length( actual ) ==
+                char_traits< char16_t >::length( expected ) );
+        REQUIRE( char_traits< char16_t >::compare(
+                actual, expected, char_traits< char16_t >::length( expected ) ) == 0 );
+
+        // The client frees the string with the task allocator.
+        intercom::free_string( test_text );
+
+        REQUIRE( pRawOutputs->Release() == 3 );
+    }
+
     REQUIRE( pAllocator->Release() == 0 );
+    REQUIRE( pStringOutputs->Release() == 2 );
     REQUIRE( pStringTestsRaw->Release() == 1 );
     REQUIRE( pStringTestsAutomation->Release() == 0 );
 }
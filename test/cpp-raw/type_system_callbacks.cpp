@@ -519,6 +519,24 @@ TEST_CASE( "type_system_callbacks" )
         {
             REQUIRE( pTest->ReceiveBstring( &impl ) == intercom::SC_OK );
         }
+        SECTION( "Pass through BString" )
+        {
+            intercom::BSTR bstr = nullptr;
+            size_t original_ptr = 0;
+            REQUIRE( pTest->PassThroughBstring( &impl, OUT &bstr, OUT &original_ptr ) == intercom::SC_OK );
+
+            // The BSTR allocated by the callback is returned without
+            // re-encoding it into a new allocation.
+            REQUIRE( reinterpret_cast< size_t >( bstr ) == original_ptr );
+
+            IAllocator_Automation* pAllocator = nullptr;
+            REQUIRE( CreateInstance(
+                    CLSID_Allocator,
+                    IID_IAllocator_Automation,
+                    &pAllocator ) == intercom::SC_OK );
+            pAllocator->FreeBstr( bstr );
+            pAllocator->Release();
+        }
         SECTION( "Pass CStr" )
         {
             REQUIRE( pTest->PassCstr( &impl ) == intercom::EC_POINTER );
@@ -529,7 +547,7 @@ TEST_CASE( "type_system_callbacks" )
         }
         SECTION( "Receive CString" )
         {
-            REQUIRE( pTest->ReceiveCstring( &impl ) == intercom::EC_POINTER );
+            REQUIRE( pTest->ReceiveCstring( &impl ) == intercom::SC_OK );
         }
 
 		pTest->Release();
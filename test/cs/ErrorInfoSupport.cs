@@ -33,6 +33,21 @@ namespace cs
         {
             throw new CustomException( hr, desc );
         }
+
+        public void ReturnTupleerror( int hr, string desc )
+        {
+            throw new CustomException( hr, desc );
+        }
+
+        public void ReturnCustomErrorinfo( int hr, string desc )
+        {
+            throw new CustomException( hr, desc );
+        }
+
+        public void ReturnCodederror( int hr, string desc )
+        {
+            throw new CustomException( hr, desc );
+        }
     }
 
     [TestClass]
@@ -80,6 +95,9 @@ namespace cs
                 ex = Assert.ThrowsException< ArgumentException >(
                         () => lib.ReturnTesterror( (int) 0x80070057, "E_INVALIDARG error message" ) );
                 Assert.AreEqual( "E_INVALIDARG error message", ex.Message );
+                ex = Assert.ThrowsException< ArgumentException >(
+                        () => lib.ReturnTupleerror( (int) 0x80070057, "x too large" ) );
+                Assert.AreEqual( "x too large", ex.Message );
             }
         }
 
@@ -157,5 +175,19 @@ namespace cs
             var lib = new TestLib.ErrorTests();
             lib.TestIoerror( new ErrorSource() );
         }
+
+        [TestMethod]
+        public void CallbackTupleErrorMaintainsErrorInfo()
+        {
+            var lib = new TestLib.ErrorTests();
+            lib.TestTupleerror( new ErrorSource() );
+        }
+
+        [TestMethod]
+        public void CustomErrorInfoMaintainsFields()
+        {
+            var lib = new TestLib.ErrorTests();
+            lib.TestCustomErrorinfo( ( TestLib.IErrorSource_Automation )lib );
+        }
     }
 }
@@ -4,8 +4,11 @@ extern crate intercom;
 use intercom::*;
 extern crate winapi;
 
+extern crate shared_interfaces;
+use shared_interfaces::IGreeter;
+
 // Declare available COM classes.
-com_library!(class HelloWorld);
+com_library!(class HelloWorld, class Greeter);
 
 #[com_interface]
 trait IHelloWorld
@@ -39,3 +42,27 @@ fn hello_world_returns_hello_world()
     let hello = HelloWorld::new();
     assert_eq!(hello.get_hello().unwrap(), "Hello World!");
 }
+
+// Implements an interface defined in another crate.
+#[com_class(clsid = "{0c6f3f0e-8b4e-4d2a-9a59-6b1e54c7d9a1}", IGreeter)]
+#[derive(Default)]
+pub struct Greeter {}
+
+impl IGreeter for Greeter
+{
+    fn greet(&self, name: &str) -> ComResult<String>
+    {
+        Ok(format!("Hello {}!", name))
+    }
+}
+
+#[test]
+fn greeter_implements_external_interface()
+{
+    let greeter = ComBox::new(Greeter::default());
+    let unknown = ComRc::<dyn IUnknown>::from(&greeter);
+
+    // Querying the interface uses the IIDs defined by the interface crate.
+    let queried: ComRc<dyn IGreeter> = ComItf::query_interface(&unknown).unwrap();
+    assert_eq!(queried.greet("World").unwrap(), "Hello World!");
+}
@@ -0,0 +1,13 @@
+//! Interfaces shared across the test libraries.
+//!
+//! The crate defines only interfaces so that the classes implementing them
+//! are defined in other crates.
+
+extern crate intercom;
+use intercom::*;
+
+#[com_interface]
+pub trait IGreeter
+{
+    fn greet(&self, name: &str) -> ComResult<String>;
+}
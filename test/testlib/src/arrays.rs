@@ -0,0 +1,51 @@
+use intercom::*;
+
+#[com_class(ArrayTests)]
+#[derive(Default)]
+pub struct ArrayTests
+{
+    data: Vec<u8>,
+}
+
+#[com_interface]
+impl ArrayTests
+{
+    pub fn checksum(&self, data: &[u8]) -> ComResult<u32>
+    {
+        Ok(data.iter().map(|&b| u32::from(b)).sum())
+    }
+
+    pub fn fill(&self, buffer: &mut [u8], value: u8) -> ComResult<()>
+    {
+        for b in buffer {
+            *b = value;
+        }
+        Ok(())
+    }
+
+    /// Reports `count` elements while writing at most `capacity` of them.
+    ///
+    /// # Safety
+    ///
+    /// The `buffer` must hold at least `capacity` elements.
+    pub unsafe fn read(
+        &self,
+        count: u32,
+        buffer: *mut u8,
+        #[com_arg(length_of = "buffer")] capacity: u32,
+        #[com_arg(length_of = "buffer")] out_count: &mut u32,
+    ) -> ComResult<()>
+    {
+        for idx in 0..count.min(capacity) {
+            *buffer.add(idx as usize) = idx as u8 + 1;
+        }
+        *out_count = count;
+        Ok(())
+    }
+
+    #[com_return(borrow_array)]
+    pub fn data(&self) -> ComResult<&[u8]>
+    {
+        Ok(&self.data)
+    }
+}
@@ -1,4 +1,6 @@
 use intercom::*;
+use intercom::error::IErrorInfo;
+use intercom::interfaces::IAgileObject;
 
 #[com_interface]
 pub trait IErrorSource
@@ -8,6 +10,13 @@ pub trait IErrorSource
     fn return_testerror(&self, hr: raw::HRESULT, desc: &str) -> Result<(), TestError>;
 
     fn return_ioerror(&self, hr: raw::HRESULT, desc: &str) -> Result<(), std::io::Error>;
+
+    fn return_tupleerror(&self, hr: raw::HRESULT, desc: &str)
+        -> Result<(), (raw::HRESULT, String)>;
+
+    fn return_custom_errorinfo(&self, hr: raw::HRESULT, desc: &str) -> ComResult<()>;
+
+    fn return_codederror(&self, hr: raw::HRESULT, desc: &str) -> Result<(), CodedError>;
 }
 
 #[com_class(ErrorTests, IErrorSource)]
@@ -75,7 +84,7 @@ impl ErrorTests
                     );
                 }
 
-                if e.to_string() != "Access denied" {
+                if e.to_string() != "E_ACCESSDENIED (0x80070005): Access denied" {
                     return Err(ComError::E_INVALIDARG.with_message(format!("Bad message: {}", e)));
                 }
 
@@ -83,6 +92,60 @@ impl ErrorTests
             }
         }
     }
+
+    pub fn test_tupleerror(&self, source: &ComItf<dyn IErrorSource>) -> ComResult<()>
+    {
+        let err = source.return_tupleerror(raw::E_INVALIDARG, "x too large");
+
+        match err {
+            Ok(..) => Err(ComError::E_FAIL),
+            Err((hr, desc)) => {
+                if hr != raw::E_INVALIDARG {
+                    return Err(
+                        ComError::E_INVALIDARG.with_message(format!("Bad HRESULT: {}", hr.hr))
+                    );
+                }
+
+                if desc != "x too large" {
+                    return Err(
+                        ComError::E_INVALIDARG.with_message(format!("Bad message: {:?}", desc))
+                    );
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    pub fn test_custom_errorinfo(&self, source: &ComItf<dyn IErrorSource>) -> ComResult<()>
+    {
+        let err = source.return_custom_errorinfo(raw::HRESULT::new(123), "Error message");
+
+        match err {
+            Ok(..) => Err(ComError::E_FAIL),
+            Err(e) => {
+                if e.hresult.hr != 123 {
+                    return Err(ComError::E_INVALIDARG
+                        .with_message(format!("Bad HRESULT: {}", e.hresult.hr)));
+                }
+
+                let info = match e.error_info {
+                    Some(info) => info,
+                    None => return Err(ComError::E_INVALIDARG.with_message("No error info")),
+                };
+
+                if info.description() != "Error message"
+                    || info.source() != "ErrorTests"
+                    || info.help_file() != "errors.chm"
+                    || info.help_context() != 42
+                {
+                    return Err(ComError::E_INVALIDARG.with_message(format!("Bad info: {:?}", info)));
+                }
+
+                Ok(())
+            }
+        }
+    }
 }
 
 impl IErrorSource for ErrorTests
@@ -104,8 +167,59 @@ impl IErrorSource for ErrorTests
             "permission denied",
         ))
     }
+
+    fn return_tupleerror(&self, hr: raw::HRESULT, desc: &str)
+        -> Result<(), (raw::HRESULT, String)>
+    {
+        Err((hr, desc.to_string()))
+    }
+
+    fn return_custom_errorinfo(&self, hr: raw::HRESULT, desc: &str) -> ComResult<()>
+    {
+        let info = ComBox::new(CustomErrorInfo {
+            description: desc.to_string(),
+        });
+        Err(ComError::with_error_info(hr, ComRc::from(info)))
+    }
+
+    fn return_codederror(&self, hr: raw::HRESULT, desc: &str) -> Result<(), CodedError>
+    {
+        Err(CodedError(hr, desc.to_string()))
+    }
+}
+
+#[com_class(clsid = None, IErrorInfo, IAgileObject)]
+pub struct CustomErrorInfo
+{
+    description: String,
 }
 
+impl IErrorInfo for CustomErrorInfo
+{
+    fn get_guid(&self) -> ComResult<GUID>
+    {
+        Ok(GUID::zero_guid())
+    }
+    fn get_source(&self) -> ComResult<String>
+    {
+        Ok("ErrorTests".to_string())
+    }
+    fn get_description(&self) -> ComResult<String>
+    {
+        Ok(self.description.clone())
+    }
+    fn get_help_file(&self) -> ComResult<String>
+    {
+        Ok("errors.chm".to_string())
+    }
+    fn get_help_context(&self) -> ComResult<u32>
+    {
+        Ok(42)
+    }
+}
+
+impl IAgileObject for CustomErrorInfo {}
+
 #[derive(Debug)]
 pub struct TestError(raw::HRESULT, String);
 
@@ -167,3 +281,54 @@ impl From<intercom::ComError> for TestError
         )
     }
 }
+
+/// Error converted into `ComError` through the `ErrorCode` trait.
+#[derive(Debug)]
+pub struct CodedError(raw::HRESULT, String);
+
+unsafe impl<TS: intercom::type_system::TypeSystem> intercom::type_system::ExternType<TS>
+    for CodedError
+{
+    type ForeignType = raw::HRESULT;
+}
+
+unsafe impl<TS: intercom::type_system::TypeSystem> intercom::type_system::ExternOutput<TS>
+    for CodedError
+{
+    fn into_foreign_output(self) -> ComResult<Self::ForeignType>
+    {
+        Ok(self.0)
+    }
+
+    unsafe fn from_foreign_output(source: Self::ForeignType) -> ComResult<Self>
+    {
+        Ok(CodedError(source, "".to_string()))
+    }
+}
+
+impl std::fmt::Display for CodedError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        write!(f, "{}", self.1)
+    }
+}
+
+impl intercom::ErrorCode for CodedError
+{
+    fn hresult(&self) -> raw::HRESULT
+    {
+        self.0
+    }
+}
+
+impl From<intercom::ComError> for CodedError
+{
+    fn from(source: intercom::ComError) -> CodedError
+    {
+        CodedError(
+            source.hresult,
+            source.description().unwrap_or("").to_owned(),
+        )
+    }
+}
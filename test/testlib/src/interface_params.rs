@@ -8,7 +8,7 @@ pub trait ISharedInterface
     fn divide_by(&self, divisor: &ComItf<dyn ISharedInterface>) -> ComResult<u32>;
 }
 
-#[com_class(ISharedInterface)]
+#[com_class(locking = RwLock, ISharedInterface)]
 #[derive(Default)]
 pub struct SharedImplementation
 {
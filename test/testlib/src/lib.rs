@@ -7,6 +7,7 @@ extern crate chrono;
 extern crate winapi;
 
 pub mod alloc;
+pub mod arrays;
 pub mod error_info;
 pub mod interface_params;
 pub mod nullable_parameters;
@@ -31,6 +32,7 @@ com_library! {
     class interface_params::SharedImplementation,
     class error_info::ErrorTests,
     class alloc::AllocTests,
+    class arrays::ArrayTests,
     class strings::StringTests,
     class type_system_callbacks::TypeSystemCaller,
     class variant::VariantTests,
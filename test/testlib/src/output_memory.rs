@@ -22,6 +22,11 @@ pub trait IOutputMemoryTests
         input: &ComItf<dyn IUnknown>,
     ) -> ComResult<(ComRc<dyn IUnknown>, FailingType, ComRc<dyn IUnknown>)>;
 
+    fn error(
+        &self,
+        input: &ComItf<dyn IUnknown>,
+    ) -> ComResult<(ComRc<dyn IUnknown>, ComRc<dyn IUnknown>)>;
+
     fn call_succeed(
         &self,
         itf: &ComItf<dyn IOutputMemoryTests>,
@@ -53,6 +58,16 @@ impl IOutputMemoryTests for OutputMemoryTests
         Ok((input.into(), FailingType, input.into()))
     }
 
+    fn error(
+        &self,
+        input: &ComItf<dyn IUnknown>,
+    ) -> ComResult<(ComRc<dyn IUnknown>, ComRc<dyn IUnknown>)>
+    {
+        // The reference acquired before the error must not leak.
+        let _first: ComRc<dyn IUnknown> = input.into();
+        Err(ComError::E_INVALIDARG)
+    }
+
     fn call_succeed(
         &self,
         itf: &ComItf<dyn IOutputMemoryTests>,
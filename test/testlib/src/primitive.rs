@@ -54,4 +54,31 @@ impl PrimitiveOperations
     {
         1f32 / v
     }
+
+    pub fn next_color(&self, c: Color) -> ComResult<Color>
+    {
+        Ok(match c {
+            Color::Red => Color::Green,
+            Color::Green => Color::Blue,
+            Color::Blue => Color::Red,
+        })
+    }
+    pub fn default_color(&self) -> ComResult<Color>
+    {
+        Ok(Color::Green)
+    }
+
+    pub fn bounds(&self, a: i32, b: i32) -> ComResult<Box<type_system::Tuple2<i32, i32>>>
+    {
+        Ok(Box::new(type_system::Tuple2(a.min(b), a.max(b))))
+    }
+}
+
+#[derive(ExternType, ExternInput, ExternOutput, Clone, Copy)]
+#[repr(C)]
+pub enum Color
+{
+    Red = 1,
+    Green = 2,
+    Blue = 3,
 }
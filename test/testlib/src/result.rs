@@ -1,7 +1,7 @@
 use intercom::*;
 use std::convert::TryFrom;
 
-#[com_class(ResultOperations)]
+#[com_class(locking = RwLock, ResultOperations)]
 #[derive(Default)]
 pub struct ResultOperations {}
 
@@ -33,4 +33,20 @@ impl ResultOperations
 
         Ok((first, second))
     }
+
+    pub fn write(&self, value: u32, written: &mut u32) -> ComResult<()>
+    {
+        if value == 0 {
+            return Err(ComError::E_INVALIDARG);
+        }
+
+        *written = value;
+        Ok(())
+    }
+
+    pub fn double(&self, #[com_arg(in_out)] value: &mut u32) -> ComResult<()>
+    {
+        *value = value.checked_mul(2).ok_or(ComError::E_INVALIDARG)?;
+        Ok(())
+    }
 }
@@ -4,6 +4,7 @@ com_module!(
     class RefCountOperations,
     class ClassCreator,
     class CreatedClass,
+    class InternalClass,
 );
 
 #[com_interface]
@@ -24,6 +25,15 @@ impl ClassCreator
         Ok(ComRc::from(&ComBox::new(CreatedClass::new_with_id(id))))
     }
 
+    pub fn try_create_root(&self, id: i32) -> ComResult<ComRc<CreatedClass>>
+    {
+        if id < 0 {
+            return Err(ComError::E_INVALIDARG);
+        }
+
+        self.create_root(id)
+    }
+
     pub fn create_child(
         &self,
         id: i32,
@@ -35,6 +45,11 @@ impl ClassCreator
             parent.get_id(),
         ))))
     }
+
+    pub fn create_internal(&self, id: i32) -> ComResult<ComRc<InternalClass>>
+    {
+        Ok(ComRc::from(&ComBox::new(InternalClass { id })))
+    }
 }
 
 #[com_class(CreatedClass, IParent, IRefCount)]
@@ -90,6 +105,22 @@ impl IParent for CreatedClass
     }
 }
 
+/// Class available only through the `ClassCreator`.
+#[com_class(no_class_factory, InternalClass)]
+pub struct InternalClass
+{
+    id: i32,
+}
+
+#[com_interface]
+impl InternalClass
+{
+    pub fn get_id(&self) -> i32
+    {
+        self.id
+    }
+}
+
 #[com_class(RefCountOperations)]
 #[derive(Default)]
 pub struct RefCountOperations {}
@@ -1,6 +1,6 @@
 use intercom::*;
 
-#[com_class(StatefulOperations)]
+#[com_class(locking = RwLock, StatefulOperations)]
 pub struct StatefulOperations
 {
     state: i32,
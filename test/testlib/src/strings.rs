@@ -2,7 +2,8 @@ use intercom::*;
 
 pub static STRING_DATA: &[&str] = &["", "Test", "öäå", "\u{1F980}"];
 
-#[com_interface]
+// The string addresses are passed as pointer-width values.
+#[com_interface(pointer_width = "target")]
 pub trait IStringTests
 {
     fn string_to_index(&self, s: &str) -> ComResult<u32>;
@@ -24,7 +25,20 @@ pub trait IStringTests
     fn invalid_string(&self, s: &str) -> ComResult<()>;
 }
 
-#[com_class(IStringTests)]
+// Kept apart from IStringTests, which the C++ tests also implement.
+#[com_interface]
+pub trait IStringOutputs
+{
+    fn greet(&self, name: &str) -> ComResult<String>;
+
+    fn greet_wide(&self, name: &str) -> ComResult<WString>;
+
+    fn append_crab(&self, #[com_arg(in_out)] text: &mut String) -> ComResult<()>;
+
+    fn append_crab_and_fail(&self, #[com_arg(in_out)] text: &mut String) -> ComResult<()>;
+}
+
+#[com_class(IStringTests, IStringOutputs)]
 #[derive(Default)]
 pub struct StringTests;
 
@@ -132,3 +146,31 @@ impl IStringTests for StringTests
         Err(ComError::E_FAIL)
     }
 }
+
+impl IStringOutputs for StringTests
+{
+    fn greet(&self, name: &str) -> ComResult<String>
+    {
+        match name {
+            "" => Err(ComError::E_INVALIDARG),
+            name => Ok(format!("Hello, {}!", name)),
+        }
+    }
+
+    fn greet_wide(&self, name: &str) -> ComResult<WString>
+    {
+        self.greet(name).map(WString::from)
+    }
+
+    fn append_crab(&self, text: &mut String) -> ComResult<()>
+    {
+        text.push_str(" \u{1f980}");
+        Ok(())
+    }
+
+    fn append_crab_and_fail(&self, text: &mut String) -> ComResult<()>
+    {
+        text.push_str(" \u{1f980}");
+        Err(ComError::E_FAIL)
+    }
+}
@@ -5,7 +5,7 @@ use strings::{IStringTests, STRING_DATA};
 #[derive(Default)]
 pub struct TypeSystemCaller;
 
-#[com_interface]
+#[com_interface(pointer_width = "target")]
 impl TypeSystemCaller
 {
     pub fn new() -> Self
@@ -62,6 +62,15 @@ impl TypeSystemCaller
         Ok(())
     }
 
+    fn pass_through_bstring(
+        &self,
+        callback: &ComItf<dyn IStringTests>,
+    ) -> ComResult<(BString, usize)>
+    {
+        // The BString received from the callback is returned as is.
+        callback.bstring_return_value()
+    }
+
     fn pass_cstr(&self, callback: &ComItf<dyn IStringTests>) -> ComResult<()>
     {
         let cstr = CString::new("\u{1F4A9}").unwrap();
@@ -82,7 +91,9 @@ impl TypeSystemCaller
             return Err(ComError::E_FAIL);
         }
 
-        if cstr.as_ptr() as usize != ptr {
+        // The string is copied out of the memory allocated by the callback so
+        // that it can be freed with the Rust allocator.
+        if cstr.as_ptr() as usize == ptr {
             return Err(ComError::E_POINTER);
         }
 